@@ -83,6 +83,27 @@ impl From<Color> for Color32F {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct BackgroundImage {
+    #[knuffel(argument)]
+    pub path: String,
+    #[knuffel(child, unwrap(argument), default)]
+    pub mode: BackgroundImageMode,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundImageMode {
+    /// Scale the image to cover the whole area, cropping it if needed.
+    #[default]
+    Fill,
+    /// Scale the image to fit into the area, leaving the background color visible around it.
+    Fit,
+    /// Show the image at its native size in the center of the area.
+    Center,
+    /// Repeat the image at its native size, starting from the top-left corner.
+    Tile,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct Gradient {
     #[knuffel(property, str)]
@@ -389,6 +410,44 @@ pub struct ShadowOffset {
     pub y: FloatOrInt<-65535, 65535>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimInactive {
+    pub on: bool,
+    pub strength: f64,
+    pub active_column_strength: f64,
+    pub other_outputs_strength: f64,
+    pub desaturation: f64,
+}
+
+impl Default for DimInactive {
+    fn default() -> Self {
+        Self {
+            on: false,
+            strength: 0.3,
+            active_column_strength: 0.15,
+            other_outputs_strength: 0.3,
+            desaturation: 0.,
+        }
+    }
+}
+
+impl MergeWith<DimInactiveRule> for DimInactive {
+    fn merge_with(&mut self, part: &DimInactiveRule) {
+        self.on |= part.on;
+        if part.off {
+            self.on = false;
+        }
+
+        merge!(
+            (self, part),
+            strength,
+            active_column_strength,
+            other_outputs_strength,
+            desaturation,
+        );
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WorkspaceShadow {
     pub off: bool,
@@ -455,6 +514,90 @@ impl MergeWith<WorkspaceShadowPart> for WorkspaceShadow {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blur {
+    pub off: bool,
+    pub passes: u8,
+    pub offset: f64,
+}
+
+impl Default for Blur {
+    fn default() -> Self {
+        Self {
+            off: false,
+            passes: 3,
+            offset: 3.,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct BlurPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub passes: Option<u8>,
+    #[knuffel(child, unwrap(argument))]
+    pub offset: Option<FloatOrInt<0, 100>>,
+}
+
+impl MergeWith<BlurPart> for Blur {
+    fn merge_with(&mut self, part: &BlurPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge!((self, part), offset);
+        merge_clone!((self, part), passes);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ColorFilter {
+    pub off: bool,
+    pub filter: Option<ColorFilterKind>,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct ColorFilterPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub filter: Option<ColorFilterKind>,
+}
+
+impl MergeWith<ColorFilterPart> for ColorFilter {
+    fn merge_with(&mut self, part: &ColorFilterPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge_clone_opt!((self, part), filter);
+    }
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorFilterKind {
+    /// Remove all color.
+    Grayscale,
+    /// Invert all colors.
+    Invert,
+    /// Increase the contrast.
+    HighContrast,
+    /// Correct colors for deuteranopia (green-blind).
+    Deuteranopia,
+    /// Correct colors for protanopia (red-blind).
+    Protanopia,
+    /// Correct colors for tritanopia (blue-blind).
+    Tritanopia,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TabIndicator {
     pub off: bool,
@@ -664,6 +807,22 @@ pub struct ShadowRule {
     pub inactive_color: Option<Color>,
 }
 
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct DimInactiveRule {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub strength: Option<FloatOrInt<0, 1>>,
+    #[knuffel(child, unwrap(argument))]
+    pub active_column_strength: Option<FloatOrInt<0, 1>>,
+    #[knuffel(child, unwrap(argument))]
+    pub other_outputs_strength: Option<FloatOrInt<0, 1>>,
+    #[knuffel(child, unwrap(argument))]
+    pub desaturation: Option<FloatOrInt<0, 1>>,
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
 pub struct TabIndicatorRule {
     #[knuffel(child)]
@@ -680,6 +839,12 @@ pub struct TabIndicatorRule {
     pub urgent_gradient: Option<Gradient>,
 }
 
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct BackgroundEffectRule {
+    #[knuffel(child, unwrap(argument))]
+    pub blur: Option<bool>,
+}
+
 impl MergeWith<Self> for BorderRule {
     fn merge_with(&mut self, part: &Self) {
         merge_on_off!((self, part));
@@ -711,6 +876,26 @@ impl MergeWith<Self> for ShadowRule {
     }
 }
 
+impl MergeWith<Self> for DimInactiveRule {
+    fn merge_with(&mut self, part: &Self) {
+        merge_on_off!((self, part));
+
+        merge_clone_opt!(
+            (self, part),
+            strength,
+            active_column_strength,
+            other_outputs_strength,
+            desaturation,
+        );
+    }
+}
+
+impl MergeWith<Self> for BackgroundEffectRule {
+    fn merge_with(&mut self, part: &Self) {
+        merge_clone_opt!((self, part), blur);
+    }
+}
+
 impl MergeWith<Self> for TabIndicatorRule {
     fn merge_with(&mut self, part: &Self) {
         merge_color_gradient_opt!(
@@ -1252,4 +1437,68 @@ mod tests {
         "
         );
     }
+
+    #[test]
+    fn parse_color_filter() {
+        let config = Config::parse_mem(
+            r#"
+            color-filter {
+                off
+                filter "deuteranopia"
+            }
+
+            output "eDP-1" {
+                color-filter "high-contrast"
+            }
+
+            output "DP-2"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.color_filter,
+            ColorFilter {
+                off: true,
+                filter: Some(ColorFilterKind::Deuteranopia),
+            }
+        );
+
+        let output_filters: Vec<_> = config.outputs.0.iter().map(|o| o.color_filter).collect();
+        assert_eq!(output_filters, [Some(ColorFilterKind::HighContrast), None]);
+
+        assert!(Config::parse_mem(r#"color-filter { filter "sepia"; }"#).is_err());
+        assert!(Config::parse_mem(r#"output "eDP-1" { color-filter "sepia"; }"#).is_err());
+    }
+
+    #[test]
+    fn color_filter_merging() {
+        let mut filter = ColorFilter::default();
+
+        filter.merge_with(&ColorFilterPart {
+            off: true,
+            filter: Some(ColorFilterKind::Invert),
+            ..Default::default()
+        });
+        assert_eq!(
+            filter,
+            ColorFilter {
+                off: true,
+                filter: Some(ColorFilterKind::Invert),
+            }
+        );
+
+        // An included file can turn the filter back on without repeating the filter.
+        filter.merge_with(&ColorFilterPart {
+            on: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            filter,
+            ColorFilter {
+                off: false,
+                filter: Some(ColorFilterKind::Invert),
+            }
+        );
+    }
 }
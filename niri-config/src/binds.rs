@@ -96,6 +96,15 @@ pub struct SwitchAction {
     pub spawn: Vec<String>,
 }
 
+/// Argument of a `send-ipc-event` action, written as a `name "value"` child node.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct IpcEventArg {
+    #[knuffel(node_name)]
+    pub name: String,
+    #[knuffel(argument)]
+    pub value: String,
+}
+
 // Remember to add new actions to the CLI enum too.
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub enum Action {
@@ -105,12 +114,44 @@ pub enum Action {
     Suspend,
     PowerOffMonitors,
     PowerOnMonitors,
+    PowerOffMonitor(#[knuffel(argument)] String),
+    PowerOnMonitor(#[knuffel(argument)] String),
+    BrightnessUp(
+        #[knuffel(property(name = "step"), default = 5)] u8,
+        #[knuffel(property(name = "output"))] Option<String>,
+    ),
+    BrightnessDown(
+        #[knuffel(property(name = "step"), default = 5)] u8,
+        #[knuffel(property(name = "output"))] Option<String>,
+    ),
+    SetBrightness(
+        #[knuffel(argument)] u8,
+        #[knuffel(property(name = "output"))] Option<String>,
+    ),
+    #[knuffel(skip)]
+    CreateVirtualOutput {
+        width: u16,
+        height: u16,
+        refresh: Option<f64>,
+        scale: Option<f64>,
+    },
+    #[knuffel(skip)]
+    DestroyVirtualOutput(String),
     ToggleDebugTint,
     DebugToggleOpaqueRegions,
     DebugToggleDamage,
     Spawn(#[knuffel(arguments)] Vec<String>),
     SpawnSh(#[knuffel(argument)] String),
+    SendIpcEvent(
+        #[knuffel(argument)] String,
+        #[knuffel(children)] Vec<IpcEventArg>,
+    ),
     DoScreenTransition(#[knuffel(property(name = "delay-ms"))] Option<u16>),
+    ToggleColorFilter,
+    ToggleNightLight,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
     #[knuffel(skip)]
     ConfirmScreenshot {
         write_to_disk: bool,
@@ -121,12 +162,14 @@ pub enum Action {
     ScreenshotTogglePointer,
     Screenshot(
         #[knuffel(property(name = "show-pointer"), default = true)] bool,
+        #[knuffel(property(name = "color-filter"), default = false)] bool,
         // Path; not settable from knuffel
         Option<String>,
     ),
     ScreenshotScreen(
         #[knuffel(property(name = "write-to-disk"), default = true)] bool,
         #[knuffel(property(name = "show-pointer"), default = true)] bool,
+        #[knuffel(property(name = "color-filter"), default = false)] bool,
         // Path; not settable from knuffel
         Option<String>,
     ),
@@ -397,17 +440,50 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::Quit { skip_confirmation } => Self::Quit(skip_confirmation),
             niri_ipc::Action::PowerOffMonitors {} => Self::PowerOffMonitors,
             niri_ipc::Action::PowerOnMonitors {} => Self::PowerOnMonitors,
+            niri_ipc::Action::PowerOffMonitor { output } => Self::PowerOffMonitor(output),
+            niri_ipc::Action::PowerOnMonitor { output } => Self::PowerOnMonitor(output),
+            niri_ipc::Action::BrightnessUp { step, output } => Self::BrightnessUp(step, output),
+            niri_ipc::Action::BrightnessDown { step, output } => Self::BrightnessDown(step, output),
+            niri_ipc::Action::SetBrightness { brightness, output } => {
+                Self::SetBrightness(brightness, output)
+            }
+            niri_ipc::Action::CreateVirtualOutput {
+                width,
+                height,
+                refresh,
+                scale,
+            } => Self::CreateVirtualOutput {
+                width,
+                height,
+                refresh,
+                scale,
+            },
+            niri_ipc::Action::DestroyVirtualOutput { output } => Self::DestroyVirtualOutput(output),
             niri_ipc::Action::Spawn { command } => Self::Spawn(command),
             niri_ipc::Action::SpawnSh { command } => Self::SpawnSh(command),
+            niri_ipc::Action::SendIpcEvent { name, args } => Self::SendIpcEvent(
+                name,
+                args.into_iter()
+                    .map(|(name, value)| IpcEventArg { name, value })
+                    .collect(),
+            ),
             niri_ipc::Action::DoScreenTransition { delay_ms } => Self::DoScreenTransition(delay_ms),
-            niri_ipc::Action::Screenshot { show_pointer, path } => {
-                Self::Screenshot(show_pointer, path)
-            }
+            niri_ipc::Action::ToggleColorFilter {} => Self::ToggleColorFilter,
+            niri_ipc::Action::ToggleNightLight {} => Self::ToggleNightLight,
+            niri_ipc::Action::ZoomIn {} => Self::ZoomIn,
+            niri_ipc::Action::ZoomOut {} => Self::ZoomOut,
+            niri_ipc::Action::ZoomReset {} => Self::ZoomReset,
+            niri_ipc::Action::Screenshot {
+                show_pointer,
+                color_filter,
+                path,
+            } => Self::Screenshot(show_pointer, color_filter, path),
             niri_ipc::Action::ScreenshotScreen {
                 write_to_disk,
                 show_pointer,
+                color_filter,
                 path,
-            } => Self::ScreenshotScreen(write_to_disk, show_pointer, path),
+            } => Self::ScreenshotScreen(write_to_disk, show_pointer, color_filter, path),
             niri_ipc::Action::ScreenshotWindow {
                 id: None,
                 write_to_disk,
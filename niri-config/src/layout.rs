@@ -2,16 +2,19 @@ use knuffel::errors::DecodeError;
 use niri_ipc::{ColumnDisplay, SizeChange};
 
 use crate::appearance::{
-    Border, FocusRing, InsertHint, Shadow, TabIndicator, DEFAULT_BACKGROUND_COLOR,
+    Border, DimInactive, FocusRing, InsertHint, Shadow, TabIndicator, DEFAULT_BACKGROUND_COLOR,
 };
 use crate::utils::{expect_only_children, Flag, MergeWith};
-use crate::{BorderRule, Color, FloatOrInt, InsertHintPart, ShadowRule, TabIndicatorPart};
+use crate::{
+    BorderRule, Color, DimInactiveRule, FloatOrInt, InsertHintPart, ShadowRule, TabIndicatorPart,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Layout {
     pub focus_ring: FocusRing,
     pub border: Border,
     pub shadow: Shadow,
+    pub dim_inactive: DimInactive,
     pub tab_indicator: TabIndicator,
     pub insert_hint: InsertHint,
     pub preset_column_widths: Vec<PresetSize>,
@@ -32,6 +35,7 @@ impl Default for Layout {
             focus_ring: FocusRing::default(),
             border: Border::default(),
             shadow: Shadow::default(),
+            dim_inactive: DimInactive::default(),
             tab_indicator: TabIndicator::default(),
             insert_hint: InsertHint::default(),
             preset_column_widths: vec![
@@ -63,6 +67,7 @@ impl MergeWith<LayoutPart> for Layout {
             focus_ring,
             border,
             shadow,
+            dim_inactive,
             tab_indicator,
             insert_hint,
             always_center_single_column,
@@ -103,6 +108,8 @@ pub struct LayoutPart {
     #[knuffel(child)]
     pub shadow: Option<ShadowRule>,
     #[knuffel(child)]
+    pub dim_inactive: Option<DimInactiveRule>,
+    #[knuffel(child)]
     pub tab_indicator: Option<TabIndicatorPart>,
     #[knuffel(child)]
     pub insert_hint: Option<InsertHintPart>,
@@ -13,7 +13,7 @@
 #[macro_use]
 extern crate tracing;
 
-use std::cell::{Cell, RefCell};
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::fs::{self, File};
@@ -54,7 +54,9 @@ pub use crate::input::{Input, ModKey, ScrollMethod, TrackLayout, WarpMouseToFocu
 pub use crate::layer_rule::LayerRule;
 pub use crate::layout::*;
 pub use crate::misc::*;
-pub use crate::output::{Output, OutputName, Outputs, Position, Vrr};
+pub use crate::output::{
+    Hdr, Output, OutputName, OutputPrimaries, OutputProfile, Outputs, Position, Vrr,
+};
 use crate::recent_windows::RecentWindowsPart;
 pub use crate::recent_windows::{MruDirection, MruFilter, MruPreviews, MruScope, RecentWindows};
 pub use crate::utils::FloatOrInt;
@@ -68,6 +70,7 @@ const RECURSION_LIMIT: u8 = 10;
 pub struct Config {
     pub input: Input,
     pub outputs: Outputs,
+    pub output_profiles: Vec<OutputProfile>,
     pub spawn_at_startup: Vec<SpawnAtStartup>,
     pub spawn_sh_at_startup: Vec<SpawnShAtStartup>,
     pub layout: Layout,
@@ -77,9 +80,13 @@ pub struct Config {
     pub clipboard: Clipboard,
     pub hotkey_overlay: HotkeyOverlay,
     pub config_notification: ConfigNotification,
+    pub bell: Bell,
     pub animations: Animations,
     pub gestures: Gestures,
     pub overview: Overview,
+    pub blur: Blur,
+    pub color_filter: ColorFilter,
+    pub night_light: NightLight,
     pub environment: Environment,
     pub xwayland_satellite: XwaylandSatellite,
     pub window_rules: Vec<WindowRule>,
@@ -122,7 +129,8 @@ struct IncludeErrors(Vec<knuffel::Error>);
 //
 // We don't *need* it because we have a recursion limit, but it makes for nicer error messages.
 struct IncludeStack(HashSet<PathBuf>);
-struct SawMruBinds(Rc<Cell<bool>>);
+// Set when parsing a config overlay, which cannot include other files.
+struct IsOverlay;
 
 // Rather than listing all fields and deriving knuffel::Decode, we implement
 // knuffel::DecodeChildren by hand, since we need custom logic for every field anyway: we want to
@@ -145,7 +153,6 @@ where
         let includes = ctx.get::<Rc<RefCell<Includes>>>().unwrap().clone();
         let include_errors = ctx.get::<Rc<RefCell<IncludeErrors>>>().unwrap().clone();
         let recursion = ctx.get::<Recursion>().unwrap().0;
-        let saw_mru_binds = ctx.get::<SawMruBinds>().unwrap().0.clone();
 
         let mut seen = HashSet::new();
 
@@ -157,6 +164,7 @@ where
             if !matches!(
                 name,
                 "output"
+                    | "output-profile"
                     | "spawn-at-startup"
                     | "spawn-sh-at-startup"
                     | "window-rule"
@@ -193,9 +201,13 @@ where
                 "clipboard" => m_merge!(clipboard),
                 "hotkey-overlay" => m_merge!(hotkey_overlay),
                 "config-notification" => m_merge!(config_notification),
+                "bell" => m_merge!(bell),
                 "animations" => m_merge!(animations),
                 "gestures" => m_merge!(gestures),
                 "overview" => m_merge!(overview),
+                "blur" => m_merge!(blur),
+                "color-filter" => m_merge!(color_filter),
+                "night-light" => m_merge!(night_light),
                 "xwayland-satellite" => m_merge!(xwayland_satellite),
                 "switch-events" => m_merge!(switch_events),
                 "debug" => m_merge!(debug),
@@ -205,6 +217,7 @@ where
                     let part = Output::decode_node(node, ctx)?;
                     config.borrow_mut().outputs.0.push(part);
                 }
+                "output-profile" => m_push!(output_profiles),
                 "spawn-at-startup" => m_push!(spawn_at_startup),
                 "spawn-sh-at-startup" => m_push!(spawn_sh_at_startup),
                 "window-rule" => m_push!(window_rules),
@@ -282,8 +295,8 @@ where
 
                     // When an MRU binds section is encountered for the first time, clear out the
                     // default MRU binds.
-                    if !saw_mru_binds.get() && part.binds.is_some() {
-                        saw_mru_binds.set(true);
+                    if !config.recent_windows.binds_configured && part.binds.is_some() {
+                        config.recent_windows.binds_configured = true;
                         config.recent_windows.binds.clear();
                     }
 
@@ -291,6 +304,15 @@ where
                 }
 
                 "include" => {
+                    if ctx.get::<IsOverlay>().is_some() {
+                        ctx.emit_error(DecodeError::unexpected(
+                            node,
+                            "node",
+                            "config overlays cannot include other files",
+                        ));
+                        continue;
+                    }
+
                     // Parse the path argument
                     let mut iter_args = node.arguments.iter();
                     let path_val = iter_args.next().ok_or_else(|| {
@@ -396,7 +418,6 @@ where
                                 ctx.set(includes.clone());
                                 ctx.set(include_errors.clone());
                                 ctx.set(IncludeStack(include_stack));
-                                ctx.set(SawMruBinds(saw_mru_binds.clone()));
                                 ctx.set(config.clone());
                             });
 
@@ -475,13 +496,33 @@ impl Config {
     }
 
     pub fn parse(path: &Path, text: &str) -> ConfigParseResult<Self, ConfigIncludeError> {
+        Self::parse_onto(Config::default(), path, text, 0, false)
+    }
+
+    /// Parses a config fragment and merges it on top of this config.
+    ///
+    /// The fragment follows the same merging rules as an included file, except that it cannot
+    /// include other files.
+    pub fn with_overlay(&self, text: &str) -> miette::Result<Self> {
+        // Parse at the include level to get the include merging behavior.
+        let res = Self::parse_onto(self.clone(), Path::new("overlay.kdl"), text, 1, true);
+        Ok(res.config?)
+    }
+
+    fn parse_onto(
+        config: Config,
+        path: &Path,
+        text: &str,
+        recursion: u8,
+        is_overlay: bool,
+    ) -> ConfigParseResult<Self, ConfigIncludeError> {
         let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
         let filename = path
             .file_name()
             .and_then(OsStr::to_str)
             .unwrap_or("config.kdl");
 
-        let config = Rc::new(RefCell::new(Config::default()));
+        let config = Rc::new(RefCell::new(config));
         let includes = Rc::new(RefCell::new(Includes(Vec::new())));
         let include_errors = Rc::new(RefCell::new(IncludeErrors(Vec::new())));
         let include_stack = HashSet::from([path.to_path_buf()]);
@@ -492,11 +533,13 @@ impl Config {
             |ctx| {
                 ctx.set(BasePath(base.clone()));
                 ctx.set(RootBase(base));
-                ctx.set(Recursion(0));
+                ctx.set(Recursion(recursion));
                 ctx.set(includes.clone());
                 ctx.set(include_errors.clone());
                 ctx.set(IncludeStack(include_stack));
-                ctx.set(SawMruBinds(Rc::new(Cell::new(false))));
+                if is_overlay {
+                    ctx.set(IsOverlay);
+                }
                 ctx.set(config.clone());
             },
         );
@@ -637,6 +680,74 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn overlay_merges_on_top() {
+        let config = do_parse(
+            r#"
+            layout {
+                gaps 8
+                focus-ring {
+                    width 4
+                }
+            }
+
+            window-rule {
+                match app-id="a"
+            }
+            "#,
+        );
+
+        let overlay = config
+            .with_overlay(
+                r#"
+                layout {
+                    gaps 16
+                }
+
+                window-rule {
+                    match app-id="b"
+                }
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(overlay.layout.gaps, 16.);
+        assert_eq!(overlay.layout.focus_ring.width, 4.);
+        assert_eq!(overlay.window_rules.len(), 2);
+
+        assert!(config.with_overlay("layout { gaps -1; }").is_err());
+        assert!(config.with_overlay(r#"include "other.kdl""#).is_err());
+    }
+
+    #[test]
+    fn overlay_merges_recent_windows_binds() {
+        let overlay = r#"
+            recent-windows {
+                binds {
+                    Super+Tab { next-window; }
+                }
+            }
+            "#;
+
+        // Like in an included file, binds merge with the binds from the config.
+        let config = do_parse(
+            r#"
+            recent-windows {
+                binds {
+                    Alt+Tab { next-window; }
+                }
+            }
+            "#,
+        );
+        let merged = config.with_overlay(overlay).unwrap();
+        assert_eq!(merged.recent_windows.binds.len(), 2);
+
+        // Without binds in the config, they replace the default binds.
+        let config = do_parse("");
+        let merged = config.with_overlay(overlay).unwrap();
+        assert_eq!(merged.recent_windows.binds.len(), 1);
+    }
+
     #[test]
     fn parse() {
         let parsed = do_parse(
@@ -727,6 +838,9 @@ mod tests {
                 mode "1920x1080@144"
                 variable-refresh-rate on-demand=true
                 background-color "rgba(25, 25, 102, 1.0)"
+                background-image "~/wallpaper.jpg" {
+                    mode "fit"
+                }
                 hot-corners {
                     off
                     top-left
@@ -738,10 +852,27 @@ mod tests {
 
             output "eDP-2" {
                 mode custom=true "1920x1080@144"
+                mirror "eDP-1"
             }
 
             output "eDP-3" {
                 modeline 173.00  1920 2048 2248 2576  1080 1083 1088 1120 "-hsync" "+vsync"
+                primaries "display-p3"
+                hdr {
+                    max-luminance 600
+                }
+            }
+
+            output-profile "desk" {
+                output "eDP-1" {
+                    off
+                }
+            }
+
+            night-light {
+                temperature 3500
+                from "21:30"
+                to "06:45"
             }
 
             layout {
@@ -822,6 +953,11 @@ mod tests {
                 skip-at-startup
             }
 
+            bell {
+                urgent
+                spawn "canberra-gtk-play" "-i" "bell"
+            }
+
             animations {
                 slowdown 2.0
 
@@ -907,6 +1043,7 @@ mod tests {
                 Mod+Shift+E allow-inhibiting=false { quit skip-confirmation=true; }
                 Mod+WheelScrollDown cooldown-ms=150 { focus-workspace-down; }
                 Super+Alt+S allow-when-locked=true { spawn-sh "pkill orca || exec orca"; }
+                Mod+Alt+D { send-ipc-event "toggle-dashboard" { monitor "eDP-1"; }; }
             }
 
             switch-events {
@@ -922,6 +1059,7 @@ mod tests {
 
             workspace "workspace-1" {
                 open-on-output "eDP-1"
+                background-image "~/pattern.png" { mode "tile"; }
             }
             workspace "workspace-2"
             workspace "workspace-3"
@@ -1165,7 +1303,14 @@ mod tests {
                                 a: 1.0,
                             },
                         ),
+                        background_image: Some(
+                            BackgroundImage {
+                                path: "~/wallpaper.jpg",
+                                mode: Fit,
+                            },
+                        ),
                         backdrop_color: None,
+                        backdrop_image: None,
                         hot_corners: Some(
                             HotCorners {
                                 off: true,
@@ -1175,6 +1320,10 @@ mod tests {
                                 bottom_right: true,
                             },
                         ),
+                        color_filter: None,
+                        mirror: None,
+                        primaries: None,
+                        hdr: None,
                         layout: None,
                     },
                     Output {
@@ -1199,8 +1348,16 @@ mod tests {
                         variable_refresh_rate: None,
                         focus_at_startup: false,
                         background_color: None,
+                        background_image: None,
                         backdrop_color: None,
+                        backdrop_image: None,
                         hot_corners: None,
+                        color_filter: None,
+                        mirror: Some(
+                            "eDP-1",
+                        ),
+                        primaries: None,
+                        hdr: None,
                         layout: None,
                     },
                     Output {
@@ -1228,12 +1385,58 @@ mod tests {
                         variable_refresh_rate: None,
                         focus_at_startup: false,
                         background_color: None,
+                        background_image: None,
                         backdrop_color: None,
+                        backdrop_image: None,
                         hot_corners: None,
+                        color_filter: None,
+                        mirror: None,
+                        primaries: Some(
+                            DisplayP3,
+                        ),
+                        hdr: Some(
+                            Hdr {
+                                max_luminance: Some(
+                                    FloatOrInt(
+                                        600.0,
+                                    ),
+                                ),
+                                min_luminance: None,
+                                reference_luminance: None,
+                            },
+                        ),
                         layout: None,
                     },
                 ],
             ),
+            output_profiles: [
+                OutputProfile {
+                    name: "desk",
+                    outputs: [
+                        Output {
+                            off: true,
+                            name: "eDP-1",
+                            scale: None,
+                            transform: Normal,
+                            position: None,
+                            mode: None,
+                            modeline: None,
+                            variable_refresh_rate: None,
+                            focus_at_startup: false,
+                            background_color: None,
+                            background_image: None,
+                            backdrop_color: None,
+                            backdrop_image: None,
+                            hot_corners: None,
+                            color_filter: None,
+                            mirror: None,
+                            primaries: None,
+                            hdr: None,
+                            layout: None,
+                        },
+                    ],
+                },
+            ],
             spawn_at_startup: [
                 SpawnAtStartup {
                     command: [
@@ -1341,6 +1544,13 @@ mod tests {
                     },
                     inactive_color: None,
                 },
+                dim_inactive: DimInactive {
+                    on: false,
+                    strength: 0.3,
+                    active_column_strength: 0.15,
+                    other_outputs_strength: 0.3,
+                    desaturation: 0.0,
+                },
                 tab_indicator: TabIndicator {
                     off: false,
                     hide_when_single_tab: false,
@@ -1476,6 +1686,17 @@ mod tests {
             config_notification: ConfigNotification {
                 disable_failed: false,
             },
+            bell: Bell {
+                flash: true,
+                urgent: true,
+                spawn: Some(
+                    [
+                        "canberra-gtk-play",
+                        "-i",
+                        "bell",
+                    ],
+                ),
+            },
             animations: Animations {
                 off: false,
                 slowdown: 2.0,
@@ -1615,6 +1836,17 @@ mod tests {
                         ),
                     },
                 ),
+                window_dim: WindowDimAnim(
+                    Animation {
+                        off: false,
+                        kind: Easing(
+                            EasingParams {
+                                duration_ms: 200,
+                                curve: EaseOutQuad,
+                            },
+                        ),
+                    },
+                ),
             },
             gestures: Gestures {
                 dnd_edge_view_scroll: DndEdgeViewScroll {
@@ -1643,6 +1875,7 @@ mod tests {
                     b: 0.15,
                     a: 1.0,
                 },
+                backdrop_image: None,
                 workspace_shadow: WorkspaceShadow {
                     off: false,
                     offset: ShadowOffset {
@@ -1663,6 +1896,34 @@ mod tests {
                     },
                 },
             },
+            blur: Blur {
+                off: false,
+                passes: 3,
+                offset: 3.0,
+            },
+            color_filter: ColorFilter {
+                off: false,
+                filter: None,
+            },
+            night_light: NightLight {
+                off: false,
+                temperature: 3500.0,
+                from: Some(
+                    TimeOfDay {
+                        hour: 21,
+                        minute: 30,
+                    },
+                ),
+                to: Some(
+                    TimeOfDay {
+                        hour: 6,
+                        minute: 45,
+                    },
+                ),
+                latitude: None,
+                longitude: None,
+                transition_minutes: 30,
+            },
             environment: Environment(
                 [
                     EnvironmentVariable {
@@ -1693,6 +1954,7 @@ mod tests {
                                 ),
                             ),
                             title: None,
+                            tag: None,
                             is_active: None,
                             is_focused: None,
                             is_active_in_column: None,
@@ -1712,6 +1974,7 @@ mod tests {
                                     ),
                                 ),
                             ),
+                            tag: None,
                             is_active: None,
                             is_focused: None,
                             is_active_in_column: None,
@@ -1723,6 +1986,7 @@ mod tests {
                         Match {
                             app_id: None,
                             title: None,
+                            tag: None,
                             is_active: Some(
                                 true,
                             ),
@@ -1822,6 +2086,14 @@ mod tests {
                         inactive_gradient: None,
                         urgent_gradient: None,
                     },
+                    dim_inactive: DimInactiveRule {
+                        off: false,
+                        on: false,
+                        strength: None,
+                        active_column_strength: None,
+                        other_outputs_strength: None,
+                        desaturation: None,
+                    },
                     draw_border_with_background: None,
                     opacity: None,
                     geometry_corner_radius: None,
@@ -1845,6 +2117,9 @@ mod tests {
                     ),
                     scroll_factor: None,
                     tiled_state: None,
+                    background_effect: BackgroundEffectRule {
+                        blur: None,
+                    },
                 },
             ],
             layer_rules: [
@@ -1879,6 +2154,9 @@ mod tests {
                     geometry_corner_radius: None,
                     place_within_backdrop: None,
                     baba_is_float: None,
+                    background_effect: BackgroundEffectRule {
+                        blur: None,
+                    },
                 },
             ],
             binds: Binds(
@@ -2151,6 +2429,30 @@ mod tests {
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
                     },
+                    Bind {
+                        key: Key {
+                            trigger: Keysym(
+                                XK_d,
+                            ),
+                            modifiers: Modifiers(
+                                ALT | COMPOSITOR,
+                            ),
+                        },
+                        action: SendIpcEvent(
+                            "toggle-dashboard",
+                            [
+                                IpcEventArg {
+                                    name: "monitor",
+                                    value: "eDP-1",
+                                },
+                            ],
+                        ),
+                        repeat: true,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                    },
                 ],
             ),
             switch_events: SwitchBinds {
@@ -2202,6 +2504,7 @@ mod tests {
                 honor_xdg_activation_with_invalid_serial: false,
                 deactivate_unfocused_windows: false,
                 skip_cursor_only_updates_during_vrr: false,
+                disable_explicit_sync: false,
             },
             workspaces: [
                 Workspace {
@@ -2211,6 +2514,12 @@ mod tests {
                     open_on_output: Some(
                         "eDP-1",
                     ),
+                    background_image: Some(
+                        BackgroundImage {
+                            path: "~/pattern.png",
+                            mode: Tile,
+                        },
+                    ),
                     layout: None,
                 },
                 Workspace {
@@ -2218,6 +2527,7 @@ mod tests {
                         "workspace-2",
                     ),
                     open_on_output: None,
+                    background_image: None,
                     layout: None,
                 },
                 Workspace {
@@ -2225,6 +2535,7 @@ mod tests {
                         "workspace-3",
                     ),
                     open_on_output: None,
+                    background_image: None,
                     layout: None,
                 },
             ],
@@ -2322,6 +2633,7 @@ mod tests {
                         hotkey_overlay_title: None,
                     },
                 ],
+                binds_configured: true,
             },
         }
         "#);
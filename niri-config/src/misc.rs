@@ -1,4 +1,10 @@
-use crate::appearance::{Color, WorkspaceShadow, WorkspaceShadowPart, DEFAULT_BACKDROP_COLOR};
+use std::str::FromStr;
+
+use miette::miette;
+
+use crate::appearance::{
+    BackgroundImage, Color, WorkspaceShadow, WorkspaceShadowPart, DEFAULT_BACKDROP_COLOR,
+};
 use crate::utils::{Flag, MergeWith};
 use crate::FloatOrInt;
 
@@ -101,6 +107,40 @@ impl MergeWith<ConfigNotificationPart> for ConfigNotification {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bell {
+    pub flash: bool,
+    pub urgent: bool,
+    pub spawn: Option<Vec<String>>,
+}
+
+impl Default for Bell {
+    fn default() -> Self {
+        Self {
+            flash: true,
+            urgent: false,
+            spawn: None,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct BellPart {
+    #[knuffel(child)]
+    pub flash: Option<Flag>,
+    #[knuffel(child)]
+    pub urgent: Option<Flag>,
+    #[knuffel(child, unwrap(arguments))]
+    pub spawn: Option<Vec<String>>,
+}
+
+impl MergeWith<BellPart> for Bell {
+    fn merge_with(&mut self, part: &BellPart) {
+        merge!((self, part), flash, urgent);
+        merge_clone_opt!((self, part), spawn);
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Clipboard {
     pub disable_primary: bool,
@@ -118,10 +158,11 @@ impl MergeWith<ClipboardPart> for Clipboard {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Overview {
     pub zoom: f64,
     pub backdrop_color: Color,
+    pub backdrop_image: Option<BackgroundImage>,
     pub workspace_shadow: WorkspaceShadow,
 }
 
@@ -130,18 +171,21 @@ impl Default for Overview {
         Self {
             zoom: 0.5,
             backdrop_color: DEFAULT_BACKDROP_COLOR,
+            backdrop_image: None,
             workspace_shadow: WorkspaceShadow::default(),
         }
     }
 }
 
-#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct OverviewPart {
     #[knuffel(child, unwrap(argument))]
     pub zoom: Option<FloatOrInt<0, 1>>,
     #[knuffel(child)]
     pub backdrop_color: Option<Color>,
     #[knuffel(child)]
+    pub backdrop_image: Option<BackgroundImage>,
+    #[knuffel(child)]
     pub workspace_shadow: Option<WorkspaceShadowPart>,
 }
 
@@ -149,6 +193,7 @@ impl MergeWith<OverviewPart> for Overview {
     fn merge_with(&mut self, part: &OverviewPart) {
         merge!((self, part), zoom, workspace_shadow);
         merge_clone!((self, part), backdrop_color);
+        merge_clone_opt!((self, part), backdrop_image);
     }
 }
 
@@ -198,3 +243,106 @@ impl MergeWith<XwaylandSatellitePart> for XwaylandSatellite {
         merge_clone!((self, part), path);
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NightLight {
+    pub off: bool,
+    /// Color temperature at night, in kelvin.
+    pub temperature: f64,
+    /// Fixed time of day when the night starts.
+    pub from: Option<TimeOfDay>,
+    /// Fixed time of day when the night ends.
+    pub to: Option<TimeOfDay>,
+    /// Coordinates for following the sunset and sunrise.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Duration of the transitions between day and night.
+    pub transition_minutes: u16,
+}
+
+impl Default for NightLight {
+    fn default() -> Self {
+        Self {
+            off: true,
+            temperature: 4000.,
+            from: None,
+            to: None,
+            latitude: None,
+            longitude: None,
+            transition_minutes: 30,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct NightLightPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub temperature: Option<FloatOrInt<1000, 10000>>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub from: Option<TimeOfDay>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub to: Option<TimeOfDay>,
+    #[knuffel(child, unwrap(argument))]
+    pub latitude: Option<FloatOrInt<-90, 90>>,
+    #[knuffel(child, unwrap(argument))]
+    pub longitude: Option<FloatOrInt<-180, 180>>,
+    #[knuffel(child, unwrap(argument))]
+    pub transition_minutes: Option<u16>,
+}
+
+impl MergeWith<NightLightPart> for NightLight {
+    fn merge_with(&mut self, part: &NightLightPart) {
+        // The section turns the night light on unless it says off.
+        self.off = part.off;
+
+        merge!((self, part), temperature);
+        merge_clone_opt!((self, part), from, to);
+        merge_clone!((self, part), transition_minutes);
+
+        if let Some(x) = part.latitude {
+            self.latitude = Some(x.0);
+        }
+        if let Some(x) = part.longitude {
+            self.longitude = Some(x.0);
+        }
+    }
+}
+
+/// Time of day in the local time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl TimeOfDay {
+    /// Returns the number of minutes since midnight.
+    pub fn minutes(self) -> u32 {
+        u32::from(self.hour) * 60 + u32::from(self.minute)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hour, minute) = s
+            .split_once(':')
+            .ok_or_else(|| miette!("time must be in the HH:MM format"))?;
+        let hour: u8 = hour.parse().map_err(|_| miette!("invalid hour: {hour}"))?;
+        let minute: u8 = minute
+            .parse()
+            .map_err(|_| miette!("invalid minute: {minute}"))?;
+
+        if hour >= 24 {
+            return Err(miette!("hour must be less than 24"));
+        }
+        if minute >= 60 {
+            return Err(miette!("minute must be less than 60"));
+        }
+
+        Ok(Self { hour, minute })
+    }
+}
@@ -8,7 +8,7 @@ use knuffel::Decode;
 use niri_ipc::{ConfiguredMode, HSyncPolarity, Transform, VSyncPolarity};
 
 use crate::gestures::HotCorners;
-use crate::{Color, FloatOrInt, LayoutPart};
+use crate::{BackgroundImage, Color, ColorFilterKind, FloatOrInt, LayoutPart};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Outputs(pub Vec<Output>);
@@ -71,9 +71,21 @@ pub struct Output {
     #[knuffel(child)]
     pub background_color: Option<Color>,
     #[knuffel(child)]
+    pub background_image: Option<BackgroundImage>,
+    #[knuffel(child)]
     pub backdrop_color: Option<Color>,
     #[knuffel(child)]
+    pub backdrop_image: Option<BackgroundImage>,
+    #[knuffel(child)]
     pub hot_corners: Option<HotCorners>,
+    #[knuffel(child, unwrap(argument))]
+    pub color_filter: Option<ColorFilterKind>,
+    #[knuffel(child, unwrap(argument))]
+    pub mirror: Option<String>,
+    #[knuffel(child, unwrap(argument))]
+    pub primaries: Option<OutputPrimaries>,
+    #[knuffel(child)]
+    pub hdr: Option<Hdr>,
     #[knuffel(child)]
     pub layout: Option<LayoutPart>,
 }
@@ -105,13 +117,28 @@ impl Default for Output {
             modeline: None,
             variable_refresh_rate: None,
             background_color: None,
+            background_image: None,
             backdrop_color: None,
+            backdrop_image: None,
             hot_corners: None,
+            color_filter: None,
+            mirror: None,
+            primaries: None,
+            hdr: None,
             layout: None,
         }
     }
 }
 
+/// Output config sections that apply while a specific set of outputs is connected.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct OutputProfile {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(children(name = "output"))]
+    pub outputs: Vec<Output>,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputName {
     pub connector: String,
@@ -134,6 +161,42 @@ pub struct Vrr {
     pub on_demand: bool,
 }
 
+/// Color primaries that the output displays.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputPrimaries {
+    Srgb,
+    DisplayP3,
+    Bt2020,
+}
+
+/// HDR signaling for an output.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hdr {
+    /// Maximum luminance of the display in cd/m².
+    #[knuffel(child, unwrap(argument))]
+    pub max_luminance: Option<FloatOrInt<1, 10000>>,
+    /// Minimum luminance of the display in cd/m².
+    #[knuffel(child, unwrap(argument))]
+    pub min_luminance: Option<FloatOrInt<0, 10000>>,
+    /// Luminance of SDR white in cd/m².
+    #[knuffel(child, unwrap(argument))]
+    pub reference_luminance: Option<FloatOrInt<1, 10000>>,
+}
+
+impl Hdr {
+    pub fn max_luminance(&self) -> f64 {
+        self.max_luminance.map_or(1000., |x| x.0)
+    }
+
+    pub fn min_luminance(&self) -> f64 {
+        self.min_luminance.map_or(0.005, |x| x.0)
+    }
+
+    pub fn reference_luminance(&self) -> f64 {
+        self.reference_luminance.map_or(203., |x| x.0)
+    }
+}
+
 impl FromIterator<Output> for Outputs {
     fn from_iter<T: IntoIterator<Item = Output>>(iter: T) -> Self {
         Self(Vec::from_iter(iter))
@@ -150,6 +213,19 @@ impl Outputs {
     }
 }
 
+impl OutputProfile {
+    /// Returns whether the connected outputs are exactly the outputs of this profile.
+    pub fn matches(&self, connected: &[OutputName]) -> bool {
+        connected
+            .iter()
+            .all(|name| self.outputs.iter().any(|o| name.matches(&o.name)))
+            && self
+                .outputs
+                .iter()
+                .all(|o| connected.iter().any(|name| name.matches(&o.name)))
+    }
+}
+
 impl OutputName {
     pub fn from_ipc_output(output: &niri_ipc::Output) -> Self {
         Self {
@@ -600,6 +676,39 @@ mod tests {
         assert!(!check("unknown unknown unknown", "DP-2", None, None, None));
     }
 
+    #[test]
+    fn test_output_profile_match() {
+        let profile = OutputProfile {
+            name: String::from("desk"),
+            outputs: vec![
+                Output {
+                    name: String::from("eDP-1"),
+                    ..Default::default()
+                },
+                Output {
+                    name: String::from("Some Company Some Monitor 1234"),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let laptop = make_output_name("eDP-1", None, None, None);
+        let monitor = make_output_name(
+            "DP-2",
+            Some("Some Company"),
+            Some("Some Monitor"),
+            Some("1234"),
+        );
+        let other = make_output_name("DP-3", Some("Other"), Some("Monitor"), None);
+
+        assert!(profile.matches(&[laptop.clone(), monitor.clone()]));
+        assert!(profile.matches(&[monitor.clone(), laptop.clone()]));
+        assert!(!profile.matches(&[laptop.clone()]));
+        assert!(!profile.matches(&[laptop.clone(), monitor.clone(), other.clone()]));
+        assert!(!profile.matches(&[laptop, other]));
+        assert!(!profile.matches(&[]));
+    }
+
     #[test]
     fn test_output_name_sorting() {
         let mut names = vec![
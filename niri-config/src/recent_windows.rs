@@ -14,6 +14,8 @@ pub struct RecentWindows {
     pub highlight: MruHighlight,
     pub previews: MruPreviews,
     pub binds: Vec<Bind>,
+    /// Whether the config has a `binds` section, which replaces the default binds.
+    pub binds_configured: bool,
 }
 
 impl Default for RecentWindows {
@@ -25,6 +27,7 @@ impl Default for RecentWindows {
             highlight: MruHighlight::default(),
             previews: MruPreviews::default(),
             binds: default_binds(),
+            binds_configured: false,
         }
     }
 }
@@ -1,6 +1,6 @@
 use knuffel::errors::DecodeError;
 
-use crate::LayoutPart;
+use crate::{BackgroundImage, LayoutPart};
 
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct Workspace {
@@ -9,6 +9,8 @@ pub struct Workspace {
     #[knuffel(child, unwrap(argument))]
     pub open_on_output: Option<String>,
     #[knuffel(child)]
+    pub background_image: Option<BackgroundImage>,
+    #[knuffel(child)]
     pub layout: Option<WorkspaceLayoutPart>,
 }
 
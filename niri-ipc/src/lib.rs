@@ -74,6 +74,9 @@ pub enum Request {
     /// Request information about open windows.
     Windows,
     /// Request information about layer-shell surfaces.
+    ///
+    /// This lists all surfaces, including ones that haven't mapped yet, whereas the event stream
+    /// only tracks mapped surfaces.
     Layers,
     /// Request information about the configured keyboard layouts.
     KeyboardLayouts,
@@ -113,12 +116,75 @@ pub enum Request {
     /// been removed. This can happen if the corresponding [`Event::WorkspacesChanged`] arrives
     /// before the corresponding [`Event::WindowOpenedOrChanged`].
     EventStream,
+    /// Start continuously receiving a subset of events from the compositor.
+    ///
+    /// This works like [`Request::EventStream`], but niri will only send events matching the
+    /// filter, both in the initial state and afterwards.
+    FilteredEventStream(EventStreamFilter),
     /// Respond with an error (for testing error handling).
     ReturnError,
     /// Request information about the overview.
     OverviewState,
     /// Request information about screencasts.
     Casts,
+    /// Request information about the configured output profiles.
+    OutputProfiles,
+    /// Force an output profile regardless of the connected outputs.
+    ///
+    /// The profile stays forced until this request is sent again with `profile: None`, which goes
+    /// back to picking the profile matching the connected outputs.
+    ForceOutputProfile {
+        /// Name of the profile to force, or `None` to pick it automatically.
+        profile: Option<String>,
+    },
+    /// Request the full layout of all workspaces.
+    LayoutTree,
+    /// Register a named custom action to show in the hotkey overlay.
+    ///
+    /// Binds with a `send-ipc-event` action of this name will show up in the hotkey overlay with
+    /// the description. Registering the same name again replaces the description. The registration
+    /// persists until unregistered, even after the client disconnects, so clients can list the
+    /// registered actions with [`Request::CustomActions`] to clean up stale ones.
+    RegisterCustomAction {
+        /// Name of the custom action, as in the `send-ipc-event` bind action.
+        name: String,
+        /// Description to show in the hotkey overlay.
+        description: String,
+    },
+    /// Unregister a named custom action.
+    UnregisterCustomAction {
+        /// Name of the custom action.
+        name: String,
+    },
+    /// Request the custom actions registered over IPC.
+    CustomActions,
+    /// Apply a KDL config fragment on top of the loaded config.
+    ///
+    /// The fragment is merged into the config the same way as an included file. Overlays are
+    /// transient: they stay applied across config file reloads until removed, or until niri
+    /// restarts.
+    ///
+    /// The compositor replies with the id of the new overlay.
+    ApplyConfigOverlay {
+        /// KDL config fragment.
+        kdl: String,
+    },
+    /// Request the currently applied config overlays.
+    ConfigOverlays,
+    /// Remove a config overlay.
+    RemoveConfigOverlay {
+        /// Id of the overlay.
+        id: u64,
+    },
+    /// Remove all config overlays, going back to the config file alone.
+    ResetConfigOverlays,
+    /// Request how the window rules match a window.
+    WindowRules {
+        /// Id of the window.
+        ///
+        /// If `None`, uses the focused window.
+        id: Option<u64>,
+    },
 }
 
 /// Reply from niri to client.
@@ -165,6 +231,18 @@ pub enum Response {
     OverviewState(Overview),
     /// Information about screencasts.
     Casts(Vec<Cast>),
+    /// Information about the configured output profiles.
+    OutputProfiles(Vec<OutputProfile>),
+    /// Layout of all workspaces.
+    LayoutTree(Vec<WorkspaceLayout>),
+    /// Custom actions registered over IPC, sorted by name.
+    CustomActions(Vec<CustomAction>),
+    /// Id of the newly applied config overlay.
+    ConfigOverlayApplied(u64),
+    /// Currently applied config overlays, in the order they are applied.
+    ConfigOverlays(Vec<ConfigOverlay>),
+    /// How the window rules match a window.
+    WindowRules(WindowRulesExplanation),
 }
 
 /// Overview information.
@@ -202,6 +280,94 @@ pub enum Action {
     PowerOffMonitors {},
     /// Power on all monitors via DPMS.
     PowerOnMonitors {},
+    /// Power off a specific monitor via DPMS.
+    ///
+    /// Monitors powered off this way stay off on input, unlike with power-off-monitors.
+    PowerOffMonitor {
+        /// Name of the output to power off.
+        #[cfg_attr(feature = "clap", arg())]
+        output: String,
+    },
+    /// Power on a specific monitor via DPMS.
+    ///
+    /// While all monitors are powered off, this wakes up only this monitor.
+    PowerOnMonitor {
+        /// Name of the output to power on.
+        #[cfg_attr(feature = "clap", arg())]
+        output: String,
+    },
+    /// Increase the backlight brightness of a monitor.
+    BrightnessUp {
+        /// Percentage points to increase the brightness by.
+        #[cfg_attr(feature = "clap", arg(long, default_value_t = 5))]
+        step: u8,
+
+        /// Name of the output to change.
+        ///
+        /// If `None`, uses the focused output, or the first output with a backlight if the
+        /// focused one has none.
+        #[cfg_attr(feature = "clap", arg(long))]
+        output: Option<String>,
+    },
+    /// Decrease the backlight brightness of a monitor.
+    ///
+    /// Stops at 1% rather than turning the backlight off.
+    BrightnessDown {
+        /// Percentage points to decrease the brightness by.
+        #[cfg_attr(feature = "clap", arg(long, default_value_t = 5))]
+        step: u8,
+
+        /// Name of the output to change.
+        ///
+        /// If `None`, uses the focused output, or the first output with a backlight if the
+        /// focused one has none.
+        #[cfg_attr(feature = "clap", arg(long))]
+        output: Option<String>,
+    },
+    /// Set the backlight brightness of a monitor.
+    SetBrightness {
+        /// Brightness in percent, from 0 to 100.
+        #[cfg_attr(feature = "clap", arg())]
+        brightness: u8,
+
+        /// Name of the output to change.
+        ///
+        /// If `None`, uses the focused output, or the first output with a backlight if the
+        /// focused one has none.
+        #[cfg_attr(feature = "clap", arg(long))]
+        output: Option<String>,
+    },
+    /// Create a virtual output.
+    ///
+    /// Virtual outputs behave like real monitors, but show nothing on their own. Screencasts and
+    /// screencopy clients can capture them. Only supported on a TTY.
+    CreateVirtualOutput {
+        /// Width in physical pixels.
+        #[cfg_attr(feature = "clap", arg())]
+        width: u16,
+
+        /// Height in physical pixels.
+        #[cfg_attr(feature = "clap", arg())]
+        height: u16,
+
+        /// Refresh rate in Hz.
+        ///
+        /// If `None`, uses 60 Hz.
+        #[cfg_attr(feature = "clap", arg(long))]
+        refresh: Option<f64>,
+
+        /// Scale of the output.
+        ///
+        /// Overrides the scale from the output config. If `None`, uses the configured scale, or 1.
+        #[cfg_attr(feature = "clap", arg(long))]
+        scale: Option<f64>,
+    },
+    /// Destroy a virtual output.
+    DestroyVirtualOutput {
+        /// Name of the virtual output to destroy.
+        #[cfg_attr(feature = "clap", arg())]
+        output: String,
+    },
     /// Spawn a command.
     Spawn {
         /// Command to spawn.
@@ -214,18 +380,44 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(last = true, required = true))]
         command: String,
     },
+    /// Send a custom action event to event stream clients.
+    SendIpcEvent {
+        /// Name of the custom action.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+        /// Arguments of the custom action.
+        #[cfg_attr(
+            feature = "clap",
+            arg(long = "arg", value_name = "KEY=VALUE", value_parser = parse_custom_action_arg)
+        )]
+        args: Vec<(String, String)>,
+    },
     /// Do a screen transition.
     DoScreenTransition {
         /// Delay in milliseconds for the screen to freeze before starting the transition.
         #[cfg_attr(feature = "clap", arg(short, long))]
         delay_ms: Option<u16>,
     },
+    /// Toggle the color filters on all outputs.
+    ToggleColorFilter {},
+    /// Toggle the night light.
+    ToggleNightLight {},
+    /// Magnify the output under the pointer more.
+    ZoomIn {},
+    /// Magnify the output under the pointer less.
+    ZoomOut {},
+    /// Turn off the magnifier on the output under the pointer.
+    ZoomReset {},
     /// Open the screenshot UI.
     Screenshot {
         ///  Whether to show the mouse pointer by default in the screenshot UI.
         #[cfg_attr(feature = "clap", arg(short = 'p', long, action = clap::ArgAction::Set, default_value_t = true))]
         show_pointer: bool,
 
+        /// Whether to apply the output color filter to the screenshot.
+        #[cfg_attr(feature = "clap", arg(long, action = clap::ArgAction::Set, default_value_t = false))]
+        color_filter: bool,
+
         /// Path to save the screenshot to.
         ///
         /// The path must be absolute, otherwise an error is returned.
@@ -246,6 +438,10 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(short = 'p', long, action = clap::ArgAction::Set, default_value_t = true))]
         show_pointer: bool,
 
+        /// Whether to apply the output color filter to the screenshot.
+        #[cfg_attr(feature = "clap", arg(long, action = clap::ArgAction::Set, default_value_t = false))]
+        color_filter: bool,
+
         /// Path to save the screenshot to.
         ///
         /// The path must be absolute, otherwise an error is returned.
@@ -1097,6 +1293,22 @@ pub enum OutputAction {
         #[cfg_attr(feature = "clap", command(flatten))]
         vrr: VrrToSet,
     },
+    /// Mirror another output.
+    Mirror {
+        /// Name of the output to mirror, or "off" to show this output's own contents.
+        #[cfg_attr(feature = "clap", arg())]
+        mirror: MirrorToSet,
+    },
+}
+
+/// Output to mirror.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum MirrorToSet {
+    /// Show the output's own contents.
+    Off,
+    /// Mirror the output with this name.
+    Output(String),
 }
 
 /// Output mode to set.
@@ -1199,7 +1411,7 @@ pub struct VrrToSet {
 }
 
 /// Connected output.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Output {
     /// Name of the output.
@@ -1226,8 +1438,17 @@ pub struct Output {
     pub vrr_enabled: bool,
     /// Logical output information.
     ///
-    /// `None` if the output is not mapped to any logical output (for example, if it is disabled).
+    /// `None` if the output is not mapped to any logical output (for example, if it is disabled
+    /// or mirroring another output).
     pub logical: Option<LogicalOutput>,
+    /// Name of the output that this output mirrors.
+    ///
+    /// `None` if the output shows its own contents.
+    pub mirror: Option<String>,
+    /// Backlight brightness from 0 to 1.
+    ///
+    /// `None` if the output has no backlight that niri can control.
+    pub brightness: Option<f64>,
 }
 
 /// Output mode.
@@ -1302,11 +1523,18 @@ pub struct Window {
     /// Do not assume that window ids will always increase without wrapping, or start at 1. That is
     /// an implementation detail subject to change. For example, ids may change to be randomly
     /// generated for each new window.
+    ///
+    /// The ext-foreign-toplevel-list identifier of this window is this id formatted as a decimal
+    /// number.
     pub id: u64,
     /// Title, if set.
     pub title: Option<String>,
     /// Application ID, if set.
     pub app_id: Option<String>,
+    /// Tag set by the application through xdg-toplevel-tag, if any.
+    ///
+    /// Tags identify specific windows of an application, and remain constant across restarts.
+    pub tag: Option<String>,
     /// Process ID that created the Wayland connection for this window, if known.
     ///
     /// Currently, windows created by xdg-desktop-portal-gnome will have a `None` PID, but this may
@@ -1392,6 +1620,100 @@ pub struct WindowLayout {
     pub window_offset_in_tile: (f64, f64),
 }
 
+/// Layout of a workspace, with its columns as first-class objects.
+///
+/// Positions and sizes of individual windows are in their [`WindowLayout`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WorkspaceLayout {
+    /// Id of the workspace.
+    pub workspace_id: u64,
+    /// Name of the output that the workspace is on.
+    ///
+    /// Can be `None` if no outputs are currently connected.
+    pub output: Option<String>,
+    /// Columns of the scrolling layout, from left to right.
+    pub columns: Vec<ColumnLayout>,
+    /// Index of the active column.
+    ///
+    /// The index is 1-based, consistent with [`Action::FocusColumn`]. `None` if there are no
+    /// columns.
+    pub active_column_idx: Option<usize>,
+    /// Offset of the view from the active column, in logical pixels.
+    ///
+    /// If the view is animating, this is the offset where the animation ends.
+    pub view_offset: f64,
+    /// Position of the left edge of the view in the scrolling layout, in logical pixels.
+    ///
+    /// If the view is animating, this is the position where the animation ends.
+    pub view_pos: f64,
+    /// Ids of the windows in the floating layout, from top to bottom.
+    pub floating_window_ids: Vec<u64>,
+    /// Whether the floating layout is active on this workspace.
+    pub is_floating_active: bool,
+}
+
+/// Column of the scrolling layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ColumnLayout {
+    /// Tiles in this column, from top to bottom.
+    pub tiles: Vec<TileLayout>,
+    /// Index of the active tile.
+    ///
+    /// The index is 1-based, consistent with [`Action::FocusWindowInColumn`].
+    pub active_tile_idx: usize,
+    /// Desired width of this column.
+    ///
+    /// Full-width and fullscreen columns go back to this width when they stop being so.
+    pub width: ColumnWidth,
+    /// Whether this column is full-width.
+    pub is_full_width: bool,
+    /// How this column displays its windows.
+    pub display: ColumnDisplay,
+    /// Position of the left edge of this column in the scrolling layout, in logical pixels.
+    pub x: f64,
+    /// Actual width of this column, in logical pixels.
+    pub actual_width: f64,
+    /// Whether this column is at least partially within the view.
+    pub is_visible: bool,
+}
+
+/// Tile in a column of the scrolling layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct TileLayout {
+    /// Id of the window in this tile.
+    pub window_id: u64,
+    /// Requested height of the window.
+    pub height: WindowHeight,
+}
+
+/// Desired width of a column.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ColumnWidth {
+    /// Proportion of the view width.
+    Proportion(f64),
+    /// Fixed width in logical pixels.
+    Fixed(f64),
+}
+
+/// Requested height of a window in a column.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum WindowHeight {
+    /// Tile height computed automatically, distributed across the column according to weights.
+    Auto {
+        /// Weight of this tile.
+        weight: f64,
+    },
+    /// Fixed window height in logical pixels.
+    Fixed(f64),
+    /// One of the preset heights, by its 0-based index in the config.
+    Preset(usize),
+}
+
 /// Output configuration change result.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -1483,6 +1805,11 @@ pub enum LayerSurfaceKeyboardInteractivity {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct LayerSurface {
+    /// Unique id of this layer surface.
+    ///
+    /// This id remains constant for the lifetime of this layer surface, including when it unmaps
+    /// and maps again.
+    pub id: u64,
     /// Namespace provided by the layer-shell client.
     pub namespace: String,
     /// Name of the output the surface is on.
@@ -1493,6 +1820,205 @@ pub struct LayerSurface {
     pub keyboard_interactivity: LayerSurfaceKeyboardInteractivity,
 }
 
+/// An output profile from the config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct OutputProfile {
+    /// Name of the profile.
+    pub name: String,
+    /// Whether the profile is currently active.
+    pub is_active: bool,
+    /// Whether the profile was forced, rather than picked by the connected outputs.
+    pub is_forced: bool,
+}
+
+/// A custom action registered via IPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct CustomAction {
+    /// Name of the custom action, as in the `send-ipc-event` bind action.
+    pub name: String,
+    /// Description shown in the hotkey overlay.
+    pub description: String,
+}
+
+/// A config overlay applied via IPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ConfigOverlay {
+    /// Unique id of this overlay.
+    pub id: u64,
+    /// KDL config fragment of this overlay.
+    pub kdl: String,
+    /// Error from applying this overlay to the config, if it failed.
+    ///
+    /// An overlay can start failing after the config file reloads, for example if it refers to
+    /// something that the config file no longer has. Failing overlays are skipped.
+    pub error: Option<String>,
+}
+
+/// How the window rules match a window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowRulesExplanation {
+    /// Id of the window.
+    pub window_id: u64,
+    /// Rules evaluated for the current state of the window and of niri.
+    pub current: WindowRulesEvaluation,
+    /// Rules as they were evaluated when the window opened, at its initial configure.
+    ///
+    /// This is what decided the window opening properties. The matchers are taken from the
+    /// current config. `None` if the window mapped without an initial configure, or if window
+    /// rules were added to or removed from the config since the window opened.
+    pub at_open: Option<WindowRulesEvaluation>,
+}
+
+/// Result of evaluating all window rules for a window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowRulesEvaluation {
+    /// Every window rule from the config, in order.
+    pub rules: Vec<WindowRuleMatch>,
+    /// Final rules resolved for the window.
+    pub resolved: ResolvedWindowRules,
+}
+
+/// How a window rule from the config matched a window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowRuleMatch {
+    /// `match` matchers of the rule, formatted like in the config.
+    pub matches: Vec<String>,
+    /// `exclude` matchers of the rule, formatted like in the config.
+    pub excludes: Vec<String>,
+    /// Whether the rule applied to the window.
+    ///
+    /// A rule applies if it has no `match` or one of them matched, and none of its `exclude`
+    /// matched.
+    pub applied: bool,
+    /// Index into `matches` of the first one that matched the window.
+    pub matched_by: Option<usize>,
+    /// Index into `excludes` of the first one that matched the window.
+    ///
+    /// Excludes are only checked for rules that matched.
+    pub excluded_by: Option<usize>,
+}
+
+/// Window rule properties resolved for a window.
+///
+/// Properties that no applied rule sets are `None`. Overrides of the focus ring, border, shadow,
+/// tab indicator, inactive dimming and background effect are not included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ResolvedWindowRules {
+    /// Default width of the column opened for the window.
+    pub default_column_width: Option<DefaultWindowSize>,
+    /// Default height of the window.
+    pub default_window_height: Option<DefaultWindowSize>,
+    /// Default display of the column opened for the window.
+    pub default_column_display: Option<ColumnDisplay>,
+    /// Default position of the window in the floating layout.
+    pub default_floating_position: Option<FloatingPosition>,
+    /// Name of the output to open the window on.
+    pub open_on_output: Option<String>,
+    /// Name of the workspace to open the window on.
+    pub open_on_workspace: Option<String>,
+    /// Whether the window opens full-width.
+    pub open_maximized: Option<bool>,
+    /// Whether the window opens maximized to edges.
+    pub open_maximized_to_edges: Option<bool>,
+    /// Whether the window opens fullscreen.
+    pub open_fullscreen: Option<bool>,
+    /// Whether the window opens floating.
+    pub open_floating: Option<bool>,
+    /// Whether the window opens focused.
+    pub open_focused: Option<bool>,
+    /// Extra bound on the minimum window width, in logical pixels.
+    pub min_width: Option<u16>,
+    /// Extra bound on the minimum window height, in logical pixels.
+    pub min_height: Option<u16>,
+    /// Extra bound on the maximum window width, in logical pixels.
+    pub max_width: Option<u16>,
+    /// Extra bound on the maximum window height, in logical pixels.
+    pub max_height: Option<u16>,
+    /// Whether to draw the border with a solid background.
+    pub draw_border_with_background: Option<bool>,
+    /// Extra opacity to draw the window with.
+    pub opacity: Option<f32>,
+    /// Corner radius to assume the window has.
+    ///
+    /// Top-left, top-right, bottom-right, bottom-left, in logical pixels.
+    pub geometry_corner_radius: Option<[f32; 4]>,
+    /// Whether to clip the window to its geometry.
+    pub clip_to_geometry: Option<bool>,
+    /// Whether to bob the window up and down.
+    pub baba_is_float: Option<bool>,
+    /// Render targets to block out the window from.
+    pub block_out_from: Option<BlockOutFrom>,
+    /// Whether to enable on-demand VRR for the window.
+    pub variable_refresh_rate: Option<bool>,
+    /// Multiplier for scroll events sent to the window.
+    pub scroll_factor: Option<f64>,
+    /// Whether to set the Tiled xdg-toplevel state on the window.
+    pub tiled_state: Option<bool>,
+}
+
+/// Default window size set by a window rule.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum DefaultWindowSize {
+    /// The window picks its own size.
+    WindowChooses,
+    /// Proportion of the working area.
+    Proportion(f64),
+    /// Fixed size in logical pixels.
+    Fixed(i32),
+}
+
+/// Default position of a floating window.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct FloatingPosition {
+    /// Horizontal offset in logical pixels.
+    pub x: f64,
+    /// Vertical offset in logical pixels.
+    pub y: f64,
+    /// Corner or edge of the working area that the offset is relative to.
+    pub relative_to: FloatingPositionRelativeTo,
+}
+
+/// Corner or edge of the working area that a floating position is relative to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum FloatingPositionRelativeTo {
+    /// Top-left corner.
+    TopLeft,
+    /// Top-right corner.
+    TopRight,
+    /// Bottom-left corner.
+    BottomLeft,
+    /// Bottom-right corner.
+    BottomRight,
+    /// Top edge, horizontally centered.
+    Top,
+    /// Bottom edge, horizontally centered.
+    Bottom,
+    /// Left edge, vertically centered.
+    Left,
+    /// Right edge, vertically centered.
+    Right,
+}
+
+/// Render targets that a window is blocked out from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum BlockOutFrom {
+    /// Screencasts.
+    Screencast,
+    /// Screencasts and screenshots.
+    ScreenCapture,
+}
+
 /// A screencast.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -1649,6 +2175,14 @@ pub enum Event {
         /// The new urgency state of the window.
         urgent: bool,
     },
+    /// The system bell was rung.
+    ///
+    /// Terminals and other applications ring the bell through the xdg-system-bell protocol. The
+    /// bell is not always associated with a window, in which case `id` is `None`.
+    WindowBell {
+        /// Id of the window that rang the bell, if any.
+        id: Option<u64>,
+    },
     /// The layout of one or more windows has changed.
     WindowLayoutsChanged {
         /// Pairs consisting of a window id and new layout information for the window.
@@ -1705,6 +2239,165 @@ pub enum Event {
         /// Stream ID of the stopped screencast.
         stream_id: u64,
     },
+    /// A different output profile became active.
+    ///
+    /// This happens when the connected outputs change, when a profile is forced, or when the
+    /// profiles change in the config.
+    OutputProfileActivated {
+        /// Name of the newly active profile, or `None` if no profile is active.
+        profile: Option<String>,
+    },
+    /// The layout of the workspaces has changed.
+    ///
+    /// This carries the full layout, like the response to [`Request::LayoutTree`].
+    ///
+    /// During animations, gestures and interactive resizes, this is sent once they finish rather
+    /// than on every frame.
+    LayoutTreeChanged {
+        /// The new layout of all workspaces.
+        workspaces: Vec<WorkspaceLayout>,
+    },
+    /// The connected outputs have changed.
+    ///
+    /// This is sent when an output is connected or disconnected.
+    OutputsChanged {
+        /// The new output information.
+        ///
+        /// This configuration completely replaces the previous configuration. I.e. if any outputs
+        /// are missing from here, then they were disconnected.
+        outputs: Vec<Output>,
+    },
+    /// An output's configuration or state changed.
+    OutputChanged {
+        /// The new output information.
+        output: Output,
+    },
+    /// The layer-shell surfaces have changed.
+    ///
+    /// This is only sent at the start of the event stream to give the initial state.
+    LayerSurfacesChanged {
+        /// The new layer surface information.
+        ///
+        /// This configuration completely replaces the previous configuration. I.e. if any layer
+        /// surfaces are missing from here, then they were closed.
+        layers: Vec<LayerSurface>,
+    },
+    /// A layer-shell surface was mapped, or an existing one changed its layer or keyboard
+    /// interactivity.
+    LayerSurfaceOpened {
+        /// The new layer surface information.
+        layer: LayerSurface,
+    },
+    /// A layer-shell surface was unmapped or destroyed.
+    LayerSurfaceClosed {
+        /// Id of the removed layer surface.
+        id: u64,
+    },
+    /// A `send-ipc-event` action was triggered.
+    CustomAction {
+        /// Name of the custom action.
+        name: String,
+        /// Arguments of the custom action.
+        args: HashMap<String, String>,
+    },
+}
+
+/// Filter for a [`Request::FilteredEventStream`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct EventStreamFilter {
+    /// Categories of events to receive.
+    ///
+    /// If empty, events of all categories are sent.
+    ///
+    /// Note that the state parts are independent. For example, if you subscribe to
+    /// [`EventCategory::Windows`] without [`EventCategory::WindowLayouts`], the layouts of your
+    /// windows will only update when they are sent as part of other window events.
+    #[serde(default)]
+    pub categories: Vec<EventCategory>,
+    /// Interval in milliseconds over which to coalesce layout events.
+    ///
+    /// Layout events ([`Event::WindowLayoutsChanged`] and [`Event::LayoutTreeChanged`]) can be
+    /// sent every frame during animations and resizes. When this is set, niri will hold them back
+    /// for up to this long and only send the latest layouts. Other events are never delayed, and
+    /// any held back layout events are sent right before them to keep the order consistent.
+    #[serde(default)]
+    pub coalesce_layout_ms: Option<u32>,
+}
+
+/// Category of an event stream [`Event`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum EventCategory {
+    /// Workspace events.
+    Workspaces,
+    /// Window events, except for layout changes.
+    Windows,
+    /// Window layout changes ([`Event::WindowLayoutsChanged`]).
+    WindowLayouts,
+    /// Keyboard layout events.
+    KeyboardLayouts,
+    /// Overview events.
+    Overview,
+    /// Config loading events.
+    Config,
+    /// Screenshot events.
+    Screenshots,
+    /// Screencast events.
+    Casts,
+    /// Output and output profile events.
+    Outputs,
+    /// Layer-shell surface events.
+    Layers,
+    /// Layout tree changes ([`Event::LayoutTreeChanged`]).
+    LayoutTree,
+    /// Custom actions triggered from binds ([`Event::CustomAction`]).
+    CustomActions,
+}
+
+impl Event {
+    /// Returns the category of this event.
+    pub fn category(&self) -> EventCategory {
+        match self {
+            Event::WorkspacesChanged { .. }
+            | Event::WorkspaceUrgencyChanged { .. }
+            | Event::WorkspaceActivated { .. }
+            | Event::WorkspaceActiveWindowChanged { .. } => EventCategory::Workspaces,
+            Event::WindowsChanged { .. }
+            | Event::WindowOpenedOrChanged { .. }
+            | Event::WindowClosed { .. }
+            | Event::WindowFocusChanged { .. }
+            | Event::WindowFocusTimestampChanged { .. }
+            | Event::WindowUrgencyChanged { .. }
+            | Event::WindowBell { .. } => EventCategory::Windows,
+            Event::WindowLayoutsChanged { .. } => EventCategory::WindowLayouts,
+            Event::KeyboardLayoutsChanged { .. } | Event::KeyboardLayoutSwitched { .. } => {
+                EventCategory::KeyboardLayouts
+            }
+            Event::OverviewOpenedOrClosed { .. } => EventCategory::Overview,
+            Event::ConfigLoaded { .. } => EventCategory::Config,
+            Event::ScreenshotCaptured { .. } => EventCategory::Screenshots,
+            Event::CastsChanged { .. }
+            | Event::CastStartedOrChanged { .. }
+            | Event::CastStopped { .. } => EventCategory::Casts,
+            Event::OutputProfileActivated { .. }
+            | Event::OutputsChanged { .. }
+            | Event::OutputChanged { .. } => EventCategory::Outputs,
+            Event::LayerSurfacesChanged { .. }
+            | Event::LayerSurfaceOpened { .. }
+            | Event::LayerSurfaceClosed { .. } => EventCategory::Layers,
+            Event::LayoutTreeChanged { .. } => EventCategory::LayoutTree,
+            Event::CustomAction { .. } => EventCategory::CustomActions,
+        }
+    }
+}
+
+impl EventStreamFilter {
+    /// Returns whether the event passes this filter.
+    pub fn matches(&self, event: &Event) -> bool {
+        self.categories.is_empty() || self.categories.contains(&event.category())
+    }
 }
 
 impl From<Duration> for Timestamp {
@@ -1722,6 +2415,14 @@ impl From<Timestamp> for Duration {
     }
 }
 
+#[cfg(feature = "clap")]
+fn parse_custom_action_arg(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("missing `=` in `{s}`"))?;
+    Ok((key.to_owned(), value.to_owned()))
+}
+
 impl FromStr for WorkspaceReferenceArg {
     type Err = &'static str;
 
@@ -1946,6 +2647,22 @@ impl FromStr for ScaleToSet {
     }
 }
 
+impl FromStr for MirrorToSet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("off") {
+            return Ok(Self::Off);
+        }
+
+        if s.is_empty() {
+            return Err("output name must not be empty");
+        }
+
+        Ok(Self::Output(s.to_owned()))
+    }
+}
+
 macro_rules! ensure {
     ($cond:expr, $fmt:literal $($arg:tt)* ) => {
         if !$cond {
@@ -2092,4 +2809,22 @@ mod tests {
         assert!("-".parse::<PositionChange>().is_err());
         assert!("10% ".parse::<PositionChange>().is_err());
     }
+
+    #[test]
+    fn event_stream_filter_categories() {
+        let filter = EventStreamFilter {
+            categories: vec![EventCategory::Windows, EventCategory::LayoutTree],
+            coalesce_layout_ms: None,
+        };
+        assert!(filter.matches(&Event::WindowClosed { id: 1 }));
+        assert!(filter.matches(&Event::WindowBell { id: None }));
+        assert!(filter.matches(&Event::LayoutTreeChanged { workspaces: vec![] }));
+        assert!(!filter.matches(&Event::OverviewOpenedOrClosed { is_open: true }));
+        assert!(!filter.matches(&Event::WindowLayoutsChanged { changes: vec![] }));
+
+        // No categories means no filtering.
+        let filter = EventStreamFilter::default();
+        assert!(filter.matches(&Event::OverviewOpenedOrClosed { is_open: true }));
+        assert!(filter.matches(&Event::WindowLayoutsChanged { changes: vec![] }));
+    }
 }
@@ -9,7 +9,9 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
-use crate::{Cast, Event, KeyboardLayouts, Window, Workspace};
+use crate::{
+    Cast, Event, KeyboardLayouts, LayerSurface, Output, Window, Workspace, WorkspaceLayout,
+};
 
 /// Part of the state communicated via the event stream.
 pub trait EventStreamStatePart {
@@ -49,6 +51,18 @@ pub struct EventStreamState {
 
     /// State of screencasts.
     pub casts: CastsState,
+
+    /// State of the layout tree.
+    pub layout_tree: LayoutTreeState,
+
+    /// State of the outputs.
+    pub outputs: OutputsState,
+
+    /// State of the output profiles.
+    pub output_profile: OutputProfileState,
+
+    /// State of the layer-shell surfaces.
+    pub layers: LayerSurfacesState,
 }
 
 /// The workspaces state communicated over the event stream.
@@ -93,6 +107,34 @@ pub struct CastsState {
     pub casts: HashMap<u64, Cast>,
 }
 
+/// The layout tree state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct LayoutTreeState {
+    /// Layout of all workspaces, grouped by output in the order of the workspaces.
+    pub workspaces: Vec<WorkspaceLayout>,
+}
+
+/// The outputs state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct OutputsState {
+    /// Map from an output name to the output.
+    pub outputs: HashMap<String, Output>,
+}
+
+/// The output profile state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct OutputProfileState {
+    /// Name of the active output profile, if any.
+    pub profile: Option<String>,
+}
+
+/// The layer-shell surfaces state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct LayerSurfacesState {
+    /// Map from a layer surface id to the layer surface.
+    pub layers: HashMap<u64, LayerSurface>,
+}
+
 impl EventStreamStatePart for EventStreamState {
     fn replicate(&self) -> Vec<Event> {
         let mut events = Vec::new();
@@ -102,6 +144,10 @@ impl EventStreamStatePart for EventStreamState {
         events.extend(self.overview.replicate());
         events.extend(self.config.replicate());
         events.extend(self.casts.replicate());
+        events.extend(self.layout_tree.replicate());
+        events.extend(self.outputs.replicate());
+        events.extend(self.output_profile.replicate());
+        events.extend(self.layers.replicate());
         events
     }
 
@@ -112,6 +158,10 @@ impl EventStreamStatePart for EventStreamState {
         let event = self.overview.apply(event)?;
         let event = self.config.apply(event)?;
         let event = self.casts.apply(event)?;
+        let event = self.layout_tree.apply(event)?;
+        let event = self.outputs.apply(event)?;
+        let event = self.output_profile.apply(event)?;
+        let event = self.layers.apply(event)?;
         Some(event)
     }
 }
@@ -321,3 +371,83 @@ impl EventStreamStatePart for CastsState {
         None
     }
 }
+
+impl EventStreamStatePart for LayoutTreeState {
+    fn replicate(&self) -> Vec<Event> {
+        let workspaces = self.workspaces.clone();
+        vec![Event::LayoutTreeChanged { workspaces }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::LayoutTreeChanged { workspaces } => {
+                self.workspaces = workspaces;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
+
+impl EventStreamStatePart for OutputsState {
+    fn replicate(&self) -> Vec<Event> {
+        let outputs = self.outputs.values().cloned().collect();
+        vec![Event::OutputsChanged { outputs }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::OutputsChanged { outputs } => {
+                self.outputs = outputs.into_iter().map(|o| (o.name.clone(), o)).collect();
+            }
+            Event::OutputChanged { output } => {
+                let ipc_output = self.outputs.get_mut(&output.name);
+                let ipc_output = ipc_output.expect("changed output was missing from the map");
+                *ipc_output = output;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
+
+impl EventStreamStatePart for OutputProfileState {
+    fn replicate(&self) -> Vec<Event> {
+        let profile = self.profile.clone();
+        vec![Event::OutputProfileActivated { profile }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::OutputProfileActivated { profile } => {
+                self.profile = profile;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
+
+impl EventStreamStatePart for LayerSurfacesState {
+    fn replicate(&self) -> Vec<Event> {
+        let layers = self.layers.values().cloned().collect();
+        vec![Event::LayerSurfacesChanged { layers }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::LayerSurfacesChanged { layers } => {
+                self.layers = layers.into_iter().map(|l| (l.id, l)).collect();
+            }
+            Event::LayerSurfaceOpened { layer } => {
+                self.layers.insert(layer.id, layer);
+            }
+            Event::LayerSurfaceClosed { id } => {
+                let layer = self.layers.remove(&id);
+                layer.expect("closed layer surface was missing from the map");
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
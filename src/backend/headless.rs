@@ -109,12 +109,31 @@ impl Headless {
                 vrr_supported: false,
                 vrr_enabled: false,
                 logical: Some(logical_output(&output)),
+                mirror: None,
+                brightness: None,
             },
         );
 
         niri.add_output(output, None, false);
     }
 
+    pub fn remove_output(&mut self, niri: &mut Niri, n: u8) {
+        let connector = format!("headless-{n}");
+        let output = niri
+            .global_space
+            .outputs()
+            .find(|output| output.name() == connector)
+            .unwrap()
+            .clone();
+
+        self.ipc_outputs
+            .lock()
+            .unwrap()
+            .retain(|_, ipc| ipc.name != connector);
+
+        niri.remove_output(&output);
+    }
+
     pub fn seat_name(&self) -> String {
         "headless".to_owned()
     }
@@ -2,13 +2,15 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use niri_config::{Config, ModKey};
+use niri_config::{Config, ModKey, OutputName};
 use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::drm::DrmNode;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::output::Output;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 
 use crate::niri::Niri;
+use crate::utils::backlight::BrightnessChange;
 use crate::utils::id::IdCounter;
 
 pub mod tty;
@@ -169,6 +171,14 @@ impl Backend {
         }
     }
 
+    pub fn primary_render_node(&self) -> Option<DrmNode> {
+        match self {
+            Backend::Tty(tty) => Some(tty.primary_render_node()),
+            Backend::Winit(_) => None,
+            Backend::Headless(_) => None,
+        }
+    }
+
     pub fn set_monitors_active(&mut self, active: bool) {
         match self {
             Backend::Tty(tty) => tty.set_monitors_active(active),
@@ -177,6 +187,43 @@ impl Backend {
         }
     }
 
+    pub fn set_output_powered(&mut self, output: &Output, powered: bool) {
+        match self {
+            Backend::Tty(tty) => tty.set_output_powered(output, powered),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
+    pub fn is_output_powered_off(&self, output: &Output) -> bool {
+        match self {
+            Backend::Tty(tty) => tty.is_output_powered_off(output),
+            Backend::Winit(_) => false,
+            Backend::Headless(_) => false,
+        }
+    }
+
+    pub fn has_backlight(&self, output: &Output) -> bool {
+        match self {
+            Backend::Tty(tty) => tty.has_backlight(output),
+            Backend::Winit(_) => false,
+            Backend::Headless(_) => false,
+        }
+    }
+
+    pub fn change_brightness(
+        &mut self,
+        niri: &mut Niri,
+        output: &Output,
+        change: BrightnessChange,
+    ) -> Option<f64> {
+        match self {
+            Backend::Tty(tty) => tty.change_brightness(niri, output, change),
+            Backend::Winit(_) => None,
+            Backend::Headless(_) => None,
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
         match self {
             Backend::Tty(tty) => tty.set_output_on_demand_vrr(niri, output, enable_vrr),
@@ -193,6 +240,14 @@ impl Backend {
         }
     }
 
+    pub fn update_explicit_sync_config(&mut self, niri: &mut Niri) {
+        match self {
+            Backend::Tty(tty) => tty.update_explicit_sync_config(niri),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
     pub fn on_output_config_changed(&mut self, niri: &mut Niri) {
         match self {
             Backend::Tty(tty) => tty.on_output_config_changed(niri),
@@ -201,6 +256,50 @@ impl Backend {
         }
     }
 
+    /// Returns the names of all connected outputs, including disabled ones.
+    pub fn connected_output_names(&self, niri: &Niri) -> Vec<OutputName> {
+        match self {
+            Backend::Tty(tty) => tty.connected_output_names(),
+            Backend::Winit(_) | Backend::Headless(_) => niri
+                .global_space
+                .outputs()
+                .map(|output| output.user_data().get::<OutputName>().unwrap().clone())
+                .collect(),
+        }
+    }
+
+    pub fn create_virtual_output(
+        &mut self,
+        niri: &mut Niri,
+        width: u16,
+        height: u16,
+        refresh: Option<f64>,
+        scale: Option<f64>,
+    ) {
+        match self {
+            Backend::Tty(tty) => tty.create_virtual_output(niri, width, height, refresh, scale),
+            Backend::Winit(_) | Backend::Headless(_) => {
+                warn!("virtual outputs are only supported on a TTY");
+            }
+        }
+    }
+
+    pub fn destroy_virtual_output(&mut self, niri: &mut Niri, name: &str) {
+        match self {
+            Backend::Tty(tty) => tty.destroy_virtual_output(niri, name),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
+    pub fn set_night_light(&mut self, multipliers: Option<[f64; 3]>) {
+        match self {
+            Backend::Tty(tty) => tty.set_night_light(multipliers),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
     pub fn tty_checked(&mut self) -> Option<&mut Tty> {
         if let Self::Tty(v) = self {
             Some(v)
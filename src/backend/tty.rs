@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::fmt::Write;
 use std::iter::zip;
 use std::num::NonZeroU64;
@@ -11,11 +12,11 @@ use std::time::Duration;
 use std::{io, mem};
 
 use anyhow::{anyhow, bail, ensure, Context};
-use bytemuck::cast_slice_mut;
+use bytemuck::{bytes_of_mut, cast_slice_mut};
 use drm_ffi::drm_mode_modeinfo;
 use libc::dev_t;
 use niri_config::output::Modeline;
-use niri_config::{Config, OutputName};
+use niri_config::{Config, Hdr, OutputName, OutputPrimaries};
 use niri_ipc::{HSyncPolarity, VSyncPolarity};
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::allocator::format::FormatSet;
@@ -29,6 +30,7 @@ use smithay::backend::drm::{
 use smithay::backend::egl::context::ContextPriority;
 use smithay::backend::egl::{EGLDevice, EGLDisplay};
 use smithay::backend::libinput::{LibinputInputBackend, LibinputSessionInterface};
+use smithay::backend::renderer::element::RenderElementStates;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::backend::renderer::multigpu::gbm::GbmGlesBackend;
 use smithay::backend::renderer::multigpu::{GpuManager, MultiFrame, MultiRenderer};
@@ -37,9 +39,12 @@ use smithay::backend::session::libseat::LibSeatSession;
 use smithay::backend::session::{Event as SessionEvent, Session};
 use smithay::backend::udev::{self, UdevBackend, UdevEvent};
 use smithay::desktop::utils::OutputPresentationFeedback;
-use smithay::output::{Mode, Output, OutputModeSource, PhysicalProperties};
+use smithay::output::{Mode, Output, OutputModeSource, PhysicalProperties, Subpixel};
+use smithay::reexports::calloop::generic::Generic;
 use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
-use smithay::reexports::calloop::{Dispatcher, LoopHandle, RegistrationToken};
+use smithay::reexports::calloop::{
+    Dispatcher, Interest, LoopHandle, Mode as LoopMode, PostAction, RegistrationToken,
+};
 use smithay::reexports::drm::control::atomic::AtomicModeReq;
 use smithay::reexports::drm::control::dumbbuffer::DumbBuffer;
 use smithay::reexports::drm::control::{
@@ -51,11 +56,12 @@ use smithay::reexports::input::Libinput;
 use smithay::reexports::rustix::fs::OFlags;
 use smithay::reexports::wayland_protocols;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::{DeviceFd, Transform};
+use smithay::utils::{DeviceFd, Size, Transform};
 use smithay::wayland::dmabuf::{DmabufFeedback, DmabufFeedbackBuilder, DmabufGlobal};
 use smithay::wayland::drm_lease::{
     DrmLease, DrmLeaseBuilder, DrmLeaseRequest, DrmLeaseState, LeaseRejected,
 };
+use smithay::wayland::drm_syncobj::{supports_syncobj_eventfd, DrmSyncobjState};
 use smithay::wayland::presentation::Refresh;
 use smithay_drm_extras::drm_scanner::{DrmScanEvent, DrmScanner};
 use wayland_protocols::wp::linux_dmabuf::zv1::server::zwp_linux_dmabuf_feedback_v1::TrancheFlags;
@@ -64,11 +70,19 @@ use wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
 use super::{IpcOutputMap, RenderResult};
 use crate::backend::OutputId;
 use crate::frame_clock::FrameClock;
+use crate::night_light;
 use crate::niri::{Niri, RedrawState, State};
+use crate::protocols::color_management::{
+    Chromaticities, ImageDescription, Luminances, NamedPrimaries, Primaries, TransferFunction,
+};
 use crate::render_helpers::debug::draw_damage;
 use crate::render_helpers::renderer::AsGlesRenderer;
 use crate::render_helpers::{resources, shaders, RenderTarget};
-use crate::utils::{get_monotonic_time, is_laptop_panel, logical_output, PanelOrientation};
+use crate::utils::backlight::{self, Backlight, BrightnessChange};
+use crate::utils::{
+    get_monotonic_time, ipc_transform_to_smithay, is_laptop_panel, logical_output, output_size,
+    panel_orientation, DefaultScale, PanelOrientation, ScaleOverride,
+};
 
 const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
     Fourcc::Xrgb8888,
@@ -77,6 +91,20 @@ const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
     Fourcc::Abgr8888,
 ];
 
+/// Formats tried first for HDR outputs, since PQ needs more than 8 bits to avoid banding.
+const HDR_COLOR_FORMATS: [Fourcc; 4] = [
+    Fourcc::Xrgb2101010,
+    Fourcc::Xbgr2101010,
+    Fourcc::Argb2101010,
+    Fourcc::Abgr2101010,
+];
+
+/// Maximum number of passes over the output config.
+///
+/// Each pass can enable or disable mirroring on outputs whose source got connected or
+/// disconnected in the previous pass, so chains of mirrors need a few passes to settle.
+const MAX_OUTPUT_CONFIG_PASSES: usize = 8;
+
 pub struct Tty {
     config: Rc<RefCell<Config>>,
     session: LibSeatSession,
@@ -102,6 +130,12 @@ pub struct Tty {
     update_ignored_nodes_on_resume: bool,
     // Whether the debug tinting is enabled.
     debug_tint: bool,
+    // Virtual outputs created at runtime over IPC.
+    virtual_outputs: Vec<VirtualOutput>,
+    // Connectors of individually powered off outputs, so that they stay off across hotplug.
+    powered_off_connectors: HashSet<String>,
+    // Color multipliers of the night light, if it's changing the colors.
+    night_light: Option<[f64; 3]>,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
 }
 
@@ -373,14 +407,54 @@ struct TtyOutputState {
     crtc: crtc::Handle,
 }
 
+/// Output without a connector, such as a second monitor streamed to another device.
+///
+/// Virtual outputs are never presented. They are only rendered for screencasts and screencopy.
+struct VirtualOutput {
+    id: OutputId,
+    name: OutputName,
+    mode: Mode,
+    scale: Option<f64>,
+    /// The output while it is enabled in the config.
+    output: Option<Output>,
+}
+
+/// State of a surface mirroring another output.
+struct Mirror {
+    /// Name of the mirrored output, as set in the config.
+    source: String,
+    /// Output of this surface.
+    ///
+    /// It is not added to niri, so it has no place in the layout.
+    output: Output,
+    /// Whether a frame was queued and we're waiting for its VBlank.
+    waiting_for_vblank: bool,
+    /// Whether the source rendered a new frame while we were waiting for the VBlank.
+    redraw_needed: bool,
+}
+
 struct Surface {
     name: OutputName,
     compositor: GbmDrmCompositor,
     connector: connector::Handle,
     dmabuf_feedback: Option<SurfaceDmabufFeedback>,
     gamma_props: Option<GammaProps>,
+    /// Gamma ramp set by a gamma-control client.
+    client_gamma: Option<Vec<u16>>,
     /// Gamma change to apply upon session resume.
     pending_gamma_change: Option<Option<Vec<u16>>>,
+    /// Whether this output was powered off on its own, rather than with all monitors.
+    ///
+    /// Such outputs stay off when the monitors are activated.
+    powered_off: bool,
+    /// Mirroring state, if this surface shows another output instead of its own contents.
+    mirror: Option<Mirror>,
+    /// Configured primaries and HDR that this surface was set up with.
+    color_config: (Option<OutputPrimaries>, Option<Hdr>),
+    /// Backlight of the panel, if it has one.
+    backlight: Option<Backlight>,
+    /// HDR metadata sent to the display, if HDR is enabled.
+    hdr_metadata: Option<HdrMetadata>,
     /// Tracy frame that goes from vblank to vblank.
     vblank_frame: Option<tracy_client::Frame>,
     /// Frame name for the VBlank frame.
@@ -404,6 +478,14 @@ struct GammaProps {
     previous_blob: Option<NonZeroU64>,
 }
 
+/// HDR metadata describing the display.
+#[derive(Debug, Clone, Copy)]
+struct HdrMetadata {
+    primaries: Chromaticities,
+    min_luminance: f64,
+    max_luminance: f64,
+}
+
 struct ConnectorProperties<'a> {
     device: &'a DrmDevice,
     connector: connector::Handle,
@@ -455,6 +537,22 @@ impl Tty {
             })
             .unwrap();
 
+        match backlight::monitor() {
+            Ok(monitor) => {
+                let source = Generic::new(monitor, Interest::READ, LoopMode::Level);
+                event_loop
+                    .insert_source(source, |_, monitor, state| {
+                        for event in monitor.iter() {
+                            let tty = state.backend.tty();
+                            tty.on_backlight_changed(&mut state.niri, event.sysname());
+                        }
+                        Ok(PostAction::Continue)
+                    })
+                    .unwrap();
+            }
+            Err(err) => warn!("error creating the backlight udev monitor: {err:?}"),
+        }
+
         let api = GbmGlesBackend::with_context_priority(ContextPriority::High);
         let gpu_manager = GpuManager::new(api).context("error creating the GPU manager")?;
 
@@ -506,6 +604,9 @@ impl Tty {
             update_output_config_on_resume: false,
             update_ignored_nodes_on_resume: false,
             debug_tint: false,
+            virtual_outputs: Vec::new(),
+            powered_off_connectors: HashSet::new(),
+            night_light: None,
             ipc_outputs: Arc::new(Mutex::new(HashMap::new())),
         })
     }
@@ -573,6 +674,24 @@ impl Tty {
         }
     }
 
+    fn on_backlight_changed(&mut self, niri: &mut Niri, name: &OsStr) {
+        let _span = tracy_client::span!("Tty::on_backlight_changed");
+
+        for device in self.devices.values_mut() {
+            for surface in device.surfaces.values_mut() {
+                let Some(backlight) = &mut surface.backlight else {
+                    continue;
+                };
+                if name != backlight.name() || !backlight.refresh() {
+                    continue;
+                }
+
+                let brightness = backlight.brightness();
+                set_ipc_brightness(&self.ipc_outputs, niri, &surface.name, brightness);
+            }
+        }
+    }
+
     fn on_session_event(&mut self, niri: &mut Niri, event: SessionEvent) {
         let _span = tracy_client::span!("Tty::on_session_event");
 
@@ -672,9 +791,15 @@ impl Tty {
                         if let Ok(props) =
                             ConnectorProperties::try_new(&device.drm, surface.connector)
                         {
-                            match reset_hdr(&props) {
-                                Ok(()) => (),
-                                Err(err) => debug!("couldn't reset HDR properties: {err:?}"),
+                            if let Some(metadata) = &surface.hdr_metadata {
+                                if let Err(err) = set_hdr(&props, metadata) {
+                                    warn!("error restoring HDR properties: {err:?}");
+                                }
+                            } else {
+                                match reset_hdr(&props) {
+                                    Ok(()) => (),
+                                    Err(err) => debug!("couldn't reset HDR properties: {err:?}"),
+                                }
                             }
                         } else {
                             warn!("failed to get connector properties");
@@ -695,6 +820,13 @@ impl Tty {
                                 warn!("error restoring gamma: {err:?}");
                             }
                         }
+
+                        // Outputs powered off on their own must stay off.
+                        if surface.powered_off {
+                            if let Err(err) = surface.compositor.clear() {
+                                warn!("error clearing drm surface: {err:?}");
+                            }
+                        }
                     }
                 }
 
@@ -844,6 +976,11 @@ impl Tty {
                 );
             assert!(self.dmabuf_global.replace(dmabuf_global).is_none());
 
+            // Expose explicit sync if the primary device supports it.
+            let syncobj_state = create_syncobj_state(&self.config.borrow(), niri, drm.device_fd());
+            assert!(niri.drm_syncobj_state.is_none());
+            niri.drm_syncobj_state = syncobj_state;
+
             // Update the dmabuf feedbacks for all surfaces.
             for (node, device) in self.devices.iter_mut() {
                 for surface in device.surfaces.values_mut() {
@@ -1032,6 +1169,8 @@ impl Tty {
         // If the device was just added or resumed, we need to cleanup any disconnected connectors
         // and planes.
         if cleanup {
+            self.update_output_profile(niri);
+
             let device = self.devices.get(&node).unwrap();
 
             // Follow the logic in on_output_config_changed().
@@ -1066,6 +1205,10 @@ impl Tty {
                     warn!("error resetting DrmCompositor state: {err:?}");
                 }
                 surface.compositor.reset_buffers();
+
+                if let Some(mirror) = &mut surface.mirror {
+                    mirror.waiting_for_vblank = false;
+                }
             }
         }
 
@@ -1148,6 +1291,9 @@ impl Tty {
                         )
                         .unwrap();
 
+                    // Explicit sync needs the primary device to import timelines.
+                    niri.drm_syncobj_state = None;
+
                     // Clear the dmabuf feedbacks for all surfaces.
                     for device in self.devices.values_mut() {
                         for surface in device.surfaces.values_mut() {
@@ -1279,12 +1425,21 @@ impl Tty {
             .map_err(|err| debug!("couldn't get gamma properties: {err:?}"))
             .ok();
 
-        // Reset gamma in case it was set before.
-        let res = if let Some(gamma_props) = &mut gamma_props {
-            gamma_props.set_gamma(&device.drm, None)
-        } else {
-            set_gamma_for_crtc(&device.drm, crtc, None)
+        // Reset gamma in case it was set before, keeping the night light.
+        let ramp = match self.night_light {
+            Some(multipliers) => surface_gamma_size(&device.drm, crtc, gamma_props.as_ref())
+                .and_then(|size| night_light::apply_to_ramp(None, size as usize, multipliers))
+                .map(Some),
+            None => Ok(None),
         };
+        let res = ramp.and_then(|ramp| {
+            let ramp = ramp.as_deref();
+            if let Some(gamma_props) = &mut gamma_props {
+                gamma_props.set_gamma(&device.drm, ramp)
+            } else {
+                set_gamma_for_crtc(&device.drm, crtc, ramp)
+            }
+        });
         if let Err(err) = res {
             debug!("couldn't reset gamma: {err:?}");
         }
@@ -1348,6 +1503,35 @@ impl Tty {
             output.user_data().insert_if_missing(|| PanelOrientation(x));
         }
 
+        let mirror_source = mirror_source(niri, &output_name, &config);
+        if mirror_source.is_some() {
+            // Nothing else sets the transform since the output isn't added to niri.
+            let transform = panel_orientation(&output) + ipc_transform_to_smithay(config.transform);
+            output.change_current_state(None, Some(transform), None, None);
+        }
+
+        // Try to enable HDR if requested. Mirrors show SDR contents, so they don't need it.
+        let mut hdr_metadata = None;
+        let mut image_description = sdr_image_description(&config);
+        if let (Some(hdr), None) = (config.hdr, &mirror_source) {
+            let metadata = HdrMetadata::new(&config, &hdr);
+            let res = ConnectorProperties::try_new(&device.drm, connector.handle())
+                .and_then(|props| set_hdr(&props, &metadata));
+            match res {
+                Ok(()) => {
+                    hdr_metadata = Some(metadata);
+                    image_description = hdr_image_description(&hdr);
+                }
+                Err(err) => warn!("error enabling HDR, falling back to SDR: {err:?}"),
+            }
+        }
+
+        let color_formats = if hdr_metadata.is_some() {
+            Vec::from_iter(HDR_COLOR_FORMATS.into_iter().chain(SUPPORTED_COLOR_FORMATS))
+        } else {
+            Vec::from(SUPPORTED_COLOR_FORMATS)
+        };
+
         let render_node = device.render_node.unwrap_or(self.primary_render_node);
         let renderer = self.gpu_manager.single_renderer(&render_node)?;
         let egl_context = renderer.as_ref().egl_context();
@@ -1398,7 +1582,7 @@ impl Tty {
             None,
             device.allocator.clone(),
             GbmFramebufferExporter::new(device.gbm.clone(), device.render_node.into()),
-            SUPPORTED_COLOR_FORMATS,
+            color_formats.clone(),
             // This is only used to pick a good internal format, so it can use the surface's render
             // formats, even though we only ever render on the primary GPU.
             render_formats.clone(),
@@ -1428,7 +1612,7 @@ impl Tty {
                     None,
                     device.allocator.clone(),
                     GbmFramebufferExporter::new(device.gbm.clone(), device.render_node.into()),
-                    SUPPORTED_COLOR_FORMATS,
+                    color_formats,
                     render_formats,
                     device.drm.cursor_size(),
                     Some(device.gbm.clone()),
@@ -1461,9 +1645,11 @@ impl Tty {
             }
         }
 
+        let powered_off = self.powered_off_connectors.contains(&connector_name);
+
         // Some buggy monitors replug upon powering off, so powering on here would prevent such
         // monitors from powering off. Therefore, we avoid unconditionally powering on.
-        if !niri.monitors_active {
+        if !niri.monitors_active || powered_off {
             if let Err(err) = compositor.clear() {
                 warn!("error clearing drm surface: {err:?}");
             }
@@ -1482,13 +1668,31 @@ impl Tty {
         let sequence_delta_plot_name =
             tracy_client::PlotName::new_leak(format!("{connector_name} sequence delta"));
 
+        let card = node.dev_path();
+        let card = card
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(|x| x.to_str());
+        let backlight = card.and_then(|card| Backlight::for_connector(card, &connector_name));
+
         let surface = Surface {
             name: output_name,
             connector: connector.handle(),
             compositor,
             dmabuf_feedback,
             gamma_props,
+            client_gamma: None,
             pending_gamma_change: None,
+            powered_off,
+            mirror: mirror_source.clone().map(|source| Mirror {
+                source,
+                output: output.clone(),
+                waiting_for_vblank: false,
+                redraw_needed: false,
+            }),
+            color_config: (config.primaries, config.hdr),
+            backlight,
+            hdr_metadata,
             vblank_frame: None,
             vblank_frame_name,
             time_since_presentation_plot_name,
@@ -1499,7 +1703,19 @@ impl Tty {
         let res = device.surfaces.insert(crtc, surface);
         assert!(res.is_none(), "crtc must not have already existed");
 
+        if let Some(source) = mirror_source {
+            debug!("mirroring {source}");
+
+            // Redraw the source to show it on the new mirror.
+            if let Some(source) = niri.output_by_name_match(&source).cloned() {
+                niri.queue_redraw(&source);
+            }
+
+            return Ok(());
+        }
+
         niri.add_output(output.clone(), Some(refresh_interval(mode)), vrr_enabled);
+        niri.set_output_image_description(&output, image_description);
 
         if niri.monitors_active {
             // Redraw the new monitor.
@@ -1546,12 +1762,17 @@ impl Tty {
 
         debug!("disconnecting connector: {:?}", surface.name.connector);
 
+        // Mirroring outputs aren't added to niri.
+        if surface.mirror.is_some() {
+            return;
+        }
+
         let output = niri
             .global_space
             .outputs()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                let tty_state = output.user_data().get::<TtyOutputState>();
+                tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
             })
             .cloned();
         if let Some(output) = output {
@@ -1586,6 +1807,21 @@ impl Tty {
         // Finish the Tracy frame, if any.
         drop(surface.vblank_frame.take());
 
+        if let Some(mirror) = &mut surface.mirror {
+            if let Err(err) = surface.compositor.frame_submitted() {
+                warn!("error marking frame as submitted: {err}");
+            }
+            mirror.waiting_for_vblank = false;
+
+            // Catch up with the frame that the source rendered in the meantime.
+            if mem::take(&mut mirror.redraw_needed) {
+                if let Some(source) = niri.output_by_name_match(&mirror.source).cloned() {
+                    self.render_mirror(niri, node, crtc, &source);
+                }
+            }
+            return;
+        }
+
         let name = &surface.name.connector;
         trace!("vblank on {name} {meta:?}");
         span.emit_text(name);
@@ -1630,8 +1866,8 @@ impl Tty {
             .global_space
             .outputs()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                let tty_state = output.user_data().get::<TtyOutputState>();
+                tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
             })
             .cloned()
         else {
@@ -1802,12 +2038,38 @@ impl Tty {
         niri: &mut Niri,
         output: &Output,
         target_presentation_time: Duration,
+    ) -> RenderResult {
+        // Let the output keep its contents around for the mirrors to show.
+        let mirrors = self.mirrors_of(output);
+        if let Some(state) = niri.output_state.get_mut(output) {
+            state.mirrored = !mirrors.is_empty();
+        }
+
+        let rv = self.render_output(niri, output, target_presentation_time);
+
+        // Damage tracking on the mirrors takes care of skipping frames where nothing changed.
+        if rv != RenderResult::Skipped {
+            for (node, crtc) in mirrors {
+                self.render_mirror(niri, node, crtc, output);
+            }
+        }
+
+        rv
+    }
+
+    fn render_output(
+        &mut self,
+        niri: &mut Niri,
+        output: &Output,
+        target_presentation_time: Duration,
     ) -> RenderResult {
         let span = tracy_client::span!("Tty::render");
 
         let mut rv = RenderResult::Skipped;
 
-        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
+        let Some(tty_state) = output.user_data().get::<TtyOutputState>() else {
+            return render_virtual_output(niri, output, target_presentation_time);
+        };
         let Some(device) = self.devices.get_mut(&tty_state.node) else {
             error!("missing output device");
             return rv;
@@ -1839,8 +2101,12 @@ impl Tty {
         };
 
         // Render the elements.
-        let mut elements =
-            niri.render::<TtyRenderer>(&mut renderer, output, true, RenderTarget::Output);
+        let mut elements = niri.render_with_postprocessing::<TtyRenderer>(
+            &mut renderer,
+            output,
+            true,
+            RenderTarget::Output,
+        );
 
         // Visualize the damage, if enabled.
         if niri.debug_draw_damage {
@@ -1956,6 +2222,107 @@ impl Tty {
         rv
     }
 
+    /// Returns the surfaces mirroring this output.
+    fn mirrors_of(&self, source: &Output) -> Vec<(DrmNode, crtc::Handle)> {
+        let source_name = source.user_data().get::<OutputName>().unwrap();
+
+        let mut mirrors = Vec::new();
+        for (&node, device) in &self.devices {
+            for (&crtc, surface) in &device.surfaces {
+                if let Some(mirror) = &surface.mirror {
+                    if source_name.matches(&mirror.source) {
+                        mirrors.push((node, crtc));
+                    }
+                }
+            }
+        }
+        mirrors
+    }
+
+    fn render_mirror(&mut self, niri: &Niri, node: DrmNode, crtc: crtc::Handle, source: &Output) {
+        let span = tracy_client::span!("Tty::render_mirror");
+
+        let Some(device) = self.devices.get_mut(&node) else {
+            error!("missing output device");
+            return;
+        };
+
+        let Some(surface) = device.surfaces.get_mut(&crtc) else {
+            error!("missing surface");
+            return;
+        };
+
+        let Some(mirror) = &mut surface.mirror else {
+            error!("surface is not a mirror");
+            return;
+        };
+
+        span.emit_text(&surface.name.connector);
+
+        if !device.drm.is_active() {
+            return;
+        }
+
+        // Render once the queued frame is presented.
+        if mirror.waiting_for_vblank {
+            mirror.redraw_needed = true;
+            return;
+        }
+
+        let mut renderer = match self.gpu_manager.renderer(
+            &self.primary_render_node,
+            &device.render_node.unwrap_or(self.primary_render_node),
+            surface.compositor.format(),
+        ) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                warn!("error creating renderer for primary GPU: {err:?}");
+                return;
+            }
+        };
+
+        let mirror_size = output_size(&mirror.output);
+        let elem = match niri.render_for_mirror(source, mirror_size) {
+            Ok(elem) => elem,
+            Err(err) => {
+                warn!("error rendering mirrored contents: {err:?}");
+                return;
+            }
+        };
+
+        let drm_compositor = &mut surface.compositor;
+        match drm_compositor.render_frame::<_, _>(
+            &mut renderer,
+            &[elem],
+            [0., 0., 0., 1.],
+            FrameFlags::empty(),
+        ) {
+            Ok(res) => {
+                if res.is_empty {
+                    return;
+                }
+
+                if res.needs_sync() {
+                    if let PrimaryPlaneElement::Swapchain(element) = res.primary_element {
+                        let _span = tracy_client::span!("wait for completion");
+                        if let Err(err) = element.sync.wait() {
+                            warn!("error waiting for frame completion: {err:?}");
+                        }
+                    }
+                }
+
+                let feedback = OutputPresentationFeedback::new(&mirror.output);
+                match drm_compositor.queue_frame((feedback, Duration::ZERO)) {
+                    Ok(()) => mirror.waiting_for_vblank = true,
+                    Err(err) => warn!("error queueing frame: {err}"),
+                }
+            }
+            Err(err) => {
+                warn!("error rendering frame: {err}");
+            }
+        }
+    }
+
     pub fn change_vt(&mut self, vt: i32) {
         if let Err(err) = self.session.change_vt(vt) {
             warn!("error changing VT: {err}");
@@ -2015,7 +2382,10 @@ impl Tty {
     }
 
     pub fn get_gamma_size(&self, output: &Output) -> anyhow::Result<u32> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("virtual outputs have no gamma")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -2024,19 +2394,15 @@ impl Tty {
             .context("missing device")?;
 
         let surface = device.surfaces.get(&crtc).context("missing surface")?;
-        if let Some(gamma_props) = &surface.gamma_props {
-            gamma_props.gamma_size(&device.drm)
-        } else {
-            let info = device
-                .drm
-                .get_crtc(crtc)
-                .context("error getting crtc info")?;
-            Ok(info.gamma_length())
-        }
+        surface_gamma_size(&device.drm, crtc, surface.gamma_props.as_ref())
     }
 
+    /// Sets the gamma ramp of a gamma-control client, composed with the night light.
     pub fn set_gamma(&mut self, output: &Output, ramp: Option<Vec<u16>>) -> anyhow::Result<()> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("virtual outputs have no gamma")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -2045,17 +2411,38 @@ impl Tty {
             .context("missing device")?;
         let surface = device.surfaces.get_mut(&crtc).context("missing surface")?;
 
-        // Cannot change properties while the device is inactive.
-        if !self.session.is_active() {
-            surface.pending_gamma_change = Some(ramp);
-            return Ok(());
+        let previous = mem::replace(&mut surface.client_gamma, ramp);
+        let res = apply_gamma(
+            &device.drm,
+            crtc,
+            surface,
+            self.night_light,
+            self.session.is_active(),
+        );
+        if res.is_err() {
+            surface.client_gamma = previous;
         }
+        res
+    }
 
-        let ramp = ramp.as_deref();
-        if let Some(gamma_props) = &mut surface.gamma_props {
-            gamma_props.set_gamma(&device.drm, ramp)
-        } else {
-            set_gamma_for_crtc(&device.drm, crtc, ramp)
+    /// Sets the color multipliers of the night light on all outputs.
+    pub fn set_night_light(&mut self, multipliers: Option<[f64; 3]>) {
+        if self.night_light == multipliers {
+            return;
+        }
+        self.night_light = multipliers;
+
+        let session_active = self.session.is_active();
+        for device in self.devices.values_mut() {
+            for (&crtc, surface) in device.surfaces.iter_mut() {
+                let res = apply_gamma(&device.drm, crtc, surface, multipliers, session_active);
+                if let Err(err) = res {
+                    debug!(
+                        "output {:?}: error applying night light: {err:?}",
+                        surface.name.connector
+                    );
+                }
+            }
         }
     }
 
@@ -2132,12 +2519,21 @@ impl Tty {
                     });
                 let vrr_enabled = surface.is_some_and(|surface| surface.compositor.vrr_enabled());
 
+                let mirror = surface.and_then(|surface| surface.mirror.as_ref());
+                let mirror = mirror.map(|mirror| {
+                    niri.output_by_name_match(&mirror.source)
+                        .map_or_else(|| mirror.source.clone(), |source| source.name())
+                });
+
+                let brightness = surface.and_then(|surface| surface.backlight.as_ref());
+                let brightness = brightness.map(Backlight::brightness);
+
                 let logical = niri
                     .global_space
                     .outputs()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == *node && tty_state.crtc == crtc
+                        let tty_state = output.user_data().get::<TtyOutputState>();
+                        tty_state.is_some_and(|state| state.node == *node && state.crtc == crtc)
                     })
                     .map(logical_output);
 
@@ -2159,12 +2555,40 @@ impl Tty {
                     vrr_supported,
                     vrr_enabled,
                     logical,
+                    mirror,
+                    brightness,
                 };
 
                 ipc_outputs.insert(id, ipc_output);
             }
         }
 
+        for virtual_output in &self.virtual_outputs {
+            let mode = virtual_output.mode;
+            let ipc_output = niri_ipc::Output {
+                name: virtual_output.name.connector.clone(),
+                make: virtual_output.name.make.clone().unwrap_or_default(),
+                model: virtual_output.name.model.clone().unwrap_or_default(),
+                serial: None,
+                physical_size: None,
+                modes: vec![niri_ipc::Mode {
+                    width: mode.size.w as u16,
+                    height: mode.size.h as u16,
+                    refresh_rate: mode.refresh as u32,
+                    is_preferred: true,
+                }],
+                current_mode: virtual_output.output.as_ref().map(|_| 0),
+                is_custom_mode: true,
+                vrr_supported: false,
+                vrr_enabled: false,
+                logical: virtual_output.output.as_ref().map(logical_output),
+                mirror: None,
+                brightness: None,
+            };
+
+            ipc_outputs.insert(virtual_output.id, ipc_output);
+        }
+
         let mut guard = self.ipc_outputs.lock().unwrap();
         *guard = ipc_outputs;
         niri.ipc_outputs_changed = true;
@@ -2174,6 +2598,10 @@ impl Tty {
         self.ipc_outputs.clone()
     }
 
+    pub fn primary_render_node(&self) -> DrmNode {
+        self.primary_render_node
+    }
+
     #[cfg(feature = "xdp-gnome-screencast")]
     pub fn primary_gbm_device(&self) -> Option<GbmDevice<DrmDeviceFd>> {
         // Try to find a device corresponding to the primary render node.
@@ -2202,8 +2630,109 @@ impl Tty {
                 if let Err(err) = surface.compositor.clear() {
                     warn!("error clearing drm surface: {err:?}");
                 }
+
+                // The queued frame, if any, won't be presented.
+                if let Some(mirror) = &mut surface.mirror {
+                    mirror.waiting_for_vblank = false;
+                }
+            }
+        }
+    }
+
+    pub fn set_output_powered(&mut self, output: &Output, powered: bool) {
+        let Some(tty_state) = output.user_data().get::<TtyOutputState>() else {
+            // Virtual outputs have no display to power off.
+            return;
+        };
+        let Some(device) = self.devices.get_mut(&tty_state.node) else {
+            error!("missing output device");
+            return;
+        };
+        let Some(surface) = device.surfaces.get_mut(&tty_state.crtc) else {
+            error!("missing surface");
+            return;
+        };
+
+        if surface.powered_off == !powered {
+            return;
+        }
+        surface.powered_off = !powered;
+
+        let connector = surface.name.connector.clone();
+        if powered {
+            self.powered_off_connectors.remove(&connector);
+        } else {
+            self.powered_off_connectors.insert(connector);
+        }
+
+        // Same as in set_monitors_active(): the next queued frame will re-enable the CRTC.
+        if !powered {
+            if let Err(err) = surface.compositor.clear() {
+                warn!("error clearing drm surface: {err:?}");
+            }
+        }
+    }
+
+    pub fn is_output_powered_off(&self, output: &Output) -> bool {
+        let Some(tty_state) = output.user_data().get::<TtyOutputState>() else {
+            return false;
+        };
+        self.devices
+            .get(&tty_state.node)
+            .and_then(|device| device.surfaces.get(&tty_state.crtc))
+            .is_some_and(|surface| surface.powered_off)
+    }
+
+    pub fn has_backlight(&self, output: &Output) -> bool {
+        let Some(tty_state) = output.user_data().get::<TtyOutputState>() else {
+            return false;
+        };
+        self.devices
+            .get(&tty_state.node)
+            .and_then(|device| device.surfaces.get(&tty_state.crtc))
+            .is_some_and(|surface| surface.backlight.is_some())
+    }
+
+    /// Changes the backlight brightness of the output.
+    ///
+    /// Returns the new brightness from 0 to 1, or `None` if the output has no backlight.
+    pub fn change_brightness(
+        &mut self,
+        niri: &mut Niri,
+        output: &Output,
+        change: BrightnessChange,
+    ) -> Option<f64> {
+        let tty_state = output.user_data().get::<TtyOutputState>()?;
+        let device = self.devices.get_mut(&tty_state.node)?;
+        let surface = device.surfaces.get_mut(&tty_state.crtc)?;
+        let backlight = surface.backlight.as_mut()?;
+
+        backlight.refresh();
+        let value = backlight.apply(change);
+        let brightness = backlight.brightness();
+
+        #[cfg(feature = "dbus")]
+        {
+            let conn = niri
+                .dbus
+                .as_ref()
+                .and_then(|dbus| dbus.conn_login1.as_ref());
+            if let Some(conn) = conn {
+                let name = backlight.name().to_owned();
+                crate::dbus::freedesktop_login1::set_brightness(conn, name, value);
+            } else {
+                warn!("can't change brightness without a connection to logind");
             }
         }
+        #[cfg(not(feature = "dbus"))]
+        {
+            let _ = value;
+            warn!("changing brightness requires the dbus feature");
+        }
+
+        set_ipc_brightness(&self.ipc_outputs, niri, &surface.name, brightness);
+
+        Some(brightness)
     }
 
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
@@ -2216,8 +2745,8 @@ impl Tty {
         }
         for (&node, device) in self.devices.iter_mut() {
             for (&crtc, surface) in device.surfaces.iter_mut() {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                if tty_state.node == node && tty_state.crtc == crtc {
+                let tty_state = output.user_data().get::<TtyOutputState>();
+                if tty_state.is_some_and(|state| state.node == node && state.crtc == crtc) {
                     let word = if enable_vrr { "enabling" } else { "disabling" };
                     if let Err(err) = surface.compositor.use_vrr(enable_vrr) {
                         warn!(
@@ -2236,6 +2765,26 @@ impl Tty {
         }
     }
 
+    pub fn update_explicit_sync_config(&mut self, niri: &mut Niri) {
+        let disable = self.config.borrow().debug.disable_explicit_sync;
+        if disable {
+            if niri.drm_syncobj_state.take().is_some() {
+                debug!("explicit sync is disabled in the config");
+            }
+            return;
+        }
+
+        if niri.drm_syncobj_state.is_some() {
+            return;
+        }
+
+        // The primary device may be missing, then explicit sync is enabled once it's added.
+        if let Some(device) = self.devices.get(&self.primary_node) {
+            let config = self.config.borrow();
+            niri.drm_syncobj_state = create_syncobj_state(&config, niri, device.drm.device_fd());
+        }
+    }
+
     pub fn update_ignored_nodes_config(&mut self, niri: &mut Niri) {
         let _span = tracy_client::span!("Tty::update_ignored_nodes_config");
 
@@ -2319,12 +2868,42 @@ impl Tty {
         }
         self.update_output_config_on_resume = false;
 
+        self.update_output_profile(niri);
+
+        // Connecting and disconnecting outputs changes which outputs can be mirrored, so go again
+        // if needed. This also connects back the outputs that were disconnected to reconnect.
+        //
+        // Outputs mirroring each other in a cycle never settle, so limit the number of passes.
+        for _ in 0..MAX_OUTPUT_CONFIG_PASSES {
+            if !self.apply_output_config(niri) {
+                return;
+            }
+        }
+
+        warn!("output config did not settle, check the outputs for circular mirroring");
+    }
+
+    /// Switches the output profile if the connected outputs changed.
+    ///
+    /// This happens before setting up the outputs, so that they come up with the config of the
+    /// new profile right away, rather than get reconfigured after.
+    fn update_output_profile(&self, niri: &mut Niri) {
+        if niri.update_output_profile(&self.connected_output_names()) {
+            niri.output_profile_switched = true;
+        }
+    }
+
+    /// Connects, disconnects and reconfigures outputs according to the config.
+    ///
+    /// Returns whether another pass is needed.
+    fn apply_output_config(&mut self, niri: &mut Niri) -> bool {
         // Figure out if we should disable laptop panels.
         let disable_laptop_panels = self.should_disable_laptop_panels(niri.is_lid_closed);
         let should_disable = |connector: &str| disable_laptop_panels && is_laptop_panel(connector);
 
         let mut to_disconnect = vec![];
         let mut to_connect = vec![];
+        let mut reconnect = false;
 
         for (&node, device) in &mut self.devices {
             for (&crtc, surface) in device.surfaces.iter_mut() {
@@ -2340,6 +2919,22 @@ impl Tty {
                     continue;
                 }
 
+                // Reconnect outputs switching between mirroring and showing their own contents.
+                let mirror_source = mirror_source(niri, &surface.name, &config);
+                if surface.mirror.as_ref().map(|m| &m.source) != mirror_source.as_ref() {
+                    to_disconnect.push((node, crtc));
+                    reconnect = true;
+                    continue;
+                }
+
+                // Reconnect outputs with changed colors, since the HDR signaling and the buffer
+                // formats are set up when connecting.
+                if surface.color_config != (config.primaries, config.hdr) {
+                    to_disconnect.push((node, crtc));
+                    reconnect = true;
+                    continue;
+                }
+
                 // Check if we need to change the mode.
                 let Some(connector) = device.drm_scanner.connectors().get(&surface.connector)
                 else {
@@ -2374,6 +2969,36 @@ impl Tty {
 
                 let change_mode = surface.compositor.pending_mode() != mode;
 
+                if let Some(mirror) = &surface.mirror {
+                    let output = &mirror.output;
+                    let transform =
+                        panel_orientation(output) + ipc_transform_to_smithay(config.transform);
+                    if !change_mode && output.current_transform() == transform {
+                        continue;
+                    }
+
+                    if change_mode {
+                        debug!(
+                            "output {:?}: picking mode: {mode:?}",
+                            surface.name.connector
+                        );
+                        if let Err(err) = surface.compositor.use_mode(mode) {
+                            warn!("error changing mode: {err:?}");
+                            continue;
+                        }
+                    }
+
+                    let wl_mode = Mode::from(mode);
+                    output.change_current_state(Some(wl_mode), Some(transform), None, None);
+                    output.set_preferred(wl_mode);
+
+                    // Redraw the source to update the mirror.
+                    if let Some(source) = niri.output_by_name_match(&mirror.source).cloned() {
+                        niri.queue_redraw(&source);
+                    }
+                    continue;
+                }
+
                 let vrr_enabled = surface.compositor.vrr_enabled();
                 let change_always_vrr = vrr_enabled != config.is_vrr_always_on();
                 let is_on_demand_vrr = config.is_vrr_on_demand();
@@ -2386,8 +3011,8 @@ impl Tty {
                     .global_space
                     .outputs()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == node && tty_state.crtc == crtc
+                        let tty_state = output.user_data().get::<TtyOutputState>();
+                        tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
                     })
                     .cloned();
                 let Some(output) = output else {
@@ -2498,14 +3123,192 @@ impl Tty {
             }
         }
 
+        self.update_virtual_outputs(niri);
+        self.refresh_ipc_outputs(niri);
+
+        reconnect || self.has_outdated_mirrors(niri)
+    }
+
+    /// Returns whether any output should switch between mirroring and showing its own contents.
+    fn has_outdated_mirrors(&self, niri: &Niri) -> bool {
+        let config = self.config.borrow();
+        self.devices
+            .values()
+            .flat_map(|device| device.surfaces.values())
+            .any(|surface| {
+                let config = config.outputs.find(&surface.name).cloned();
+                let source = mirror_source(niri, &surface.name, &config.unwrap_or_default());
+                surface.mirror.as_ref().map(|m| &m.source) != source.as_ref()
+            })
+    }
+
+    pub fn create_virtual_output(
+        &mut self,
+        niri: &mut Niri,
+        width: u16,
+        height: u16,
+        refresh: Option<f64>,
+        scale: Option<f64>,
+    ) {
+        let _span = tracy_client::span!("Tty::create_virtual_output");
+
+        if width == 0 || height == 0 {
+            warn!("virtual output size must not be zero");
+            return;
+        }
+
+        let refresh = refresh.unwrap_or(60.);
+        if !(1. ..=1000.).contains(&refresh) {
+            warn!("virtual output refresh rate must be between 1 and 1000 Hz");
+            return;
+        }
+
+        if let Some(scale) = scale {
+            if !(0.1..=10.).contains(&scale) {
+                warn!("virtual output scale must be between 0.1 and 10");
+                return;
+            }
+        }
+
+        // Reuse the lowest free number, like connector names do.
+        let connector = (1..)
+            .map(|n| format!("HEADLESS-{n}"))
+            .find(|connector| {
+                !self
+                    .virtual_outputs
+                    .iter()
+                    .any(|v| v.name.connector == *connector)
+            })
+            .unwrap();
+        debug!("creating virtual output {connector}");
+
+        self.virtual_outputs.push(VirtualOutput {
+            id: OutputId::next(),
+            name: OutputName {
+                connector,
+                make: Some("niri".to_owned()),
+                model: Some("virtual".to_owned()),
+                serial: None,
+            },
+            mode: Mode {
+                size: Size::from((i32::from(width), i32::from(height))),
+                refresh: (refresh * 1000.).round() as i32,
+            },
+            scale,
+            output: None,
+        });
+
+        self.update_virtual_outputs(niri);
         self.refresh_ipc_outputs(niri);
     }
 
+    pub fn destroy_virtual_output(&mut self, niri: &mut Niri, target: &str) {
+        let _span = tracy_client::span!("Tty::destroy_virtual_output");
+
+        let Some(idx) = self
+            .virtual_outputs
+            .iter()
+            .position(|v| v.name.matches(target))
+        else {
+            warn!("no virtual output matches {target:?}");
+            return;
+        };
+
+        let virtual_output = self.virtual_outputs.remove(idx);
+        debug!(
+            "destroying virtual output {}",
+            virtual_output.name.connector
+        );
+
+        if let Some(output) = virtual_output.output {
+            niri.remove_output(&output);
+        }
+
+        self.refresh_ipc_outputs(niri);
+    }
+
+    /// Adds and removes virtual outputs according to whether they are off in the config.
+    fn update_virtual_outputs(&mut self, niri: &mut Niri) {
+        let config = self.config.borrow();
+        let is_off = |name: &OutputName| config.outputs.find(name).is_some_and(|c| c.off);
+
+        let mut to_remove = vec![];
+        let mut to_add = vec![];
+        for (idx, virtual_output) in self.virtual_outputs.iter_mut().enumerate() {
+            let is_enabled = virtual_output.output.is_some();
+            match (is_enabled, is_off(&virtual_output.name)) {
+                (true, true) => to_remove.push(virtual_output.output.take().unwrap()),
+                (false, false) => to_add.push(idx),
+                _ => (),
+            }
+        }
+        drop(config);
+
+        for output in to_remove {
+            niri.remove_output(&output);
+        }
+
+        for idx in to_add {
+            let virtual_output = &mut self.virtual_outputs[idx];
+            let name = &virtual_output.name;
+
+            let output = Output::new(
+                name.connector.clone(),
+                PhysicalProperties {
+                    size: (0, 0).into(),
+                    subpixel: Subpixel::Unknown,
+                    make: name.make.clone().unwrap_or_default(),
+                    model: name.model.clone().unwrap_or_default(),
+                    serial_number: "Unknown".to_owned(),
+                },
+            );
+
+            let mode = virtual_output.mode;
+            output.change_current_state(Some(mode), None, None, None);
+            output.set_preferred(mode);
+
+            output.user_data().insert_if_missing(|| name.clone());
+            match virtual_output.scale {
+                Some(scale) => output
+                    .user_data()
+                    .insert_if_missing(|| ScaleOverride(scale)),
+                None => output.user_data().insert_if_missing(|| DefaultScale(1.)),
+            };
+
+            let refresh_interval = Duration::from_secs_f64(1000. / f64::from(mode.refresh));
+            niri.add_output(output.clone(), Some(refresh_interval), false);
+            virtual_output.output = Some(output);
+        }
+    }
+
     pub fn get_device_from_node(&mut self, node: DrmNode) -> Option<&mut OutputDevice> {
         self.devices.get_mut(&node)
     }
 
-    pub fn disconnected_connector_name_by_name_match(&self, target: &str) -> Option<OutputName> {
+    /// Returns the names of connected outputs, including disabled ones, but not virtual outputs.
+    pub fn connected_output_names(&self) -> Vec<OutputName> {
+        let disable_monitor_names = self.config.borrow().debug.disable_monitor_names;
+
+        let mut names = Vec::new();
+        for device in self.devices.values() {
+            for (connector, crtc) in device.drm_scanner.crtcs() {
+                if connector.state() != connector::State::Connected
+                    || device
+                        .non_desktop_connectors
+                        .contains(&(connector.handle(), crtc))
+                {
+                    continue;
+                }
+
+                names.push(device.known_crtc_name(&crtc, connector, disable_monitor_names));
+            }
+        }
+        names
+    }
+
+    /// Returns the name of a connected output that has no niri output, such as a disabled or
+    /// mirroring output.
+    pub fn unmapped_connector_name_by_name_match(&self, target: &str) -> Option<OutputName> {
         let disable_monitor_names = self.config.borrow().debug.disable_monitor_names;
         for device in self.devices.values() {
             for (connector, crtc) in device.drm_scanner.crtcs() {
@@ -2514,8 +3317,9 @@ impl Tty {
                     continue;
                 }
 
-                // Check if already enabled.
-                if device.surfaces.contains_key(&crtc)
+                // Check if already enabled. Mirroring outputs have no niri output, so keep them.
+                let surface = device.surfaces.get(&crtc);
+                if surface.is_some_and(|surface| surface.mirror.is_none())
                     || device
                         .non_desktop_connectors
                         .contains(&(connector.handle(), crtc))
@@ -2714,6 +3518,27 @@ fn primary_node_from_config(config: &Config) -> Option<(DrmNode, DrmNode)> {
     primary_node_from_render_node(path)
 }
 
+fn create_syncobj_state(
+    config: &Config,
+    niri: &Niri,
+    device_fd: &DrmDeviceFd,
+) -> Option<DrmSyncobjState> {
+    if config.debug.disable_explicit_sync {
+        debug!("explicit sync is disabled in the config");
+        return None;
+    }
+
+    if !supports_syncobj_eventfd(device_fd) {
+        debug!("primary device does not support syncobj eventfd, disabling explicit sync");
+        return None;
+    }
+
+    Some(DrmSyncobjState::new::<State>(
+        &niri.display_handle,
+        device_fd.clone(),
+    ))
+}
+
 fn ignored_nodes_from_config(config: &Config) -> HashSet<DrmNode> {
     let mut disabled_nodes = HashSet::new();
 
@@ -2880,6 +3705,65 @@ fn suspend() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns the output that an output with this config should mirror, if any.
+///
+/// Mirroring works only while the source is connected as a normal output. Otherwise, the output
+/// shows its own contents.
+fn mirror_source(niri: &Niri, name: &OutputName, config: &niri_config::Output) -> Option<String> {
+    resolve_mirror_source(name, config, |source| {
+        niri.output_by_name_match(source).is_some()
+    })
+}
+
+fn resolve_mirror_source(
+    name: &OutputName,
+    config: &niri_config::Output,
+    is_connected: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let source = config.mirror.as_ref()?;
+    if name.matches(source) || !is_connected(source) {
+        return None;
+    }
+    Some(source.clone())
+}
+
+fn set_ipc_brightness(
+    ipc_outputs: &Mutex<IpcOutputMap>,
+    niri: &mut Niri,
+    name: &OutputName,
+    brightness: f64,
+) {
+    let mut ipc_outputs = ipc_outputs.lock().unwrap();
+    let ipc_output = ipc_outputs
+        .values_mut()
+        .find(|ipc_output| ipc_output.name == name.connector);
+    if let Some(ipc_output) = ipc_output {
+        ipc_output.brightness = Some(brightness);
+        niri.ipc_outputs_changed = true;
+    }
+}
+
+fn render_virtual_output(
+    niri: &mut Niri,
+    output: &Output,
+    target_presentation_time: Duration,
+) -> RenderResult {
+    let _span = tracy_client::span!("render_virtual_output");
+
+    // Virtual outputs have nothing to present. Screencasts and screencopy render them on demand
+    // right after this, so here we only keep the frame callbacks going at the refresh rate.
+    //
+    // Nothing reaches a display, so discard the presentation feedback rather than report a
+    // made-up presentation time.
+    let states = RenderElementStates::default();
+    let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
+    presentation_feedbacks.discarded();
+
+    queue_estimated_vblank_timer(niri, output.clone(), target_presentation_time);
+
+    RenderResult::NoDamage
+}
+
 fn queue_estimated_vblank_timer(
     niri: &mut Niri,
     output: Output,
@@ -3243,6 +4127,135 @@ fn reset_hdr(props: &ConnectorProperties) -> anyhow::Result<()> {
     Ok(())
 }
 
+const HDMI_STATIC_METADATA_TYPE1: u32 = 0;
+const HDMI_EOTF_SMPTE_ST2084: u8 = 2;
+const DRM_MODE_COLORIMETRY_BT2020_RGB: u64 = 9;
+
+impl HdrMetadata {
+    fn new(config: &niri_config::Output, hdr: &Hdr) -> Self {
+        let primaries = config
+            .primaries
+            .map_or(NamedPrimaries::Bt2020, named_primaries);
+        Self {
+            primaries: primaries.chromaticities(),
+            min_luminance: hdr.min_luminance(),
+            max_luminance: hdr.max_luminance(),
+        }
+    }
+}
+
+fn set_hdr(props: &ConnectorProperties, metadata: &HdrMetadata) -> anyhow::Result<()> {
+    // struct hdr_output_metadata from the kernel, with the HDMI infoframe inlined.
+    #[allow(non_camel_case_types)]
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    struct hdr_output_metadata {
+        metadata_type: u32,
+        eotf: u8,
+        infoframe_metadata_type: u8,
+        display_primaries: [[u16; 2]; 3],
+        white_point: [u16; 2],
+        max_display_mastering_luminance: u16,
+        min_display_mastering_luminance: u16,
+        max_cll: u16,
+        max_fall: u16,
+        padding: u16,
+    }
+
+    // Chromaticities are in units of 0.00002.
+    let xy = |[x, y]: [f64; 2]| [(x * 50000.).round() as u16, (y * 50000.).round() as u16];
+    let c = &metadata.primaries;
+    let mut data = hdr_output_metadata {
+        metadata_type: HDMI_STATIC_METADATA_TYPE1,
+        eotf: HDMI_EOTF_SMPTE_ST2084,
+        infoframe_metadata_type: 0,
+        display_primaries: [xy(c.red), xy(c.green), xy(c.blue)],
+        white_point: xy(c.white),
+        max_display_mastering_luminance: metadata.max_luminance.round() as u16,
+        // In units of 0.0001 cd/m².
+        min_display_mastering_luminance: (metadata.min_luminance * 10000.).round() as u16,
+        // Unknown.
+        max_cll: 0,
+        max_fall: 0,
+        padding: 0,
+    };
+
+    let (info, _) = props.find(c"HDR_OUTPUT_METADATA")?;
+    let property::ValueType::Blob = info.value_type() else {
+        bail!("wrong property type")
+    };
+
+    let device = props.device;
+    let blob = drm_ffi::mode::create_property_blob(device.as_fd(), bytes_of_mut(&mut data))
+        .context("error creating property blob")?;
+    let blob = u64::from(blob.blob_id);
+    let res = device
+        .set_property(
+            props.connector,
+            info.handle(),
+            property::Value::Blob(blob).into(),
+        )
+        .context("error setting HDR_OUTPUT_METADATA");
+    // The property keeps its own reference to the blob.
+    if let Err(err) = device.destroy_property_blob(blob) {
+        warn!("error destroying HDR_OUTPUT_METADATA property blob: {err:?}");
+    }
+    res?;
+
+    // The PQ signal always uses the BT.2020 primaries.
+    let (info, value) = props.find(c"Colorspace")?;
+    let property::ValueType::Enum(_) = info.value_type() else {
+        bail!("wrong property type")
+    };
+    if *value != DRM_MODE_COLORIMETRY_BT2020_RGB {
+        device
+            .set_property(
+                props.connector,
+                info.handle(),
+                DRM_MODE_COLORIMETRY_BT2020_RGB,
+            )
+            .context("error setting Colorspace")?;
+    }
+
+    Ok(())
+}
+
+fn named_primaries(primaries: OutputPrimaries) -> NamedPrimaries {
+    match primaries {
+        OutputPrimaries::Srgb => NamedPrimaries::Srgb,
+        OutputPrimaries::DisplayP3 => NamedPrimaries::DisplayP3,
+        OutputPrimaries::Bt2020 => NamedPrimaries::Bt2020,
+    }
+}
+
+/// Returns the image description of an SDR output with this config.
+fn sdr_image_description(config: &niri_config::Output) -> ImageDescription {
+    let primaries = config
+        .primaries
+        .map_or(NamedPrimaries::Srgb, named_primaries);
+    ImageDescription {
+        primaries: Primaries::Named(primaries),
+        ..ImageDescription::SRGB
+    }
+}
+
+/// Returns the image description of an HDR output with this config.
+///
+/// The configured primaries only go into the HDR metadata, since the signal itself is BT.2020.
+fn hdr_image_description(hdr: &Hdr) -> ImageDescription {
+    let min = hdr.min_luminance();
+    ImageDescription {
+        tf: TransferFunction::St2084Pq,
+        primaries: Primaries::Named(NamedPrimaries::Bt2020),
+        luminances: Luminances {
+            min,
+            max: 10000.,
+            reference: hdr.reference_luminance(),
+        },
+        target_luminance: Some((min, hdr.max_luminance())),
+    }
+}
+
 fn is_vrr_capable(device: &DrmDevice, connector: connector::Handle) -> Option<bool> {
     let (_, info, value) = find_drm_property(device, connector, "vrr_capable")?;
     info.value_type().convert_value(value).as_boolean()
@@ -3266,6 +4279,51 @@ fn get_panel_orientation(props: &ConnectorProperties) -> anyhow::Result<Transfor
     }
 }
 
+fn surface_gamma_size(
+    device: &DrmDevice,
+    crtc: crtc::Handle,
+    gamma_props: Option<&GammaProps>,
+) -> anyhow::Result<u32> {
+    if let Some(gamma_props) = gamma_props {
+        gamma_props.gamma_size(device)
+    } else {
+        let info = device.get_crtc(crtc).context("error getting crtc info")?;
+        Ok(info.gamma_length())
+    }
+}
+
+/// Sets the gamma of the surface, composing the gamma-control client ramp with the night light.
+fn apply_gamma(
+    device: &DrmDevice,
+    crtc: crtc::Handle,
+    surface: &mut Surface,
+    night_light: Option<[f64; 3]>,
+    session_active: bool,
+) -> anyhow::Result<()> {
+    let ramp = match night_light {
+        Some(multipliers) => {
+            let gamma_size = surface_gamma_size(device, crtc, surface.gamma_props.as_ref())?;
+            let client_gamma = surface.client_gamma.as_deref();
+            let ramp = night_light::apply_to_ramp(client_gamma, gamma_size as usize, multipliers)?;
+            Some(ramp)
+        }
+        None => surface.client_gamma.clone(),
+    };
+
+    // Cannot change properties while the device is inactive.
+    if !session_active {
+        surface.pending_gamma_change = Some(ramp);
+        return Ok(());
+    }
+
+    let ramp = ramp.as_deref();
+    if let Some(gamma_props) = &mut surface.gamma_props {
+        gamma_props.set_gamma(device, ramp)
+    } else {
+        set_gamma_for_crtc(device, crtc, ramp)
+    }
+}
+
 pub fn set_gamma_for_crtc(
     device: &DrmDevice,
     crtc: crtc::Handle,
@@ -3383,9 +4441,12 @@ unsafe fn init_libinput_plugin_system(libinput: &Libinput) {
 mod tests {
     use insta::assert_debug_snapshot;
     use niri_config::output::Modeline;
+    use niri_config::OutputName;
     use niri_ipc::{HSyncPolarity, VSyncPolarity};
 
-    use crate::backend::tty::{calculate_drm_mode_from_modeline, calculate_mode_cvt};
+    use crate::backend::tty::{
+        calculate_drm_mode_from_modeline, calculate_mode_cvt, resolve_mirror_source,
+    };
 
     #[test]
     fn test_calculate_drmmode_from_modeline() {
@@ -3525,4 +4586,54 @@ mod tests {
         }
         "#);
     }
+
+    fn output_name(connector: &str) -> OutputName {
+        OutputName {
+            connector: connector.to_owned(),
+            make: Some(String::from("Vendor")),
+            model: Some(String::from("Monitor")),
+            serial: Some(connector.to_owned()),
+        }
+    }
+
+    fn mirror_source_of(name: &str, mirror: Option<&str>, connected: &[&str]) -> Option<String> {
+        let config = niri_config::Output {
+            mirror: mirror.map(String::from),
+            ..Default::default()
+        };
+        let connected = connected.iter().map(|c| output_name(c)).collect::<Vec<_>>();
+        resolve_mirror_source(&output_name(name), &config, |source| {
+            connected.iter().any(|name| name.matches(source))
+        })
+    }
+
+    #[test]
+    fn mirror_source_resolution() {
+        // Mirroring is off by default.
+        assert_eq!(mirror_source_of("HDMI-A-1", None, &["eDP-1"]), None);
+
+        // Sources match by connector and by make, model and serial.
+        assert_eq!(
+            mirror_source_of("HDMI-A-1", Some("eDP-1"), &["eDP-1"]),
+            Some(String::from("eDP-1"))
+        );
+        assert_eq!(
+            mirror_source_of("HDMI-A-1", Some("vendor monitor eDP-1"), &["eDP-1"]),
+            Some(String::from("vendor monitor eDP-1"))
+        );
+
+        // Disconnected sources leave the output showing its own contents.
+        assert_eq!(mirror_source_of("HDMI-A-1", Some("DP-1"), &["eDP-1"]), None);
+        assert_eq!(mirror_source_of("HDMI-A-1", Some("DP-1"), &[]), None);
+
+        // So does an output set to mirror itself, by either name.
+        assert_eq!(
+            mirror_source_of("HDMI-A-1", Some("HDMI-A-1"), &["HDMI-A-1"]),
+            None
+        );
+        assert_eq!(
+            mirror_source_of("HDMI-A-1", Some("Vendor Monitor HDMI-A-1"), &["HDMI-A-1"]),
+            None
+        );
+    }
 }
@@ -89,6 +89,8 @@ impl Winit {
                 vrr_supported: false,
                 vrr_enabled: false,
                 logical: Some(logical_output(&output)),
+                mirror: None,
+                brightness: None,
             },
         )])));
 
@@ -180,7 +182,7 @@ impl Winit {
         let _span = tracy_client::span!("Winit::render");
 
         // Render the elements.
-        let mut elements = niri.render::<GlesRenderer>(
+        let mut elements = niri.render_with_postprocessing::<GlesRenderer>(
             self.backend.renderer(),
             output,
             true,
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
-use niri_ipc::{Action, OutputAction};
+use niri_ipc::{Action, EventCategory, OutputAction};
 
 use crate::utils::version;
 
@@ -100,7 +100,16 @@ pub enum Msg {
         action: OutputAction,
     },
     /// Start continuously receiving events from the compositor.
-    EventStream,
+    EventStream {
+        /// Only receive events of this category.
+        ///
+        /// Can be given multiple times. By default, events of all categories are received.
+        #[arg(long = "category", value_enum)]
+        categories: Vec<EventCategory>,
+        /// Coalesce layout events over this many milliseconds.
+        #[arg(long)]
+        coalesce_layout_ms: Option<u32>,
+    },
     /// Print the version of the running niri instance.
     Version,
     /// Request an error from the running niri instance.
@@ -109,6 +118,71 @@ pub enum Msg {
     OverviewState,
     /// List screencasts.
     Casts,
+    /// List output profiles.
+    OutputProfiles,
+    /// Force an output profile regardless of the connected outputs.
+    ///
+    /// Run without a profile name to go back to picking the profile matching the connected
+    /// outputs.
+    ForceOutputProfile {
+        /// Name of the profile to force.
+        ///
+        /// Run `niri msg output-profiles` to see the profile names.
+        #[arg()]
+        profile: Option<String>,
+    },
+    /// Print the layout of columns and tiles on all workspaces.
+    LayoutTree,
+    /// Register a custom action to show in the hotkey overlay.
+    ///
+    /// Binds with a `send-ipc-event` action of this name will show up in the hotkey overlay with
+    /// the description.
+    RegisterCustomAction {
+        /// Name of the custom action.
+        #[arg()]
+        name: String,
+        /// Description to show in the hotkey overlay.
+        #[arg()]
+        description: String,
+    },
+    /// Unregister a custom action.
+    UnregisterCustomAction {
+        /// Name of the custom action.
+        #[arg()]
+        name: String,
+    },
+    /// List the custom actions registered over IPC.
+    CustomActions,
+    /// Apply a KDL config fragment on top of the config file.
+    ///
+    /// The overlay stays applied across config reloads until removed or reset. Prints the id of
+    /// the new overlay.
+    ApplyConfigOverlay {
+        /// KDL config fragment, for example `layout { gaps 32; }`.
+        #[arg()]
+        kdl: String,
+    },
+    /// List the applied config overlays.
+    ConfigOverlays,
+    /// Remove a config overlay.
+    RemoveConfigOverlay {
+        /// Id of the overlay.
+        #[arg()]
+        id: u64,
+    },
+    /// Remove all config overlays.
+    ResetConfigOverlays,
+    /// Explain which window rules match a window.
+    ///
+    /// Shows every window rule with whether it matched, and the final rules for the window, both
+    /// for the current state and as evaluated when the window opened.
+    WindowRules {
+        /// Id of the window to explain.
+        ///
+        /// If not set, uses the focused window.
+        #[arg(long)]
+        id: Option<u64>,
+    },
 }
 
 #[derive(Clone, Debug, clap::ValueEnum)]
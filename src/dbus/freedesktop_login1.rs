@@ -103,3 +103,30 @@ pub fn start(
 
     Ok(conn)
 }
+
+/// Sets the brightness of a backlight device through logind.
+///
+/// This doesn't require write access to sysfs. The call is asynchronous, errors are only logged.
+pub fn set_brightness(conn: &zbus::blocking::Connection, name: String, brightness: u32) {
+    let async_conn = conn.inner().clone();
+    let future = async move {
+        let res = async_conn
+            .call_method(
+                Some("org.freedesktop.login1"),
+                "/org/freedesktop/login1/session/auto",
+                Some("org.freedesktop.login1.Session"),
+                "SetBrightness",
+                &("backlight", &name, brightness),
+            )
+            .await;
+        if let Err(err) = res {
+            warn!("error setting brightness of {name}: {err:?}");
+        }
+    };
+
+    let task = conn
+        .inner()
+        .executor()
+        .spawn(future, "set backlight brightness");
+    task.detach();
+}
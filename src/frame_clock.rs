@@ -53,6 +53,17 @@ impl FrameClock {
         self.last_presentation_time = Some(presentation_time);
     }
 
+    /// Returns the earliest presentation time of a frame queued after the one at
+    /// `presentation_time`.
+    pub fn presentation_time_after(&self, presentation_time: Duration) -> Duration {
+        match self.refresh_interval_ns {
+            Some(refresh_interval_ns) => {
+                presentation_time + Duration::from_nanos(refresh_interval_ns.get())
+            }
+            None => presentation_time,
+        }
+    }
+
     pub fn next_presentation_time(&self) -> Duration {
         let mut now = get_monotonic_time();
 
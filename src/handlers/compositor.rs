@@ -1,9 +1,10 @@
 use std::collections::hash_map::Entry;
 
 use niri_ipc::PositionChange;
+use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
 use smithay::input::pointer::{CursorImageStatus, CursorImageSurfaceData};
-use smithay::reexports::calloop::Interest;
+use smithay::reexports::calloop::{Interest, LoopHandle};
 use smithay::reexports::wayland_server::protocol::wl_buffer;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Client, Resource};
@@ -14,6 +15,7 @@ use smithay::wayland::compositor::{
     SurfaceAttributes,
 };
 use smithay::wayland::dmabuf::get_dmabuf;
+use smithay::wayland::drm_syncobj::{DrmSyncPoint, DrmSyncobjCachedState};
 use smithay::wayland::shell::xdg::ToplevelCachedState;
 use smithay::wayland::shm::{ShmHandler, ShmState};
 use smithay::{delegate_compositor, delegate_shm};
@@ -85,6 +87,7 @@ impl CompositorHandler for State {
                         window,
                         state,
                         activation_token_data,
+                        toplevel_tag,
                     } = entry.remove();
 
                     window.on_commit();
@@ -93,6 +96,7 @@ impl CompositorHandler for State {
 
                     let (
                         rules,
+                        rules_at_open,
                         width,
                         height,
                         is_full_width,
@@ -101,6 +105,7 @@ impl CompositorHandler for State {
                         is_pending_maximized,
                     ) = if let InitialConfigureState::Configured {
                         rules,
+                        rules_at_open,
                         width,
                         height,
                         floating_width: _,
@@ -123,6 +128,7 @@ impl CompositorHandler for State {
 
                         (
                             rules,
+                            Some(rules_at_open),
                             width,
                             height,
                             is_full_width,
@@ -138,6 +144,7 @@ impl CompositorHandler for State {
                             ResolvedWindowRules::default(),
                             None,
                             None,
+                            None,
                             false,
                             None,
                             None,
@@ -195,7 +202,8 @@ impl CompositorHandler for State {
                     // The mapped pre-commit hook deals with dma-bufs on its own.
                     self.remove_default_dmabuf_pre_commit_hook(surface);
                     let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
-                    let mapped = Mapped::new(window, rules, hook);
+                    let mut mapped = Mapped::new(window, rules, rules_at_open, hook);
+                    mapped.set_toplevel_tag(toplevel_tag);
                     let window = mapped.window.clone();
 
                     let target = if let Some(p) = &parent {
@@ -266,6 +274,9 @@ impl CompositorHandler for State {
                 // This is a commit of a previously-mapped toplevel.
                 let is_mapped = is_mapped(surface);
 
+                // The tag outlives unmapping, carry it over.
+                let toplevel_tag = (!is_mapped).then(|| mapped.toplevel_tag().clone());
+
                 // Must start the close animation before window.on_commit().
                 let transaction = Transaction::new();
                 if !is_mapped {
@@ -307,7 +318,8 @@ impl CompositorHandler for State {
 
                     // Newly-unmapped toplevels must perform the initial commit-configure sequence
                     // afresh.
-                    let unmapped = Unmapped::new(window);
+                    let mut unmapped = Unmapped::new(window);
+                    unmapped.toplevel_tag = toplevel_tag.unwrap_or_default();
                     self.niri.unmapped_windows.insert(surface.clone(), unmapped);
 
                     if let Some(output) = output {
@@ -518,39 +530,7 @@ delegate_shm!(State);
 impl State {
     pub fn add_default_dmabuf_pre_commit_hook(&mut self, surface: &WlSurface) {
         let hook = add_pre_commit_hook::<Self, _>(surface, move |state, _dh, surface| {
-            let maybe_dmabuf = with_states(surface, |surface_data| {
-                surface_data
-                    .cached_state
-                    .get::<SurfaceAttributes>()
-                    .pending()
-                    .buffer
-                    .as_ref()
-                    .and_then(|assignment| match assignment {
-                        BufferAssignment::NewBuffer(buffer) => get_dmabuf(buffer).cloned().ok(),
-                        _ => None,
-                    })
-            });
-            if let Some(dmabuf) = maybe_dmabuf {
-                if let Ok((blocker, source)) = dmabuf.generate_blocker(Interest::READ) {
-                    if let Some(client) = surface.client() {
-                        let res =
-                            state
-                                .niri
-                                .event_loop
-                                .insert_source(source, move |_, _, state| {
-                                    let display_handle = state.niri.display_handle.clone();
-                                    state
-                                        .client_compositor_state(&client)
-                                        .blocker_cleared(state, &display_handle);
-                                    Ok(())
-                                });
-                        if res.is_ok() {
-                            add_blocker(surface, blocker);
-                            trace!("added default dmabuf blocker");
-                        }
-                    }
-                }
-            }
+            add_buffer_blocker(state, surface, |_| ());
         });
 
         let s = surface.clone();
@@ -568,3 +548,278 @@ impl State {
         }
     }
 }
+
+/// Blocks the pending commit of the surface until its new dmabuf is ready for reading.
+///
+/// Waits for the explicit sync acquire point if the client set one, and for the implicit dmabuf
+/// fence otherwise. `on_ready` runs right before the blocker is cleared. If there's nothing to
+/// wait for, `on_ready` is dropped without running.
+pub fn add_buffer_blocker(
+    state: &mut State,
+    surface: &WlSurface,
+    on_ready: impl FnOnce(&mut State) + 'static,
+) {
+    let (dmabuf, acquire_point) = with_states(surface, |states| {
+        let dmabuf = states
+            .cached_state
+            .get::<SurfaceAttributes>()
+            .pending()
+            .buffer
+            .as_ref()
+            .and_then(|assignment| match assignment {
+                BufferAssignment::NewBuffer(buffer) => get_dmabuf(buffer).cloned().ok(),
+                _ => None,
+            });
+        let acquire_point = states
+            .cached_state
+            .get::<DrmSyncobjCachedState>()
+            .pending()
+            .acquire_point
+            .clone();
+        (dmabuf, acquire_point)
+    });
+
+    let Some(dmabuf) = dmabuf else {
+        return;
+    };
+    let Some(client) = surface.client() else {
+        return;
+    };
+
+    let mut on_ready = Some(on_ready);
+    let on_ready = move |state: &mut State| {
+        if let Some(on_ready) = on_ready.take() {
+            on_ready(state);
+        }
+
+        let display_handle = state.niri.display_handle.clone();
+        state
+            .client_compositor_state(&client)
+            .blocker_cleared(state, &display_handle);
+    };
+
+    let event_loop = &state.niri.event_loop;
+    let acquire_point = acquire_point.map(|point| AcquirePoint {
+        point,
+        surface,
+        event_loop,
+    });
+    let fence = DmabufFence {
+        dmabuf,
+        surface,
+        event_loop,
+    };
+
+    match wait_for_buffer(acquire_point.as_ref(), &fence, on_ready) {
+        BufferWait::AcquirePoint => trace!("added acquire point blocker"),
+        BufferWait::ImplicitFence => trace!("added dmabuf blocker"),
+        BufferWait::None => (),
+    }
+}
+
+/// What the pending commit of a surface ended up waiting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BufferWait {
+    AcquirePoint,
+    ImplicitFence,
+    None,
+}
+
+/// Something the pending commit of a surface can wait on.
+trait SyncWait<D> {
+    /// Blocks the pending commit and calls `on_ready` once the buffer is ready.
+    ///
+    /// Returns `on_ready` back if waiting isn't possible.
+    fn wait<F: FnMut(&mut D) + 'static>(&self, on_ready: F) -> Result<(), F>;
+}
+
+/// Explicit sync acquire point of the pending buffer.
+struct AcquirePoint<'a> {
+    point: DrmSyncPoint,
+    surface: &'a WlSurface,
+    event_loop: &'a LoopHandle<'static, State>,
+}
+
+/// Implicit sync fence of the pending dmabuf.
+struct DmabufFence<'a> {
+    dmabuf: Dmabuf,
+    surface: &'a WlSurface,
+    event_loop: &'a LoopHandle<'static, State>,
+}
+
+impl SyncWait<State> for AcquirePoint<'_> {
+    fn wait<F: FnMut(&mut State) + 'static>(&self, mut on_ready: F) -> Result<(), F> {
+        let (blocker, source) = match self.point.generate_blocker() {
+            Ok(x) => x,
+            Err(err) => {
+                warn!("error generating acquire point blocker: {err:?}");
+                return Err(on_ready);
+            }
+        };
+
+        let res = self.event_loop.insert_source(source, move |_, _, state| {
+            on_ready(state);
+            Ok(())
+        });
+        if res.is_ok() {
+            add_blocker(self.surface, blocker);
+        }
+        Ok(())
+    }
+}
+
+impl SyncWait<State> for DmabufFence<'_> {
+    fn wait<F: FnMut(&mut State) + 'static>(&self, mut on_ready: F) -> Result<(), F> {
+        let Ok((blocker, source)) = self.dmabuf.generate_blocker(Interest::READ) else {
+            return Err(on_ready);
+        };
+
+        let res = self.event_loop.insert_source(source, move |_, _, state| {
+            on_ready(state);
+            Ok(())
+        });
+        if res.is_ok() {
+            add_blocker(self.surface, blocker);
+        }
+        Ok(())
+    }
+}
+
+/// Waits for the acquire point if there is one, falling back to the implicit fence.
+fn wait_for_buffer<D>(
+    acquire_point: Option<&impl SyncWait<D>>,
+    fence: &impl SyncWait<D>,
+    on_ready: impl FnMut(&mut D) + 'static,
+) -> BufferWait {
+    let on_ready = match acquire_point {
+        Some(acquire_point) => match acquire_point.wait(on_ready) {
+            Ok(()) => return BufferWait::AcquirePoint,
+            Err(on_ready) => on_ready,
+        },
+        None => on_ready,
+    };
+
+    match fence.wait(on_ready) {
+        Ok(()) => BufferWait::ImplicitFence,
+        Err(_) => BufferWait::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+
+    type OnReady = Box<dyn FnMut(&mut u32)>;
+
+    /// Timeline that signals its points on demand.
+    #[derive(Default)]
+    struct FakeTimeline {
+        broken: bool,
+        waiters: RefCell<Vec<OnReady>>,
+    }
+
+    impl FakeTimeline {
+        fn broken() -> Self {
+            Self {
+                broken: true,
+                ..Self::default()
+            }
+        }
+
+        fn is_waited_on(&self) -> bool {
+            !self.waiters.borrow().is_empty()
+        }
+
+        fn signal(&self, ready_count: &mut u32) {
+            for mut on_ready in self.waiters.take() {
+                on_ready(ready_count);
+            }
+        }
+    }
+
+    impl SyncWait<u32> for FakeTimeline {
+        fn wait<F: FnMut(&mut u32) + 'static>(&self, on_ready: F) -> Result<(), F> {
+            if self.broken {
+                return Err(on_ready);
+            }
+
+            self.waiters.borrow_mut().push(Box::new(on_ready));
+            Ok(())
+        }
+    }
+
+    fn count_ready(ready_count: &mut u32) {
+        *ready_count += 1;
+    }
+
+    #[test]
+    fn waits_for_acquire_point() {
+        let acquire_point = FakeTimeline::default();
+        let fence = FakeTimeline::default();
+
+        let res = wait_for_buffer(Some(&acquire_point), &fence, count_ready);
+        assert_eq!(res, BufferWait::AcquirePoint);
+        assert!(acquire_point.is_waited_on());
+        assert!(!fence.is_waited_on());
+
+        let mut ready_count = 0;
+        fence.signal(&mut ready_count);
+        assert_eq!(ready_count, 0);
+
+        acquire_point.signal(&mut ready_count);
+        assert_eq!(ready_count, 1);
+    }
+
+    #[test]
+    fn waits_for_fence_without_acquire_point() {
+        let fence = FakeTimeline::default();
+
+        let res = wait_for_buffer(None::<&FakeTimeline>, &fence, count_ready);
+        assert_eq!(res, BufferWait::ImplicitFence);
+
+        let mut ready_count = 0;
+        fence.signal(&mut ready_count);
+        assert_eq!(ready_count, 1);
+    }
+
+    #[test]
+    fn falls_back_to_fence_on_broken_acquire_point() {
+        let acquire_point = FakeTimeline::broken();
+        let fence = FakeTimeline::default();
+
+        let res = wait_for_buffer(Some(&acquire_point), &fence, count_ready);
+        assert_eq!(res, BufferWait::ImplicitFence);
+
+        let mut ready_count = 0;
+        fence.signal(&mut ready_count);
+        assert_eq!(ready_count, 1);
+    }
+
+    #[test]
+    fn nothing_to_wait_for() {
+        let acquire_point = FakeTimeline::broken();
+        let fence = FakeTimeline::broken();
+
+        let dropped = Rc::new(Cell::new(false));
+        let guard = DropFlag(dropped.clone());
+        let on_ready = move |_: &mut u32| {
+            let _ = &guard;
+            panic!("on_ready must not run");
+        };
+
+        let res = wait_for_buffer(Some(&acquire_point), &fence, on_ready);
+        assert_eq!(res, BufferWait::None);
+        assert!(dropped.get());
+    }
+
+    struct DropFlag(Rc<Cell<bool>>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+}
@@ -10,6 +10,7 @@ use smithay::wayland::shell::wlr_layer::{
 };
 use smithay::wayland::shell::xdg::PopupSurface;
 
+use crate::layer::mapped::LayerId;
 use crate::layer::{MappedLayer, ResolvedLayerRules};
 use crate::niri::State;
 use crate::utils::{is_mapped, output_size, send_scale_transform};
@@ -38,8 +39,11 @@ impl WlrLayerShellHandler for State {
         };
 
         let wl_surface = surface.wl_surface().clone();
-        let is_new = self.niri.unmapped_layer_surfaces.insert(wl_surface);
-        assert!(is_new);
+        let prev = self
+            .niri
+            .unmapped_layer_surfaces
+            .insert(wl_surface, LayerId::next());
+        assert!(prev.is_none());
 
         let mut map = layer_map_for_output(&output);
         map.map_layer(&LayerSurface::new(surface, namespace))
@@ -68,6 +72,8 @@ impl WlrLayerShellHandler for State {
         if let Some(output) = output {
             self.niri.output_resized(&output);
         }
+
+        self.ipc_refresh_layers();
     }
 
     fn new_popup(&mut self, _parent: WlrLayerSurface, popup: PopupSurface) {
@@ -113,11 +119,14 @@ impl State {
             .layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
             .unwrap();
 
+        let mapping_changed;
         if is_mapped(surface) {
-            let was_unmapped = self.niri.unmapped_layer_surfaces.remove(surface);
+            let unmapped_id = self.niri.unmapped_layer_surfaces.remove(surface);
+            let was_unmapped = unmapped_id.is_some();
+            mapping_changed = was_unmapped;
 
             // Resolve rules for newly mapped layer surfaces.
-            if was_unmapped {
+            if let Some(id) = unmapped_id {
                 let config = self.niri.config.borrow();
 
                 let rules = &config.layer_rules;
@@ -128,6 +137,7 @@ impl State {
 
                 let mapped = MappedLayer::new(
                     layer.clone(),
+                    id,
                     rules,
                     output_size,
                     scale,
@@ -166,10 +176,14 @@ impl State {
             }
         } else {
             // The surface is unmapped.
-            if self.niri.mapped_layer_surfaces.remove(layer).is_some() {
+            let mapped = self.niri.mapped_layer_surfaces.remove(layer);
+            mapping_changed = mapped.is_some();
+            if let Some(mapped) = mapped {
                 // A mapped surface got unmapped via a null commit. Now it needs to do a new
                 // initial commit again.
-                self.niri.unmapped_layer_surfaces.insert(surface.clone());
+                self.niri
+                    .unmapped_layer_surfaces
+                    .insert(surface.clone(), mapped.id());
             } else {
                 // An unmapped surface remains unmapped. If we haven't sent an initial configure
                 // yet, we should do so.
@@ -196,11 +210,20 @@ impl State {
             }
         }
 
+        let layer = layer.clone();
         drop(map);
 
         // This will call queue_redraw() inside.
         self.niri.output_resized(&output);
 
+        // Only mapping and unmapping changes the set of layer surfaces, otherwise it's enough to
+        // check this one surface.
+        if mapping_changed {
+            self.ipc_refresh_layers();
+        } else {
+            self.ipc_refresh_layer(&output, &layer);
+        }
+
         true
     }
 }
@@ -18,16 +18,28 @@ use smithay::input::pointer::{CursorIcon, CursorImageStatus, Focus, PointerHandl
 use smithay::input::{keyboard, Seat, SeatHandler, SeatState};
 use smithay::output::Output;
 use smithay::reexports::rustix::fs::{fcntl_setfl, OFlags};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1;
+use smithay::reexports::wayland_protocols::wp::text_input::zv3::server::zwp_text_input_manager_v3::ZwpTextInputManagerV3;
+use smithay::reexports::wayland_protocols::wp::text_input::zv3::server::zwp_text_input_v3::{
+    self, ZwpTextInputV3,
+};
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::XdgToplevel;
+use smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::Mode as OutputPowerMode;
 use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
 use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::reexports::wayland_server::Resource;
-use smithay::utils::{Logical, Point, Rectangle, Serial};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::{
+    delegate_dispatch, delegate_global_dispatch, Client, DataInit, Dispatch, DisplayHandle,
+    Resource,
+};
+use smithay::utils::{Logical, Point, Rectangle, Serial, Size};
 use smithay::wayland::compositor::{get_parent, with_states};
 use smithay::wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportNotifier};
 use smithay::wayland::drm_lease::{
     DrmLease, DrmLeaseBuilder, DrmLeaseHandler, DrmLeaseRequest, DrmLeaseState, LeaseRejected,
 };
+use smithay::wayland::drm_syncobj::{DrmSyncobjHandler, DrmSyncobjState};
 use smithay::wayland::fractional_scale::FractionalScaleHandler;
 use smithay::wayland::idle_inhibit::IdleInhibitHandler;
 use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
@@ -57,46 +69,70 @@ use smithay::wayland::session_lock::{
     LockSurface, SessionLockHandler, SessionLockManagerState, SessionLocker,
 };
 use smithay::wayland::tablet_manager::TabletSeatHandler;
+use smithay::wayland::text_input::{TextInputManagerState, TextInputUserData};
 use smithay::wayland::xdg_activation::{
     XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
 };
+use smithay::wayland::xdg_system_bell::XdgSystemBellHandler;
 use smithay::{
     delegate_cursor_shape, delegate_data_control, delegate_data_device, delegate_dmabuf,
-    delegate_drm_lease, delegate_ext_data_control, delegate_fractional_scale,
+    delegate_drm_lease, delegate_drm_syncobj, delegate_ext_data_control, delegate_fractional_scale,
     delegate_idle_inhibit, delegate_idle_notify, delegate_input_method_manager,
     delegate_keyboard_shortcuts_inhibit, delegate_output, delegate_pointer_constraints,
     delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
     delegate_relative_pointer, delegate_seat, delegate_security_context, delegate_session_lock,
-    delegate_single_pixel_buffer, delegate_tablet_manager, delegate_text_input_manager,
-    delegate_viewporter, delegate_xdg_activation,
+    delegate_single_pixel_buffer, delegate_tablet_manager, delegate_viewporter,
+    delegate_xdg_activation, delegate_xdg_system_bell,
 };
 
 pub use crate::handlers::xdg_shell::KdeDecorationsModeState;
 use crate::layout::workspace::WorkspaceId;
 use crate::layout::ActivateWindow;
 use crate::niri::{DndIcon, NewClient, State};
+use crate::protocols::background_effect::{BackgroundEffectHandler, BackgroundEffectManagerState};
+use crate::protocols::color_management::{ColorManagementHandler, ColorManagementState};
+use crate::protocols::commit_timing::CommitTimingHandler;
+use crate::protocols::ext_foreign_toplevel_list::{
+    ExtForeignToplevelListHandler, ExtForeignToplevelListManagerState,
+};
 use crate::protocols::ext_workspace::{self, ExtWorkspaceHandler, ExtWorkspaceManagerState};
+use crate::protocols::fifo::FifoHandler;
 use crate::protocols::foreign_toplevel::{
     self, ForeignToplevelHandler, ForeignToplevelManagerState,
 };
 use crate::protocols::gamma_control::{GammaControlHandler, GammaControlManagerState};
+use crate::protocols::image_copy_capture::{ImageCopyCaptureHandler, ImageCopyCaptureManagerState};
 use crate::protocols::mutter_x11_interop::MutterX11InteropHandler;
 use crate::protocols::output_management::{OutputManagementHandler, OutputManagementManagerState};
+use crate::protocols::output_power_management::{
+    OutputPowerManagementHandler, OutputPowerManagementManagerState,
+};
 use crate::protocols::screencopy::{Screencopy, ScreencopyHandler, ScreencopyManagerState};
 use crate::protocols::virtual_pointer::{
     VirtualPointerAxisEvent, VirtualPointerButtonEvent, VirtualPointerHandler,
     VirtualPointerInputBackend, VirtualPointerManagerState, VirtualPointerMotionAbsoluteEvent,
     VirtualPointerMotionEvent,
 };
-use crate::utils::{output_size, send_scale_transform};
+use crate::protocols::xdg_toplevel_tag::XdgToplevelTagHandler;
+use crate::utils::spawning::spawn;
+use crate::utils::{get_monotonic_time, output_size, send_scale_transform};
 use crate::{
-    delegate_ext_workspace, delegate_foreign_toplevel, delegate_gamma_control,
-    delegate_mutter_x11_interop, delegate_output_management, delegate_screencopy,
-    delegate_virtual_pointer,
+    delegate_background_effect, delegate_color_management, delegate_commit_timing,
+    delegate_ext_foreign_toplevel_list, delegate_ext_workspace, delegate_fifo,
+    delegate_foreign_toplevel, delegate_gamma_control, delegate_image_capture_source,
+    delegate_image_copy_capture, delegate_mutter_x11_interop, delegate_output_management,
+    delegate_output_power_management, delegate_screencopy, delegate_virtual_pointer,
+    delegate_xdg_toplevel_tag,
 };
 
 pub const XDG_ACTIVATION_TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Minimum time between two handled system bells.
+///
+/// Bells that ring sooner are dropped entirely, so a client ringing in a loop cannot spawn a
+/// process, flash or send an IPC event for every ring.
+pub const BELL_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
 impl SeatHandler for State {
     type KeyboardFocus = WlSurface;
     type PointerFocus = WlSurface;
@@ -141,7 +177,66 @@ delegate_seat!(State);
 delegate_cursor_shape!(State);
 delegate_pointer_gestures!(State);
 delegate_relative_pointer!(State);
-delegate_text_input_manager!(State);
+
+// The text input is delegated by hand to let the magnifier follow the cursor rectangle.
+delegate_global_dispatch!(State: [ZwpTextInputManagerV3: ()] => TextInputManagerState);
+delegate_dispatch!(State: [ZwpTextInputManagerV3: ()] => TextInputManagerState);
+
+impl Dispatch<ZwpTextInputV3, TextInputUserData> for State {
+    fn request(
+        state: &mut Self,
+        client: &Client,
+        resource: &ZwpTextInputV3,
+        request: zwp_text_input_v3::Request,
+        data: &TextInputUserData,
+        dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwp_text_input_v3::Request::SetCursorRectangle {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let caret = Rectangle::new(Point::from((x, y)), Size::from((width, height)));
+                state
+                    .niri
+                    .pending_text_input_carets
+                    .insert(resource.clone(), caret);
+            }
+            zwp_text_input_v3::Request::Commit => {
+                if let Some(caret) = state.niri.pending_text_input_carets.remove(resource) {
+                    // The text input is active on the keyboard focus of its client.
+                    let surface = state.niri.keyboard_focus.surface().cloned();
+                    if let Some(surface) = surface {
+                        if surface.id().same_client_as(&resource.id()) {
+                            state.niri.magnifier_follow_text_input(&surface, caret);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        <TextInputManagerState as Dispatch<ZwpTextInputV3, TextInputUserData, Self>>::request(
+            state, client, resource, request, data, dhandle, data_init,
+        );
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        client: ClientId,
+        resource: &ZwpTextInputV3,
+        data: &TextInputUserData,
+    ) {
+        state.niri.pending_text_input_carets.remove(resource);
+
+        <TextInputManagerState as Dispatch<ZwpTextInputV3, TextInputUserData, Self>>::destroyed(
+            state, client, resource, data,
+        );
+    }
+}
 
 impl TabletSeatHandler for State {
     fn tablet_tool_image(&mut self, _tool: &TabletToolDescriptor, image: CursorImageStatus) {
@@ -443,6 +538,13 @@ impl DmabufHandler for State {
 }
 delegate_dmabuf!(State);
 
+impl DrmSyncobjHandler for State {
+    fn drm_syncobj_state(&mut self) -> Option<&mut DrmSyncobjState> {
+        self.niri.drm_syncobj_state.as_mut()
+    }
+}
+delegate_drm_syncobj!(State);
+
 impl SessionLockHandler for State {
     fn lock_state(&mut self) -> &mut SessionLockManagerState {
         &mut self.niri.session_lock_state
@@ -582,6 +684,13 @@ impl ForeignToplevelHandler for State {
 }
 delegate_foreign_toplevel!(State);
 
+impl ExtForeignToplevelListHandler for State {
+    fn ext_foreign_toplevel_list_state(&mut self) -> &mut ExtForeignToplevelListManagerState {
+        &mut self.niri.ext_foreign_toplevel_list_state
+    }
+}
+delegate_ext_foreign_toplevel_list!(State);
+
 impl ExtWorkspaceHandler for State {
     fn ext_workspace_manager_state(&mut self) -> &mut ExtWorkspaceManagerState {
         &mut self.niri.ext_workspace_state
@@ -642,6 +751,23 @@ impl ScreencopyHandler for State {
 }
 delegate_screencopy!(State);
 
+delegate_image_capture_source!(State);
+
+impl ImageCopyCaptureHandler for State {
+    fn image_copy_capture_state(&mut self) -> &mut ImageCopyCaptureManagerState {
+        &mut self.niri.image_copy_capture_state
+    }
+
+    fn new_session(&mut self, session: &ExtImageCopyCaptureSessionV1) {
+        self.niri.image_copy_capture_new_session(session);
+    }
+
+    fn frame(&mut self, session: &ExtImageCopyCaptureSessionV1) {
+        self.niri.image_copy_capture_frame(session);
+    }
+}
+delegate_image_copy_capture!(State);
+
 impl VirtualPointerHandler for State {
     fn virtual_pointer_manager_state(&mut self) -> &mut VirtualPointerManagerState {
         &mut self.niri.virtual_pointer_state
@@ -716,6 +842,25 @@ delegate_drm_lease!(State);
 
 delegate_viewporter!(State);
 
+impl FifoHandler for State {
+    fn fifo_barrier_set(&mut self, surface: &WlSurface) {
+        self.niri
+            .presentation_barrier_surfaces
+            .insert(surface.clone());
+    }
+}
+delegate_fifo!(State);
+
+impl CommitTimingHandler for State {
+    fn commit_timer_added(&mut self, surface: &WlSurface, target: Duration) {
+        self.niri
+            .presentation_barrier_surfaces
+            .insert(surface.clone());
+        self.niri.queue_redraw_for_commit_timer(surface, target);
+    }
+}
+delegate_commit_timing!(State);
+
 impl GammaControlHandler for State {
     fn gamma_control_manager_state(&mut self) -> &mut GammaControlManagerState {
         &mut self.niri.gamma_control_manager_state
@@ -747,6 +892,31 @@ impl GammaControlHandler for State {
 }
 delegate_gamma_control!(State);
 
+impl OutputPowerManagementHandler for State {
+    fn output_power_management_state(&mut self) -> &mut OutputPowerManagementManagerState {
+        &mut self.niri.output_power_management_state
+    }
+
+    fn output_power_mode(&mut self, output: &Output) -> OutputPowerMode {
+        self.niri.output_power_mode(&self.backend, output)
+    }
+
+    fn set_output_power_mode(&mut self, output: &Output, mode: OutputPowerMode) {
+        match mode {
+            OutputPowerMode::Off => {
+                self.niri
+                    .set_output_powered(&mut self.backend, output, false);
+            }
+            OutputPowerMode::On => {
+                self.niri
+                    .set_output_powered(&mut self.backend, output, true);
+            }
+            _ => (),
+        }
+    }
+}
+delegate_output_power_management!(State);
+
 struct UrgentOnlyMarker;
 
 impl XdgActivationHandler for State {
@@ -839,4 +1009,72 @@ delegate_output_management!(State);
 impl MutterX11InteropHandler for State {}
 delegate_mutter_x11_interop!(State);
 
+impl XdgSystemBellHandler for State {
+    fn ring(&mut self, surface: Option<WlSurface>) {
+        let now = get_monotonic_time();
+        if let Some(last) = self.niri.last_bell_time {
+            if now.saturating_sub(last) < BELL_MIN_INTERVAL {
+                trace!("dropping system bell rung too soon after the previous one");
+                return;
+            }
+        }
+        self.niri.last_bell_time = Some(now);
+
+        let bell = self.niri.config.borrow().bell.clone();
+
+        let mut id = None;
+        if let Some(surface) = surface {
+            let root = self.niri.find_root_shell_surface(&surface);
+            if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&root) {
+                id = Some(mapped.id().get());
+
+                if bell.urgent {
+                    mapped.set_urgent(true);
+                }
+
+                let window = mapped.window.clone();
+                if bell.flash {
+                    self.niri.layout.flash_window(&window);
+                }
+
+                self.niri.queue_redraw_all();
+            }
+        }
+
+        if let Some(command) = bell.spawn {
+            spawn(command, None);
+        }
+
+        self.ipc_window_bell(id);
+    }
+}
+delegate_xdg_system_bell!(State);
+
+impl XdgToplevelTagHandler for State {
+    fn set_tag(&mut self, toplevel: &XdgToplevel, tag: String) {
+        self.update_toplevel_tag(toplevel, |toplevel_tag| toplevel_tag.tag = Some(tag));
+    }
+
+    fn set_description(&mut self, toplevel: &XdgToplevel, description: String) {
+        self.update_toplevel_tag(toplevel, |toplevel_tag| {
+            toplevel_tag.description = Some(description)
+        });
+    }
+}
+delegate_xdg_toplevel_tag!(State);
+
+impl BackgroundEffectHandler for State {
+    fn background_effect_state(&mut self) -> &mut BackgroundEffectManagerState {
+        &mut self.niri.background_effect_state
+    }
+}
+delegate_background_effect!(State);
+
+impl ColorManagementHandler for State {
+    fn color_management_state(&mut self) -> &mut ColorManagementState {
+        &mut self.niri.color_management_state
+    }
+}
+delegate_color_management!(State);
+
 delegate_single_pixel_buffer!(State);
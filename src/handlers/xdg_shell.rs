@@ -1,6 +1,5 @@
 use std::cell::Cell;
 
-use calloop::Interest;
 use niri_config::PresetSize;
 use smithay::desktop::{
     find_popup_root_surface, get_popup_toplevel_coords, layer_map_for_output, utils, LayerSurface,
@@ -11,7 +10,7 @@ use smithay::input::pointer::Focus;
 use smithay::output::Output;
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_positioner::ConstraintAdjustment;
-use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::{self};
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::{self, XdgToplevel};
 use smithay::reexports::wayland_protocols_misc::server_decoration::server::org_kde_kwin_server_decoration;
 use smithay::reexports::wayland_server::protocol::wl_output;
 use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
@@ -19,10 +18,8 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{self, Resource, WEnum};
 use smithay::utils::{Logical, Rectangle, Serial};
 use smithay::wayland::compositor::{
-    add_blocker, add_pre_commit_hook, with_states, BufferAssignment, CompositorHandler as _,
-    HookId, SurfaceAttributes,
+    add_blocker, add_pre_commit_hook, with_states, BufferAssignment, HookId, SurfaceAttributes,
 };
-use smithay::wayland::dmabuf::get_dmabuf;
 use smithay::wayland::input_method::InputMethodSeat;
 use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorationState};
 use smithay::wayland::shell::wlr_layer::{self, Layer};
@@ -37,6 +34,7 @@ use smithay::{
 };
 use tracing::field::Empty;
 
+use super::compositor::add_buffer_blocker;
 use crate::input::move_grab::MoveGrab;
 use crate::input::resize_grab::ResizeGrab;
 use crate::input::touch_resize_grab::TouchResizeGrab;
@@ -47,7 +45,9 @@ use crate::utils::transaction::Transaction;
 use crate::utils::{
     get_monotonic_time, output_matches_name, send_scale_transform, update_tiled_state, ResizeEdge,
 };
-use crate::window::{InitialConfigureState, ResolvedWindowRules, Unmapped, WindowRef};
+use crate::window::{
+    InitialConfigureState, ResolvedWindowRules, RulesAtOpen, ToplevelTag, Unmapped, WindowRef,
+};
 
 impl XdgShellHandler for State {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
@@ -1027,11 +1027,15 @@ impl State {
         };
 
         let config = self.niri.config.borrow();
-        let rules = ResolvedWindowRules::compute(
+        let (rules, matches) = ResolvedWindowRules::compute_explained(
             &config.window_rules,
             WindowRef::Unmapped(unmapped),
             self.niri.is_at_startup,
         );
+        let rules_at_open = RulesAtOpen {
+            resolved: rules.clone(),
+            matches,
+        };
 
         let Unmapped { window, state, .. } = unmapped;
 
@@ -1161,6 +1165,7 @@ impl State {
         // Set the configured settings.
         *state = InitialConfigureState::Configured {
             rules,
+            rules_at_open,
             width,
             height,
             floating_width,
@@ -1388,6 +1393,29 @@ impl State {
             }
         }
     }
+
+    pub fn update_toplevel_tag(
+        &mut self,
+        toplevel: &XdgToplevel,
+        f: impl FnOnce(&mut ToplevelTag),
+    ) {
+        let Some(toplevel) = self.niri.xdg_shell_state.get_toplevel(toplevel) else {
+            return;
+        };
+        let surface = toplevel.wl_surface();
+
+        if let Some(unmapped) = self.niri.unmapped_windows.get_mut(surface) {
+            f(&mut unmapped.toplevel_tag);
+        } else if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
+            let mut toplevel_tag = mapped.toplevel_tag().clone();
+            f(&mut toplevel_tag);
+            mapped.set_toplevel_tag(toplevel_tag);
+        } else {
+            return;
+        }
+
+        self.update_window_rules(&toplevel);
+    }
 }
 
 fn unconstrain_with_padding(
@@ -1442,17 +1470,10 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
             return;
         };
 
-        let (got_unmapped, dmabuf, commit_serial) = with_states(surface, |states| {
-            let (got_unmapped, dmabuf) = {
+        let (got_unmapped, commit_serial) = with_states(surface, |states| {
+            let got_unmapped = {
                 let mut guard = states.cached_state.get::<SurfaceAttributes>();
-                match guard.pending().buffer.as_ref() {
-                    Some(BufferAssignment::NewBuffer(buffer)) => {
-                        let dmabuf = get_dmabuf(buffer).cloned().ok();
-                        (false, dmabuf)
-                    }
-                    Some(BufferAssignment::Removed) => (true, None),
-                    None => (false, None),
-                }
+                matches!(guard.pending().buffer, Some(BufferAssignment::Removed))
             };
 
             let role = states
@@ -1463,7 +1484,7 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
                 .unwrap();
             let serial = role.last_acked.as_ref().map(|c| c.serial);
 
-            (got_unmapped, dmabuf, serial)
+            (got_unmapped, serial)
         });
 
         let mut transaction_for_dmabuf = None;
@@ -1500,9 +1521,10 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
                         }
                     }
 
-                    // Delay dropping (and completing) the transaction until the dmabuf is ready.
-                    // If there's no dmabuf, this will be dropped by the end of this pre-commit
-                    // hook.
+                    // Delay dropping (and completing) the transaction until the dmabuf is ready,
+                    // either through its implicit fence or through the explicit sync acquire
+                    // point. If there's no dmabuf, this will be dropped by the end of this
+                    // pre-commit hook.
                     transaction_for_dmabuf = Some(transaction);
                 }
             }
@@ -1512,31 +1534,6 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
             error!("commit on a mapped surface without a configured serial");
         };
 
-        if let Some((blocker, source)) =
-            dmabuf.and_then(|dmabuf| dmabuf.generate_blocker(Interest::READ).ok())
-        {
-            if let Some(client) = surface.client() {
-                let res = state
-                    .niri
-                    .event_loop
-                    .insert_source(source, move |_, _, state| {
-                        // This surface is now ready for the transaction.
-                        drop(transaction_for_dmabuf.take());
-
-                        let display_handle = state.niri.display_handle.clone();
-                        state
-                            .client_compositor_state(&client)
-                            .blocker_cleared(state, &display_handle);
-
-                        Ok(())
-                    });
-                if res.is_ok() {
-                    add_blocker(surface, blocker);
-                    trace!("added dmabuf blocker");
-                }
-            }
-        }
-
         let window = mapped.window.clone();
         if got_unmapped {
             state.backend.with_primary_renderer(|renderer| {
@@ -1552,5 +1549,8 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
             // The toplevel remains mapped; clear any stored unmap snapshot.
             state.niri.layout.clear_unmap_snapshot(&window);
         }
+
+        // Once the buffer is ready, this surface is ready for the transaction.
+        add_buffer_blocker(state, surface, move |_| drop(transaction_for_dmabuf));
     })
 }
@@ -51,6 +51,7 @@ use crate::niri::{CastTarget, PointerVisibility, State};
 use crate::protocols::virtual_keyboard::VirtualKeyboard;
 use crate::ui::mru::{WindowMru, WindowMruUi};
 use crate::ui::screenshot_ui::ScreenshotUi;
+use crate::utils::backlight::BrightnessChange;
 use crate::utils::spawning::{spawn, spawn_sh};
 use crate::utils::{center, get_monotonic_time, CastSessionId, ResizeEdge};
 
@@ -707,6 +708,39 @@ impl State {
             Action::PowerOnMonitors => {
                 self.niri.activate_monitors(&mut self.backend);
             }
+            Action::PowerOffMonitor(output) => {
+                if let Some(output) = self.niri.output_by_name_match(&output).cloned() {
+                    self.niri
+                        .set_output_powered(&mut self.backend, &output, false);
+                }
+            }
+            Action::PowerOnMonitor(output) => {
+                if let Some(output) = self.niri.output_by_name_match(&output).cloned() {
+                    self.niri
+                        .set_output_powered(&mut self.backend, &output, true);
+                }
+            }
+            Action::BrightnessUp(step, output) => {
+                self.change_brightness(output.as_deref(), BrightnessChange::Up(step));
+            }
+            Action::BrightnessDown(step, output) => {
+                self.change_brightness(output.as_deref(), BrightnessChange::Down(step));
+            }
+            Action::SetBrightness(percent, output) => {
+                self.change_brightness(output.as_deref(), BrightnessChange::Set(percent));
+            }
+            Action::CreateVirtualOutput {
+                width,
+                height,
+                refresh,
+                scale,
+            } => {
+                self.backend
+                    .create_virtual_output(&mut self.niri, width, height, refresh, scale);
+            }
+            Action::DestroyVirtualOutput(output) => {
+                self.backend.destroy_virtual_output(&mut self.niri, &output);
+            }
             Action::ToggleDebugTint => {
                 self.backend.toggle_debug_tint();
                 self.niri.queue_redraw_all();
@@ -726,12 +760,31 @@ impl State {
                 let (token, _) = self.niri.activation_state.create_external_token(None);
                 spawn_sh(command, Some(token.clone()));
             }
+            Action::SendIpcEvent(name, args) => {
+                let args = args.into_iter().map(|arg| (arg.name, arg.value)).collect();
+                self.ipc_custom_action(name, args);
+            }
             Action::DoScreenTransition(delay_ms) => {
                 self.backend.with_primary_renderer(|renderer| {
                     self.niri.do_screen_transition(renderer, delay_ms);
                 });
             }
-            Action::ScreenshotScreen(write_to_disk, show_pointer, path) => {
+            Action::ToggleColorFilter => {
+                self.niri.toggle_color_filter();
+            }
+            Action::ToggleNightLight => {
+                self.toggle_night_light();
+            }
+            Action::ZoomIn => {
+                self.niri.zoom_in();
+            }
+            Action::ZoomOut => {
+                self.niri.zoom_out();
+            }
+            Action::ZoomReset => {
+                self.niri.zoom_reset();
+            }
+            Action::ScreenshotScreen(write_to_disk, show_pointer, color_filter, path) => {
                 let active = self.niri.layout.active_output().cloned();
                 if let Some(active) = active {
                     self.backend.with_primary_renderer(|renderer| {
@@ -740,6 +793,7 @@ impl State {
                             &active,
                             write_to_disk,
                             show_pointer,
+                            color_filter,
                             path,
                         ) {
                             warn!("error taking screenshot: {err:?}");
@@ -765,8 +819,8 @@ impl State {
                 self.niri.screenshot_ui.toggle_pointer();
                 self.niri.queue_redraw_all();
             }
-            Action::Screenshot(show_cursor, path) => {
-                self.open_screenshot_ui(show_cursor, path);
+            Action::Screenshot(show_cursor, color_filter, path) => {
+                self.open_screenshot_ui(show_cursor, color_filter, path);
                 self.niri.cancel_mru();
             }
             Action::ScreenshotWindow(write_to_disk, show_pointer, path) => {
@@ -4077,7 +4131,10 @@ impl State {
         &self,
         evt: &impl AbsolutePositionEvent<I>,
     ) -> Option<Point<f64, Logical>> {
-        self.compute_absolute_location(evt, self.niri.output_for_touch())
+        let pos = self.compute_absolute_location(evt, self.niri.output_for_touch())?;
+        // Touch doesn't move the magnifier focus, so map the touch through the magnification to
+        // the contents under the finger.
+        Some(self.niri.unmagnify(pos))
     }
 
     fn on_touch_down<I: InputBackend>(&mut self, evt: I::TouchDownEvent) {
@@ -4612,8 +4669,18 @@ fn allowed_when_locked(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::PowerOffMonitor(_)
+            | Action::PowerOnMonitor(_)
+            | Action::BrightnessUp(..)
+            | Action::BrightnessDown(..)
+            | Action::SetBrightness(..)
             | Action::SwitchLayout(_)
             | Action::ToggleKeyboardShortcutsInhibit
+            | Action::ToggleColorFilter
+            | Action::ToggleNightLight
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::ZoomReset
     )
 }
 
@@ -4625,6 +4692,16 @@ fn allowed_during_screenshot(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::PowerOffMonitor(_)
+            | Action::PowerOnMonitor(_)
+            | Action::BrightnessUp(..)
+            | Action::BrightnessDown(..)
+            | Action::SetBrightness(..)
+            | Action::ToggleColorFilter
+            | Action::ToggleNightLight
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::ZoomReset
             // The screenshot UI can handle these.
             | Action::MoveColumnLeft
             | Action::MoveColumnLeftOrToMonitorLeft
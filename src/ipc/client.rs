@@ -7,8 +7,11 @@ use anyhow::{anyhow, bail, Context};
 use niri_config::OutputName;
 use niri_ipc::socket::Socket;
 use niri_ipc::{
-    Action, Cast, CastKind, CastTarget, Event, KeyboardLayouts, LogicalOutput, Mode, Output,
-    OutputConfigChanged, Overview, Request, Response, Transform, Window, WindowLayout,
+    Action, BlockOutFrom, Cast, CastKind, CastTarget, ColumnDisplay, ColumnWidth,
+    DefaultWindowSize, Event, EventStreamFilter, FloatingPositionRelativeTo, KeyboardLayouts,
+    LogicalOutput, Mode, Output, OutputConfigChanged, OutputProfile, Overview, Request,
+    ResolvedWindowRules, Response, Transform, Window, WindowHeight, WindowLayout,
+    WindowRulesEvaluation, WorkspaceLayout,
 };
 use serde_json::json;
 
@@ -45,10 +48,41 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
         Msg::Windows => Request::Windows,
         Msg::Layers => Request::Layers,
         Msg::KeyboardLayouts => Request::KeyboardLayouts,
-        Msg::EventStream => Request::EventStream,
+        Msg::EventStream {
+            categories,
+            coalesce_layout_ms,
+        } => {
+            if categories.is_empty() && coalesce_layout_ms.is_none() {
+                // Keep working with older niri versions when no filter is requested.
+                Request::EventStream
+            } else {
+                Request::FilteredEventStream(EventStreamFilter {
+                    categories: categories.clone(),
+                    coalesce_layout_ms: *coalesce_layout_ms,
+                })
+            }
+        }
         Msg::RequestError => Request::ReturnError,
         Msg::OverviewState => Request::OverviewState,
         Msg::Casts => Request::Casts,
+        Msg::OutputProfiles => Request::OutputProfiles,
+        Msg::ForceOutputProfile { profile } => Request::ForceOutputProfile {
+            profile: profile.clone(),
+        },
+        Msg::LayoutTree => Request::LayoutTree,
+        Msg::RegisterCustomAction { name, description } => Request::RegisterCustomAction {
+            name: name.clone(),
+            description: description.clone(),
+        },
+        Msg::UnregisterCustomAction { name } => {
+            Request::UnregisterCustomAction { name: name.clone() }
+        }
+        Msg::CustomActions => Request::CustomActions,
+        Msg::ApplyConfigOverlay { kdl } => Request::ApplyConfigOverlay { kdl: kdl.clone() },
+        Msg::ConfigOverlays => Request::ConfigOverlays,
+        Msg::RemoveConfigOverlay { id } => Request::RemoveConfigOverlay { id: *id },
+        Msg::ResetConfigOverlays => Request::ResetConfigOverlays,
+        Msg::WindowRules { id } => Request::WindowRules { id: *id },
     };
 
     let mut socket = Socket::connect().context("error connecting to the niri socket")?;
@@ -215,11 +249,12 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 Ord::cmp(&a.output, &b.output)
                     .then_with(|| Ord::cmp(&a.layer, &b.layer))
                     .then_with(|| Ord::cmp(&a.namespace, &b.namespace))
+                    .then_with(|| Ord::cmp(&a.id, &b.id))
             });
             let mut iter = layers.iter().peekable();
 
             let print = |surface: &niri_ipc::LayerSurface| {
-                println!("    Surface:");
+                println!("    Surface ID {}:", surface.id);
                 println!("      Namespace: \"{}\"", &surface.namespace);
 
                 let interactivity = match surface.keyboard_interactivity {
@@ -408,7 +443,7 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("{is_active}{idx} {name}");
             }
         }
-        Msg::EventStream => {
+        Msg::EventStream { .. } => {
             let Response::Handled = response else {
                 bail!("unexpected response: expected Handled, got {response:?}");
             };
@@ -468,6 +503,10 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::WindowUrgencyChanged { id, urgent } => {
                         println!("Window {id}: urgency changed to {urgent}");
                     }
+                    Event::WindowBell { id } => match id {
+                        Some(id) => println!("Window {id}: rang the bell"),
+                        None => println!("Bell rang"),
+                    },
                     Event::WindowLayoutsChanged { changes } => {
                         println!("Window layouts changed: {changes:?}");
                     }
@@ -506,6 +545,31 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::CastStopped { stream_id } => {
                         println!("Cast stopped: stream id {stream_id}");
                     }
+                    Event::OutputProfileActivated { profile } => match profile {
+                        Some(profile) => println!("Output profile activated: {profile}"),
+                        None => println!("Output profile deactivated"),
+                    },
+                    Event::LayoutTreeChanged { workspaces } => {
+                        println!("Layout tree changed: {workspaces:?}");
+                    }
+                    Event::OutputsChanged { outputs } => {
+                        println!("Outputs changed: {outputs:?}");
+                    }
+                    Event::OutputChanged { output } => {
+                        println!("Output changed: {output:?}");
+                    }
+                    Event::LayerSurfacesChanged { layers } => {
+                        println!("Layer surfaces changed: {layers:?}");
+                    }
+                    Event::LayerSurfaceOpened { layer } => {
+                        println!("Layer surface opened or changed: {layer:?}");
+                    }
+                    Event::LayerSurfaceClosed { id } => {
+                        println!("Layer surface closed: {id}");
+                    }
+                    Event::CustomAction { name, args } => {
+                        println!("Custom action: {name} {args:?}");
+                    }
                 }
             }
         }
@@ -550,6 +614,161 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!();
             }
         }
+        Msg::OutputProfiles => {
+            let Response::OutputProfiles(profiles) = response else {
+                bail!("unexpected response: expected OutputProfiles, got {response:?}");
+            };
+
+            if json {
+                let profiles =
+                    serde_json::to_string(&profiles).context("error formatting response")?;
+                println!("{profiles}");
+                return Ok(());
+            }
+
+            if profiles.is_empty() {
+                println!("No output profiles.");
+                return Ok(());
+            }
+
+            for profile in profiles {
+                let OutputProfile {
+                    name,
+                    is_active,
+                    is_forced,
+                } = profile;
+
+                let status = match (is_active, is_forced) {
+                    (true, true) => " (active, forced)",
+                    (true, false) => " (active)",
+                    _ => "",
+                };
+                println!("{name}{status}");
+            }
+        }
+        Msg::ForceOutputProfile { .. }
+        | Msg::RegisterCustomAction { .. }
+        | Msg::UnregisterCustomAction { .. }
+        | Msg::RemoveConfigOverlay { .. }
+        | Msg::ResetConfigOverlays => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::CustomActions => {
+            let Response::CustomActions(actions) = response else {
+                bail!("unexpected response: expected CustomActions, got {response:?}");
+            };
+
+            if json {
+                let actions =
+                    serde_json::to_string(&actions).context("error formatting response")?;
+                println!("{actions}");
+                return Ok(());
+            }
+
+            if actions.is_empty() {
+                println!("No custom actions registered.");
+                return Ok(());
+            }
+
+            for action in actions {
+                println!("{}: {}", action.name, action.description);
+            }
+        }
+        Msg::ApplyConfigOverlay { .. } => {
+            let Response::ConfigOverlayApplied(id) = response else {
+                bail!("unexpected response: expected ConfigOverlayApplied, got {response:?}");
+            };
+
+            if json {
+                let id = serde_json::to_string(&id).context("error formatting response")?;
+                println!("{id}");
+                return Ok(());
+            }
+
+            println!("Applied config overlay {id}.");
+        }
+        Msg::ConfigOverlays => {
+            let Response::ConfigOverlays(overlays) = response else {
+                bail!("unexpected response: expected ConfigOverlays, got {response:?}");
+            };
+
+            if json {
+                let overlays =
+                    serde_json::to_string(&overlays).context("error formatting response")?;
+                println!("{overlays}");
+                return Ok(());
+            }
+
+            if overlays.is_empty() {
+                println!("No config overlays.");
+                return Ok(());
+            }
+
+            for (idx, overlay) in overlays.iter().enumerate() {
+                if idx != 0 {
+                    println!();
+                }
+
+                let status = if overlay.error.is_some() {
+                    " (failed to apply, skipped)"
+                } else {
+                    ""
+                };
+                println!("Overlay {}{status}:", overlay.id);
+                for line in overlay.kdl.trim().lines() {
+                    println!("  {line}");
+                }
+
+                if let Some(error) = &overlay.error {
+                    println!("  Error:");
+                    for line in error.trim().lines() {
+                        println!("    {line}");
+                    }
+                }
+            }
+        }
+        Msg::WindowRules { .. } => {
+            let Response::WindowRules(explanation) = response else {
+                bail!("unexpected response: expected WindowRules, got {response:?}");
+            };
+
+            if json {
+                let explanation =
+                    serde_json::to_string(&explanation).context("error formatting response")?;
+                println!("{explanation}");
+                return Ok(());
+            }
+
+            println!("Window ID {}:", explanation.window_id);
+            println!();
+            println!("Current state:");
+            print_window_rules(&explanation.current);
+            println!();
+            println!("When the window opened:");
+            match &explanation.at_open {
+                Some(evaluation) => print_window_rules(evaluation),
+                None => println!("  Window opened without an initial configure."),
+            }
+        }
+        Msg::LayoutTree => {
+            let Response::LayoutTree(workspaces) = response else {
+                bail!("unexpected response: expected LayoutTree, got {response:?}");
+            };
+
+            if json {
+                let workspaces =
+                    serde_json::to_string(&workspaces).context("error formatting response")?;
+                println!("{workspaces}");
+                return Ok(());
+            }
+
+            for workspace in &workspaces {
+                print_workspace_layout(workspace);
+                println!();
+            }
+        }
     }
 
     Ok(())
@@ -568,6 +787,8 @@ fn print_output(output: Output) -> anyhow::Result<()> {
         vrr_supported,
         vrr_enabled,
         logical,
+        mirror,
+        brightness,
     } = output;
 
     let serial = serial.as_deref().unwrap_or("Unknown");
@@ -612,6 +833,14 @@ fn print_output(output: Output) -> anyhow::Result<()> {
         println!("  Disabled");
     }
 
+    if let Some(mirror) = mirror {
+        println!("  Mirroring: {mirror}");
+    }
+
+    if let Some(brightness) = brightness {
+        println!("  Brightness: {:.0}%", brightness * 100.);
+    }
+
     if vrr_supported {
         let enabled = if vrr_enabled { "enabled" } else { "disabled" };
         println!("  Variable refresh rate: supported, {enabled}");
@@ -686,6 +915,12 @@ fn print_window(window: &Window) {
         println!("  App ID: (unset)");
     }
 
+    if let Some(tag) = &window.tag {
+        println!("  Tag: \"{tag}\"");
+    } else {
+        println!("  Tag: (unset)");
+    }
+
     println!(
         "  Is floating: {}",
         if window.is_floating { "yes" } else { "no" }
@@ -738,6 +973,253 @@ fn print_window(window: &Window) {
     );
 }
 
+fn print_window_rules(evaluation: &WindowRulesEvaluation) {
+    if evaluation.rules.is_empty() {
+        println!("  No window rules.");
+    }
+
+    for (idx, rule) in evaluation.rules.iter().enumerate() {
+        let status = match (rule.applied, rule.matched_by, rule.excluded_by) {
+            (_, _, Some(exclude)) => format!("excluded by exclude {}", exclude + 1),
+            (true, Some(m), None) => format!("applied, matched by match {}", m + 1),
+            (true, None, None) => String::from("applied, matches all windows"),
+            (false, _, None) => String::from("not matched"),
+        };
+        println!("  Window rule {}: {status}", idx + 1);
+
+        let matches = rule.matches.iter().enumerate().map(|(i, m)| {
+            let hit = rule.matched_by == Some(i);
+            ("match", m, hit)
+        });
+        let excludes = rule.excludes.iter().enumerate().map(|(i, m)| {
+            let hit = rule.excluded_by == Some(i);
+            ("exclude", m, hit)
+        });
+        for (node, props, hit) in matches.chain(excludes) {
+            let props = if props.is_empty() {
+                String::new()
+            } else {
+                format!(" {props}")
+            };
+            let hit = if hit { " (matched)" } else { "" };
+            println!("    {node}{props}{hit}");
+        }
+    }
+
+    println!("  Resolved rules:");
+    let lines = fmt_resolved_window_rules(&evaluation.resolved);
+    if lines.is_empty() {
+        println!("    None.");
+    }
+    for line in lines {
+        println!("    {line}");
+    }
+}
+
+fn fmt_resolved_window_rules(rules: &ResolvedWindowRules) -> Vec<String> {
+    let ResolvedWindowRules {
+        default_column_width,
+        default_window_height,
+        default_column_display,
+        default_floating_position,
+        open_on_output,
+        open_on_workspace,
+        open_maximized,
+        open_maximized_to_edges,
+        open_fullscreen,
+        open_floating,
+        open_focused,
+        min_width,
+        min_height,
+        max_width,
+        max_height,
+        draw_border_with_background,
+        opacity,
+        geometry_corner_radius,
+        clip_to_geometry,
+        baba_is_float,
+        block_out_from,
+        variable_refresh_rate,
+        scroll_factor,
+        tiled_state,
+    } = rules;
+
+    let fmt_size = |size: &DefaultWindowSize| match size {
+        DefaultWindowSize::WindowChooses => String::from("chosen by the window"),
+        DefaultWindowSize::Proportion(prop) => format!("proportion {prop}"),
+        DefaultWindowSize::Fixed(fixed) => format!("fixed {fixed}"),
+    };
+
+    let mut lines = Vec::new();
+    let mut push = |name: &str, value: Option<String>| {
+        if let Some(value) = value {
+            lines.push(format!("{name}: {value}"));
+        }
+    };
+
+    push(
+        "default-column-width",
+        default_column_width.as_ref().map(fmt_size),
+    );
+    push(
+        "default-window-height",
+        default_window_height.as_ref().map(fmt_size),
+    );
+    push(
+        "default-column-display",
+        default_column_display.map(|display| {
+            let display = match display {
+                ColumnDisplay::Normal => "normal",
+                ColumnDisplay::Tabbed => "tabbed",
+            };
+            String::from(display)
+        }),
+    );
+    push(
+        "default-floating-position",
+        default_floating_position.map(|pos| {
+            let relative_to = match pos.relative_to {
+                FloatingPositionRelativeTo::TopLeft => "top-left",
+                FloatingPositionRelativeTo::TopRight => "top-right",
+                FloatingPositionRelativeTo::BottomLeft => "bottom-left",
+                FloatingPositionRelativeTo::BottomRight => "bottom-right",
+                FloatingPositionRelativeTo::Top => "top",
+                FloatingPositionRelativeTo::Bottom => "bottom",
+                FloatingPositionRelativeTo::Left => "left",
+                FloatingPositionRelativeTo::Right => "right",
+            };
+            format!(
+                "x={} y={} relative-to={relative_to}",
+                fmt_rounded(pos.x),
+                fmt_rounded(pos.y),
+            )
+        }),
+    );
+    push("open-on-output", open_on_output.clone());
+    push("open-on-workspace", open_on_workspace.clone());
+    push("open-maximized", open_maximized.map(|x| x.to_string()));
+    push(
+        "open-maximized-to-edges",
+        open_maximized_to_edges.map(|x| x.to_string()),
+    );
+    push("open-fullscreen", open_fullscreen.map(|x| x.to_string()));
+    push("open-floating", open_floating.map(|x| x.to_string()));
+    push("open-focused", open_focused.map(|x| x.to_string()));
+    push("min-width", min_width.map(|x| x.to_string()));
+    push("min-height", min_height.map(|x| x.to_string()));
+    push("max-width", max_width.map(|x| x.to_string()));
+    push("max-height", max_height.map(|x| x.to_string()));
+    push(
+        "draw-border-with-background",
+        draw_border_with_background.map(|x| x.to_string()),
+    );
+    push("opacity", opacity.map(|x| x.to_string()));
+    push(
+        "geometry-corner-radius",
+        geometry_corner_radius.map(|r| format!("{} {} {} {}", r[0], r[1], r[2], r[3])),
+    );
+    push("clip-to-geometry", clip_to_geometry.map(|x| x.to_string()));
+    push("baba-is-float", baba_is_float.map(|x| x.to_string()));
+    push(
+        "block-out-from",
+        block_out_from.map(|x| {
+            let x = match x {
+                BlockOutFrom::Screencast => "screencast",
+                BlockOutFrom::ScreenCapture => "screen-capture",
+            };
+            String::from(x)
+        }),
+    );
+    push(
+        "variable-refresh-rate",
+        variable_refresh_rate.map(|x| x.to_string()),
+    );
+    push("scroll-factor", scroll_factor.map(|x| x.to_string()));
+    push("tiled-state", tiled_state.map(|x| x.to_string()));
+
+    lines
+}
+
+fn print_workspace_layout(workspace: &WorkspaceLayout) {
+    match &workspace.output {
+        Some(output) => println!(
+            "Workspace ID {} on output \"{output}\":",
+            workspace.workspace_id
+        ),
+        None => println!("Workspace ID {} (no output):", workspace.workspace_id),
+    }
+    println!(
+        "  View offset: {} (view position {})",
+        workspace.view_offset, workspace.view_pos
+    );
+
+    for (idx, column) in workspace.columns.iter().enumerate() {
+        let idx = idx + 1;
+        let active = if workspace.active_column_idx == Some(idx) && !workspace.is_floating_active {
+            " (active)"
+        } else {
+            ""
+        };
+        let visible = if column.is_visible {
+            ""
+        } else {
+            " (off-screen)"
+        };
+        println!("  Column {idx}:{active}{visible}");
+
+        let width = match column.width {
+            ColumnWidth::Proportion(proportion) => format!("{}%", proportion * 100.),
+            ColumnWidth::Fixed(width) => format!("{width} px"),
+        };
+        let full_width = if column.is_full_width {
+            ", full width"
+        } else {
+            ""
+        };
+        println!(
+            "    Width: {width}{full_width} (actual {})",
+            column.actual_width
+        );
+        println!("    X: {}", column.x);
+
+        let display = match column.display {
+            ColumnDisplay::Normal => "normal",
+            ColumnDisplay::Tabbed => "tabbed",
+        };
+        println!("    Display: {display}");
+
+        for (idx, tile) in column.tiles.iter().enumerate() {
+            let idx = idx + 1;
+            let active = if column.active_tile_idx == idx {
+                " (active)"
+            } else {
+                ""
+            };
+            let height = match tile.height {
+                WindowHeight::Auto { weight } => format!("auto (weight {weight})"),
+                WindowHeight::Fixed(height) => format!("{height} px"),
+                WindowHeight::Preset(idx) => format!("preset {}", idx + 1),
+            };
+            println!("    Window ID {}: height {height}{active}", tile.window_id);
+        }
+    }
+
+    if !workspace.floating_window_ids.is_empty() {
+        let active = if workspace.is_floating_active {
+            " (active)"
+        } else {
+            ""
+        };
+        let ids = workspace
+            .floating_window_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  Floating windows: {ids}{active}");
+    }
+}
+
 fn print_cast(cast: &Cast) {
     let active = if cast.is_active { "" } else { " (inactive)" };
     println!("Cast stream ID {}:{active}", cast.stream_id);
@@ -1,29 +1,36 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::{env, io, process};
+use std::time::Duration;
+use std::{env, io, mem, process};
 
 use anyhow::Context;
 use async_channel::{Receiver, Sender, TrySendError};
 use calloop::futures::Scheduler;
 use calloop::io::Async;
+use calloop::timer::TimeoutFuture;
 use directories::BaseDirs;
+use futures_util::future::{Fuse, FusedFuture as _};
 use futures_util::io::{AsyncReadExt, BufReader};
 use futures_util::{select_biased, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, FutureExt as _};
-use niri_config::OutputName;
+use niri_config::window_rule::{Match, RelativeTo, WindowRule};
+use niri_config::{BlockOutFrom, OutputName, PresetSize};
 use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
 use niri_ipc::{
-    Action, Event, KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response,
-    Timestamp, WindowLayout, Workspace,
+    Action, DefaultWindowSize, Event, EventStreamFilter, FloatingPositionRelativeTo,
+    KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response, Timestamp,
+    WindowLayout, WindowRuleMatch, WindowRulesEvaluation, WindowRulesExplanation, Workspace,
+    WorkspaceLayout,
 };
-use smithay::desktop::layer_map_for_output;
+use smithay::desktop::{layer_map_for_output, LayerSurface};
 use smithay::input::pointer::{
     CursorIcon, CursorImageStatus, Focus, GrabStartData as PointerGrabStartData,
 };
+use smithay::output::Output;
 use smithay::reexports::calloop::generic::Generic;
 use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction};
 use smithay::reexports::rustix::fs::unlink;
@@ -32,10 +39,11 @@ use smithay::wayland::shell::wlr_layer::{KeyboardInteractivity, Layer};
 
 use crate::backend::IpcOutputMap;
 use crate::input::pick_window_grab::PickWindowGrab;
+use crate::layer::mapped::LayerId;
 use crate::layout::workspace::WorkspaceId;
 use crate::niri::State;
 use crate::utils::{version, with_toplevel_role};
-use crate::window::Mapped;
+use crate::window::{Mapped, ResolvedWindowRules, RuleMatch, WindowRef};
 
 // If an event stream client fails to read events fast enough that we accumulate more than this
 // number in our buffer, we drop that event stream client.
@@ -59,14 +67,25 @@ struct ClientCtx {
 }
 
 struct EventStreamClient {
+    event_loop: LoopHandle<'static, State>,
     events: Receiver<Event>,
     disconnect: Receiver<()>,
     write: Box<dyn AsyncWrite + Unpin>,
+    /// Interval to coalesce layout events over, if requested.
+    coalesce_layout: Option<Duration>,
 }
 
 struct EventStreamSender {
     events: Sender<Event>,
     disconnect: Sender<()>,
+    filter: EventStreamFilter,
+}
+
+/// Layout events held back by an event stream client with coalescing.
+#[derive(Default)]
+struct CoalescedLayoutEvents {
+    window_layouts: Vec<(u64, WindowLayout)>,
+    layout_tree: Option<Vec<WorkspaceLayout>>,
 }
 
 impl IpcServer {
@@ -112,10 +131,27 @@ impl IpcServer {
         })
     }
 
+    /// Adds an event stream that is not backed by a client connection.
+    #[cfg(test)]
+    pub fn subscribe(&self, filter: EventStreamFilter) -> Receiver<Event> {
+        let (events_tx, events_rx) = async_channel::bounded(EVENT_STREAM_BUFFER_SIZE);
+        let (disconnect_tx, _) = async_channel::bounded(1);
+        self.event_streams.borrow_mut().push(EventStreamSender {
+            events: events_tx,
+            disconnect: disconnect_tx,
+            filter,
+        });
+        events_rx
+    }
+
     fn send_event(&self, event: Event) {
         let mut streams = self.event_streams.borrow_mut();
         let mut to_remove = Vec::new();
         for (idx, stream) in streams.iter_mut().enumerate() {
+            if !stream.filter.matches(&event) {
+                continue;
+            }
+
             match stream.events.try_send(event.clone()) {
                 Ok(()) => (),
                 Err(TrySendError::Closed(_)) => to_remove.push(idx),
@@ -206,7 +242,11 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
             .context("error parsing request")
             .map_err(|err| err.to_string());
         let requested_error = matches!(request, Ok(Request::ReturnError));
-        let requested_event_stream = matches!(request, Ok(Request::EventStream));
+        let event_stream_filter = match &request {
+            Ok(Request::EventStream) => Some(EventStreamFilter::default()),
+            Ok(Request::FilteredEventStream(filter)) => Some(filter.clone()),
+            _ => None,
+        };
 
         let reply = match request {
             Ok(request) => process(&ctx, request).await,
@@ -224,15 +264,22 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
         buf.push(b'\n');
         write.write_all(&buf).await.context("error writing reply")?;
 
-        if requested_event_stream {
+        if let Some(filter) = event_stream_filter {
             let (events_tx, events_rx) = async_channel::bounded(EVENT_STREAM_BUFFER_SIZE);
             let (disconnect_tx, disconnect_rx) = async_channel::bounded(1);
 
+            let coalesce_layout = filter
+                .coalesce_layout_ms
+                .filter(|ms| *ms > 0)
+                .map(|ms| Duration::from_millis(u64::from(ms)));
+
             // Spawn a task for the client.
             let client = EventStreamClient {
+                event_loop: ctx.event_loop.clone(),
                 events: events_rx,
                 disconnect: disconnect_rx,
                 write: Box::new(write) as _,
+                coalesce_layout,
             };
             let future = async move {
                 if let Err(err) = handle_event_stream_client(client).await {
@@ -247,6 +294,10 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
             {
                 let state = ctx.event_stream_state.borrow();
                 for event in state.replicate() {
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+
                     events_tx
                         .try_send(event)
                         .expect("initial event burst had more events than buffer size");
@@ -259,6 +310,7 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
                 let sender = EventStreamSender {
                     events: events_tx,
                     disconnect: disconnect_tx,
+                    filter,
                 };
                 streams.push(sender);
             }
@@ -290,39 +342,7 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
         Request::Layers => {
             let (tx, rx) = async_channel::bounded(1);
             ctx.event_loop.insert_idle(move |state| {
-                let mut layers = Vec::new();
-                for output in state.niri.global_space.outputs() {
-                    let name = output.name();
-                    for surface in layer_map_for_output(output).layers() {
-                        let layer = match surface.layer() {
-                            Layer::Background => niri_ipc::Layer::Background,
-                            Layer::Bottom => niri_ipc::Layer::Bottom,
-                            Layer::Top => niri_ipc::Layer::Top,
-                            Layer::Overlay => niri_ipc::Layer::Overlay,
-                        };
-                        let keyboard_interactivity =
-                            match surface.cached_state().keyboard_interactivity {
-                                KeyboardInteractivity::None => {
-                                    niri_ipc::LayerSurfaceKeyboardInteractivity::None
-                                }
-                                KeyboardInteractivity::Exclusive => {
-                                    niri_ipc::LayerSurfaceKeyboardInteractivity::Exclusive
-                                }
-                                KeyboardInteractivity::OnDemand => {
-                                    niri_ipc::LayerSurfaceKeyboardInteractivity::OnDemand
-                                }
-                            };
-
-                        layers.push(niri_ipc::LayerSurface {
-                            namespace: surface.namespace().to_owned(),
-                            output: name.clone(),
-                            layer,
-                            keyboard_interactivity,
-                        });
-                    }
-                }
-
-                let _ = tx.send_blocking(layers);
+                let _ = tx.send_blocking(state.ipc_layers());
             });
             let result = rx.recv().await;
             let layers = result.map_err(|_| String::from("error getting layers info"))?;
@@ -444,17 +464,117 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let output = result.map_err(|_| String::from("error getting active output info"))?;
             Response::FocusedOutput(output)
         }
-        Request::EventStream => Response::Handled,
+        Request::EventStream | Request::FilteredEventStream(_) => Response::Handled,
         Request::OverviewState => {
             let state = ctx.event_stream_state.borrow();
             let is_open = state.overview.is_open;
             Response::OverviewState(Overview { is_open })
         }
+        Request::LayoutTree => {
+            let state = ctx.event_stream_state.borrow();
+            let workspaces = state.layout_tree.workspaces.clone();
+            Response::LayoutTree(workspaces)
+        }
         Request::Casts => {
             let state = ctx.event_stream_state.borrow();
             let casts = state.casts.casts.values().cloned().collect();
             Response::Casts(casts)
         }
+        Request::OutputProfiles => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.ipc_output_profiles());
+            });
+            let result = rx.recv().await;
+            let profiles = result.map_err(|_| String::from("error getting output profiles"))?;
+            Response::OutputProfiles(profiles)
+        }
+        Request::ForceOutputProfile { profile } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.force_output_profile(profile));
+            });
+            let result = rx.recv().await;
+            let result = result.map_err(|_| String::from("error forcing output profile"))?;
+            result.map_err(|err| err.to_string())?;
+            Response::Handled
+        }
+        Request::RegisterCustomAction { name, description } => {
+            if name.is_empty() {
+                return Err(String::from("custom action name must not be empty"));
+            }
+
+            ctx.event_loop.insert_idle(move |state| {
+                if state
+                    .niri
+                    .hotkey_overlay
+                    .register_custom_action(name, description)
+                {
+                    state.niri.queue_redraw_all();
+                }
+            });
+            Response::Handled
+        }
+        Request::UnregisterCustomAction { name } => {
+            ctx.event_loop.insert_idle(move |state| {
+                if state.niri.hotkey_overlay.unregister_custom_action(&name) {
+                    state.niri.queue_redraw_all();
+                }
+            });
+            Response::Handled
+        }
+        Request::CustomActions => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.niri.hotkey_overlay.custom_actions());
+            });
+            let result = rx.recv().await;
+            let actions = result.map_err(|_| String::from("error getting custom actions"))?;
+            Response::CustomActions(actions)
+        }
+        Request::ApplyConfigOverlay { kdl } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.apply_config_overlay(kdl));
+            });
+            let result = rx.recv().await;
+            let id = result.map_err(|_| String::from("error applying config overlay"))??;
+            Response::ConfigOverlayApplied(id)
+        }
+        Request::ConfigOverlays => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.niri.config_overlays.clone());
+            });
+            let result = rx.recv().await;
+            let overlays = result.map_err(|_| String::from("error getting config overlays"))?;
+            Response::ConfigOverlays(overlays)
+        }
+        Request::RemoveConfigOverlay { id } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.remove_config_overlay(id));
+            });
+            let result = rx.recv().await;
+            let result = result.map_err(|_| String::from("error removing config overlay"))?;
+            result.map_err(|err| err.to_string())?;
+            Response::Handled
+        }
+        Request::ResetConfigOverlays => {
+            ctx.event_loop.insert_idle(move |state| {
+                state.reset_config_overlays();
+            });
+            Response::Handled
+        }
+        Request::WindowRules { id } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.ipc_window_rules(id));
+            });
+            let result = rx.recv().await;
+            let explanation = result.map_err(|_| String::from("error getting window rules"))??;
+            Response::WindowRules(explanation)
+        }
     };
 
     Ok(response)
@@ -487,31 +607,103 @@ fn validate_action(action: &Action) -> Result<(), String> {
 
 async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result<()> {
     let EventStreamClient {
+        event_loop,
         events,
         disconnect,
         mut write,
+        coalesce_layout,
     } = client;
 
-    while let Ok(event) = events.recv().await {
-        let mut buf = serde_json::to_vec(&event).context("error formatting event")?;
-        buf.push(b'\n');
+    let mut coalesced = CoalescedLayoutEvents::default();
+    let mut flush_timer = Fuse::terminated();
 
-        let res = select_biased! {
-            _ = disconnect.recv().fuse() => return Ok(()),
-            res = write.write_all(&buf).fuse() => res,
+    loop {
+        let event = select_biased! {
+            event = events.recv().fuse() => match event {
+                Ok(event) => Some(event),
+                Err(_) => break,
+            },
+            () = flush_timer => None,
         };
 
-        match res {
-            Ok(()) => (),
-            // Normal client disconnection.
-            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
-            res @ Err(_) => res.context("error writing event")?,
+        let mut to_send = Vec::new();
+        match event {
+            Some(event) => {
+                if let Some(interval) = coalesce_layout {
+                    let Some(event) = coalesced.push(event) else {
+                        if flush_timer.is_terminated() {
+                            flush_timer =
+                                TimeoutFuture::from_duration(&event_loop, interval).fuse();
+                        }
+                        continue;
+                    };
+
+                    // Send held back layout events first to keep the order consistent.
+                    to_send.extend(coalesced.take());
+                    flush_timer = Fuse::terminated();
+                    to_send.push(event);
+                } else {
+                    to_send.push(event);
+                }
+            }
+            None => to_send.extend(coalesced.take()),
+        }
+
+        for event in to_send {
+            let mut buf = serde_json::to_vec(&event).context("error formatting event")?;
+            buf.push(b'\n');
+
+            let res = select_biased! {
+                _ = disconnect.recv().fuse() => return Ok(()),
+                res = write.write_all(&buf).fuse() => res,
+            };
+
+            match res {
+                Ok(()) => (),
+                // Normal client disconnection.
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                res @ Err(_) => res.context("error writing event")?,
+            }
         }
     }
 
     Ok(())
 }
 
+impl CoalescedLayoutEvents {
+    /// Holds back a layout event, or returns the event back if it isn't a layout event.
+    fn push(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::WindowLayoutsChanged { changes } => {
+                for (id, layout) in changes {
+                    match self.window_layouts.iter_mut().find(|(x, _)| *x == id) {
+                        Some((_, existing)) => *existing = layout,
+                        None => self.window_layouts.push((id, layout)),
+                    }
+                }
+            }
+            Event::LayoutTreeChanged { workspaces } => self.layout_tree = Some(workspaces),
+            event => return Some(event),
+        }
+        None
+    }
+
+    fn take(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        let changes = mem::take(&mut self.window_layouts);
+        if !changes.is_empty() {
+            events.push(Event::WindowLayoutsChanged { changes });
+        }
+
+        if let Some(workspaces) = self.layout_tree.take() {
+            events.push(Event::LayoutTreeChanged { workspaces });
+        }
+
+        events
+    }
+}
+
 fn make_ipc_window(
     mapped: &Mapped,
     workspace_id: Option<WorkspaceId>,
@@ -521,6 +713,7 @@ fn make_ipc_window(
         id: mapped.id().get(),
         title: role.title.clone(),
         app_id: role.app_id.clone(),
+        tag: mapped.toplevel_tag().tag.clone(),
         pid: mapped.credentials().map(|c| c.pid),
         workspace_id: workspace_id.map(|id| id.get()),
         is_focused: mapped.is_focused(),
@@ -531,7 +724,204 @@ fn make_ipc_window(
     })
 }
 
+fn make_ipc_layer(output: &str, surface: &LayerSurface, id: LayerId) -> niri_ipc::LayerSurface {
+    let layer = match surface.layer() {
+        Layer::Background => niri_ipc::Layer::Background,
+        Layer::Bottom => niri_ipc::Layer::Bottom,
+        Layer::Top => niri_ipc::Layer::Top,
+        Layer::Overlay => niri_ipc::Layer::Overlay,
+    };
+    let keyboard_interactivity = match surface.cached_state().keyboard_interactivity {
+        KeyboardInteractivity::None => niri_ipc::LayerSurfaceKeyboardInteractivity::None,
+        KeyboardInteractivity::Exclusive => niri_ipc::LayerSurfaceKeyboardInteractivity::Exclusive,
+        KeyboardInteractivity::OnDemand => niri_ipc::LayerSurfaceKeyboardInteractivity::OnDemand,
+    };
+
+    niri_ipc::LayerSurface {
+        id: id.get(),
+        namespace: surface.namespace().to_owned(),
+        output: output.to_owned(),
+        layer,
+        keyboard_interactivity,
+    }
+}
+
+fn make_ipc_window_rules(
+    rules: &[WindowRule],
+    resolved: &ResolvedWindowRules,
+    matches: &[RuleMatch],
+) -> WindowRulesEvaluation {
+    let rules = rules
+        .iter()
+        .zip(matches)
+        .map(|(rule, m)| WindowRuleMatch {
+            matches: rule.matches.iter().map(format_match).collect(),
+            excludes: rule.excludes.iter().map(format_match).collect(),
+            applied: m.applied,
+            matched_by: m.matched_by,
+            excluded_by: m.excluded_by,
+        })
+        .collect();
+
+    WindowRulesEvaluation {
+        rules,
+        resolved: make_ipc_resolved_window_rules(resolved),
+    }
+}
+
+fn make_ipc_resolved_window_rules(rules: &ResolvedWindowRules) -> niri_ipc::ResolvedWindowRules {
+    let default_size = |size: Option<PresetSize>| match size {
+        None => DefaultWindowSize::WindowChooses,
+        Some(PresetSize::Proportion(prop)) => DefaultWindowSize::Proportion(prop),
+        Some(PresetSize::Fixed(fixed)) => DefaultWindowSize::Fixed(fixed),
+    };
+
+    let default_floating_position = rules.default_floating_position.map(|pos| {
+        let relative_to = match pos.relative_to {
+            RelativeTo::TopLeft => FloatingPositionRelativeTo::TopLeft,
+            RelativeTo::TopRight => FloatingPositionRelativeTo::TopRight,
+            RelativeTo::BottomLeft => FloatingPositionRelativeTo::BottomLeft,
+            RelativeTo::BottomRight => FloatingPositionRelativeTo::BottomRight,
+            RelativeTo::Top => FloatingPositionRelativeTo::Top,
+            RelativeTo::Bottom => FloatingPositionRelativeTo::Bottom,
+            RelativeTo::Left => FloatingPositionRelativeTo::Left,
+            RelativeTo::Right => FloatingPositionRelativeTo::Right,
+        };
+        niri_ipc::FloatingPosition {
+            x: pos.x.0,
+            y: pos.y.0,
+            relative_to,
+        }
+    });
+
+    let block_out_from = rules
+        .block_out_from
+        .map(|block_out_from| match block_out_from {
+            BlockOutFrom::Screencast => niri_ipc::BlockOutFrom::Screencast,
+            BlockOutFrom::ScreenCapture => niri_ipc::BlockOutFrom::ScreenCapture,
+        });
+
+    niri_ipc::ResolvedWindowRules {
+        default_column_width: rules.default_width.map(default_size),
+        default_window_height: rules.default_height.map(default_size),
+        default_column_display: rules.default_column_display,
+        default_floating_position,
+        open_on_output: rules.open_on_output.clone(),
+        open_on_workspace: rules.open_on_workspace.clone(),
+        open_maximized: rules.open_maximized,
+        open_maximized_to_edges: rules.open_maximized_to_edges,
+        open_fullscreen: rules.open_fullscreen,
+        open_floating: rules.open_floating,
+        open_focused: rules.open_focused,
+        min_width: rules.min_width,
+        min_height: rules.min_height,
+        max_width: rules.max_width,
+        max_height: rules.max_height,
+        draw_border_with_background: rules.draw_border_with_background,
+        opacity: rules.opacity,
+        geometry_corner_radius: rules.geometry_corner_radius.map(<[f32; 4]>::from),
+        clip_to_geometry: rules.clip_to_geometry,
+        baba_is_float: rules.baba_is_float,
+        block_out_from,
+        variable_refresh_rate: rules.variable_refresh_rate,
+        scroll_factor: rules.scroll_factor,
+        tiled_state: rules.tiled_state,
+    }
+}
+
+/// Formats the properties of a window rule matcher like in the config.
+fn format_match(m: &Match) -> String {
+    let Match {
+        app_id,
+        title,
+        tag,
+        is_active,
+        is_focused,
+        is_active_in_column,
+        is_floating,
+        is_window_cast_target,
+        is_urgent,
+        at_startup,
+    } = m;
+
+    let regexes = [("app-id", app_id), ("title", title), ("tag", tag)];
+    let regexes = regexes.into_iter().filter_map(|(key, re)| {
+        let re = re.as_ref()?;
+        Some(format!("{key}={}", format_kdl_string(re.0.as_str())))
+    });
+
+    let flags = [
+        ("is-active", is_active),
+        ("is-focused", is_focused),
+        ("is-active-in-column", is_active_in_column),
+        ("is-floating", is_floating),
+        ("is-window-cast-target", is_window_cast_target),
+        ("is-urgent", is_urgent),
+        ("at-startup", at_startup),
+    ];
+    let flags = flags
+        .into_iter()
+        .filter_map(|(key, value)| Some(format!("{key}={}", (*value)?)));
+
+    regexes.chain(flags).collect::<Vec<_>>().join(" ")
+}
+
+/// Formats a string as a KDL string, using a raw string if it contains characters to escape.
+fn format_kdl_string(s: &str) -> String {
+    if !s.contains(['\\', '"']) {
+        return format!("\"{s}\"");
+    }
+
+    // Add enough hashes so that no quote inside the string can end the raw string.
+    let mut hashes = String::from("#");
+    while s.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{s}\"{hashes}")
+}
+
 impl State {
+    pub fn ipc_window_rules(&self, id: Option<u64>) -> Result<WindowRulesExplanation, String> {
+        let mapped = match id {
+            Some(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let Some((_, mapped)) = window else {
+                    return Err(format!("window with id {id} does not exist"));
+                };
+                mapped
+            }
+            None => {
+                let Some(mapped) = self.niri.layout.focus() else {
+                    return Err(String::from("no window is focused"));
+                };
+                mapped
+            }
+        };
+
+        let config = self.niri.config.borrow();
+        let rules = &config.window_rules;
+
+        let (resolved, matches) = ResolvedWindowRules::compute_explained(
+            rules,
+            WindowRef::Mapped(mapped),
+            self.niri.is_at_startup,
+        );
+        let current = make_ipc_window_rules(rules, &resolved, &matches);
+
+        // The matchers are formatted from the current config, so they only line up with the
+        // evaluation at open if the window rules weren't added or removed since.
+        let at_open = mapped
+            .rules_at_open()
+            .filter(|at_open| at_open.matches.len() == rules.len())
+            .map(|at_open| make_ipc_window_rules(rules, &at_open.resolved, &at_open.matches));
+
+        Ok(WindowRulesExplanation {
+            window_id: mapped.id().get(),
+            current,
+            at_open,
+        })
+    }
+
     pub fn ipc_keyboard_layouts_changed(&mut self) {
         let keyboard = self.niri.seat.get_keyboard().unwrap();
         let keyboard_layouts = keyboard.with_xkb_state(self, |context| {
@@ -584,6 +974,7 @@ impl State {
         self.ipc_refresh_workspaces();
         self.ipc_refresh_windows();
         self.ipc_refresh_overview();
+        self.ipc_refresh_layout_tree();
     }
 
     fn ipc_refresh_workspaces(&mut self) {
@@ -721,6 +1112,8 @@ impl State {
             let mut changed =
                 ipc_win.workspace_id != workspace_id || ipc_win.is_floating != mapped.is_floating();
 
+            changed |= ipc_win.tag != mapped.toplevel_tag().tag;
+
             changed |= with_toplevel_role(mapped.toplevel(), |role| {
                 ipc_win.title != role.title || ipc_win.app_id != role.app_id
             });
@@ -788,6 +1181,160 @@ impl State {
         }
     }
 
+    fn ipc_refresh_layout_tree(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        // The layout tree can change every frame during animations and gestures, so wait for it
+        // to settle rather than flooding the clients with events.
+        if self.niri.layout.is_changing_continuously() {
+            return;
+        }
+
+        let _span = tracy_client::span!("State::ipc_refresh_layout_tree");
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.layout_tree;
+
+        let workspaces = self.niri.layout.ipc_layout_tree(|mapped| mapped.id().get());
+        if state.workspaces == workspaces {
+            return;
+        }
+
+        let event = Event::LayoutTreeChanged { workspaces };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
+    pub fn ipc_refresh_outputs(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let _span = tracy_client::span!("State::ipc_refresh_outputs");
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.outputs;
+
+        let ipc_outputs = self.backend.ipc_outputs();
+        let ipc_outputs = ipc_outputs.lock().unwrap();
+
+        let mut events = Vec::new();
+
+        // Connecting or disconnecting outputs replaces the whole list, since the names of other
+        // outputs can change too (e.g. with disable-monitor-names).
+        let same_outputs = ipc_outputs.len() == state.outputs.len()
+            && ipc_outputs
+                .values()
+                .all(|output| state.outputs.contains_key(&output.name));
+        if same_outputs {
+            for output in ipc_outputs.values() {
+                if state.outputs.get(&output.name) != Some(output) {
+                    let output = output.clone();
+                    events.push(Event::OutputChanged { output });
+                }
+            }
+        } else {
+            let outputs = ipc_outputs.values().cloned().collect();
+            events.push(Event::OutputsChanged { outputs });
+        }
+
+        for event in events {
+            state.apply(event.clone());
+            server.send_event(event);
+        }
+    }
+
+    /// Lists all layer surfaces, including the ones that haven't mapped yet.
+    pub fn ipc_layers(&self) -> Vec<niri_ipc::LayerSurface> {
+        let mut layers = Vec::new();
+        for output in self.niri.global_space.outputs() {
+            let name = output.name();
+            for surface in layer_map_for_output(output).layers() {
+                let id = if let Some(mapped) = self.niri.mapped_layer_surfaces.get(surface) {
+                    mapped.id()
+                } else if let Some(id) = self.niri.unmapped_layer_surfaces.get(surface.wl_surface())
+                {
+                    *id
+                } else {
+                    error!("layer surface is neither mapped nor unmapped");
+                    continue;
+                };
+
+                layers.push(make_ipc_layer(&name, surface, id));
+            }
+        }
+        layers
+    }
+
+    pub fn ipc_refresh_layers(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let _span = tracy_client::span!("State::ipc_refresh_layers");
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.layers;
+
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+
+        for output in self.niri.global_space.outputs() {
+            let name = output.name();
+            for surface in layer_map_for_output(output).layers() {
+                // Unmapped surfaces don't have an id yet.
+                let Some(mapped) = self.niri.mapped_layer_surfaces.get(surface) else {
+                    continue;
+                };
+
+                let layer = make_ipc_layer(&name, surface, mapped.id());
+                seen.insert(layer.id);
+
+                if state.layers.get(&layer.id) != Some(&layer) {
+                    events.push(Event::LayerSurfaceOpened { layer });
+                }
+            }
+        }
+
+        for id in state.layers.keys() {
+            if !seen.contains(id) {
+                events.push(Event::LayerSurfaceClosed { id: *id });
+            }
+        }
+
+        for event in events {
+            state.apply(event.clone());
+            server.send_event(event);
+        }
+    }
+
+    /// Sends an event if the properties of a mapped layer surface have changed.
+    pub fn ipc_refresh_layer(&mut self, output: &Output, layer: &LayerSurface) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let Some(mapped) = self.niri.mapped_layer_surfaces.get(layer) else {
+            return;
+        };
+
+        let _span = tracy_client::span!("State::ipc_refresh_layer");
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.layers;
+
+        let layer = make_ipc_layer(&output.name(), layer, mapped.id());
+        if state.layers.get(&layer.id) == Some(&layer) {
+            return;
+        }
+
+        let event = Event::LayerSurfaceOpened { layer };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
     pub fn ipc_refresh_overview(&mut self) {
         let Some(server) = &self.niri.ipc_server else {
             return;
@@ -932,6 +1479,55 @@ impl State {
         server.send_event(event);
     }
 
+    pub fn ipc_window_bell(&mut self, id: Option<u64>) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+        let mut state = server.event_stream_state.borrow_mut();
+
+        let event = Event::WindowBell { id };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
+    pub fn ipc_output_profiles(&self) -> Vec<niri_ipc::OutputProfile> {
+        let config = self.niri.config.borrow();
+        let is_forced = self.niri.forced_output_profile.is_some();
+
+        config
+            .output_profiles
+            .iter()
+            .map(|profile| {
+                let is_active = self.niri.output_profile.as_ref() == Some(&profile.name);
+                niri_ipc::OutputProfile {
+                    name: profile.name.clone(),
+                    is_active,
+                    is_forced: is_active && is_forced,
+                }
+            })
+            .collect()
+    }
+
+    pub fn ipc_refresh_output_profile(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.output_profile;
+        let profile = &self.niri.output_profile;
+
+        if state.profile == *profile {
+            return;
+        }
+
+        let event = Event::OutputProfileActivated {
+            profile: profile.clone(),
+        };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
     pub fn ipc_screenshot_taken(&mut self, path: Option<String>) {
         let Some(server) = &self.niri.ipc_server else {
             return;
@@ -942,4 +1538,99 @@ impl State {
         state.apply(event.clone());
         server.send_event(event);
     }
+
+    pub fn ipc_custom_action(&mut self, name: String, args: HashMap<String, String>) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let event = Event::CustomAction { name, args };
+        server.send_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_layout(width: i32) -> WindowLayout {
+        WindowLayout {
+            pos_in_scrolling_layout: Some((1, 1)),
+            tile_size: (f64::from(width), 100.),
+            window_size: (width, 100),
+            tile_pos_in_workspace_view: Some((0., 0.)),
+            window_offset_in_tile: (0., 0.),
+        }
+    }
+
+    fn workspace_layout(view_pos: f64) -> WorkspaceLayout {
+        WorkspaceLayout {
+            workspace_id: 1,
+            output: None,
+            columns: Vec::new(),
+            active_column_idx: None,
+            view_offset: 0.,
+            view_pos,
+            floating_window_ids: Vec::new(),
+            is_floating_active: false,
+        }
+    }
+
+    #[test]
+    fn coalesced_layout_events_keep_latest_state() {
+        let mut coalesced = CoalescedLayoutEvents::default();
+
+        // A burst of layout events, like during a resize animation.
+        for width in [100, 200, 300] {
+            let event = Event::WindowLayoutsChanged {
+                changes: vec![(1, window_layout(width)), (2, window_layout(width + 1))],
+            };
+            assert!(coalesced.push(event).is_none());
+
+            let event = Event::LayoutTreeChanged {
+                workspaces: vec![workspace_layout(f64::from(width))],
+            };
+            assert!(coalesced.push(event).is_none());
+        }
+        let event = Event::WindowLayoutsChanged {
+            changes: vec![(3, window_layout(50))],
+        };
+        assert!(coalesced.push(event).is_none());
+
+        let events = coalesced.take();
+        let [Event::WindowLayoutsChanged { changes }, Event::LayoutTreeChanged { workspaces }] =
+            &events[..]
+        else {
+            panic!("unexpected coalesced events: {events:?}");
+        };
+        assert_eq!(
+            changes,
+            &[
+                (1, window_layout(300)),
+                (2, window_layout(301)),
+                (3, window_layout(50)),
+            ]
+        );
+        assert_eq!(workspaces, &[workspace_layout(300.)]);
+
+        // Everything was flushed.
+        assert!(coalesced.take().is_empty());
+    }
+
+    #[test]
+    fn coalesced_layout_events_pass_other_events_through() {
+        let mut coalesced = CoalescedLayoutEvents::default();
+
+        let event = Event::WindowLayoutsChanged {
+            changes: vec![(1, window_layout(100))],
+        };
+        assert!(coalesced.push(event).is_none());
+
+        let event = coalesced.push(Event::WindowClosed { id: 1 });
+        assert!(matches!(event, Some(Event::WindowClosed { id: 1 })));
+
+        // The held back layout event is still there to be sent before the other event.
+        let events = coalesced.take();
+        assert!(matches!(&events[..], [Event::WindowLayoutsChanged { .. }]));
+    }
 }
@@ -1,20 +1,24 @@
 use niri_config::utils::MergeWith as _;
 use niri_config::{Config, LayerRule};
-use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Kind;
 use smithay::desktop::{LayerSurface, PopupManager};
-use smithay::utils::{Logical, Point, Scale, Size};
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 use smithay::wayland::shell::wlr_layer::{ExclusiveZone, Layer};
 
 use super::ResolvedLayerRules;
 use crate::animation::Clock;
 use crate::layout::shadow::Shadow;
 use crate::niri_render_elements;
+use crate::protocols::background_effect::blur_region;
+use crate::render_helpers::blur::{
+    region_rects, BlurArea, BlurRenderElement, BlurRequests, SurfaceBlur,
+};
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
-use crate::render_helpers::surface::push_elements_from_surface_tree;
+use crate::render_helpers::surface::{push_elements_from_surface_tree, SurfaceRenderElement};
 use crate::render_helpers::RenderTarget;
+use crate::utils::id::IdCounter;
 use crate::utils::{baba_is_float_offset, round_logical_in_physical};
 
 #[derive(Debug)]
@@ -22,6 +26,9 @@ pub struct MappedLayer {
     /// The surface itself.
     surface: LayerSurface,
 
+    /// Unique ID of this layer surface.
+    id: LayerId,
+
     /// Up-to-date rules.
     rules: ResolvedLayerRules,
 
@@ -31,6 +38,9 @@ pub struct MappedLayer {
     /// The shadow around the surface.
     shadow: Shadow,
 
+    /// The blur behind the surface.
+    blur: SurfaceBlur,
+
     /// The view size for the layer surface's output.
     view_size: Size<f64, Logical>,
 
@@ -43,15 +53,32 @@ pub struct MappedLayer {
 
 niri_render_elements! {
     LayerSurfaceRenderElement<R> => {
-        Wayland = WaylandSurfaceRenderElement<R>,
+        Wayland = SurfaceRenderElement<R>,
         SolidColor = SolidColorRenderElement,
         Shadow = ShadowRenderElement,
+        Blur = BlurRenderElement,
+    }
+}
+
+static LAYER_ID_COUNTER: IdCounter = IdCounter::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerId(u64);
+
+impl LayerId {
+    pub fn next() -> LayerId {
+        LayerId(LAYER_ID_COUNTER.next())
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
     }
 }
 
 impl MappedLayer {
     pub fn new(
         surface: LayerSurface,
+        id: LayerId,
         rules: ResolvedLayerRules,
         view_size: Size<f64, Logical>,
         scale: f64,
@@ -65,11 +92,13 @@ impl MappedLayer {
 
         Self {
             surface,
+            id,
             rules,
             block_out_buffer: SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.]),
             view_size,
             scale,
             shadow: Shadow::new(shadow_config),
+            blur: SurfaceBlur::default(),
             clock,
         }
     }
@@ -113,6 +142,10 @@ impl MappedLayer {
         &self.surface
     }
 
+    pub fn id(&self) -> LayerId {
+        self.id
+    }
+
     pub fn rules(&self) -> &ResolvedLayerRules {
         &self.rules
     }
@@ -160,6 +193,7 @@ impl MappedLayer {
         renderer: &mut R,
         location: Point<f64, Logical>,
         target: RenderTarget,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(LayerSurfaceRenderElement<R>),
     ) {
         let scale = Scale::from(self.scale);
@@ -192,6 +226,26 @@ impl MappedLayer {
                 Kind::ScanoutCandidate,
                 &mut |elem| push(elem.into()),
             );
+
+            // The blur goes right behind the surface.
+            if let Some(requests) = blur {
+                let blur_area = BlurArea::resolve(self.rules.background_effect, || {
+                    blur_region(surface).map(|region| region_rects(&region))
+                });
+                if let Some(blur_area) = blur_area {
+                    let location = location.to_physical_precise_round(scale).to_logical(scale);
+                    let geometry = Rectangle::new(location, self.block_out_buffer.size());
+                    let radius = self.rules.geometry_corner_radius.unwrap_or_default();
+                    self.blur.render(
+                        renderer,
+                        requests,
+                        &blur_area,
+                        geometry,
+                        radius,
+                        &mut |elem| push(elem.into()),
+                    );
+                }
+            }
         }
 
         let location = location.to_physical_precise_round(scale).to_logical(scale);
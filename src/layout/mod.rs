@@ -41,15 +41,14 @@ use niri_config::utils::MergeWith as _;
 use niri_config::{
     Config, CornerRadius, LayoutPart, PresetSize, Workspace as WorkspaceConfig, WorkspaceReference,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout};
+use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout, WorkspaceLayout};
 use scrolling::{Column, ColumnWidth};
-use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::utils::RescaleRenderElement;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::output::{self, Output};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
-use tile::{Tile, TileRenderElement};
+use tile::{Tile, TileFocus, TileRenderElement};
 use workspace::{WorkspaceAddWindowTarget, WorkspaceId};
 
 pub use self::monitor::MonitorRenderElement;
@@ -59,10 +58,12 @@ use crate::animation::{Animation, Clock};
 use crate::input::swipe_tracker::SwipeTracker;
 use crate::layout::scrolling::ScrollDirection;
 use crate::niri_render_elements;
+use crate::render_helpers::blur::BlurRequests;
 use crate::render_helpers::offscreen::OffscreenData;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::surface::SurfaceRenderElement;
 use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::{BakedBuffer, RenderTarget};
 use crate::rubber_band::RubberBand;
@@ -110,7 +111,7 @@ pub struct SizeFrac;
 
 niri_render_elements! {
     LayoutElementRenderElement<R> => {
-        Wayland = WaylandSurfaceRenderElement<R>,
+        Wayland = SurfaceRenderElement<R>,
         SolidColor = SolidColorRenderElement,
     }
 }
@@ -279,6 +280,13 @@ pub trait LayoutElement {
 
     fn rules(&self) -> &ResolvedWindowRules;
 
+    /// Region behind the element that the element asked to blur.
+    ///
+    /// The region is relative to the element's visual geometry.
+    fn blur_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        None
+    }
+
     /// Runs periodic clean-up tasks.
     fn refresh(&self);
 
@@ -610,7 +618,7 @@ impl Options {
             layout: config.layout.clone(),
             animations: config.animations.clone(),
             gestures: config.gestures,
-            overview: config.overview,
+            overview: config.overview.clone(),
             disable_resize_throttling: config.debug.disable_resize_throttling,
             disable_transactions: config.debug.disable_transactions,
             deactivate_unfocused_windows: config.debug.deactivate_unfocused_windows,
@@ -1652,6 +1660,16 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Returns the layout of all workspaces for IPC.
+    pub fn ipc_layout_tree(&self, window_id: impl Fn(&W) -> u64) -> Vec<WorkspaceLayout> {
+        self.workspaces()
+            .map(|(mon, _, ws)| {
+                let output = mon.map(|mon| mon.output_name().clone());
+                ws.ipc_layout(output, &window_id)
+            })
+            .collect()
+    }
+
     pub fn with_windows_mut(&mut self, mut f: impl FnMut(&mut W, Option<&Output>)) {
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             f(move_.tile.window_mut(), Some(&move_.output));
@@ -2723,6 +2741,16 @@ impl<W: LayoutElement> Layout<W> {
         false
     }
 
+    /// Returns whether the layout can change every frame right now.
+    ///
+    /// This is the case during animations, gestures and interactive resizes.
+    pub fn is_changing_continuously(&self) -> bool {
+        self.monitors().any(|mon| mon.are_transitions_ongoing())
+            || self
+                .workspaces()
+                .any(|(_, _, ws)| ws.is_interactive_resize_ongoing())
+    }
+
     pub fn update_render_elements(&mut self, output: Option<&Output>) {
         let _span = tracy_client::span!("Layout::update_render_elements");
 
@@ -2734,7 +2762,9 @@ impl<W: LayoutElement> Layout<W> {
                 let pos_within_output = move_.tile_render_location(zoom);
                 let view_rect =
                     Rectangle::new(pos_within_output.upscale(-1.), output_size(&move_.output));
-                move_.tile.update_render_elements(true, view_rect);
+                move_
+                    .tile
+                    .update_render_elements(TileFocus::Active, view_rect);
             }
         }
 
@@ -2755,8 +2785,15 @@ impl<W: LayoutElement> Layout<W> {
                 let is_active = self.is_active
                     && idx == *active_monitor_idx
                     && !matches!(self.interactive_move, Some(InteractiveMoveState::Moving(_)));
+                let focus = if is_active {
+                    TileFocus::Active
+                } else if idx == *active_monitor_idx {
+                    TileFocus::ActiveInBackground
+                } else {
+                    TileFocus::OtherOutput
+                };
                 mon.set_overview_progress(self.overview_progress.as_ref());
-                mon.update_render_elements(is_active);
+                mon.update_render_elements(focus);
             }
         }
     }
@@ -2897,6 +2934,7 @@ impl<W: LayoutElement> Layout<W> {
             let Some(name) = ws.name() else { continue };
             if let Some(config) = config.workspaces.iter().find(|w| &w.name.0 == name) {
                 ws.update_layout_config(config.layout.clone().map(|x| x.0));
+                ws.update_background_image_config(config.background_image.clone());
             }
         }
 
@@ -4596,6 +4634,22 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    pub fn flash_window(&mut self, window: &W::Id) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
+            if move_.tile.window().id() == window {
+                move_.tile.start_bell_flash();
+                return;
+            }
+        }
+
+        for ws in self.workspaces_mut() {
+            if let Some(tile) = ws.tiles_mut().find(|tile| tile.window().id() == window) {
+                tile.start_bell_flash();
+                return;
+            }
+        }
+    }
+
     pub fn store_unmap_snapshot(&mut self, renderer: &mut GlesRenderer, window: &W::Id) {
         let _span = tracy_client::span!("Layout::store_unmap_snapshot");
 
@@ -4724,6 +4778,7 @@ impl<W: LayoutElement> Layout<W> {
         renderer: &mut R,
         output: &Output,
         target: RenderTarget,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(RescaleRenderElement<TileRenderElement<R>>),
     ) {
         if self.update_render_elements_time != self.clock.now() {
@@ -4743,7 +4798,7 @@ impl<W: LayoutElement> Layout<W> {
         let location = move_.tile_render_location(zoom);
         move_
             .tile
-            .render(renderer, location, true, target, &mut |elem| {
+            .render(renderer, location, true, target, blur, &mut |elem| {
                 push(RescaleRenderElement::from_element(
                     elem,
                     location.to_physical_precise_round(scale),
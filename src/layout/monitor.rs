@@ -12,7 +12,7 @@ use smithay::utils::{Logical, Point, Rectangle, Size};
 
 use super::insert_hint_element::{InsertHintElement, InsertHintRenderElement};
 use super::scrolling::{Column, ColumnWidth};
-use super::tile::Tile;
+use super::tile::{Tile, TileFocus};
 use super::workspace::{
     compute_working_area, OutputId, Workspace, WorkspaceAddWindowTarget, WorkspaceId,
     WorkspaceRenderElement,
@@ -21,6 +21,7 @@ use super::{compute_overview_zoom, ActivateWindow, HitType, LayoutElement, Optio
 use crate::animation::{Animation, Clock};
 use crate::input::swipe_tracker::SwipeTracker;
 use crate::niri_render_elements;
+use crate::render_helpers::blur::BlurRequests;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::solid_color::SolidColorRenderElement;
@@ -87,6 +88,8 @@ pub struct Monitor<W: LayoutElement> {
     pub(super) options: Rc<Options>,
     /// Layout config overrides for this monitor.
     layout_config: Option<niri_config::LayoutPart>,
+    /// Background image for workspaces on this monitor that don't set their own.
+    background_image: Option<niri_config::BackgroundImage>,
 }
 
 #[derive(Debug)]
@@ -345,6 +348,7 @@ impl<W: LayoutElement> Monitor<W> {
             base_options,
             options,
             layout_config,
+            background_image: None,
         }
     }
 
@@ -1088,15 +1092,20 @@ impl<W: LayoutElement> Monitor<W> {
                 .any(|ws| ws.are_transitions_ongoing())
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool) {
+    pub fn update_render_elements(&mut self, focus: TileFocus) {
         let mut insert_hint_ws_geo = None;
         let insert_hint_ws_id = self
             .insert_hint
             .as_ref()
             .and_then(|hint| hint.workspace.existing_id());
 
+        // Workspaces can move between monitors, so resolve their background image here.
+        for ws in &mut self.workspaces {
+            ws.update_background_image(self.background_image.as_ref());
+        }
+
         for (ws, geo) in self.workspaces_with_render_geo_mut(true) {
-            ws.update_render_elements(is_active);
+            ws.update_render_elements(focus);
 
             if Some(ws.id()) == insert_hint_ws_id {
                 insert_hint_ws_geo = Some(geo);
@@ -1220,6 +1229,18 @@ impl<W: LayoutElement> Monitor<W> {
         true
     }
 
+    pub fn update_background_image(
+        &mut self,
+        background_image: Option<niri_config::BackgroundImage>,
+    ) -> bool {
+        if self.background_image == background_image {
+            return false;
+        }
+
+        self.background_image = background_image;
+        true
+    }
+
     pub fn update_shaders(&mut self) {
         for ws in &mut self.workspaces {
             ws.update_shaders();
@@ -1523,6 +1544,45 @@ impl<W: LayoutElement> Monitor<W> {
             .filter(move |(_ws, geo)| geo.intersection(output_geo).is_some())
     }
 
+    /// Returns workspaces to draw the backgrounds of, with their render geometry and alpha.
+    ///
+    /// Outside the overview, when background images are involved, workspace backgrounds
+    /// crossfade during a workspace switch instead of moving together with the workspaces.
+    pub fn workspace_backgrounds_with_render_geo(
+        &self,
+    ) -> impl Iterator<Item = (&Workspace<W>, Rectangle<f64, Logical>, f32)> {
+        let crossfade = self.workspace_switch.is_some()
+            && self.overview_progress.is_none()
+            && self
+                .workspaces_with_render_geo()
+                .any(|(ws, _geo)| ws.has_background_image());
+
+        let sliding = (!crossfade).then(|| {
+            self.workspaces_with_render_geo()
+                .map(|(ws, geo)| (ws, geo, 1.))
+        });
+
+        let crossfading = crossfade.then(|| {
+            let last_idx = self.workspaces.len() - 1;
+            let render_idx = self.workspace_render_idx().clamp(0., last_idx as f64);
+            let first_idx = render_idx.floor() as usize;
+            let second_idx = render_idx.ceil() as usize;
+            let progress = (render_idx - first_idx as f64) as f32;
+
+            let geo = Rectangle::from_size(self.view_size);
+            // The second workspace background fades in on top of the first one.
+            let second =
+                (second_idx != first_idx).then(|| (&self.workspaces[second_idx], geo, progress));
+            let first = Some((&self.workspaces[first_idx], geo, 1.));
+            [second, first]
+        });
+
+        sliding
+            .into_iter()
+            .flatten()
+            .chain(crossfading.into_iter().flatten().flatten())
+    }
+
     pub fn workspaces_with_render_geo_mut(
         &mut self,
         cull: bool,
@@ -1672,6 +1732,7 @@ impl<W: LayoutElement> Monitor<W> {
         renderer: &mut R,
         target: RenderTarget,
         focus_ring: bool,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(MonitorRenderElement<R>),
     ) {
         let _span = tracy_client::span!("Monitor::render_workspaces");
@@ -1734,7 +1795,7 @@ impl<W: LayoutElement> Monitor<W> {
                 }};
             }
 
-            ws.render_floating(renderer, target, focus_ring, push!());
+            ws.render_floating(renderer, target, focus_ring, blur, push!());
 
             if let Some(loc) = insert_hint_render_loc {
                 if loc.workspace == InsertWorkspace::Existing(ws.id()) {
@@ -1743,7 +1804,7 @@ impl<W: LayoutElement> Monitor<W> {
                 }
             }
 
-            ws.render_scrolling(renderer, target, focus_ring, push!());
+            ws.render_scrolling(renderer, target, focus_ring, blur, push!());
         }
     }
 
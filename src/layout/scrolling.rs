@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use niri_config::utils::MergeWith as _;
 use niri_config::{CenterFocusedColumn, PresetSize, Struts};
-use niri_ipc::{ColumnDisplay, SizeChange, WindowLayout};
+use niri_ipc::{ColumnDisplay, ColumnLayout, SizeChange, TileLayout, WindowLayout};
 use ordered_float::NotNan;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
@@ -13,13 +13,14 @@ use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::monitor::InsertPosition;
 use super::tab_indicator::{TabIndicator, TabIndicatorRenderElement, TabInfo};
-use super::tile::{Tile, TileRenderElement, TileRenderSnapshot};
+use super::tile::{Tile, TileFocus, TileRenderElement, TileRenderSnapshot};
 use super::workspace::{InteractiveResize, ResolvedSize};
 use super::{ConfigureIntent, HitType, InteractiveResizeData, LayoutElement, Options, RemovedTile};
 use crate::animation::{Animation, Clock};
 use crate::input::swipe_tracker::SwipeTracker;
 use crate::layout::SizingMode;
 use crate::niri_render_elements;
+use crate::render_helpers::blur::BlurRequests;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
@@ -395,16 +396,20 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             || !self.closing_windows.is_empty()
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool) {
+    pub fn update_render_elements(&mut self, focus: TileFocus) {
         let view_pos = Point::from((self.view_pos(), 0.));
         let view_size = self.view_size;
         let active_idx = self.active_column_idx;
         for (col_idx, (col, col_x)) in self.columns_mut().enumerate() {
-            let is_active = is_active && col_idx == active_idx;
+            let focus = if col_idx == active_idx {
+                focus
+            } else {
+                focus.unfocused()
+            };
             let col_off = Point::from((col_x, 0.));
             let col_pos = view_pos - col_off - col.render_offset();
             let view_rect = Rectangle::new(col_pos, view_size);
-            col.update_render_elements(is_active, view_rect);
+            col.update_render_elements(focus, view_rect);
         }
     }
 
@@ -2432,6 +2437,34 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             })
     }
 
+    /// Returns the columns for the IPC layout tree.
+    ///
+    /// Visibility is computed for the target view position to avoid IPC spam during animations.
+    pub fn ipc_columns(&self, window_id: &impl Fn(&W) -> u64) -> Vec<ColumnLayout> {
+        let view_pos = self.target_view_pos();
+        let view_end = view_pos + self.view_size.w;
+
+        let xs = self.column_xs(self.data.iter().copied());
+        zip(zip(&self.columns, &self.data), xs)
+            .map(|((col, data), x)| ColumnLayout {
+                tiles: zip(&col.tiles, &col.data)
+                    .map(|(tile, data)| TileLayout {
+                        window_id: window_id(tile.window()),
+                        height: data.height.into(),
+                    })
+                    .collect(),
+                // Our indices are 1-based, consistent with the actions.
+                active_tile_idx: col.active_tile_idx + 1,
+                width: col.width.into(),
+                is_full_width: col.is_full_width,
+                display: col.display_mode,
+                x,
+                actual_width: data.width,
+                is_visible: view_pos < x + data.width && x < view_end,
+            })
+            .collect()
+    }
+
     pub(super) fn insert_hint_area(
         &self,
         position: InsertPosition,
@@ -2902,6 +2935,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         renderer: &mut R,
         target: RenderTarget,
         focus_ring: bool,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(ScrollingSpaceRenderElement<R>),
     ) {
         let scale = Scale::from(self.scale);
@@ -2955,7 +2989,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                     continue;
                 }
 
-                tile.render(renderer, tile_pos, focus_ring, target, &mut |elem| {
+                tile.render(renderer, tile_pos, focus_ring, target, blur, &mut |elem| {
                     push(elem.into())
                 });
             }
@@ -3512,6 +3546,10 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.view_offset_gesture_end(None);
     }
 
+    pub fn is_interactive_resize_ongoing(&self) -> bool {
+        self.interactive_resize.is_some()
+    }
+
     pub fn interactive_resize_begin(&mut self, window: W::Id, edges: ResizeEdge) -> bool {
         if self.interactive_resize.is_some() {
             return false;
@@ -3916,6 +3954,25 @@ impl From<PresetSize> for ColumnWidth {
     }
 }
 
+impl From<ColumnWidth> for niri_ipc::ColumnWidth {
+    fn from(value: ColumnWidth) -> Self {
+        match value {
+            ColumnWidth::Proportion(p) => Self::Proportion(p),
+            ColumnWidth::Fixed(f) => Self::Fixed(f),
+        }
+    }
+}
+
+impl From<WindowHeight> for niri_ipc::WindowHeight {
+    fn from(value: WindowHeight) -> Self {
+        match value {
+            WindowHeight::Auto { weight } => Self::Auto { weight },
+            WindowHeight::Fixed(f) => Self::Fixed(f),
+            WindowHeight::Preset(idx) => Self::Preset(idx),
+        }
+    }
+}
+
 impl WindowHeight {
     const fn auto_1() -> Self {
         Self::Auto { weight: 1. }
@@ -4095,14 +4152,21 @@ impl<W: LayoutElement> Column<W> {
             || self.tiles.iter().any(Tile::are_transitions_ongoing)
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
+    pub fn update_render_elements(&mut self, focus: TileFocus, view_rect: Rectangle<f64, Logical>) {
+        let is_focused = focus.is_focused();
         let active_idx = self.active_tile_idx;
         for (tile_idx, (tile, tile_off)) in self.tiles_mut().enumerate() {
-            let is_active = is_active && tile_idx == active_idx;
+            let focus = if tile_idx == active_idx {
+                focus
+            } else if is_focused {
+                TileFocus::ActiveColumn
+            } else {
+                focus
+            };
 
             let mut tile_view_rect = view_rect;
             tile_view_rect.loc -= tile_off + tile.render_offset();
-            tile.update_render_elements(is_active, tile_view_rect);
+            tile.update_render_elements(focus, tile_view_rect);
         }
 
         let config = self.tab_indicator.config();
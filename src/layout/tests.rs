@@ -854,6 +854,7 @@ impl Op {
                 layout.ensure_named_workspace(&WorkspaceConfig {
                     name: WorkspaceName(format!("ws{ws_name}")),
                     open_on_output: output_name.map(|name| format!("output{name}")),
+                    background_image: None,
                     layout: layout_config.map(|x| niri_config::WorkspaceLayoutPart(*x)),
                 });
             }
@@ -3726,6 +3727,47 @@ fn workspace_render_geo_at_fractional_scale() {
     );
 }
 
+#[test]
+fn ipc_layout_tree_columns() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::ToggleColumnTabbedDisplay,
+        Op::CompleteAnimations,
+    ];
+
+    let layout = check_ops(ops);
+    let tree = layout.ipc_layout_tree(|win| *win.id() as u64);
+
+    let ws = &tree[0];
+    assert_eq!(ws.output.as_deref(), Some("output1"));
+    assert_eq!(ws.active_column_idx, Some(2));
+    assert!(ws.floating_window_ids.is_empty());
+
+    let ids = |col: &niri_ipc::ColumnLayout| -> Vec<u64> {
+        col.tiles.iter().map(|tile| tile.window_id).collect()
+    };
+    assert_eq!(ws.columns.len(), 2);
+    assert_eq!(ids(&ws.columns[0]), [1]);
+    assert_eq!(ids(&ws.columns[1]), [2, 3]);
+    assert_eq!(ws.columns[1].active_tile_idx, 2);
+    assert_eq!(ws.columns[1].display, ColumnDisplay::Tabbed);
+    assert!(ws.columns[1].is_visible);
+
+    // The empty workspace below has no columns.
+    assert_eq!(tree[1].active_column_idx, None);
+    assert!(tree[1].columns.is_empty());
+}
+
 fn parent_id_causes_loop(layout: &Layout<TestWindow>, id: usize, mut parent_id: usize) -> bool {
     if parent_id == id {
         return true;
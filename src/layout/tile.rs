@@ -2,7 +2,7 @@ use core::f64;
 use std::rc::Rc;
 
 use niri_config::utils::MergeWith as _;
-use niri_config::{Color, CornerRadius, GradientInterpolation};
+use niri_config::{Color, CornerRadius, DimInactive, GradientInterpolation};
 use niri_ipc::WindowLayout;
 use smithay::backend::renderer::element::{Element, Kind};
 use smithay::backend::renderer::gles::GlesRenderer;
@@ -15,24 +15,33 @@ use super::{
     HitType, LayoutElement, LayoutElementRenderElement, LayoutElementRenderSnapshot, Options,
     SizeFrac, RESIZE_ANIMATION_THRESHOLD,
 };
-use crate::animation::{Animation, Clock};
+use crate::animation::{Animation, Clock, Curve};
 use crate::layout::SizingMode;
 use crate::niri_render_elements;
+use crate::render_helpers::blur::{BlurArea, BlurRenderElement, BlurRequests, SurfaceBlur};
 use crate::render_helpers::border::BorderRenderElement;
 use crate::render_helpers::clipped_surface::{ClippedSurfaceRenderElement, RoundedCornerDamage};
 use crate::render_helpers::damage::ExtraDamage;
+use crate::render_helpers::dim::DimRenderElement;
 use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenRenderElement};
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::resize::ResizeRenderElement;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::surface::SurfaceRenderElement;
 use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::Transaction;
 use crate::utils::{
     baba_is_float_offset, round_logical_in_physical, round_logical_in_physical_max1,
 };
 
+/// Duration of the bell flash.
+const BELL_FLASH_DURATION_MS: u64 = 600;
+
+/// Number of times the focus ring and border blink during the bell flash.
+const BELL_FLASH_BLINKS: u32 = 2;
+
 /// Toplevel window with decorations.
 #[derive(Debug)]
 pub struct Tile<W: LayoutElement> {
@@ -48,6 +57,15 @@ pub struct Tile<W: LayoutElement> {
     /// The shadow around the window.
     shadow: Shadow,
 
+    /// The blur behind the window.
+    blur: SurfaceBlur,
+
+    /// The dimming of the window when it's inactive.
+    dim: Dim,
+
+    /// Focus as of the last render elements update.
+    focus: TileFocus,
+
     /// This tile's current sizing mode.
     ///
     /// This will update only when the `window` actually goes maximized or fullscreen, rather than
@@ -94,6 +112,9 @@ pub struct Tile<W: LayoutElement> {
     /// The animation of the tile's opacity.
     pub(super) alpha_animation: Option<AlphaAnimation>,
 
+    /// The focus ring and border flashing after the window rang the bell.
+    bell_flash: Option<Animation>,
+
     /// Offset during the initial interactive move rubberband.
     pub(super) interactive_move_offset: Point<f64, Logical>,
 
@@ -127,6 +148,8 @@ niri_render_elements! {
         Resize = ResizeRenderElement,
         Border = BorderRenderElement,
         Shadow = ShadowRenderElement,
+        Blur = BlurRenderElement,
+        Dim = DimRenderElement,
         ClippedSurface = ClippedSurfaceRenderElement<R>,
         Offscreen = OffscreenRenderElement,
         ExtraDamage = ExtraDamage,
@@ -159,6 +182,45 @@ struct MoveAnimation {
     from: f64,
 }
 
+#[derive(Debug)]
+struct Dim {
+    /// Dimming config with the window rules applied.
+    config: DimInactive,
+    /// Dim and desaturation at the start of the animation.
+    from: (f64, f64),
+    /// Target dim and desaturation.
+    to: (f64, f64),
+    /// The animation from `from` to `to`.
+    anim: Option<Animation>,
+    /// Dim and desaturation as of the last render elements update.
+    current: (f64, f64),
+    /// Black overlay drawn on top of the window for plain dimming.
+    overlay: SolidColorBuffer,
+    /// The same overlay with rounded corners.
+    rounded_overlay: BorderRenderElement,
+    /// Buffer for rendering the window before passing it through the desaturation shader.
+    offscreen: OffscreenBuffer,
+    /// Extra damage for desaturation changes.
+    damage: ExtraDamage,
+}
+
+/// Where a tile is relative to the keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFocus {
+    /// The tile is focused.
+    Active,
+    /// The tile is focused, but the layout isn't, or an interactive move is ongoing.
+    ///
+    /// It shows an inactive focus ring, but isn't dimmed.
+    ActiveInBackground,
+    /// The tile is not focused, but it is in the focused column.
+    ActiveColumn,
+    /// The tile is not focused and is on the active output.
+    Inactive,
+    /// The tile is on an output other than the active one.
+    OtherOutput,
+}
+
 #[derive(Debug)]
 pub(super) struct AlphaAnimation {
     pub(super) anim: Animation,
@@ -171,6 +233,115 @@ pub(super) struct AlphaAnimation {
     offscreen: OffscreenBuffer,
 }
 
+impl TileFocus {
+    pub fn is_active(self) -> bool {
+        self == Self::Active
+    }
+
+    /// Returns whether this is the focused tile, whether the layout is focused or not.
+    pub fn is_focused(self) -> bool {
+        matches!(self, Self::Active | Self::ActiveInBackground)
+    }
+
+    /// Returns the focus of an unfocused sibling of a tile with this focus.
+    pub fn unfocused(self) -> Self {
+        match self {
+            Self::Active | Self::ActiveInBackground | Self::ActiveColumn => Self::Inactive,
+            x => x,
+        }
+    }
+}
+
+impl Dim {
+    fn new(config: DimInactive) -> Self {
+        Self {
+            config,
+            from: (0., 0.),
+            to: (0., 0.),
+            anim: None,
+            current: (0., 0.),
+            overlay: SolidColorBuffer::new((0., 0.), [0., 0., 0., 0.]),
+            rounded_overlay: BorderRenderElement::empty(),
+            offscreen: OffscreenBuffer::default(),
+            damage: ExtraDamage::new(),
+        }
+    }
+
+    fn target(&self, focus: TileFocus) -> (f64, f64) {
+        let config = &self.config;
+        if !config.on {
+            return (0., 0.);
+        }
+
+        let strength = match focus {
+            TileFocus::Active | TileFocus::ActiveInBackground => return (0., 0.),
+            TileFocus::ActiveColumn => config.active_column_strength,
+            TileFocus::Inactive => config.strength,
+            TileFocus::OtherOutput => config.other_outputs_strength,
+        };
+        (strength, config.desaturation)
+    }
+
+    fn set_focus(&mut self, focus: TileFocus, clock: Clock, config: niri_config::Animation) {
+        let to = self.target(focus);
+        if self.to == to {
+            return;
+        }
+
+        self.from = self.current;
+        self.to = to;
+        self.anim = Some(Animation::new(clock, 0., 1., 0., config));
+    }
+
+    fn update(&mut self, size: Size<f64, Logical>, radius: CornerRadius, scale: f64) {
+        let current = match &self.anim {
+            Some(anim) => {
+                let p = anim.value();
+                let (from_dim, from_desat) = self.from;
+                let (to_dim, to_desat) = self.to;
+                (
+                    from_dim + (to_dim - from_dim) * p,
+                    from_desat + (to_desat - from_desat) * p,
+                )
+            }
+            None => self.to,
+        };
+
+        if self.current.1 != current.1 {
+            self.damage.damage_all();
+        }
+        self.current = current;
+        self.damage.set_size(size);
+
+        let dim = current.0.clamp(0., 1.) as f32;
+        self.overlay.update(size, [0., 0., 0., dim]);
+
+        let color = Color::from_color32f(self.overlay.color());
+        let area = Rectangle::from_size(size);
+        self.rounded_overlay.update(
+            size,
+            area,
+            GradientInterpolation::default(),
+            color,
+            color,
+            0.,
+            area,
+            0.,
+            radius,
+            scale as f32,
+            1.,
+        );
+    }
+
+    /// Returns the current dim and desaturation, or `None` if the window isn't dimmed.
+    fn value(&self) -> Option<(f32, f32)> {
+        let (dim, desaturation) = self.current;
+        let dim = dim.clamp(0., 1.) as f32;
+        let desaturation = desaturation.clamp(0., 1.) as f32;
+        (dim > 0. || desaturation > 0.).then_some((dim, desaturation))
+    }
+}
+
 impl<W: LayoutElement> Tile<W> {
     pub fn new(
         window: W,
@@ -183,6 +354,7 @@ impl<W: LayoutElement> Tile<W> {
         let border_config = options.layout.border.merged_with(&rules.border);
         let focus_ring_config = options.layout.focus_ring.merged_with(&rules.focus_ring);
         let shadow_config = options.layout.shadow.merged_with(&rules.shadow);
+        let dim_config = options.layout.dim_inactive.merged_with(&rules.dim_inactive);
         let sizing_mode = window.sizing_mode();
 
         Self {
@@ -190,6 +362,9 @@ impl<W: LayoutElement> Tile<W> {
             border: FocusRing::new(border_config.into()),
             focus_ring: FocusRing::new(focus_ring_config),
             shadow: Shadow::new(shadow_config),
+            blur: SurfaceBlur::default(),
+            dim: Dim::new(dim_config),
+            focus: TileFocus::Inactive,
             sizing_mode,
             fullscreen_backdrop: SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.]),
             restore_to_floating: false,
@@ -202,6 +377,7 @@ impl<W: LayoutElement> Tile<W> {
             move_x_animation: None,
             move_y_animation: None,
             alpha_animation: None,
+            bell_flash: None,
             interactive_move_offset: Point::from((0., 0.)),
             unmap_snapshot: None,
             rounded_corner_damage: Default::default(),
@@ -248,6 +424,12 @@ impl<W: LayoutElement> Tile<W> {
 
         let shadow_config = self.options.layout.shadow.merged_with(&rules.shadow);
         self.shadow.update_config(shadow_config);
+
+        self.dim.config = self
+            .options
+            .layout
+            .dim_inactive
+            .merged_with(&rules.dim_inactive);
     }
 
     pub fn update_shaders(&mut self) {
@@ -397,6 +579,12 @@ impl<W: LayoutElement> Tile<W> {
         let shadow_config = self.options.layout.shadow.merged_with(&rules.shadow);
         self.shadow.update_config(shadow_config);
 
+        self.dim.config = self
+            .options
+            .layout
+            .dim_inactive
+            .merged_with(&rules.dim_inactive);
+
         let window_size = self.window_size();
         let radius = rules
             .geometry_corner_radius
@@ -435,6 +623,22 @@ impl<W: LayoutElement> Tile<W> {
                 self.alpha_animation = None;
             }
         }
+
+        if let Some(flash) = &self.bell_flash {
+            if flash.is_done() {
+                self.bell_flash = None;
+            }
+        }
+
+        if let Some(anim) = &self.dim.anim {
+            if anim.is_done() {
+                self.dim.anim = None;
+            }
+        }
+    }
+
+    pub fn is_bell_flashing(&self) -> bool {
+        self.bell_flash.is_some()
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
@@ -450,18 +654,36 @@ impl<W: LayoutElement> Tile<W> {
                 .alpha_animation
                 .as_ref()
                 .is_some_and(|alpha| !alpha.anim.is_done())
+            || self.bell_flash.is_some()
+            || self.dim.anim.is_some()
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
+    pub fn update_render_elements(&mut self, focus: TileFocus, view_rect: Rectangle<f64, Logical>) {
+        self.focus = focus;
+        let is_active = focus.is_active();
+
         let rules = self.window.rules();
         let animated_tile_size = self.animated_tile_size();
         let expanded_progress = self.expanded_progress();
 
+        let dim_anim_config = self.options.animations.window_dim.0;
+        self.dim
+            .set_focus(focus, self.clock.clone(), dim_anim_config);
+        let dim_radius = rules
+            .geometry_corner_radius
+            .unwrap_or_default()
+            .scaled_by(1. - expanded_progress as f32);
+        self.dim
+            .update(self.animated_window_size(), dim_radius, self.scale);
+
         let draw_border_with_background = rules
             .draw_border_with_background
             .unwrap_or_else(|| !self.window.has_ssd());
         let border_width = self.visual_border_width().unwrap_or(0.);
 
+        // The bell flash blinks the border and the focus ring with the urgent color.
+        let is_urgent = self.window.is_urgent() || self.is_bell_flash_on();
+
         // Do the inverse of tile_size() in order to handle the unfullscreen animation for windows
         // that were smaller than the fullscreen size, and therefore their animated_window_size() is
         // currently much smaller than the tile size.
@@ -479,7 +701,7 @@ impl<W: LayoutElement> Tile<W> {
             border_window_size,
             is_active,
             !draw_border_with_background,
-            self.window.is_urgent(),
+            is_urgent,
             Rectangle::new(
                 view_rect.loc - Point::from((border_width, border_width)),
                 view_rect.size,
@@ -515,7 +737,7 @@ impl<W: LayoutElement> Tile<W> {
             animated_tile_size,
             is_active,
             !draw_focus_ring_with_background,
-            self.window.is_urgent(),
+            is_urgent,
             view_rect,
             radius,
             self.scale,
@@ -529,6 +751,10 @@ impl<W: LayoutElement> Tile<W> {
         self.scale
     }
 
+    pub fn focus(&self) -> TileFocus {
+        self.focus
+    }
+
     pub fn render_offset(&self) -> Point<f64, Logical> {
         let mut offset = Point::from((0., 0.));
 
@@ -554,6 +780,29 @@ impl<W: LayoutElement> Tile<W> {
         )));
     }
 
+    pub fn start_bell_flash(&mut self) {
+        self.bell_flash = Some(Animation::ease(
+            self.clock.clone(),
+            0.,
+            1.,
+            0.,
+            BELL_FLASH_DURATION_MS,
+            Curve::Linear,
+        ));
+    }
+
+    fn is_bell_flash_on(&self) -> bool {
+        self.bell_flash.as_ref().is_some_and(|flash| {
+            if flash.is_done() {
+                return false;
+            }
+
+            // Split the flash into equal on and off phases, starting with on.
+            let phase = (flash.value() * f64::from(BELL_FLASH_BLINKS) * 2.) as u32;
+            phase % 2 == 0
+        })
+    }
+
     pub fn resize_animation(&self) -> Option<&Animation> {
         self.resize_animation.as_ref().map(|resize| &resize.anim)
     }
@@ -1013,6 +1262,7 @@ impl<W: LayoutElement> Tile<W> {
         location: Point<f64, Logical>,
         focus_ring: bool,
         target: RenderTarget,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(TileRenderElement<R>),
     ) {
         let _span = tracy_client::span!("Tile::render_inner");
@@ -1042,7 +1292,6 @@ impl<W: LayoutElement> Tile<W> {
         let location = location + self.bob_offset();
 
         let window_loc = self.window_loc();
-        let window_size = self.window_size();
         let animated_window_size = self.animated_window_size();
         let window_render_loc = location + window_loc;
         let area = Rectangle::new(window_render_loc, animated_window_size);
@@ -1067,6 +1316,162 @@ impl<W: LayoutElement> Tile<W> {
             &mut |elem| push(elem.into()),
         );
 
+        // Render the window itself. Plain dimming is a black overlay on top, while desaturation
+        // needs to pass the window through a shader.
+        let mut pushed_dim = false;
+        if let Some((dim, desaturation)) = self.dim.value() {
+            if desaturation > 0. {
+                if let Some(program) = DimRenderElement::shader(renderer).cloned() {
+                    let gles_renderer = renderer.as_gles_renderer();
+                    let mut elements = Vec::new();
+                    self.render_window(
+                        gles_renderer,
+                        window_render_loc,
+                        win_alpha,
+                        clip_to_geometry,
+                        radius,
+                        target,
+                        &mut |elem| elements.push(elem),
+                    );
+
+                    match self.dim.offscreen.render(gles_renderer, scale, &elements) {
+                        Ok((elem, _sync, data)) => {
+                            self.window.set_offscreen_data(Some(data));
+
+                            let damage = self.dim.damage.clone();
+                            push(damage.with_location(window_render_loc).into());
+
+                            let elem = DimRenderElement::new(elem, program, dim, desaturation);
+                            push(elem.into());
+                            pushed_dim = true;
+                        }
+                        Err(err) => {
+                            warn!("error rendering window to offscreen for desaturation: {err:?}");
+                        }
+                    }
+                }
+            } else if radius != CornerRadius::default() && BorderRenderElement::has_shader(renderer)
+            {
+                let elem = self.dim.rounded_overlay.clone();
+                push(elem.with_location(window_render_loc).into());
+            } else {
+                let elem = SolidColorRenderElement::from_buffer(
+                    &self.dim.overlay,
+                    window_render_loc,
+                    1.,
+                    Kind::Unspecified,
+                );
+                push(elem.into());
+            }
+        }
+
+        if !pushed_dim {
+            self.render_window(
+                renderer,
+                window_render_loc,
+                win_alpha,
+                clip_to_geometry,
+                radius,
+                target,
+                push,
+            );
+        }
+
+        // The blur goes right behind the window.
+        if let Some(requests) = blur {
+            let blur_area =
+                BlurArea::resolve(rules.background_effect, || self.window.blur_region());
+            if let Some(blur_area) = blur_area {
+                self.blur
+                    .render(renderer, requests, &blur_area, area, radius, &mut |elem| {
+                        push(elem.into())
+                    });
+            }
+        }
+
+        let has_border_shader = BorderRenderElement::has_shader(renderer);
+        if fullscreen_progress > 0. {
+            let alpha = fullscreen_progress as f32;
+
+            // During the un/fullscreen animation, render a border element in order to use the
+            // animated corner radius.
+            if fullscreen_progress < 1. && has_border_shader {
+                let border_width = self.visual_border_width().unwrap_or(0.);
+                let radius = rules
+                    .geometry_corner_radius
+                    .map_or(CornerRadius::default(), |radius| {
+                        radius.expanded_by(border_width as f32)
+                    })
+                    .scaled_by(1. - expanded_progress as f32);
+
+                let size = self.fullscreen_backdrop.size();
+                let color = self.fullscreen_backdrop.color();
+                let elem = BorderRenderElement::new(
+                    size,
+                    Rectangle::from_size(size),
+                    GradientInterpolation::default(),
+                    Color::from_color32f(color),
+                    Color::from_color32f(color),
+                    0.,
+                    Rectangle::from_size(size),
+                    0.,
+                    radius,
+                    scale.x as f32,
+                    alpha,
+                )
+                .with_location(location);
+                push(elem.into());
+            } else {
+                let elem = SolidColorRenderElement::from_buffer(
+                    &self.fullscreen_backdrop,
+                    location,
+                    alpha,
+                    Kind::Unspecified,
+                );
+                push(elem.into());
+            }
+        }
+
+        if let Some(width) = self.visual_border_width() {
+            self.border.render(
+                renderer,
+                location + Point::from((width, width)),
+                &mut |elem| push(elem.into()),
+            );
+        }
+
+        // Hide the focus ring when maximized/fullscreened. It's not normally visible anyway due to
+        // being outside the monitor or obscured by a solid colored bar, but it is visible under
+        // semitransparent bars in maximized state (which is a bit weird) and in the overview (also
+        // a bit weird).
+        // Also draw the focus ring on inactive tiles while the bell flash is on, otherwise it'd be
+        // invisible for windows without a border.
+        if (focus_ring || self.is_bell_flash_on()) && expanded_progress < 1. {
+            self.focus_ring
+                .render(renderer, location, &mut |elem| push(elem.into()));
+        }
+
+        if expanded_progress < 1. {
+            self.shadow
+                .render(renderer, location, &mut |elem| push(elem.into()));
+        }
+    }
+
+    fn render_window<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        window_render_loc: Point<f64, Logical>,
+        win_alpha: f32,
+        clip_to_geometry: bool,
+        radius: CornerRadius,
+        target: RenderTarget,
+        push: &mut dyn FnMut(TileRenderElement<R>),
+    ) {
+        let scale = Scale::from(self.scale);
+        let window_size = self.window_size();
+        let area = Rectangle::new(window_render_loc, self.animated_window_size());
+        let rules = self.window.rules();
+
         // If we're resizing, try to render a shader, or a fallback.
         let mut pushed_resize = false;
         if let Some(resize) = &self.resize_animation {
@@ -1154,8 +1559,10 @@ impl<W: LayoutElement> Tile<W> {
             let radius = radius.fit_to(window_size.w as f32, window_size.h as f32);
 
             let clip_shader = ClippedSurfaceRenderElement::shader(renderer).cloned();
+            let color_clip_shader =
+                ClippedSurfaceRenderElement::color_managed_shader(renderer).cloned();
             let clip = |elem| match elem {
-                LayoutElementRenderElement::Wayland(elem) => {
+                LayoutElementRenderElement::Wayland(SurfaceRenderElement::Wayland(elem)) => {
                     // If we should clip to geometry, render a clipped window.
                     if clip_to_geometry {
                         if let Some(shader) = clip_shader.clone() {
@@ -1173,7 +1580,33 @@ impl<W: LayoutElement> Tile<W> {
                     }
 
                     // Otherwise, render it normally.
-                    LayoutElementRenderElement::Wayland(elem).into()
+                    LayoutElementRenderElement::Wayland(elem.into()).into()
+                }
+                LayoutElementRenderElement::Wayland(SurfaceRenderElement::ColorManaged(elem)) => {
+                    // Color-managed surfaces need the clipping and the conversion in one pass.
+                    if clip_to_geometry {
+                        if let Some(shader) = color_clip_shader.clone() {
+                            if ClippedSurfaceRenderElement::will_clip(
+                                elem.inner(),
+                                scale,
+                                geo,
+                                radius,
+                            ) {
+                                let (elem, conversion) = elem.into_parts();
+                                return ClippedSurfaceRenderElement::new(
+                                    elem,
+                                    scale,
+                                    geo,
+                                    shader.clone(),
+                                    radius,
+                                )
+                                .with_conversion(conversion)
+                                .into();
+                            }
+                        }
+                    }
+
+                    LayoutElementRenderElement::Wayland(elem.into()).into()
                 }
                 LayoutElementRenderElement::SolidColor(elem) => {
                     // In this branch we're rendering a blocked-out window with a solid
@@ -1219,70 +1652,6 @@ impl<W: LayoutElement> Tile<W> {
                 &mut |elem| push(clip(elem)),
             );
         }
-
-        if fullscreen_progress > 0. {
-            let alpha = fullscreen_progress as f32;
-
-            // During the un/fullscreen animation, render a border element in order to use the
-            // animated corner radius.
-            if fullscreen_progress < 1. && has_border_shader {
-                let border_width = self.visual_border_width().unwrap_or(0.);
-                let radius = rules
-                    .geometry_corner_radius
-                    .map_or(CornerRadius::default(), |radius| {
-                        radius.expanded_by(border_width as f32)
-                    })
-                    .scaled_by(1. - expanded_progress as f32);
-
-                let size = self.fullscreen_backdrop.size();
-                let color = self.fullscreen_backdrop.color();
-                let elem = BorderRenderElement::new(
-                    size,
-                    Rectangle::from_size(size),
-                    GradientInterpolation::default(),
-                    Color::from_color32f(color),
-                    Color::from_color32f(color),
-                    0.,
-                    Rectangle::from_size(size),
-                    0.,
-                    radius,
-                    scale.x as f32,
-                    alpha,
-                )
-                .with_location(location);
-                push(elem.into());
-            } else {
-                let elem = SolidColorRenderElement::from_buffer(
-                    &self.fullscreen_backdrop,
-                    location,
-                    alpha,
-                    Kind::Unspecified,
-                );
-                push(elem.into());
-            }
-        }
-
-        if let Some(width) = self.visual_border_width() {
-            self.border.render(
-                renderer,
-                location + Point::from((width, width)),
-                &mut |elem| push(elem.into()),
-            );
-        }
-
-        // Hide the focus ring when maximized/fullscreened. It's not normally visible anyway due to
-        // being outside the monitor or obscured by a solid colored bar, but it is visible under
-        // semitransparent bars in maximized state (which is a bit weird) and in the overview (also
-        // a bit weird).
-        if focus_ring && expanded_progress < 1. {
-            self.focus_ring
-                .render(renderer, location, &mut |elem| push(elem.into()));
-        }
-
-        if expanded_progress < 1. {
-            self.shadow
-                .render(renderer, location, &mut |elem| push(elem.into()));
-        }
     }
 
     pub fn render<R: NiriRenderer>(
@@ -1291,6 +1660,7 @@ impl<W: LayoutElement> Tile<W> {
         location: Point<f64, Logical>,
         focus_ring: bool,
         target: RenderTarget,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(TileRenderElement<R>),
     ) {
         let _span = tracy_client::span!("Tile::render");
@@ -1308,11 +1678,13 @@ impl<W: LayoutElement> Tile<W> {
         if let Some(open) = &self.open_animation {
             let renderer = renderer.as_gles_renderer();
             let mut elements = Vec::new();
+            // The blur samples the screen under the element, which doesn't work offscreen.
             self.render_inner(
                 renderer,
                 Point::from((0., 0.)),
                 focus_ring,
                 target,
+                None,
                 &mut |elem| elements.push(elem),
             );
             match open.render(
@@ -1340,6 +1712,7 @@ impl<W: LayoutElement> Tile<W> {
                 Point::from((0., 0.)),
                 focus_ring,
                 target,
+                None,
                 &mut |elem| elements.push(elem),
             );
             match alpha.offscreen.render(renderer, scale, &elements) {
@@ -1358,7 +1731,7 @@ impl<W: LayoutElement> Tile<W> {
         }
 
         if !pushed {
-            self.render_inner(renderer, location, focus_ring, target, &mut |elem| {
+            self.render_inner(renderer, location, focus_ring, target, blur, &mut |elem| {
                 push(elem)
             });
         }
@@ -1381,6 +1754,7 @@ impl<W: LayoutElement> Tile<W> {
             Point::from((0., 0.)),
             false,
             RenderTarget::Output,
+            None,
             &mut |elem| contents.push(elem),
         );
 
@@ -1391,6 +1765,7 @@ impl<W: LayoutElement> Tile<W> {
             Point::from((0., 0.)),
             false,
             RenderTarget::Screencast,
+            None,
             &mut |elem| blocked_out_contents.push(elem),
         );
 
@@ -6,7 +6,7 @@ use niri_config::utils::MergeWith as _;
 use niri_config::{
     CenterFocusedColumn, CornerRadius, OutputName, PresetSize, Workspace as WorkspaceConfig,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout};
+use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout, WorkspaceLayout};
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::{layer_map_for_output, Window};
@@ -22,13 +22,16 @@ use super::scrolling::{
     Column, ColumnWidth, ScrollDirection, ScrollingSpace, ScrollingSpaceRenderElement,
 };
 use super::shadow::Shadow;
-use super::tile::{Tile, TileRenderSnapshot};
+use super::tile::{Tile, TileFocus, TileRenderSnapshot};
 use super::{
     ActivateWindow, HitType, InsertPosition, InteractiveResizeData, LayoutElement, Options,
     RemovedTile, SizeFrac,
 };
 use crate::animation::Clock;
 use crate::niri_render_elements;
+use crate::render_helpers::background_image::BackgroundImageBuffer;
+use crate::render_helpers::blur::BlurRequests;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
@@ -93,6 +96,9 @@ pub struct Workspace<W: LayoutElement> {
     /// This workspace's background.
     background_buffer: SolidColorBuffer,
 
+    /// This workspace's background image, drawn on top of the background color.
+    background_image: BackgroundImageBuffer,
+
     /// Clock for driving animations.
     pub(super) clock: Clock,
 
@@ -108,6 +114,9 @@ pub struct Workspace<W: LayoutElement> {
     /// Layout config overrides for this workspace.
     layout_config: Option<niri_config::LayoutPart>,
 
+    /// Background image for this workspace, overriding the one of the output.
+    background_image_config: Option<niri_config::BackgroundImage>,
+
     /// Unique ID of this workspace.
     id: WorkspaceId,
 }
@@ -148,6 +157,13 @@ niri_render_elements! {
     }
 }
 
+niri_render_elements! {
+    WorkspaceBackgroundRenderElement => {
+        SolidColor = SolidColorRenderElement,
+        Image = PrimaryGpuTextureRenderElement,
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct InteractiveResize<W: LayoutElement> {
     pub window: W::Id,
@@ -222,6 +238,7 @@ impl<W: LayoutElement> Workspace<W> {
             .unwrap_or(OutputId::new(&output));
 
         let layout_config = config.as_mut().and_then(|c| c.layout.take().map(|x| x.0));
+        let background_image_config = config.as_mut().and_then(|c| c.background_image.take());
 
         let scale = output.current_scale();
         let options = Rc::new(
@@ -263,12 +280,17 @@ impl<W: LayoutElement> Workspace<W> {
             working_area,
             shadow: Shadow::new(shadow_config),
             background_buffer: SolidColorBuffer::new(view_size, options.layout.background_color),
+            background_image: BackgroundImageBuffer::new(
+                background_image_config.as_ref(),
+                view_size,
+            ),
             output: Some(output),
             clock,
             base_options,
             options,
             name: config.map(|c| c.name.0),
             layout_config,
+            background_image_config,
             id: WorkspaceId::next(),
         }
     }
@@ -286,6 +308,7 @@ impl<W: LayoutElement> Workspace<W> {
         );
 
         let layout_config = config.as_mut().and_then(|c| c.layout.take().map(|x| x.0));
+        let background_image_config = config.as_mut().and_then(|c| c.background_image.take());
 
         let scale = smithay::output::Scale::Integer(1);
         let options = Rc::new(
@@ -328,11 +351,16 @@ impl<W: LayoutElement> Workspace<W> {
             working_area,
             shadow: Shadow::new(shadow_config),
             background_buffer: SolidColorBuffer::new(view_size, options.layout.background_color),
+            background_image: BackgroundImageBuffer::new(
+                background_image_config.as_ref(),
+                view_size,
+            ),
             clock,
             base_options,
             options,
             name: config.map(|c| c.name.0),
             layout_config,
+            background_image_config,
             id: WorkspaceId::next(),
         }
     }
@@ -374,13 +402,23 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.are_transitions_ongoing() || self.floating.are_transitions_ongoing()
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool) {
-        self.scrolling
-            .update_render_elements(is_active && !self.floating_is_active.get());
+    pub fn is_interactive_resize_ongoing(&self) -> bool {
+        self.scrolling.is_interactive_resize_ongoing()
+            || self.floating.is_interactive_resize_ongoing()
+    }
+
+    pub fn update_render_elements(&mut self, focus: TileFocus) {
+        let (scrolling_focus, floating_focus) = if self.floating_is_active.get() {
+            (focus.unfocused(), focus)
+        } else {
+            (focus, focus.unfocused())
+        };
+
+        self.scrolling.update_render_elements(scrolling_focus);
 
         let view_rect = Rectangle::from_size(self.view_size);
         self.floating
-            .update_render_elements(is_active && self.floating_is_active.get(), view_rect);
+            .update_render_elements(floating_focus, view_rect);
 
         self.shadow.update_render_elements(
             self.view_size,
@@ -433,6 +471,22 @@ impl<W: LayoutElement> Workspace<W> {
         self.update_config(self.base_options.clone());
     }
 
+    pub fn update_background_image_config(
+        &mut self,
+        background_image_config: Option<niri_config::BackgroundImage>,
+    ) {
+        self.background_image_config = background_image_config;
+    }
+
+    /// Updates the background image, falling back to the one of the output.
+    pub fn update_background_image(
+        &mut self,
+        output_config: Option<&niri_config::BackgroundImage>,
+    ) {
+        let config = self.background_image_config.as_ref().or(output_config);
+        self.background_image.update_config(config);
+    }
+
     pub fn update_shaders(&mut self) {
         self.scrolling.update_shaders();
         self.floating.update_shaders();
@@ -575,6 +629,7 @@ impl<W: LayoutElement> Workspace<W> {
         }
 
         self.background_buffer.resize(size);
+        self.background_image.resize(size);
 
         if scale_transform_changed {
             for window in self.windows() {
@@ -1608,6 +1663,32 @@ impl<W: LayoutElement> Workspace<W> {
         floating.chain(scrolling)
     }
 
+    pub fn ipc_layout(
+        &self,
+        output: Option<String>,
+        window_id: &impl Fn(&W) -> u64,
+    ) -> WorkspaceLayout {
+        let columns = self.scrolling.ipc_columns(window_id);
+        // Our indices are 1-based, consistent with the actions.
+        let active_column_idx =
+            (!columns.is_empty()).then(|| self.scrolling.active_column_idx() + 1);
+
+        WorkspaceLayout {
+            workspace_id: self.id().get(),
+            output,
+            columns,
+            active_column_idx,
+            view_offset: self.scrolling.view_offset().target(),
+            view_pos: self.scrolling.target_view_pos(),
+            floating_window_ids: self
+                .floating
+                .tiles()
+                .map(|t| window_id(t.window()))
+                .collect(),
+            is_floating_active: self.floating_is_active(),
+        }
+    }
+
     pub fn active_tile_visual_rectangle(&self) -> Option<Rectangle<f64, Logical>> {
         if self.floating_is_active.get() {
             self.floating.active_tile_visual_rectangle()
@@ -1629,11 +1710,12 @@ impl<W: LayoutElement> Workspace<W> {
         renderer: &mut R,
         target: RenderTarget,
         focus_ring: bool,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(WorkspaceRenderElement<R>),
     ) {
         let scrolling_focus_ring = focus_ring && !self.floating_is_active();
         self.scrolling
-            .render(renderer, target, scrolling_focus_ring, &mut |elem| {
+            .render(renderer, target, scrolling_focus_ring, blur, &mut |elem| {
                 push(elem.into())
             });
     }
@@ -1643,6 +1725,7 @@ impl<W: LayoutElement> Workspace<W> {
         renderer: &mut R,
         target: RenderTarget,
         focus_ring: bool,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(WorkspaceRenderElement<R>),
     ) {
         if !self.is_floating_visible() {
@@ -1656,6 +1739,7 @@ impl<W: LayoutElement> Workspace<W> {
             view_rect,
             target,
             floating_focus_ring,
+            blur,
             &mut |elem| push(elem.into()),
         );
     }
@@ -1668,13 +1752,33 @@ impl<W: LayoutElement> Workspace<W> {
         self.shadow.render(renderer, Point::from((0., 0.)), push);
     }
 
-    pub fn render_background(&self) -> SolidColorRenderElement {
-        SolidColorRenderElement::from_buffer(
+    pub fn render_background<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        alpha: f32,
+        push: &mut dyn FnMut(WorkspaceBackgroundRenderElement),
+    ) {
+        let scale = self.scale.fractional_scale();
+        let location = Point::new(0., 0.);
+
+        if let Some(elem) = self
+            .background_image
+            .render(renderer, location, scale, alpha)
+        {
+            push(elem.into());
+        }
+
+        let elem = SolidColorRenderElement::from_buffer(
             &self.background_buffer,
-            Point::new(0., 0.),
-            1.,
+            location,
+            alpha,
             Kind::Unspecified,
-        )
+        );
+        push(elem.into());
+    }
+
+    pub fn has_background_image(&self) -> bool {
+        self.background_image.has_image()
     }
 
     pub fn render_above_top_layer(&self) -> bool {
@@ -1695,7 +1799,7 @@ impl<W: LayoutElement> Workspace<W> {
             if tile.window().id() == window {
                 let view_pos = Point::from((-tile_pos.x, -tile_pos.y));
                 let view_rect = Rectangle::new(view_pos, view_size);
-                tile.update_render_elements(false, view_rect);
+                tile.update_render_elements(tile.focus(), view_rect);
                 tile.store_unmap_snapshot_if_empty(renderer);
                 return;
             }
@@ -1996,6 +2100,7 @@ impl<W: LayoutElement> Workspace<W> {
         assert!(self.view_size.h > 0.);
 
         assert_eq!(self.background_buffer.size(), self.view_size);
+        assert_eq!(self.background_image.size(), self.view_size);
         assert_eq!(
             self.background_buffer.color().components(),
             options.layout.background_color.to_array_unpremul(),
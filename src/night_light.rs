@@ -0,0 +1,401 @@
+//! Night light: warmer colors at night, applied through the output gamma ramps.
+//!
+//! Going through gamma rather than rendering keeps screenshots and screencasts unaffected and
+//! costs nothing per frame. Gamma ramps set by gamma-control clients are composed with the night
+//! light rather than replaced.
+
+use std::f64::consts::PI;
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+use anyhow::ensure;
+use niri_config::NightLight as Config;
+
+/// Temperature that leaves the colors unchanged, in kelvin.
+const NEUTRAL_TEMPERATURE: f64 = 6500.;
+
+/// Duration of the fade when toggling the night light or changing its config.
+const FADE_DURATION: Duration = Duration::from_secs(1);
+const FADE_UPDATE_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How often to update during a scheduled transition.
+const TRANSITION_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+/// How often to check the schedule otherwise.
+const IDLE_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+const MINUTES_PER_DAY: f64 = 24. * 60.;
+
+#[derive(Debug)]
+pub struct NightLight {
+    config: Config,
+    /// Whether the night light is turned on.
+    ///
+    /// When on, it follows the schedule.
+    on: bool,
+    /// Temperature that was applied last.
+    current: f64,
+    /// Ongoing fade after a toggle or a config change.
+    fade: Option<Fade>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f64,
+    start: Duration,
+}
+
+/// Times when the sun sets and rises, in minutes since the local midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SunTimes {
+    Normal {
+        sunrise: f64,
+        sunset: f64,
+    },
+    /// The sun doesn't rise on this day.
+    PolarNight,
+    /// The sun doesn't set on this day.
+    PolarDay,
+}
+
+/// Current local time.
+#[derive(Debug, Clone, Copy)]
+struct LocalTime {
+    /// Minutes since the local midnight.
+    minutes: f64,
+    /// Day of the year, starting at 0.
+    day_of_year: i32,
+    /// Offset of the local time zone from UTC, in minutes.
+    utc_offset: f64,
+}
+
+impl NightLight {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            on: !config.off,
+            current: NEUTRAL_TEMPERATURE,
+            fade: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: Config, now: Duration) {
+        if self.config == config {
+            return;
+        }
+
+        if self.config.off != config.off {
+            self.on = !config.off;
+        }
+        self.config = config;
+        self.start_fade(now);
+    }
+
+    pub fn toggle(&mut self, now: Duration) {
+        self.on = !self.on;
+        self.start_fade(now);
+    }
+
+    fn start_fade(&mut self, now: Duration) {
+        self.fade = Some(Fade {
+            from: self.current,
+            start: now,
+        });
+    }
+
+    /// Computes the color multipliers to apply right now.
+    ///
+    /// Returns `None` multipliers when the colors should stay unchanged, and the delay until the
+    /// next update, if any.
+    pub fn update(&mut self, now: Duration) -> (Option<[f64; 3]>, Option<Duration>) {
+        let (target, mut next) = self.target_temperature();
+
+        let mut temperature = target;
+        if let Some(fade) = self.fade {
+            let progress =
+                now.saturating_sub(fade.start).as_secs_f64() / FADE_DURATION.as_secs_f64();
+            if progress < 1. {
+                temperature = fade.from + (target - fade.from) * progress;
+                next = Some(FADE_UPDATE_INTERVAL);
+            } else {
+                self.fade = None;
+            }
+        }
+
+        self.current = temperature;
+
+        let multipliers = (temperature != NEUTRAL_TEMPERATURE).then(|| whitepoint(temperature));
+        (multipliers, next)
+    }
+
+    fn target_temperature(&self) -> (f64, Option<Duration>) {
+        if !self.on {
+            return (NEUTRAL_TEMPERATURE, None);
+        }
+
+        let night = self.config.temperature;
+        let transition = f64::from(self.config.transition_minutes);
+
+        let schedule = if let (Some(latitude), Some(longitude)) =
+            (self.config.latitude, self.config.longitude)
+        {
+            let Some(time) = LocalTime::now() else {
+                return (night, Some(IDLE_UPDATE_INTERVAL));
+            };
+
+            match sun_times(time, latitude, longitude) {
+                SunTimes::Normal { sunrise, sunset } => Some((time.minutes, sunset, sunrise)),
+                SunTimes::PolarNight => return (night, Some(IDLE_UPDATE_INTERVAL)),
+                SunTimes::PolarDay => return (NEUTRAL_TEMPERATURE, Some(IDLE_UPDATE_INTERVAL)),
+            }
+        } else if let (Some(from), Some(to)) = (self.config.from, self.config.to) {
+            let Some(time) = LocalTime::now() else {
+                return (night, Some(IDLE_UPDATE_INTERVAL));
+            };
+
+            Some((
+                time.minutes,
+                f64::from(from.minutes()),
+                f64::from(to.minutes()),
+            ))
+        } else {
+            None
+        };
+
+        // Without a schedule, it's always night.
+        let Some((now, start, end)) = schedule else {
+            return (night, None);
+        };
+
+        let amount = night_amount(now, start, end, transition);
+        let next = if 0. < amount && amount < 1. {
+            TRANSITION_UPDATE_INTERVAL
+        } else {
+            IDLE_UPDATE_INTERVAL
+        };
+        let temperature = NEUTRAL_TEMPERATURE + (night - NEUTRAL_TEMPERATURE) * amount;
+        (temperature, Some(next))
+    }
+}
+
+impl LocalTime {
+    fn now() -> Option<Self> {
+        unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm = MaybeUninit::<libc::tm>::uninit();
+            if libc::localtime_r(&now, tm.as_mut_ptr()).is_null() {
+                warn!("error getting the local time");
+                return None;
+            }
+            let tm = tm.assume_init();
+
+            Some(Self {
+                minutes: f64::from(tm.tm_hour * 60 + tm.tm_min) + f64::from(tm.tm_sec) / 60.,
+                day_of_year: tm.tm_yday,
+                utc_offset: tm.tm_gmtoff as f64 / 60.,
+            })
+        }
+    }
+}
+
+/// Returns how far into the night the time is, from 0 (day) to 1 (night).
+///
+/// All times are in minutes since midnight. The transitions are centered on `start` and `end`.
+fn night_amount(now: f64, start: f64, end: f64, transition: f64) -> f64 {
+    let wrap = |x: f64| x.rem_euclid(MINUTES_PER_DAY);
+
+    let night = wrap(end - start);
+    if night == 0. {
+        return 0.;
+    }
+
+    // Transitions can't be longer than the night or the day.
+    let transition = transition.min(night).min(MINUTES_PER_DAY - night);
+
+    // Minutes since the evening transition started.
+    let since = wrap(now - start + transition / 2.);
+    if transition == 0. {
+        return if since < night { 1. } else { 0. };
+    }
+
+    if since < transition {
+        since / transition
+    } else if since < night {
+        1.
+    } else if since < night + transition {
+        1. - (since - night) / transition
+    } else {
+        0.
+    }
+}
+
+/// Computes the sunrise and sunset times with the NOAA approximation.
+fn sun_times(time: LocalTime, latitude: f64, longitude: f64) -> SunTimes {
+    // Fractional year in radians, at noon.
+    let gamma = 2. * PI / 365. * f64::from(time.day_of_year);
+
+    // Equation of time in minutes.
+    let eqtime = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2. * gamma).cos()
+            - 0.040849 * (2. * gamma).sin());
+
+    // Solar declination in radians.
+    let decl = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2. * gamma).cos()
+        + 0.000907 * (2. * gamma).sin()
+        - 0.002697 * (3. * gamma).cos()
+        + 0.00148 * (3. * gamma).sin();
+
+    // Hour angle of the sunrise, accounting for the atmospheric refraction and the size of the
+    // solar disk.
+    let latitude = latitude.to_radians();
+    let cos_ha =
+        90.833f64.to_radians().cos() / (latitude.cos() * decl.cos()) - latitude.tan() * decl.tan();
+    if cos_ha > 1. {
+        return SunTimes::PolarNight;
+    }
+    if cos_ha < -1. {
+        return SunTimes::PolarDay;
+    }
+    let ha = cos_ha.acos().to_degrees();
+
+    let local = |utc: f64| (utc + time.utc_offset).rem_euclid(MINUTES_PER_DAY);
+    SunTimes::Normal {
+        sunrise: local(720. - 4. * (longitude + ha) - eqtime),
+        sunset: local(720. - 4. * (longitude - ha) - eqtime),
+    }
+}
+
+/// Returns the RGB multipliers for a color temperature in kelvin.
+///
+/// Uses an approximation of the black body colors, normalized so that 6500 K is neutral.
+pub fn whitepoint(temperature: f64) -> [f64; 3] {
+    fn black_body(temperature: f64) -> [f64; 3] {
+        let t = temperature / 100.;
+
+        let red = if t <= 66. {
+            255.
+        } else {
+            329.698727446 * (t - 60.).powf(-0.1332047592)
+        };
+
+        let green = if t <= 66. {
+            99.4708025861 * t.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (t - 60.).powf(-0.0755148492)
+        };
+
+        let blue = if t >= 66. {
+            255.
+        } else if t <= 19. {
+            0.
+        } else {
+            138.5177312231 * (t - 10.).ln() - 305.0447927307
+        };
+
+        [red, green, blue].map(|x| x.clamp(0., 255.))
+    }
+
+    let color = black_body(temperature);
+    let neutral = black_body(NEUTRAL_TEMPERATURE);
+    [0, 1, 2].map(|i| (color[i] / neutral[i]).min(1.))
+}
+
+/// Applies the color multipliers to a gamma ramp.
+///
+/// Without a ramp, applies them to a linear ramp of `gamma_size` entries per channel.
+pub fn apply_to_ramp(
+    ramp: Option<&[u16]>,
+    gamma_size: usize,
+    multipliers: [f64; 3],
+) -> anyhow::Result<Vec<u16>> {
+    ensure!(gamma_size != 0, "setting gamma is not supported");
+
+    let mut ramp = match ramp {
+        Some(ramp) => {
+            ensure!(ramp.len() == gamma_size * 3, "wrong gamma length");
+            ramp.to_vec()
+        }
+        None => {
+            let denom = gamma_size.max(2) as u64 - 1;
+            let channel = (0..gamma_size).map(|i| (0xFFFFu64 * i as u64 / denom) as u16);
+            Vec::from_iter(channel.clone().chain(channel.clone()).chain(channel))
+        }
+    };
+
+    for (channel, multiplier) in ramp.chunks_exact_mut(gamma_size).zip(multipliers) {
+        for value in channel {
+            *value = (f64::from(*value) * multiplier).round() as u16;
+        }
+    }
+
+    Ok(ramp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neutral_whitepoint() {
+        assert_eq!(whitepoint(NEUTRAL_TEMPERATURE), [1., 1., 1.]);
+
+        let [r, g, b] = whitepoint(3000.);
+        assert_eq!(r, 1.);
+        assert!(g < 1. && b < g);
+    }
+
+    #[test]
+    fn night_amount_transitions() {
+        let (start, end) = (21. * 60., 7. * 60.);
+
+        assert_eq!(night_amount(12. * 60., start, end, 30.), 0.);
+        assert_eq!(night_amount(0., start, end, 30.), 1.);
+        assert_eq!(night_amount(start, start, end, 30.), 0.5);
+        assert_eq!(night_amount(start + 15., start, end, 30.), 1.);
+        assert_eq!(night_amount(end, start, end, 30.), 0.5);
+        assert_eq!(night_amount(end + 15., start, end, 30.), 0.);
+
+        // Without transitions.
+        assert_eq!(night_amount(start - 1., start, end, 0.), 0.);
+        assert_eq!(night_amount(start, start, end, 0.), 1.);
+        assert_eq!(night_amount(end, start, end, 0.), 0.);
+    }
+
+    #[test]
+    fn sun_times_berlin() {
+        // Around the summer solstice in Berlin, CEST.
+        let time = LocalTime {
+            minutes: 0.,
+            day_of_year: 171,
+            utc_offset: 120.,
+        };
+        let SunTimes::Normal { sunrise, sunset } = sun_times(time, 52.52, 13.40) else {
+            panic!();
+        };
+        // 4:43 and 21:33.
+        assert!((sunrise - 283.).abs() < 5., "{sunrise}");
+        assert!((sunset - 1293.).abs() < 5., "{sunset}");
+
+        // Polar night in Tromsø around the winter solstice.
+        let time = LocalTime {
+            minutes: 0.,
+            day_of_year: 354,
+            utc_offset: 60.,
+        };
+        assert_eq!(sun_times(time, 69.65, 18.96), SunTimes::PolarNight);
+    }
+
+    #[test]
+    fn ramp_composition() {
+        let ramp = apply_to_ramp(None, 3, [1., 0.5, 0.]).unwrap();
+        assert_eq!(ramp, [0, 32767, 65535, 0, 16384, 32768, 0, 0, 0]);
+
+        let client = [100, 200, 300, 100, 200, 300, 100, 200, 300];
+        let ramp = apply_to_ramp(Some(&client), 3, [1., 0.5, 0.]).unwrap();
+        assert_eq!(ramp, [100, 200, 300, 50, 100, 150, 0, 0, 0]);
+
+        assert!(apply_to_ramp(Some(&client), 2, [1., 1., 1.]).is_err());
+    }
+}
@@ -15,14 +15,13 @@ use anyhow::{bail, ensure, Context};
 use calloop::futures::Scheduler;
 use niri_config::debug::PreviewRender;
 use niri_config::{
-    Config, FloatOrInt, Key, Modifiers, OutputName, TrackLayout, WarpMouseToFocusMode,
-    WorkspaceReference, Xkb,
+    ColorFilterKind, Config, FloatOrInt, Key, Modifiers, OutputName, TrackLayout,
+    WarpMouseToFocusMode, WorkspaceReference, Xkb,
 };
 use smithay::backend::allocator::Fourcc;
 use smithay::backend::input::Keycode;
 use smithay::backend::renderer::damage::OutputDamageTracker;
 use smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement;
-use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::utils::{
     select_dmabuf_feedback, CropRenderElement, Relocate, RelocateRenderElement,
     RescaleRenderElement,
@@ -56,9 +55,12 @@ use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
 use smithay::reexports::calloop::{
     Interest, LoopHandle, LoopSignal, Mode, PostAction, RegistrationToken,
 };
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1;
 use smithay::reexports::wayland_protocols::ext::session_lock::v1::server::ext_session_lock_v1::ExtSessionLockV1;
+use smithay::reexports::wayland_protocols::wp::text_input::zv3::server::zwp_text_input_v3::ZwpTextInputV3;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::WmCapabilities;
 use smithay::reexports::wayland_protocols_misc::server_decoration as _server_decoration;
+use smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::Mode as OutputPowerMode;
 use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
 use smithay::reexports::wayland_server::backend::{
     ClientData, ClientId, DisconnectReason, GlobalId,
@@ -71,11 +73,12 @@ use smithay::utils::{
     Transform, SERIAL_COUNTER,
 };
 use smithay::wayland::compositor::{
-    with_states, with_surface_tree_downward, CompositorClientState, CompositorHandler,
+    get_parent, with_states, with_surface_tree_downward, CompositorClientState, CompositorHandler,
     CompositorState, HookId, SurfaceData, TraversalAction,
 };
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
 use smithay::wayland::dmabuf::DmabufState;
+use smithay::wayland::drm_syncobj::DrmSyncobjState;
 use smithay::wayland::fractional_scale::FractionalScaleManagerState;
 use smithay::wayland::idle_inhibit::IdleInhibitManagerState;
 use smithay::wayland::idle_notify::IdleNotifierState;
@@ -108,6 +111,7 @@ use smithay::wayland::viewporter::ViewporterState;
 use smithay::wayland::virtual_keyboard::VirtualKeyboardManagerState;
 use smithay::wayland::xdg_activation::XdgActivationState;
 use smithay::wayland::xdg_foreign::XdgForeignState;
+use smithay::wayland::xdg_system_bell::XdgSystemBellState;
 
 #[cfg(feature = "dbus")]
 use crate::a11y::A11y;
@@ -133,26 +137,43 @@ use crate::input::{
     mods_with_wheel_binds, TabletData,
 };
 use crate::ipc::server::IpcServer;
-use crate::layer::mapped::LayerSurfaceRenderElement;
+use crate::layer::mapped::{LayerId, LayerSurfaceRenderElement};
 use crate::layer::MappedLayer;
 use crate::layout::tile::TileRenderElement;
-use crate::layout::workspace::{Workspace, WorkspaceId};
+use crate::layout::workspace::{Workspace, WorkspaceBackgroundRenderElement, WorkspaceId};
 use crate::layout::{
     HitType, Layout, LayoutElement as _, LayoutElementRenderElement, MonitorRenderElement,
 };
+use crate::night_light::NightLight;
 use crate::niri_render_elements;
+use crate::protocols::background_effect::{blur_region, BackgroundEffectManagerState};
+use crate::protocols::color_management::{ColorManagementState, ImageDescription};
+use crate::protocols::commit_timing::{self, CommitTimingManagerState};
+use crate::protocols::ext_foreign_toplevel_list::{self, ExtForeignToplevelListManagerState};
 use crate::protocols::ext_workspace::{self, ExtWorkspaceManagerState};
+use crate::protocols::fifo::{self, FifoManagerState};
 use crate::protocols::foreign_toplevel::{self, ForeignToplevelManagerState};
 use crate::protocols::gamma_control::GammaControlManagerState;
+use crate::protocols::image_capture_source::{ImageCaptureSource, ImageCaptureSourceState};
+use crate::protocols::image_copy_capture::{
+    self as image_copy_capture, CaptureBuffer, ImageCopyCaptureManagerState,
+};
 use crate::protocols::mutter_x11_interop::MutterX11InteropManagerState;
 use crate::protocols::output_management::OutputManagementManagerState;
+use crate::protocols::output_power_management::OutputPowerManagementManagerState;
 use crate::protocols::screencopy::{Screencopy, ScreencopyBuffer, ScreencopyManagerState};
 use crate::protocols::virtual_pointer::VirtualPointerManagerState;
+use crate::protocols::xdg_toplevel_tag::XdgToplevelTagManagerState;
+use crate::render_helpers::background_image::{self, BackgroundImageBuffer};
+use crate::render_helpers::blur::{region_rects, BackdropBlur, BlurArea, BlurRequests};
+use crate::render_helpers::color_filter::ColorFilterRenderElement;
+use crate::render_helpers::color_management::{ColorConversion, OutputColorRenderElement};
 use crate::render_helpers::debug::push_opaque_regions;
+use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
-use crate::render_helpers::surface::push_elements_from_surface_tree;
+use crate::render_helpers::surface::{push_elements_from_surface_tree, SurfaceRenderElement};
 use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::{
     encompassing_geo, render_to_dmabuf, render_to_encompassing_texture, render_to_shm,
@@ -160,23 +181,26 @@ use crate::render_helpers::{
 };
 #[cfg(feature = "xdp-gnome-screencast")]
 use crate::screencasting::Screencasting;
+use crate::ui::brightness_osd::BrightnessOsd;
 use crate::ui::config_error_notification::ConfigErrorNotification;
 use crate::ui::exit_confirm_dialog::{ExitConfirmDialog, ExitConfirmDialogRenderElement};
 use crate::ui::hotkey_overlay::HotkeyOverlay;
+use crate::ui::magnifier::{Magnifier, MagnifierCaret, MagnifierRenderElement};
 use crate::ui::mru::{MruCloseRequest, WindowMruUi, WindowMruUiRenderElement};
 use crate::ui::screen_transition::{self, ScreenTransition};
 use crate::ui::screenshot_ui::{OutputScreenshot, ScreenshotUi, ScreenshotUiRenderElement};
-use crate::utils::scale::{closest_representable_scale, guess_monitor_scale};
+use crate::utils::backlight::BrightnessChange;
+use crate::utils::scale::closest_representable_scale;
 use crate::utils::spawning::{CHILD_DISPLAY, CHILD_ENV};
 use crate::utils::vblank_throttle::VBlankThrottle;
 use crate::utils::watcher::Watcher;
 use crate::utils::xwayland::satellite::Satellite;
 use crate::utils::{
     center, center_f64, expand_home, get_monotonic_time, ipc_transform_to_smithay, is_mapped,
-    logical_output, make_screenshot_path, output_matches_name, output_size, panel_orientation,
-    send_scale_transform, write_png_rgba8, xwayland,
+    logical_output, make_screenshot_path, output_matches_name, output_scale, output_size,
+    panel_orientation, send_scale_transform, write_png_rgba8, xwayland,
 };
-use crate::window::mapped::MappedId;
+use crate::window::mapped::{MappedId, WindowCastRenderElements};
 use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef};
 
 const CLEAR_COLOR_LOCKED: [f32; 4] = [0.3, 0.1, 0.1, 1.];
@@ -189,12 +213,27 @@ const FRAME_CALLBACK_THROTTLE: Option<Duration> = Some(Duration::from_millis(995
 pub struct Niri {
     pub config: Rc<RefCell<Config>>,
 
+    /// Config as last loaded from the file, before applying the config overlays.
+    pub config_file_config: Config,
+    /// Config overlays applied via IPC on top of the config file, in order.
+    pub config_overlays: Vec<niri_ipc::ConfigOverlay>,
+    /// Id to give to the next config overlay.
+    pub next_config_overlay_id: u64,
+
     /// Output config from the config file.
     ///
-    /// This does not include transient output config changes done via IPC. It is only used when
-    /// reloading the config from disk to determine if the output configuration should be reloaded
-    /// (and transient changes dropped).
+    /// This does not include transient output config changes done via IPC, nor the sections of
+    /// the active output profile. It is used when reloading the config from disk to determine if
+    /// the output configuration should be reloaded (and transient changes dropped), and when
+    /// switching output profiles.
     pub config_file_output_config: niri_config::Outputs,
+    /// Name of the active output profile, if any.
+    pub output_profile: Option<String>,
+    /// Name of the output profile forced via IPC, if any.
+    pub forced_output_profile: Option<String>,
+    /// Whether the backend switched the output profile, and the rest of the output config
+    /// needs reloading.
+    pub output_profile_switched: bool,
 
     pub config_file_watcher: Option<Watcher>,
 
@@ -216,6 +255,9 @@ pub struct Niri {
     /// Whether the at-startup=true window rules are active.
     pub is_at_startup: bool,
 
+    /// Monotonic time when the system bell was last handled.
+    pub last_bell_time: Option<Duration>,
+
     /// Clock for driving animations.
     pub clock: Clock,
 
@@ -233,8 +275,8 @@ pub struct Niri {
     // Windows which don't have a buffer attached yet.
     pub unmapped_windows: HashMap<WlSurface, Unmapped>,
 
-    /// Layer surfaces which don't have a buffer attached yet.
-    pub unmapped_layer_surfaces: HashSet<WlSurface>,
+    /// Layer surfaces which don't have a buffer attached yet, along with their ids.
+    pub unmapped_layer_surfaces: HashMap<WlSurface, LayerId>,
 
     /// Extra data for mapped layer surfaces.
     pub mapped_layer_surfaces: HashMap<LayerSurface, MappedLayer>,
@@ -249,6 +291,8 @@ pub struct Niri {
     /// Clients to notify about their blockers being cleared.
     pub blocker_cleared_tx: Sender<Client>,
     pub blocker_cleared_rx: Receiver<Client>,
+    /// Surfaces with an active fifo barrier or a pending commit timer.
+    pub presentation_barrier_surfaces: HashSet<WlSurface>,
 
     pub output_state: HashMap<Output, OutputState>,
 
@@ -273,14 +317,21 @@ pub struct Niri {
     pub layer_shell_state: WlrLayerShellState,
     pub session_lock_state: SessionLockManagerState,
     pub foreign_toplevel_state: ForeignToplevelManagerState,
+    pub ext_foreign_toplevel_list_state: ExtForeignToplevelListManagerState,
     pub ext_workspace_state: ExtWorkspaceManagerState,
     pub screencopy_state: ScreencopyManagerState,
+    pub image_capture_source_state: ImageCaptureSourceState,
+    pub image_copy_capture_state: ImageCopyCaptureManagerState,
     pub output_management_state: OutputManagementManagerState,
     pub viewporter_state: ViewporterState,
+    pub fifo_manager_state: FifoManagerState,
+    pub commit_timing_manager_state: CommitTimingManagerState,
     pub xdg_foreign_state: XdgForeignState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
     pub dmabuf_state: DmabufState,
+    // Only `Some()` on the tty backend when the primary GPU supports explicit sync.
+    pub drm_syncobj_state: Option<DrmSyncobjState>,
     pub fractional_scale_manager_state: FractionalScaleManagerState,
     pub seat_state: SeatState<State>,
     pub tablet_state: TabletManagerState,
@@ -303,8 +354,13 @@ pub struct Niri {
     pub presentation_state: PresentationState,
     pub security_context_state: SecurityContextState,
     pub gamma_control_manager_state: GammaControlManagerState,
+    pub output_power_management_state: OutputPowerManagementManagerState,
     pub activation_state: XdgActivationState,
     pub mutter_x11_interop_state: MutterX11InteropManagerState,
+    pub xdg_system_bell_state: XdgSystemBellState,
+    pub xdg_toplevel_tag_state: XdgToplevelTagManagerState,
+    pub background_effect_state: BackgroundEffectManagerState,
+    pub color_management_state: ColorManagementState,
 
     // This will not work as is outside of tests, so it is gated with #[cfg(test)] for now. In
     // particular, shaders will need to learn about the single pixel buffer. Also, it must be
@@ -385,6 +441,7 @@ pub struct Niri {
 
     pub screenshot_ui: ScreenshotUi,
     pub config_error_notification: ConfigErrorNotification,
+    pub brightness_osd: BrightnessOsd,
     pub hotkey_overlay: HotkeyOverlay,
     pub exit_confirm_dialog: ExitConfirmDialog,
 
@@ -394,6 +451,15 @@ pub struct Niri {
     pub pick_window: Option<async_channel::Sender<Option<MappedId>>>,
     pub pick_color: Option<async_channel::Sender<Option<niri_ipc::PickedColor>>>,
 
+    /// Whether the configured color filters are currently applied.
+    pub color_filter_on: bool,
+    pub night_light: NightLight,
+    pub night_light_timer: Option<RegistrationToken>,
+    /// Text caret that the magnifier follows instead of the pointer.
+    pub magnifier_caret: Option<MagnifierCaret>,
+    /// Text input cursor rectangles set by clients, waiting for the text input commit.
+    pub pending_text_input_carets: HashMap<ZwpTextInputV3, Rectangle<i32, Logical>>,
+
     pub debug_draw_opaque_regions: bool,
     pub debug_draw_damage: bool,
 
@@ -448,6 +514,8 @@ pub struct OutputState {
     pub frame_clock: FrameClock,
     pub redraw_state: RedrawState,
     pub on_demand_vrr_enabled: bool,
+    /// Whether this output was powered on by itself while the monitors are inactive.
+    pub powered_on_while_inactive: bool,
     // After the last redraw, some ongoing animations still remain.
     pub unfinished_animations_remain: bool,
     /// Last sequence received in a vblank event.
@@ -476,10 +544,33 @@ pub struct OutputState {
     /// Solid color buffer for the backdrop that we use instead of clearing to avoid damage
     /// tracking issues and make screenshots easier.
     pub backdrop_buffer: SolidColorBuffer,
+    /// Image drawn on top of the backdrop color.
+    pub backdrop_image: BackgroundImageBuffer,
     pub lock_render_state: LockRenderState,
     pub lock_surface: Option<LockSurface>,
     pub lock_color_buffer: SolidColorBuffer,
     screen_transition: Option<ScreenTransition>,
+    /// Blurred backdrops of the blur elements on this output, per render target.
+    pub blur_caches: RefCell<HashMap<RenderTarget, HashMap<Id, BackdropBlur>>>,
+    /// Color filter applied to the output contents, if any.
+    pub color_filter: Option<ColorFilterKind>,
+    /// Buffer that the output contents are rendered into before applying the color filter.
+    pub color_filter_buffer: OffscreenBuffer,
+    /// Screen magnifier of this output.
+    pub magnifier: Magnifier,
+    /// Whether other outputs mirror this one.
+    ///
+    /// Set by the backend before rendering. The mirrors show the contents of `color_buffer`.
+    pub mirrored: bool,
+    /// Image description that the output expects its contents in.
+    pub image_description: ImageDescription,
+    /// Floating-point buffer that the output contents are rendered into before converting them
+    /// into the output image description.
+    pub color_buffer: OffscreenBuffer,
+    /// Offscreen data of the post-processing passes for the last render to the output itself.
+    ///
+    /// Goes from the innermost pass to the outermost.
+    pub postprocess_data: RefCell<Vec<OffscreenData>>,
     /// Damage tracker used for the debug damage visualization.
     pub debug_damage_tracker: OutputDamageTracker,
 }
@@ -565,6 +656,55 @@ struct SurfaceFrameThrottlingState {
     last_sent_at: RefCell<Option<(Output, u32)>>,
 }
 
+/// Where an image capture source currently is.
+enum ImageCaptureLocation<'a> {
+    /// The source no longer exists.
+    Gone,
+    /// The source is a window that isn't on any output.
+    Nowhere(&'a Mapped),
+    /// The source is rendered on an output.
+    Target(ImageCaptureTarget<'a>),
+}
+
+/// Where an image capture source is rendered.
+enum ImageCaptureTarget<'a> {
+    Output(Output),
+    Window { mapped: &'a Mapped, output: Output },
+}
+
+impl ImageCaptureTarget<'_> {
+    fn output(&self) -> &Output {
+        match self {
+            ImageCaptureTarget::Output(output) => output,
+            ImageCaptureTarget::Window { output, .. } => output,
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        match self {
+            ImageCaptureTarget::Output(output) => output.current_transform(),
+            _ => Transform::Normal,
+        }
+    }
+
+    /// Returns the untransformed buffer size for capturing this target.
+    fn buffer_size(&self, scale: Scale<f64>) -> Size<i32, Physical> {
+        match self {
+            ImageCaptureTarget::Output(output) => output.current_mode().unwrap().size,
+            ImageCaptureTarget::Window { mapped, .. } => window_capture_size(mapped, scale),
+        }
+    }
+}
+
+/// Returns the untransformed buffer size for capturing a window at this scale.
+fn window_capture_size(mapped: &Mapped, scale: Scale<f64>) -> Size<i32, Physical> {
+    mapped
+        .window
+        .bbox_with_popups()
+        .to_physical_precise_up(scale)
+        .size
+}
+
 pub enum CenterCoords {
     Separately,
     Both,
@@ -745,6 +885,8 @@ impl State {
         state.ipc_keyboard_layouts_changed();
         // Focus the default monitor if set by the user.
         state.focus_default_monitor();
+        // Apply the night light if it's enabled.
+        state.update_night_light();
 
         Ok(state)
     }
@@ -813,7 +955,10 @@ impl State {
         self.niri.refresh_idle_inhibit();
         self.refresh_pointer_contents();
         foreign_toplevel::refresh(self);
+        ext_foreign_toplevel_list::refresh(self);
         ext_workspace::refresh(self);
+        self.niri.color_management_state.refresh();
+        self.niri.refresh_image_copy_capture();
 
         #[cfg(feature = "xdp-gnome-screencast")]
         self.niri.refresh_mapped_cast_outputs();
@@ -824,6 +969,9 @@ impl State {
         self.ipc_refresh_casts();
 
         self.niri.refresh_window_rules();
+        // Needs to be called before refresh_ipc_outputs() since it checks for changed outputs.
+        self.refresh_output_profile();
+        self.ipc_refresh_output_profile();
         self.refresh_ipc_outputs();
         self.ipc_refresh_layout();
         self.ipc_refresh_keyboard_layout_index();
@@ -967,6 +1115,73 @@ impl State {
         self.move_cursor_to_output(&target);
     }
 
+    /// Changes the backlight brightness and shows it in the OSD.
+    ///
+    /// Without an output name, uses the active output, or the first output with a backlight if
+    /// the active one has none.
+    pub fn change_brightness(&mut self, output: Option<&str>, change: BrightnessChange) {
+        let output = if let Some(name) = output {
+            self.niri.output_by_name_match(name).cloned()
+        } else {
+            let active = self.niri.layout.active_output().cloned();
+            active
+                .filter(|output| self.backend.has_backlight(output))
+                .or_else(|| {
+                    let mut outputs = self.niri.sorted_outputs.iter();
+                    outputs
+                        .find(|output| self.backend.has_backlight(output))
+                        .cloned()
+                })
+        };
+        let Some(output) = output else {
+            return;
+        };
+
+        let Some(brightness) = self
+            .backend
+            .change_brightness(&mut self.niri, &output, change)
+        else {
+            debug!("output {} has no backlight", output.name());
+            return;
+        };
+
+        self.niri.brightness_osd.show(output.clone(), brightness);
+        self.niri.queue_redraw(&output);
+    }
+
+    pub fn toggle_night_light(&mut self) {
+        self.niri.night_light.toggle(get_monotonic_time());
+        self.update_night_light();
+    }
+
+    /// Applies the current night light colors and schedules the next update.
+    pub fn update_night_light(&mut self) {
+        let _span = tracy_client::span!("State::update_night_light");
+
+        if let Some(token) = self.niri.night_light_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let (multipliers, next_update) = self.niri.night_light.update(get_monotonic_time());
+        self.backend.set_night_light(multipliers);
+
+        let Some(next_update) = next_update else {
+            return;
+        };
+
+        let timer = Timer::from_duration(next_update);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.night_light_timer = None;
+                state.update_night_light();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.niri.night_light_timer = Some(token);
+    }
+
     /// Focus a specific window, taking care of a potential active output change and cursor
     /// warp.
     pub fn focus_window(&mut self, window: &Window) {
@@ -1409,7 +1624,7 @@ impl State {
     pub fn reload_config(&mut self, config: Result<Config, ()>) {
         let _span = tracy_client::span!("State::reload_config");
 
-        let mut config = match config {
+        let config = match config {
             Ok(config) => config,
             Err(()) => {
                 self.niri.config_error_notification.show();
@@ -1424,6 +1639,10 @@ impl State {
 
         self.niri.config_error_notification.hide();
 
+        // Keep the config from the file to apply the overlays onto when they change.
+        self.niri.config_file_config.clone_from(&config);
+        let mut config = self.with_config_overlays(config);
+
         // Find & orphan removed named workspaces.
         let mut removed_workspaces: Vec<String> = vec![];
         for ws in &self.niri.config.borrow().workspaces {
@@ -1456,11 +1675,13 @@ impl State {
         let mut reload_xkb = None;
         let mut libinput_config_changed = false;
         let mut output_config_changed = false;
+        let mut output_sections_changed = false;
         let mut preserved_output_config = None;
         let mut window_rules_changed = false;
         let mut layer_rules_changed = false;
         let mut shaders_changed = false;
         let mut cursor_inactivity_timeout_changed = false;
+        let mut night_light_changed = false;
         let mut recent_windows_changed = false;
         let mut xwls_changed = false;
         let mut old_config = self.niri.config.borrow_mut();
@@ -1501,9 +1722,14 @@ impl State {
 
         let ignored_nodes_changed =
             config.debug.ignored_drm_devices != old_config.debug.ignored_drm_devices;
+        let explicit_sync_changed =
+            config.debug.disable_explicit_sync != old_config.debug.disable_explicit_sync;
 
-        if config.outputs != self.niri.config_file_output_config {
+        if config.outputs != self.niri.config_file_output_config
+            || config.output_profiles != old_config.output_profiles
+        {
             output_config_changed = true;
+            output_sections_changed = true;
             self.niri
                 .config_file_output_config
                 .clone_from(&config.outputs);
@@ -1533,6 +1759,26 @@ impl State {
             layer_rules_changed = true;
         }
 
+        if config.blur != old_config.blur {
+            self.niri
+                .background_effect_state
+                .set_blur_available(!config.blur.off);
+        }
+
+        if config.night_light != old_config.night_light {
+            self.niri
+                .night_light
+                .update_config(config.night_light, get_monotonic_time());
+            night_light_changed = true;
+        }
+
+        if config.color_filter != old_config.color_filter {
+            if config.color_filter.off != old_config.color_filter.off {
+                self.niri.color_filter_on = !config.color_filter.off;
+            }
+            output_config_changed = true;
+        }
+
         if config.animations.window_resize.custom_shader
             != old_config.animations.window_resize.custom_shader
         {
@@ -1578,7 +1824,9 @@ impl State {
         }
 
         // FIXME: move backdrop rendering into layout::Monitor, then this will become unnecessary.
-        if config.overview.backdrop_color != old_config.overview.backdrop_color {
+        if config.overview.backdrop_color != old_config.overview.backdrop_color
+            || config.overview.backdrop_image != old_config.overview.backdrop_image
+        {
             output_config_changed = true;
         }
         if config.layout.background_color != old_config.layout.background_color {
@@ -1641,6 +1889,18 @@ impl State {
             self.backend.update_ignored_nodes_config(&mut self.niri);
         }
 
+        if explicit_sync_changed {
+            self.backend.update_explicit_sync_config(&mut self.niri);
+        }
+
+        if output_sections_changed {
+            // The new config has only the output sections from the file, so apply the profile
+            // sections again.
+            let connected = self.backend.connected_output_names(&self.niri);
+            let profile = self.niri.wanted_output_profile(&connected);
+            self.niri.apply_output_profile(profile);
+        }
+
         if output_config_changed {
             self.reload_output_config();
         }
@@ -1663,6 +1923,10 @@ impl State {
             self.niri.reset_pointer_inactivity_timer();
         }
 
+        if night_light_changed {
+            self.update_night_light();
+        }
+
         if binds_changed {
             self.niri.window_mru_ui.update_binds();
         }
@@ -1703,7 +1967,102 @@ impl State {
         self.niri.queue_redraw_all();
     }
 
+    /// Switches the output profile if the connected outputs changed.
+    fn refresh_output_profile(&mut self) {
+        // The tty backend switches the profile itself before setting up newly connected outputs,
+        // so that they come up with the config of the new profile right away. Apply the rest of
+        // the output config here.
+        if mem::take(&mut self.niri.output_profile_switched) {
+            self.reload_output_config();
+            return;
+        }
+
+        // The connected outputs change together with the IPC outputs.
+        if !self.niri.ipc_outputs_changed {
+            return;
+        }
+
+        let connected = self.backend.connected_output_names(&self.niri);
+        if self.niri.wanted_output_profile(&connected) != self.niri.output_profile {
+            self.reload_output_config();
+        }
+    }
+
+    /// Applies the config overlays on top of the config and records their errors.
+    fn with_config_overlays(&mut self, mut config: Config) -> Config {
+        for overlay in &mut self.niri.config_overlays {
+            overlay.error = match config.with_overlay(&overlay.kdl) {
+                Ok(x) => {
+                    config = x;
+                    None
+                }
+                Err(err) => {
+                    warn!("error applying config overlay {}: {err:?}", overlay.id);
+                    Some(format!("{err:?}"))
+                }
+            };
+        }
+        config
+    }
+
+    /// Applies a config overlay on top of the config file and returns its id.
+    pub fn apply_config_overlay(&mut self, kdl: String) -> Result<u64, String> {
+        // Check the overlay against the current config to report errors back.
+        let config = self.with_config_overlays(self.niri.config_file_config.clone());
+        config
+            .with_overlay(&kdl)
+            .map_err(|err| format!("{err:?}"))?;
+
+        let id = self.niri.next_config_overlay_id;
+        self.niri.next_config_overlay_id += 1;
+        self.niri.config_overlays.push(niri_ipc::ConfigOverlay {
+            id,
+            kdl,
+            error: None,
+        });
+
+        self.reload_config(Ok(self.niri.config_file_config.clone()));
+        Ok(id)
+    }
+
+    pub fn remove_config_overlay(&mut self, id: u64) -> anyhow::Result<()> {
+        let idx = self.niri.config_overlays.iter().position(|o| o.id == id);
+        let Some(idx) = idx else {
+            bail!("config overlay {id} does not exist");
+        };
+
+        self.niri.config_overlays.remove(idx);
+        self.reload_config(Ok(self.niri.config_file_config.clone()));
+        Ok(())
+    }
+
+    pub fn reset_config_overlays(&mut self) {
+        if self.niri.config_overlays.is_empty() {
+            return;
+        }
+
+        self.niri.config_overlays.clear();
+        self.reload_config(Ok(self.niri.config_file_config.clone()));
+    }
+
+    pub fn force_output_profile(&mut self, profile: Option<String>) -> anyhow::Result<()> {
+        if let Some(name) = &profile {
+            let config = self.niri.config.borrow();
+            ensure!(
+                config.output_profiles.iter().any(|p| p.name == *name),
+                "output profile {name:?} does not exist"
+            );
+        }
+
+        self.niri.forced_output_profile = profile;
+        self.reload_output_config();
+        Ok(())
+    }
+
     pub fn reload_output_config(&mut self) {
+        let connected = self.backend.connected_output_names(&self.niri);
+        self.niri.update_output_profile(&connected);
+
         let mut resized_outputs = vec![];
         let mut recolored_outputs = vec![];
 
@@ -1712,14 +2071,7 @@ impl State {
             let full_config = self.niri.config.borrow_mut();
             let config = full_config.outputs.find(name);
 
-            let scale = config
-                .and_then(|c| c.scale)
-                .map(|s| s.0)
-                .unwrap_or_else(|| {
-                    let size_mm = output.physical_properties().size;
-                    let resolution = output.current_mode().unwrap().size;
-                    guess_monitor_scale(size_mm, resolution)
-                });
+            let scale = output_scale(output, config.and_then(|c| c.scale).map(|s| s.0));
             let scale = closest_representable_scale(scale.clamp(0.1, 10.));
 
             let mut transform = panel_orientation(output)
@@ -1751,11 +2103,28 @@ impl State {
             backdrop_color[3] = 1.;
             let backdrop_color = Color32F::from(backdrop_color);
 
+            let backdrop_image = config
+                .and_then(|c| c.backdrop_image.as_ref())
+                .or(full_config.overview.backdrop_image.as_ref());
+
+            let color_filter = config
+                .and_then(|c| c.color_filter)
+                .or(full_config.color_filter.filter);
+
             if let Some(state) = self.niri.output_state.get_mut(output) {
                 if state.backdrop_buffer.color() != backdrop_color {
                     state.backdrop_buffer.set_color(backdrop_color);
                     recolored_outputs.push(output.clone());
                 }
+
+                if state.backdrop_image.update_config(backdrop_image) {
+                    recolored_outputs.push(output.clone());
+                }
+
+                if state.color_filter != color_filter {
+                    state.color_filter = color_filter;
+                    recolored_outputs.push(output.clone());
+                }
             }
 
             for mon in self.niri.layout.monitors_mut() {
@@ -1775,6 +2144,11 @@ impl State {
                     // Also redraw these; if anything, the background color could've changed.
                     recolored_outputs.push(output.clone());
                 }
+
+                let background_image = config.and_then(|c| c.background_image.clone());
+                if mon.update_background_image(background_image) {
+                    recolored_outputs.push(output.clone());
+                }
                 break;
             }
         }
@@ -1812,7 +2186,7 @@ impl State {
         } else if let Some(output_name) = self
             .backend
             .tty_checked()
-            .and_then(|tty| tty.disconnected_connector_name_by_name_match(name))
+            .and_then(|tty| tty.unmapped_connector_name_by_name_match(name))
         {
             temp = output_name;
             &temp
@@ -1913,6 +2287,12 @@ impl State {
                     None
                 }
             }
+            niri_ipc::OutputAction::Mirror { mirror } => {
+                config.mirror = match mirror {
+                    niri_ipc::MirrorToSet::Off => None,
+                    niri_ipc::MirrorToSet::Output(name) => Some(name),
+                }
+            }
         });
 
         self.reload_output_config();
@@ -1941,9 +2321,16 @@ impl State {
 
         let new_config = self.backend.ipc_outputs().lock().unwrap().clone();
         self.niri.output_management_state.notify_changes(new_config);
+
+        self.ipc_refresh_outputs();
     }
 
-    pub fn open_screenshot_ui(&mut self, show_pointer: bool, path: Option<String>) {
+    pub fn open_screenshot_ui(
+        &mut self,
+        show_pointer: bool,
+        color_filter: bool,
+        path: Option<String>,
+    ) {
         if self.niri.is_locked() || self.niri.screenshot_ui.is_open() {
             return;
         }
@@ -1976,9 +2363,14 @@ impl State {
         }
 
         self.backend.with_primary_renderer(|renderer| {
-            self.niri
-                .screenshot_ui
-                .open(renderer, screenshots, default_output, show_pointer, path)
+            self.niri.screenshot_ui.open(
+                renderer,
+                screenshots,
+                default_output,
+                show_pointer,
+                color_filter,
+                path,
+            )
         });
 
         self.niri
@@ -2004,13 +2396,23 @@ impl State {
     }
 
     pub fn confirm_screenshot(&mut self, write_to_disk: bool) {
-        let ScreenshotUi::Open { path, .. } = &mut self.niri.screenshot_ui else {
+        let ScreenshotUi::Open {
+            path, color_filter, ..
+        } = &mut self.niri.screenshot_ui
+        else {
             return;
         };
         let path = path.take();
 
+        let color_filter = if *color_filter && self.niri.color_filter_on {
+            let output = self.niri.screenshot_ui.selection_output();
+            output.and_then(|output| self.niri.output_state[output].color_filter)
+        } else {
+            None
+        };
+
         self.backend.with_primary_renderer(|renderer| {
-            match self.niri.screenshot_ui.capture(renderer) {
+            match self.niri.screenshot_ui.capture(renderer, color_filter) {
                 Ok((size, pixels)) => {
                     if let Err(err) = self.niri.save_screenshot(size, pixels, write_to_disk, path) {
                         warn!("error saving screenshot: {err:?}");
@@ -2180,8 +2582,21 @@ impl Niri {
         let (executor, scheduler) = calloop::futures::executor().unwrap();
         event_loop.insert_source(executor, |_, _, _| ()).unwrap();
 
+        let (decoded_tx, decoded_rx) = calloop::channel::channel();
+        event_loop
+            .insert_source(decoded_rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(decoded) => {
+                    background_image::finish_decoding(decoded);
+                    state.niri.queue_redraw_all();
+                }
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+        background_image::set_decoded_sender(decoded_tx);
+
         let display_handle = display.handle();
         let config_ = config.borrow();
+        let config_file_config = config_.clone();
         let config_file_output_config = config_.outputs.clone();
 
         let mut animation_clock = Clock::default();
@@ -2277,6 +2692,10 @@ impl Niri {
             VirtualPointerManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
         let foreign_toplevel_state =
             ForeignToplevelManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+        let ext_foreign_toplevel_list_state = ExtForeignToplevelListManagerState::new::<State, _>(
+            &display_handle,
+            client_is_unrestricted,
+        );
         let ext_workspace_state =
             ExtWorkspaceManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
         let mut output_management_state =
@@ -2284,7 +2703,16 @@ impl Niri {
         output_management_state.on_config_changed(config_.outputs.clone());
         let screencopy_state =
             ScreencopyManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+        let image_capture_source_state =
+            ImageCaptureSourceState::new::<State, _>(&display_handle, client_is_unrestricted);
+        let image_copy_capture_state = ImageCopyCaptureManagerState::new::<State, _>(
+            &display_handle,
+            backend.primary_render_node(),
+            client_is_unrestricted,
+        );
         let viewporter_state = ViewporterState::new::<State>(&display_handle);
+        let fifo_manager_state = FifoManagerState::new::<State>(&display_handle);
+        let commit_timing_manager_state = CommitTimingManagerState::new::<State>(&display_handle);
         let xdg_foreign_state = XdgForeignState::new::<State>(&display_handle);
 
         let is_tty = matches!(backend, Backend::Tty(_));
@@ -2292,6 +2720,10 @@ impl Niri {
             GammaControlManagerState::new::<State, _>(&display_handle, move |client| {
                 is_tty && !client.get_data::<ClientState>().unwrap().restricted
             });
+        let output_power_management_state =
+            OutputPowerManagementManagerState::new::<State, _>(&display_handle, move |client| {
+                is_tty && !client.get_data::<ClientState>().unwrap().restricted
+            });
         let activation_state = XdgActivationState::new::<State>(&display_handle);
         event_loop
             .insert_source(
@@ -2307,6 +2739,11 @@ impl Niri {
 
         let mutter_x11_interop_state =
             MutterX11InteropManagerState::new::<State, _>(&display_handle, move |_| true);
+        let xdg_system_bell_state = XdgSystemBellState::new::<State>(&display_handle);
+        let xdg_toplevel_tag_state = XdgToplevelTagManagerState::new::<State>(&display_handle);
+        let background_effect_state =
+            BackgroundEffectManagerState::new::<State>(&display_handle, !config_.blur.off);
+        let color_management_state = ColorManagementState::new::<State>(&display_handle);
 
         #[cfg(test)]
         let single_pixel_buffer_state = SinglePixelBufferState::new::<State>(&display_handle);
@@ -2347,11 +2784,14 @@ impl Niri {
         let mods_with_mouse_binds = mods_with_mouse_binds(mod_key, &config_.binds);
         let mods_with_wheel_binds = mods_with_wheel_binds(mod_key, &config_.binds);
         let mods_with_finger_scroll_binds = mods_with_finger_scroll_binds(mod_key, &config_.binds);
+        let color_filter_on = !config_.color_filter.off;
+        let night_light = NightLight::new(config_.night_light);
 
         let screenshot_ui = ScreenshotUi::new(animation_clock.clone(), config.clone());
         let window_mru_ui = WindowMruUi::new(config.clone());
         let config_error_notification =
             ConfigErrorNotification::new(animation_clock.clone(), config.clone());
+        let brightness_osd = BrightnessOsd::new(animation_clock.clone());
 
         let mut hotkey_overlay = HotkeyOverlay::new(config.clone(), mod_key);
         if !config_.hotkey_overlay.skip_at_startup {
@@ -2367,7 +2807,9 @@ impl Niri {
             .insert_source(
                 Timer::from_duration(Duration::from_secs(1)),
                 |_, _, state| {
-                    state.niri.send_frame_callbacks_on_fallback_timer();
+                    state
+                        .niri
+                        .send_frame_callbacks_on_fallback_timer(&state.backend);
                     TimeoutAction::ToDuration(Duration::from_secs(1))
                 },
             )
@@ -2426,7 +2868,13 @@ impl Niri {
         drop(config_);
         let mut niri = Self {
             config,
+            config_file_config,
+            config_overlays: Vec::new(),
+            next_config_overlay_id: 1,
             config_file_output_config,
+            output_profile: None,
+            forced_output_profile: None,
+            output_profile_switched: false,
             config_file_watcher: None,
 
             event_loop,
@@ -2437,6 +2885,7 @@ impl Niri {
             is_session_instance,
             start_time: Instant::now(),
             is_at_startup: true,
+            last_bell_time: None,
             clock: animation_clock,
 
             layout,
@@ -2444,12 +2893,13 @@ impl Niri {
             sorted_outputs: Vec::default(),
             output_state: HashMap::new(),
             unmapped_windows: HashMap::new(),
-            unmapped_layer_surfaces: HashSet::new(),
+            unmapped_layer_surfaces: HashMap::new(),
             mapped_layer_surfaces: HashMap::new(),
             root_surface: HashMap::new(),
             dmabuf_pre_commit_hook: HashMap::new(),
             blocker_cleared_tx,
             blocker_cleared_rx,
+            presentation_barrier_surfaces: HashSet::new(),
             monitors_active: true,
             is_lid_closed: false,
 
@@ -2464,10 +2914,15 @@ impl Niri {
             layer_shell_state,
             session_lock_state,
             foreign_toplevel_state,
+            ext_foreign_toplevel_list_state,
             ext_workspace_state,
             output_management_state,
             screencopy_state,
+            image_capture_source_state,
+            image_copy_capture_state,
             viewporter_state,
+            fifo_manager_state,
+            commit_timing_manager_state,
             xdg_foreign_state,
             text_input_state,
             input_method_state,
@@ -2477,6 +2932,7 @@ impl Niri {
             shm_state,
             output_manager_state,
             dmabuf_state,
+            drm_syncobj_state: None,
             fractional_scale_manager_state,
             seat_state,
             tablet_state,
@@ -2498,8 +2954,13 @@ impl Niri {
             presentation_state,
             security_context_state,
             gamma_control_manager_state,
+            output_power_management_state,
             activation_state,
             mutter_x11_interop_state,
+            xdg_system_bell_state,
+            xdg_toplevel_tag_state,
+            background_effect_state,
+            color_management_state,
             #[cfg(test)]
             single_pixel_buffer_state,
 
@@ -2539,6 +3000,7 @@ impl Niri {
 
             screenshot_ui,
             config_error_notification,
+            brightness_osd,
             hotkey_overlay,
             exit_confirm_dialog,
 
@@ -2548,6 +3010,12 @@ impl Niri {
             pick_window: None,
             pick_color: None,
 
+            color_filter_on,
+            night_light,
+            night_light_timer: None,
+            magnifier_caret: None,
+            pending_text_input_carets: HashMap::new(),
+
             debug_draw_opaque_regions: false,
             debug_draw_damage: false,
 
@@ -2752,11 +3220,7 @@ impl Niri {
 
         let config = self.config.borrow();
         let c = config.outputs.find(name);
-        let scale = c.and_then(|c| c.scale).map(|s| s.0).unwrap_or_else(|| {
-            let size_mm = output.physical_properties().size;
-            let resolution = output.current_mode().unwrap().size;
-            guess_monitor_scale(size_mm, resolution)
-        });
+        let scale = output_scale(&output, c.and_then(|c| c.scale).map(|s| s.0));
         let scale = closest_representable_scale(scale.clamp(0.1, 10.));
 
         let mut transform = panel_orientation(&output)
@@ -2769,6 +3233,15 @@ impl Niri {
             .to_array_unpremul();
         backdrop_color[3] = 1.;
 
+        let backdrop_image = c
+            .and_then(|c| c.backdrop_image.as_ref())
+            .or(config.overview.backdrop_image.as_ref());
+        let backdrop_image = BackgroundImageBuffer::new(backdrop_image, output_size(&output));
+
+        let color_filter = c
+            .and_then(|c| c.color_filter)
+            .or(config.color_filter.filter);
+
         // FIXME: fix winit damage on other transforms.
         if name.connector == "winit" {
             transform = Transform::Flipped180;
@@ -2781,6 +3254,7 @@ impl Niri {
                 layout.background_color = c.and_then(|c| c.background_color);
             }
         }
+        let background_image = c.and_then(|c| c.background_image.clone());
         drop(config);
 
         // Set scale and transform before adding to the layout since that will read the output size.
@@ -2792,6 +3266,9 @@ impl Niri {
         );
 
         self.layout.add_output(output.clone(), layout_config);
+        if let Some(mon) = self.layout.monitor_for_output_mut(&output) {
+            mon.update_background_image(background_image);
+        }
 
         let lock_render_state = if self.is_locked() {
             // We haven't rendered anything yet so it's as good as locked.
@@ -2805,16 +3282,26 @@ impl Niri {
             global,
             redraw_state: RedrawState::Idle,
             on_demand_vrr_enabled: false,
+            powered_on_while_inactive: false,
             unfinished_animations_remain: false,
             frame_clock: FrameClock::new(refresh_interval, vrr),
             last_drm_sequence: None,
             vblank_throttle: VBlankThrottle::new(self.event_loop.clone(), name.connector.clone()),
             frame_callback_sequence: 0,
             backdrop_buffer: SolidColorBuffer::new(size, backdrop_color),
+            backdrop_image,
             lock_render_state,
             lock_surface: None,
             lock_color_buffer: SolidColorBuffer::new(size, CLEAR_COLOR_LOCKED),
             screen_transition: None,
+            blur_caches: RefCell::new(HashMap::new()),
+            color_filter,
+            color_filter_buffer: OffscreenBuffer::default(),
+            mirrored: false,
+            image_description: ImageDescription::SRGB,
+            color_buffer: OffscreenBuffer::with_format(Fourcc::Abgr16161616f),
+            magnifier: Magnifier::new(),
+            postprocess_data: RefCell::new(Vec::new()),
             debug_damage_tracker: OutputDamageTracker::from_output(&output),
         };
         let rv = self.output_state.insert(output.clone(), state);
@@ -2833,6 +3320,8 @@ impl Niri {
         self.global_space.unmap_output(output);
         self.reposition_outputs(None);
         self.gamma_control_manager_state.output_removed(output);
+        self.output_power_management_state.output_removed(output);
+        self.color_management_state.remove_output(output);
 
         let state = self.output_state.remove(output).unwrap();
 
@@ -2921,6 +3410,7 @@ impl Niri {
 
         if let Some(state) = self.output_state.get_mut(output) {
             state.backdrop_buffer.resize(output_size);
+            state.backdrop_image.resize(output_size);
 
             state.lock_color_buffer.resize(output_size);
             if let Some(lock_surface) = &state.lock_surface {
@@ -2948,13 +3438,45 @@ impl Niri {
         self.queue_redraw(output);
     }
 
+    /// Sets the image description that the output expects its contents in.
+    pub fn set_output_image_description(&mut self, output: &Output, description: ImageDescription) {
+        let Some(state) = self.output_state.get_mut(output) else {
+            return;
+        };
+        if state.image_description == description {
+            return;
+        }
+
+        state.image_description = description;
+
+        // Keep HDR contents intact through the intermediate passes before the conversion.
+        let format = if ColorConversion::from_composition(&description).is_identity() {
+            Fourcc::Abgr8888
+        } else {
+            Fourcc::Abgr16161616f
+        };
+        state.color_filter_buffer.set_format(format);
+        state.magnifier.set_format(format);
+
+        self.color_management_state
+            .set_output_image_description(output, description);
+        self.queue_redraw(output);
+    }
+
     pub fn deactivate_monitors(&mut self, backend: &mut Backend) {
         if !self.monitors_active {
             return;
         }
 
+        let was_on = self.powered_on_outputs(backend);
+
         self.monitors_active = false;
         backend.set_monitors_active(false);
+        for state in self.output_state.values_mut() {
+            state.powered_on_while_inactive = false;
+        }
+
+        self.send_output_power_mode_changes(backend, &was_on);
     }
 
     pub fn activate_monitors(&mut self, backend: &mut Backend) {
@@ -2962,12 +3484,95 @@ impl Niri {
             return;
         }
 
+        let was_on = self.powered_on_outputs(backend);
+
         self.monitors_active = true;
         backend.set_monitors_active(true);
+        for state in self.output_state.values_mut() {
+            state.powered_on_while_inactive = false;
+        }
 
+        self.send_output_power_mode_changes(backend, &was_on);
         self.queue_redraw_all();
     }
 
+    /// Powers an individual output on or off.
+    ///
+    /// Outputs powered off this way stay off when the monitors are activated, e.g. on input.
+    /// Powering on an output while the monitors are inactive wakes up only that output.
+    pub fn set_output_powered(&mut self, backend: &mut Backend, output: &Output, powered: bool) {
+        let was_on = self.is_output_powered_on(backend, output);
+
+        backend.set_output_powered(output, powered);
+        if !self.monitors_active {
+            if let Some(state) = self.output_state.get_mut(output) {
+                state.powered_on_while_inactive = powered;
+            }
+        }
+
+        let is_on = self.is_output_powered_on(backend, output);
+        if is_on == was_on {
+            return;
+        }
+
+        let mode = if is_on {
+            OutputPowerMode::On
+        } else {
+            OutputPowerMode::Off
+        };
+        self.output_power_management_state
+            .output_power_mode_changed(output, mode);
+
+        if is_on {
+            self.queue_redraw(output);
+        }
+    }
+
+    pub fn is_output_powered_on(&self, backend: &Backend, output: &Output) -> bool {
+        if backend.is_output_powered_off(output) {
+            return false;
+        }
+
+        self.monitors_active
+            || self
+                .output_state
+                .get(output)
+                .is_some_and(|state| state.powered_on_while_inactive)
+    }
+
+    pub fn output_power_mode(&self, backend: &Backend, output: &Output) -> OutputPowerMode {
+        if self.is_output_powered_on(backend, output) {
+            OutputPowerMode::On
+        } else {
+            OutputPowerMode::Off
+        }
+    }
+
+    fn powered_on_outputs(&self, backend: &Backend) -> HashSet<Output> {
+        self.output_state
+            .keys()
+            .filter(|output| self.is_output_powered_on(backend, output))
+            .cloned()
+            .collect()
+    }
+
+    fn send_output_power_mode_changes(&mut self, backend: &Backend, was_on: &HashSet<Output>) {
+        for output in self.output_state.keys() {
+            let is_on = self.is_output_powered_on(backend, output);
+            if is_on == was_on.contains(output) {
+                continue;
+            }
+
+            let mode = if is_on {
+                OutputPowerMode::On
+            } else {
+                OutputPowerMode::Off
+            };
+            self.output_power_management_state
+                .output_power_mode_changed(output, mode);
+        }
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -3544,6 +4149,64 @@ impl Niri {
             .or_else(|| self.global_space.outputs().next())
     }
 
+    /// Returns the name of the output profile that should be active.
+    ///
+    /// This is the forced profile if any, otherwise the first profile matching the connected
+    /// outputs.
+    pub fn wanted_output_profile(&self, connected: &[OutputName]) -> Option<String> {
+        let config = self.config.borrow();
+        let profiles = &config.output_profiles;
+
+        if let Some(name) = &self.forced_output_profile {
+            if profiles.iter().any(|profile| profile.name == *name) {
+                return Some(name.clone());
+            }
+        }
+
+        let profile = profiles.iter().find(|profile| profile.matches(connected));
+        profile.map(|profile| profile.name.clone())
+    }
+
+    /// Activates the output profile that should be active with these outputs connected.
+    ///
+    /// Returns whether the active profile changed.
+    pub fn update_output_profile(&mut self, connected: &[OutputName]) -> bool {
+        let profile = self.wanted_output_profile(connected);
+        if profile == self.output_profile {
+            return false;
+        }
+
+        self.apply_output_profile(profile);
+        true
+    }
+
+    /// Replaces the output config with the config file sections and the sections of the profile.
+    ///
+    /// This drops transient output config changes.
+    pub fn apply_output_profile(&mut self, profile: Option<String>) {
+        let mut config = self.config.borrow_mut();
+
+        let mut outputs = Vec::new();
+        let sections = profile.as_ref().and_then(|name| {
+            let profile = config.output_profiles.iter().find(|p| p.name == *name);
+            profile.map(|profile| &profile.outputs)
+        });
+        // Profile sections go first so that they take precedence.
+        outputs.extend(sections.into_iter().flatten().cloned());
+        outputs.extend(self.config_file_output_config.0.iter().cloned());
+        config.outputs = niri_config::Outputs(outputs);
+        drop(config);
+
+        if self.output_profile != profile {
+            match &profile {
+                Some(name) => info!("activating output profile {name:?}"),
+                None => info!("deactivating output profile"),
+            }
+
+            self.output_profile = profile;
+        }
+    }
+
     pub fn output_by_name_match(&self, target: &str) -> Option<&Output> {
         self.global_space
             .outputs()
@@ -3977,6 +4640,7 @@ impl Niri {
 
         self.layout.advance_animations();
         self.config_error_notification.advance_animations();
+        self.brightness_osd.advance_animations();
         self.exit_confirm_dialog.advance_animations();
         self.screenshot_ui.advance_animations();
         self.window_mru_ui.advance_animations();
@@ -4032,19 +4696,308 @@ impl Niri {
         include_pointer: bool,
         target: RenderTarget,
     ) -> Vec<OutputRenderElements<R>> {
+        let blur = self.wants_blur(output).then(BlurRequests::default);
+
+        // Blur needs the elements behind it, so resolve it before rendering for real.
+        if let Some(requests) = &blur {
+            let renderer = renderer.as_gles_renderer();
+            self.render_blur(renderer, output, include_pointer, target, requests);
+        } else {
+            let state = &self.output_state[output];
+            state.blur_caches.borrow_mut().remove(&target);
+        }
+
         let mut elements = Vec::new();
-        self.render_inner(renderer, output, include_pointer, target, &mut |elem| {
-            elements.push(elem)
-        });
+        self.render_inner(
+            renderer,
+            output,
+            include_pointer,
+            target,
+            blur.as_ref(),
+            &mut |elem| elements.push(elem),
+        );
         elements
     }
 
+    /// Renders the output through the post-processing passes.
+    ///
+    /// These are the magnifier, which only applies when rendering to the output itself, the
+    /// color filter on top, and finally the conversion into the output image description, which
+    /// also only applies when rendering to the output itself.
+    ///
+    /// When other outputs mirror this one, the contents always go through the buffer of the
+    /// conversion, so that the mirrors can show them without rendering again.
+    pub fn render_with_postprocessing<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        include_pointer: bool,
+        target: RenderTarget,
+    ) -> Vec<OutputRenderElements<R>> {
+        if target != RenderTarget::Output {
+            return self.render_color_filtered(renderer, output, include_pointer, target);
+        }
+
+        let state = &self.output_state[output];
+        state.postprocess_data.borrow_mut().clear();
+
+        let conversion = ColorConversion::from_composition(&state.image_description);
+        let program = ColorConversion::shader(renderer)
+            .filter(|_| !conversion.is_identity())
+            .cloned();
+        if program.is_none() && !state.mirrored {
+            return self.render_color_filtered(renderer, output, include_pointer, target);
+        }
+
+        let _span = tracy_client::span!("Niri::render_color_converted");
+
+        let gles_renderer = renderer.as_gles_renderer();
+        let elements = self.render_color_filtered(gles_renderer, output, include_pointer, target);
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        match state.color_buffer.render(gles_renderer, scale, &elements) {
+            Ok((elem, _sync, data)) => {
+                state.postprocess_data.borrow_mut().push(data);
+                let elem = match program {
+                    Some(program) => OutputRenderElements::OutputColor(
+                        OutputColorRenderElement::new(elem, program, conversion),
+                    ),
+                    None => OutputRenderElements::Offscreen(elem),
+                };
+                vec![elem]
+            }
+            Err(err) => {
+                warn!("error rendering output contents for the color conversion: {err:?}");
+                state.postprocess_data.borrow_mut().clear();
+                self.render_color_filtered(renderer, output, include_pointer, target)
+            }
+        }
+    }
+
+    fn render_color_filtered<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        include_pointer: bool,
+        target: RenderTarget,
+    ) -> Vec<OutputRenderElements<R>> {
+        let state = &self.output_state[output];
+        let filter = state.color_filter.filter(|_| self.color_filter_on);
+        let program = ColorFilterRenderElement::shader(renderer).cloned();
+        let (Some(filter), Some(program)) = (filter, program) else {
+            return self.render_magnified(renderer, output, include_pointer, target);
+        };
+
+        let _span = tracy_client::span!("Niri::render_color_filter");
+
+        let gles_renderer = renderer.as_gles_renderer();
+        let elements = self.render_magnified(gles_renderer, output, include_pointer, target);
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        match state
+            .color_filter_buffer
+            .render(gles_renderer, scale, &elements)
+        {
+            Ok((elem, _sync, data)) => {
+                if target == RenderTarget::Output {
+                    state.postprocess_data.borrow_mut().push(data);
+                }
+                let elem = ColorFilterRenderElement::new(elem, program, filter);
+                vec![OutputRenderElements::ColorFilter(elem)]
+            }
+            Err(err) => {
+                warn!("error rendering output contents for the color filter: {err:?}");
+                if target == RenderTarget::Output {
+                    state.postprocess_data.borrow_mut().clear();
+                }
+                self.render_magnified(renderer, output, include_pointer, target)
+            }
+        }
+    }
+
+    fn render_magnified<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        include_pointer: bool,
+        target: RenderTarget,
+    ) -> Vec<OutputRenderElements<R>> {
+        let state = &self.output_state[output];
+        if target != RenderTarget::Output || !state.magnifier.is_active() {
+            return self.render(renderer, output, include_pointer, target);
+        }
+
+        let _span = tracy_client::span!("Niri::render_magnified");
+
+        let gles_renderer = renderer.as_gles_renderer();
+        let elements = self.render(gles_renderer, output, include_pointer, target);
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        let output_size = output_size(output);
+        let focus = self.magnifier_focus(output);
+        match state
+            .magnifier
+            .render(gles_renderer, scale, output_size, focus, &elements)
+        {
+            Ok((elem, data)) => {
+                state.postprocess_data.borrow_mut().push(data);
+                vec![OutputRenderElements::Magnified(elem)]
+            }
+            Err(err) => {
+                warn!("error rendering magnified output contents: {err:?}");
+                self.render(renderer, output, include_pointer, target)
+            }
+        }
+    }
+
+    /// Returns the output contents for an output mirroring it.
+    ///
+    /// Reuses the last render of the output itself, which must have happened with `mirrored` set.
+    /// The contents are scaled to fit the mirror size, keeping their aspect ratio. The mirror is
+    /// expected to render at scale 1.
+    pub fn render_for_mirror(
+        &self,
+        output: &Output,
+        mirror_size: Size<f64, Logical>,
+    ) -> anyhow::Result<MirrorRenderElement> {
+        let _span = tracy_client::span!("Niri::render_for_mirror");
+
+        // Mirrors show the contents before the conversion into the output image description, since
+        // they are always sRGB.
+        let state = &self.output_state[output];
+        let elem = state
+            .color_buffer
+            .last_element()
+            .context("output has not rendered yet")?;
+
+        // At scale 1, the offscreen element has the logical size of the output.
+        let output_size = output_size(output);
+        let zoom = f64::min(mirror_size.w / output_size.w, mirror_size.h / output_size.h);
+        let size = output_size.upscale(zoom);
+        let loc = (mirror_size.to_point() - size.to_point()).downscale(2.);
+        let rect = Rectangle::new(loc, size).to_physical_precise_round(1.);
+
+        let elem = RescaleRenderElement::from_element(elem, Point::default(), zoom);
+        let elem = RelocateRenderElement::from_element(elem, rect.loc, Relocate::Relative);
+        CropRenderElement::from_element(elem, 1., rect).context("output contents are empty")
+    }
+
+    /// Returns the point that the magnifier zooms around, in output-local coordinates.
+    fn magnifier_focus(&self, output: &Output) -> Point<f64, Logical> {
+        let output_geo = self.global_space.output_geometry(output).unwrap();
+        let pointer_location = self.seat.get_pointer().unwrap().current_location();
+
+        let location = match self.magnifier_caret {
+            Some(caret) if caret.pointer_location == pointer_location => caret.location,
+            _ => pointer_location,
+        };
+
+        // Keep the focus within the output, in case it's on another one.
+        let output_geo = output_geo.to_f64();
+        let location = location - output_geo.loc;
+        Point::from((
+            location.x.clamp(0., output_geo.size.w),
+            location.y.clamp(0., output_geo.size.h),
+        ))
+    }
+
+    /// Converts a point on the magnified screen to the global location of the contents shown
+    /// there.
+    pub fn unmagnify(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
+        let Some((output, pos_within_output)) = self.output_under(pos) else {
+            return pos;
+        };
+        let magnifier = &self.output_state[output].magnifier;
+        if !magnifier.is_active() {
+            return pos;
+        }
+
+        let output_loc = pos - pos_within_output;
+        let focus = self.magnifier_focus(output);
+        output_loc + magnifier.unmagnify(focus, pos_within_output)
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.change_magnifier(Magnifier::zoom_in);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.change_magnifier(Magnifier::zoom_out);
+    }
+
+    pub fn zoom_reset(&mut self) {
+        self.change_magnifier(Magnifier::reset);
+    }
+
+    /// Changes the magnifier of the output under the pointer.
+    fn change_magnifier(&mut self, f: impl FnOnce(&mut Magnifier)) {
+        let output = self
+            .output_under_cursor()
+            .or_else(|| self.layout.active_output().cloned());
+        let Some(output) = output else {
+            return;
+        };
+
+        f(&mut self.output_state.get_mut(&output).unwrap().magnifier);
+        self.queue_redraw(&output);
+    }
+
+    /// Makes the magnifier follow the text input caret of a window.
+    ///
+    /// The caret rectangle is relative to the window surface.
+    pub fn magnifier_follow_text_input(
+        &mut self,
+        surface: &WlSurface,
+        caret: Rectangle<i32, Logical>,
+    ) {
+        if !self
+            .output_state
+            .values()
+            .any(|state| state.magnifier.is_active())
+        {
+            return;
+        }
+
+        let mut location = None;
+        self.layout.with_windows(|mapped, output, _, layout| {
+            if location.is_some() || !mapped.is_wl_surface(surface) {
+                return;
+            }
+            let (Some(output), Some(tile_pos)) = (output, layout.tile_pos_in_workspace_view) else {
+                return;
+            };
+
+            let output_loc = self.global_space.output_geometry(output).unwrap().loc;
+            let window_loc = Point::from(tile_pos) + Point::from(layout.window_offset_in_tile);
+            // The window location is of its geometry, which is offset within the surface.
+            let surface_loc = window_loc - mapped.window.geometry().loc.to_f64();
+            location = Some(output_loc.to_f64() + surface_loc + center_f64(caret.to_f64()));
+        });
+
+        if let Some(location) = location {
+            self.update_magnifier_caret(location);
+        }
+    }
+
+    fn update_magnifier_caret(&mut self, location: Point<f64, Logical>) {
+        let pointer_location = self.seat.get_pointer().unwrap().current_location();
+        self.magnifier_caret = Some(MagnifierCaret {
+            location,
+            pointer_location,
+        });
+
+        if let Some((output, _)) = self.output_under(location) {
+            if self.output_state[output].magnifier.is_active() {
+                let output = output.clone();
+                self.queue_redraw(&output);
+            }
+        }
+    }
+
     pub fn render_inner<R: NiriRenderer>(
         &self,
         renderer: &mut R,
         output: &Output,
         include_pointer: bool,
         mut target: RenderTarget,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(OutputRenderElements<R>),
     ) {
         let _span = tracy_client::span!("Niri::render");
@@ -4091,6 +5044,11 @@ impl Niri {
             push(element.into());
         }
 
+        // Next, the brightness OSD.
+        if let Some(element) = self.brightness_osd.render(renderer, output) {
+            push(element.into());
+        }
+
         // If the session is locked, draw the lock surface.
         if self.is_locked() {
             let state = self.output_state.get(output).unwrap();
@@ -4129,6 +5087,10 @@ impl Niri {
             Kind::Unspecified,
         )
         .into();
+        let backdrop_image =
+            state
+                .backdrop_image
+                .render(renderer, Point::new(0., 0.), output_scale.x, 1.);
 
         // If the screenshot UI is open, draw it.
         if self.screenshot_ui.is_open() {
@@ -4181,7 +5143,9 @@ impl Niri {
         }
         macro_rules! push_normal_from_layer {
             ($layer:expr, $backdrop:expr, $push:expr) => {{
-                self.render_layer_normal(renderer, target, &layer_map, $layer, $backdrop, $push);
+                self.render_layer_normal(
+                    renderer, target, &layer_map, $layer, $backdrop, blur, $push,
+                );
             }};
             ($layer:expr, true) => {{
                 push_normal_from_layer!($layer, true, &mut |elem| push(elem.into()));
@@ -4201,14 +5165,19 @@ impl Niri {
         // When rendering above the top layer, we put the regular monitor elements first.
         // Otherwise, we will render all layer-shell pop-ups and the top layer on top.
         if mon.render_above_top_layer() {
-            self.layout
-                .render_interactive_move_for_output(renderer, output, target, &mut |elem| {
-                    push(elem.into())
-                });
+            self.layout.render_interactive_move_for_output(
+                renderer,
+                output,
+                target,
+                blur,
+                &mut |elem| push(elem.into()),
+            );
 
             mon.render_insert_hint_between_workspaces(renderer, &mut |elem| push(elem.into()));
 
-            mon.render_workspaces(renderer, target, focus_ring, &mut |elem| push(elem.into()));
+            mon.render_workspaces(renderer, target, focus_ring, blur, &mut |elem| {
+                push(elem.into())
+            });
 
             push_popups_from_layer!(Layer::Top);
             push_normal_from_layer!(Layer::Top);
@@ -4220,16 +5189,19 @@ impl Niri {
 
             // We don't expect more than one workspace when render_above_top_layer().
             if let Some((ws, _geo)) = mon.workspaces_with_render_geo().next() {
-                push(ws.render_background().into());
+                ws.render_background(renderer, 1., &mut |elem| push(elem.into()));
             }
         } else {
             push_popups_from_layer!(Layer::Top);
             push_normal_from_layer!(Layer::Top);
 
-            self.layout
-                .render_interactive_move_for_output(renderer, output, target, &mut |elem| {
-                    push(elem.into())
-                });
+            self.layout.render_interactive_move_for_output(
+                renderer,
+                output,
+                target,
+                blur,
+                &mut |elem| push(elem.into()),
+            );
 
             mon.render_insert_hint_between_workspaces(renderer, &mut |elem| push(elem.into()));
 
@@ -4249,13 +5221,17 @@ impl Niri {
                 push_popups_from_layer!(Layer::Background, process!(geo));
             }
 
-            mon.render_workspaces(renderer, target, focus_ring, &mut |elem| push(elem.into()));
+            mon.render_workspaces(renderer, target, focus_ring, blur, &mut |elem| {
+                push(elem.into())
+            });
 
-            for (ws, geo) in mon.workspaces_with_render_geo() {
+            for (_ws, geo) in mon.workspaces_with_render_geo() {
                 push_normal_from_layer!(Layer::Bottom, process!(geo));
                 push_normal_from_layer!(Layer::Background, process!(geo));
+            }
 
-                process!(geo)(ws.render_background());
+            for (ws, geo, alpha) in mon.workspace_backgrounds_with_render_geo() {
+                ws.render_background(renderer, alpha, process!(geo));
             }
         }
 
@@ -4265,6 +5241,9 @@ impl Niri {
         push_popups_from_layer!(Layer::Background, true);
         push_normal_from_layer!(Layer::Background, true);
 
+        if let Some(elem) = backdrop_image {
+            push(elem.into());
+        }
         push(backdrop);
     }
 
@@ -4294,11 +5273,73 @@ impl Niri {
         layer_map: &LayerMap,
         layer: Layer,
         for_backdrop: bool,
+        blur: Option<&BlurRequests>,
         push: &mut dyn FnMut(LayerSurfaceRenderElement<R>),
     ) {
         for (mapped, geo) in self.layers_in_render_order(layer_map, layer, for_backdrop) {
-            mapped.render_normal(renderer, geo.loc.to_f64(), target, push);
+            mapped.render_normal(renderer, geo.loc.to_f64(), target, blur, push);
+        }
+    }
+
+    /// Returns whether some surface on the output wants background blur.
+    fn wants_blur(&self, output: &Output) -> bool {
+        if self.config.borrow().blur.off {
+            return false;
+        }
+
+        let windows_want_blur = self.layout.windows_for_output(output).any(|mapped| {
+            BlurArea::resolve(mapped.rules().background_effect, || mapped.blur_region()).is_some()
+        });
+        if windows_want_blur {
+            return true;
+        }
+
+        let layer_map = layer_map_for_output(output);
+        layer_map.layers().any(|surface| {
+            let Some(mapped) = self.mapped_layer_surfaces.get(surface) else {
+                return false;
+            };
+
+            BlurArea::resolve(mapped.rules().background_effect, || {
+                blur_region(surface.wl_surface()).map(|region| region_rects(&region))
+            })
+            .is_some()
+        })
+    }
+
+    /// Renders the blurred backdrops of the blur elements on the output.
+    ///
+    /// Every blur element gets the elements behind it blurred, so it shows what's actually under
+    /// the surface on screen.
+    fn render_blur(
+        &self,
+        renderer: &mut GlesRenderer,
+        output: &Output,
+        include_pointer: bool,
+        target: RenderTarget,
+        requests: &BlurRequests,
+    ) {
+        let _span = tracy_client::span!("Niri::render_blur");
+
+        let mut elements = Vec::new();
+        self.render_inner(
+            renderer,
+            output,
+            include_pointer,
+            target,
+            Some(requests),
+            &mut |elem| elements.push(elem),
+        );
+        if requests.is_empty() {
+            return;
         }
+
+        let config = self.config.borrow().blur;
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        let state = &self.output_state[output];
+        let mut caches = state.blur_caches.borrow_mut();
+        let caches = caches.entry(target).or_default();
+        requests.resolve(renderer, scale, config, &elements, caches);
     }
 
     fn render_layer_popups<R: NiriRenderer>(
@@ -4326,18 +5367,24 @@ impl Niri {
         ));
 
         let target_presentation_time = state.frame_clock.next_presentation_time();
+        let next_frame_time = state
+            .frame_clock
+            .presentation_time_after(target_presentation_time);
 
         // Freeze the clock at the target time.
         self.clock.set_unadjusted(target_presentation_time);
 
         self.update_render_elements(Some(output));
 
+        let is_powered_off = !self.is_output_powered_on(backend, output);
+
         let mut res = RenderResult::Skipped;
-        if self.monitors_active {
+        if !is_powered_off {
             let state = self.output_state.get_mut(output).unwrap();
             state.unfinished_animations_remain = self.layout.are_animations_ongoing(Some(output));
             state.unfinished_animations_remain |=
                 self.config_error_notification.are_animations_ongoing();
+            state.unfinished_animations_remain |= self.brightness_osd.are_animations_ongoing();
             state.unfinished_animations_remain |= self.exit_confirm_dialog.are_animations_ongoing();
             state.unfinished_animations_remain |= self.screenshot_ui.are_animations_ongoing();
             state.unfinished_animations_remain |= self.window_mru_ui.are_animations_ongoing();
@@ -4360,6 +5407,19 @@ impl Niri {
             res = backend.render(self, output, target_presentation_time);
         }
 
+        if res != RenderResult::Skipped {
+            // Release the fifo barriers and commit timers of the surfaces on this output now that
+            // a frame went out. The released commits will apply in time for the next frame.
+            let next_commit_timer =
+                self.signal_presentation_barriers(|o| o == Some(output), next_frame_time);
+
+            // Keep redrawing if a held back commit is due by the frame after the next one.
+            let state = self.output_state.get_mut(output).unwrap();
+            let frame_after_next = state.frame_clock.presentation_time_after(next_frame_time);
+            state.unfinished_animations_remain |=
+                next_commit_timer.is_some_and(|target| target <= frame_after_next);
+        }
+
         let is_locked = self.is_locked();
         let state = self.output_state.get_mut(output).unwrap();
 
@@ -4375,10 +5435,10 @@ impl Niri {
             };
         }
 
-        // Update the lock render state on successful render, or if monitors are inactive. When
+        // Update the lock render state on successful render, or if the monitor is inactive. When
         // monitors are inactive on a TTY, they have no framebuffer attached, so no sensitive data
         // from a last render will be visible.
-        if res != RenderResult::Skipped || !self.monitors_active {
+        if res != RenderResult::Skipped || is_powered_off {
             state.lock_render_state = if is_locked {
                 LockRenderState::Locked
             } else {
@@ -4441,6 +5501,7 @@ impl Niri {
             }
 
             self.render_for_screencopy_with_damage(renderer, output);
+            self.render_for_image_copy_capture(renderer, output);
         });
     }
 
@@ -4480,6 +5541,10 @@ impl Niri {
         output: &Output,
         render_element_states: &RenderElementStates,
     ) {
+        let postprocess_data = self.output_state[output].postprocess_data.borrow();
+        let render_element_states =
+            contents_render_element_states(&postprocess_data, render_element_states);
+
         // FIXME: potentially tweak the compare function. The default one currently always prefers a
         // higher refresh-rate output, which is not always desirable (i.e. with a very small
         // overlap).
@@ -4616,6 +5681,10 @@ impl Niri {
     ) {
         let _span = tracy_client::span!("Niri::send_dmabuf_feedbacks");
 
+        let postprocess_data = self.output_state[output].postprocess_data.borrow();
+        let render_element_states =
+            contents_render_element_states(&postprocess_data, render_element_states);
+
         // We can unconditionally send the current output's feedback to regular and layer-shell
         // surfaces, as they can only be displayed on a single output at a time. Even if a surface
         // is currently invisible, this is the DMABUF feedback that it should know about.
@@ -4704,16 +5773,7 @@ impl Niri {
         let state = self.output_state.get(output).unwrap();
         let sequence = state.frame_callback_sequence;
 
-        let should_send = |surface: &WlSurface, states: &SurfaceData| {
-            // Do the standard primary scanout output check. For pointer surfaces it deduplicates
-            // the frame callbacks across potentially multiple outputs, and for regular windows and
-            // layer-shell surfaces it avoids sending frame callbacks to invisible surfaces.
-            let current_primary_output = surface_primary_scanout_output(surface, states);
-            if current_primary_output.as_ref() != Some(output) {
-                return None;
-            }
-
-            // Next, check the throttling status.
+        let throttle = |states: &SurfaceData| {
             let frame_throttling_state = states
                 .data_map
                 .get_or_insert(SurfaceFrameThrottlingState::default);
@@ -4737,15 +5797,44 @@ impl Niri {
             }
         };
 
+        let should_send = |surface: &WlSurface, states: &SurfaceData| {
+            // Do the standard primary scanout output check. For pointer surfaces it deduplicates
+            // the frame callbacks across potentially multiple outputs, and for regular windows and
+            // layer-shell surfaces it avoids sending frame callbacks to invisible surfaces.
+            let current_primary_output = surface_primary_scanout_output(surface, states);
+            if current_primary_output.as_ref() != Some(output) {
+                return None;
+            }
+
+            // Next, check the throttling status.
+            throttle(states)
+        };
+
+        // Windows captured with ext-image-copy-capture keep receiving frame callbacks even when
+        // they are invisible, so that the capture keeps updating.
+        let should_send_captured = |_: &WlSurface, states: &SurfaceData| throttle(states);
+
         let frame_callback_time = get_monotonic_time();
 
         for mapped in self.layout.windows_for_output_mut(output) {
-            mapped.send_frame(
-                output,
-                frame_callback_time,
-                FRAME_CALLBACK_THROTTLE,
-                should_send,
-            );
+            let is_captured = self
+                .image_copy_capture_state
+                .is_capturing_toplevel(mapped.toplevel().wl_surface());
+            if is_captured {
+                mapped.send_frame(
+                    output,
+                    frame_callback_time,
+                    FRAME_CALLBACK_THROTTLE,
+                    should_send_captured,
+                );
+            } else {
+                mapped.send_frame(
+                    output,
+                    frame_callback_time,
+                    FRAME_CALLBACK_THROTTLE,
+                    should_send,
+                );
+            }
         }
 
         for surface in layer_map_for_output(output).layers() {
@@ -4788,7 +5877,102 @@ impl Niri {
         }
     }
 
-    pub fn send_frame_callbacks_on_fallback_timer(&mut self) {
+    /// Clears fifo barriers and releases commit timers up to `until`.
+    ///
+    /// Handles surfaces for whose primary scanout output `should_signal` returns `true`, where
+    /// `None` means that the surface isn't shown on any output. Only surfaces in
+    /// `presentation_barrier_surfaces` are visited; they are dropped once nothing is pending.
+    ///
+    /// Returns the earliest remaining commit timer of the handled surfaces.
+    fn signal_presentation_barriers(
+        &mut self,
+        should_signal: impl Fn(Option<&Output>) -> bool,
+        until: Duration,
+    ) -> Option<Duration> {
+        let _span = tracy_client::span!("Niri::signal_presentation_barriers");
+
+        let mut clients = HashMap::<ClientId, Client>::new();
+        let mut next_commit_timer: Option<Duration> = None;
+
+        let surfaces = mem::take(&mut self.presentation_barrier_surfaces);
+        for surface in surfaces {
+            if !surface.is_alive() {
+                continue;
+            }
+
+            let keep = with_states(&surface, |states| {
+                if !should_signal(surface_primary_scanout_output(&surface, states).as_ref()) {
+                    return true;
+                }
+
+                let mut released = fifo::signal_fifo_barrier(states);
+                released |= commit_timing::signal_commit_timers(states, until);
+                if released {
+                    if let Some(client) = surface.client() {
+                        clients.insert(client.id(), client);
+                    }
+                }
+
+                let Some(target) = commit_timing::next_commit_timer(states) else {
+                    return false;
+                };
+                next_commit_timer = Some(next_commit_timer.map_or(target, |t| t.min(target)));
+                true
+            });
+
+            if keep {
+                self.presentation_barrier_surfaces.insert(surface);
+            }
+        }
+
+        if !clients.is_empty() {
+            for client in clients.into_values() {
+                let _ = self.blocker_cleared_tx.send(client);
+            }
+
+            // Make sure the event loop wakes up to apply the released commits.
+            self.event_loop.insert_idle(|_| ());
+        }
+
+        next_commit_timer
+    }
+
+    /// Queues a redraw of the output showing the surface in time for the commit timer.
+    pub fn queue_redraw_for_commit_timer(&mut self, surface: &WlSurface, target: Duration) {
+        let mut root = surface.clone();
+        while let Some(parent) = get_parent(&root) {
+            root = parent;
+        }
+
+        let Some(output) = self.output_for_root(&root).cloned() else {
+            // Not shown anywhere; the fallback timer will release it.
+            return;
+        };
+
+        let state = self.output_state.get(&output).unwrap();
+        let refresh_interval = state.frame_clock.refresh_interval().unwrap_or_default();
+
+        // The redraw preceding the target frame releases the commit.
+        let delay = target.saturating_sub(get_monotonic_time() + refresh_interval);
+        if delay.is_zero() {
+            self.queue_redraw(&output);
+            return;
+        }
+
+        let output = output.downgrade();
+        self.event_loop
+            .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                if let Some(output) = output.upgrade() {
+                    if state.niri.output_state.contains_key(&output) {
+                        state.niri.queue_redraw(&output);
+                    }
+                }
+                TimeoutAction::Drop
+            })
+            .unwrap();
+    }
+
+    pub fn send_frame_callbacks_on_fallback_timer(&mut self, backend: &Backend) {
         let _span = tracy_client::span!("Niri::send_frame_callbacks_on_fallback_timer");
 
         // Make up a bogus output; we don't care about it here anyway, just the throttling timer.
@@ -4806,6 +5990,19 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
+        // Surfaces not shown anywhere or shown only on powered off outputs don't get a refresh
+        // cycle, so release their barriers here.
+        let powered_on: Vec<_> = self
+            .output_state
+            .keys()
+            .filter(|output| self.is_output_powered_on(backend, output))
+            .cloned()
+            .collect();
+        self.signal_presentation_barriers(
+            |output| output.is_none_or(|output| !powered_on.contains(output)),
+            frame_callback_time,
+        );
+
         self.layout.with_windows_mut(|mapped, _| {
             mapped.send_frame(
                 output,
@@ -4862,6 +6059,10 @@ impl Niri {
         output: &Output,
         render_element_states: &RenderElementStates,
     ) -> OutputPresentationFeedback {
+        let postprocess_data = self.output_state[output].postprocess_data.borrow();
+        let render_element_states =
+            contents_render_element_states(&postprocess_data, render_element_states);
+
         let mut feedback = OutputPresentationFeedback::new(output);
 
         if let CursorImageStatus::Surface(surface) = &self.cursor_manager.cursor_image() {
@@ -5092,6 +6293,354 @@ impl Niri {
         Ok((sync, damages))
     }
 
+    /// Returns where the image capture source currently is.
+    fn image_capture_location(&self, source: &ImageCaptureSource) -> ImageCaptureLocation<'_> {
+        match source {
+            ImageCaptureSource::Output(output) => match output.upgrade() {
+                Some(output) if self.output_state.contains_key(&output) => {
+                    ImageCaptureLocation::Target(ImageCaptureTarget::Output(output))
+                }
+                _ => ImageCaptureLocation::Gone,
+            },
+            ImageCaptureSource::Toplevel(surface) => {
+                match self.layout.find_window_and_output(surface) {
+                    Some((mapped, Some(output))) => {
+                        ImageCaptureLocation::Target(ImageCaptureTarget::Window {
+                            mapped,
+                            output: output.clone(),
+                        })
+                    }
+                    Some((mapped, None)) => ImageCaptureLocation::Nowhere(mapped),
+                    None => ImageCaptureLocation::Gone,
+                }
+            }
+            ImageCaptureSource::Invalid => ImageCaptureLocation::Gone,
+        }
+    }
+
+    /// Stops image copy capture sessions whose source no longer exists.
+    pub fn refresh_image_copy_capture(&mut self) {
+        let mut state = mem::take(&mut self.image_copy_capture_state);
+
+        for session in state.sessions_mut() {
+            if session.is_stopped() {
+                continue;
+            }
+
+            if let ImageCaptureLocation::Gone = self.image_capture_location(session.source()) {
+                session.stop();
+            }
+        }
+
+        self.image_copy_capture_state = state;
+    }
+
+    pub fn image_copy_capture_new_session(&mut self, session: &ExtImageCopyCaptureSessionV1) {
+        let mut state = mem::take(&mut self.image_copy_capture_state);
+
+        if let Some(session) = state.session_mut(session) {
+            match self.image_capture_location(session.source()) {
+                ImageCaptureLocation::Gone => session.stop(),
+                ImageCaptureLocation::Nowhere(mapped) => {
+                    // Use the scale the window was last shown at; the size will be updated if
+                    // the window appears on an output with a different scale.
+                    let scale = mapped.preferred_scale().unwrap_or(1.);
+                    let size = if session.is_cursor() {
+                        let cursor_size = self.config.borrow().cursor.xcursor_size;
+                        let size = i32::from(cursor_size) * scale.ceil() as i32;
+                        Size::from((size, size))
+                    } else {
+                        window_capture_size(mapped, Scale::from(scale))
+                    };
+                    session.set_buffer_size(size);
+                }
+                ImageCaptureLocation::Target(target) => {
+                    let output = target.output().clone();
+                    let scale = Scale::from(output.current_scale().fractional_scale());
+
+                    let size = if session.is_cursor() {
+                        // The real cursor size is known on the next redraw, so start with the
+                        // configured cursor size.
+                        let cursor_size = self.config.borrow().cursor.xcursor_size;
+                        let size = i32::from(cursor_size) * output.current_scale().integer_scale();
+                        Size::from((size, size))
+                    } else {
+                        target.buffer_size(scale)
+                    };
+                    session.set_buffer_size(size);
+
+                    self.queue_redraw(&output);
+                }
+            }
+        }
+
+        self.image_copy_capture_state = state;
+    }
+
+    pub fn image_copy_capture_frame(&mut self, session: &ExtImageCopyCaptureSessionV1) {
+        let state = &mut self.image_copy_capture_state;
+        let Some(source) = state.session_mut(session).map(|s| s.source().clone()) else {
+            return;
+        };
+
+        // The capture happens on the next redraw of the output showing the source.
+        match self.image_capture_location(&source) {
+            ImageCaptureLocation::Gone => {
+                if let Some(session) = self.image_copy_capture_state.session_mut(session) {
+                    session.stop();
+                }
+            }
+            ImageCaptureLocation::Nowhere(_) => (),
+            ImageCaptureLocation::Target(target) => {
+                let output = target.output().clone();
+                self.queue_redraw(&output);
+            }
+        }
+    }
+
+    pub fn render_for_image_copy_capture(&mut self, renderer: &mut GlesRenderer, output: &Output) {
+        let _span = tracy_client::span!("Niri::render_for_image_copy_capture");
+
+        let mut state = mem::take(&mut self.image_copy_capture_state);
+        let scale = Scale::from(output.current_scale().fractional_scale());
+
+        for session in state.sessions_mut() {
+            if session.is_stopped() {
+                continue;
+            }
+
+            let target = match self.image_capture_location(session.source()) {
+                ImageCaptureLocation::Gone => {
+                    session.stop();
+                    continue;
+                }
+                ImageCaptureLocation::Nowhere(_) => continue,
+                ImageCaptureLocation::Target(target) => target,
+            };
+            if target.output() != output {
+                continue;
+            }
+
+            let mut elements = Vec::new();
+            let (size, transform) = if session.is_cursor() {
+                let Some((geo, position)) =
+                    self.image_copy_capture_cursor(renderer, &target, scale, &mut elements)
+                else {
+                    // The cursor is outside the captured area; pause the session.
+                    session.set_cursor_position(None);
+                    continue;
+                };
+
+                session.set_buffer_size(geo.size);
+                session.set_cursor_position(Some((position, position - geo.loc)));
+
+                (geo.size, Transform::Normal)
+            } else {
+                let size = target.buffer_size(scale);
+                session.set_buffer_size(size);
+
+                if !session.has_capture() {
+                    continue;
+                }
+
+                let paint_cursors = session.paint_cursors();
+                match &target {
+                    ImageCaptureTarget::Output(output) => {
+                        let output_elements = self.render(
+                            renderer,
+                            output,
+                            paint_cursors,
+                            RenderTarget::ScreenCapture,
+                        );
+                        elements.extend(output_elements.into_iter().map(Into::into));
+
+                        (size, target.transform())
+                    }
+                    ImageCaptureTarget::Window { mapped, output } => {
+                        if paint_cursors && self.pointer_visibility.is_visible() {
+                            if let Some((_, win_pos)) = self.pointer_pos_for_window_cast(mapped) {
+                                let bbox = mapped
+                                    .window
+                                    .bbox_with_popups()
+                                    .to_physical_precise_up(scale);
+                                let buf_pos = win_pos + bbox.loc.to_f64().to_logical(scale);
+                                let pos = buf_pos.to_physical_precise_round(scale).upscale(-1);
+                                self.render_pointer(renderer, output, &mut |elem| {
+                                    let elem = RelocateRenderElement::from_element(
+                                        elem,
+                                        pos,
+                                        Relocate::Relative,
+                                    );
+                                    elements.push(elem.into());
+                                });
+                            }
+                        }
+
+                        mapped.render_for_screen_cast(
+                            renderer,
+                            scale,
+                            RenderTarget::ScreenCapture,
+                            &mut |elem| elements.push(elem.into()),
+                        );
+
+                        (size, Transform::Normal)
+                    }
+                }
+            };
+
+            if !session.has_capture() {
+                continue;
+            }
+
+            let render_result = Self::render_for_image_copy_capture_internal(
+                renderer, &elements, size, scale, transform, session,
+            );
+            match render_result {
+                Ok(Some((sync, damages))) => {
+                    let capture = session.take_capture().unwrap();
+                    capture.submit_after_sync(transform, damages, sync, &self.event_loop);
+                }
+                Ok(None) => trace!("no damage found, waiting till next redraw"),
+                Err(err) => {
+                    // Reset the damage tracker to report full damage next time.
+                    session.reset_damage();
+                    session.take_capture();
+                    warn!("error rendering for image copy capture: {err:?}");
+                }
+            }
+        }
+
+        self.image_copy_capture_state = state;
+    }
+
+    /// Renders the cursor for an image copy capture cursor session.
+    ///
+    /// Returns the cursor geometry and the hotspot position relative to the source buffer, or
+    /// `None` if the cursor is outside of the source.
+    fn image_copy_capture_cursor(
+        &self,
+        renderer: &mut GlesRenderer,
+        target: &ImageCaptureTarget,
+        scale: Scale<f64>,
+        elements: &mut Vec<ImageCopyCaptureRenderElement<GlesRenderer>>,
+    ) -> Option<(Rectangle<i32, Physical>, Point<i32, Physical>)> {
+        if !self.pointer_visibility.is_visible() {
+            return None;
+        }
+
+        let output = target.output();
+        let output_pos = self.global_space.output_geometry(output).unwrap().loc;
+
+        // Offset from output-local coordinates to source buffer coordinates.
+        let (offset, pointer_pos) = match target {
+            ImageCaptureTarget::Output(_) => {
+                let pointer_pos = self
+                    .tablet_cursor_location
+                    .unwrap_or_else(|| self.seat.get_pointer().unwrap().current_location());
+                (Point::default(), pointer_pos)
+            }
+            ImageCaptureTarget::Window { mapped, .. } => {
+                let (pointer_pos, win_pos) = self.pointer_pos_for_window_cast(mapped)?;
+                let bbox = mapped
+                    .window
+                    .bbox_with_popups()
+                    .to_physical_precise_up(scale);
+                let buf_pos = win_pos + bbox.loc.to_f64().to_logical(scale);
+                let offset = buf_pos.to_physical_precise_round(scale).upscale(-1);
+                (offset, pointer_pos)
+            }
+        };
+
+        let mut pointer_elements = Vec::new();
+        self.render_pointer(renderer, output, &mut |elem| pointer_elements.push(elem));
+        if pointer_elements.is_empty() {
+            return None;
+        }
+
+        let mut geo = encompassing_geo(scale, pointer_elements.iter());
+        let element_offset = geo.loc.upscale(-1);
+        geo.loc += offset;
+
+        let source_size = target.transform().transform_size(target.buffer_size(scale));
+        let source_rect = Rectangle::from_size(source_size);
+        if !geo.overlaps(source_rect) {
+            return None;
+        }
+
+        let position =
+            (pointer_pos - output_pos.to_f64()).to_physical_precise_round(scale) + offset;
+
+        elements.extend(pointer_elements.into_iter().map(|elem| {
+            RelocateRenderElement::from_element(elem, element_offset, Relocate::Relative).into()
+        }));
+
+        Some((geo, position))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn render_for_image_copy_capture_internal(
+        renderer: &mut GlesRenderer,
+        elements: &[ImageCopyCaptureRenderElement<GlesRenderer>],
+        size: Size<i32, Physical>,
+        scale: Scale<f64>,
+        transform: Transform,
+        session: &mut image_copy_capture::Session,
+    ) -> anyhow::Result<
+        Option<(
+            Option<SyncPoint>,
+            Vec<Rectangle<i32, smithay::utils::Buffer>>,
+        )>,
+    > {
+        let (damage_tracker, capture) = session.split();
+        let capture = capture.context("missing capture")?;
+
+        let OutputModeSource::Static {
+            size: last_size,
+            scale: last_scale,
+            transform: last_transform,
+        } = damage_tracker.mode().clone()
+        else {
+            unreachable!("damage tracker must have static mode");
+        };
+
+        if size != last_size || scale != last_scale || transform != last_transform {
+            *damage_tracker = OutputDamageTracker::new(size, scale, transform);
+        }
+
+        // Just checked damage tracker has static mode
+        let Some(damages) = damage_tracker.damage_output(1, elements).unwrap().0 else {
+            return Ok(None);
+        };
+
+        // Convert from Physical coordinates back to Buffer coordinates.
+        let physical_size = transform.transform_size(size);
+        let damages = damages
+            .iter()
+            .map(|dmg| {
+                dmg.to_logical(1)
+                    .to_buffer(1, transform.invert(), &physical_size.to_logical(1))
+            })
+            .collect();
+
+        let elements = elements.iter().rev();
+
+        let sync = match capture.buffer() {
+            CaptureBuffer::Dmabuf(dmabuf) => {
+                let sync =
+                    render_to_dmabuf(renderer, dmabuf.clone(), size, scale, transform, elements)
+                        .context("error rendering to image copy capture dmabuf")?;
+                Some(sync)
+            }
+            CaptureBuffer::Shm(wl_buffer) => {
+                render_to_shm(renderer, wl_buffer, size, scale, transform, elements)
+                    .context("error rendering to image copy capture shm buffer")?;
+                None
+            }
+        };
+
+        Ok(Some((sync, damages)))
+    }
+
     #[cfg(not(feature = "xdp-gnome-screencast"))]
     pub fn stop_casts_for_target(&mut self, _target: CastTarget) {}
 
@@ -5110,6 +6659,11 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    pub fn toggle_color_filter(&mut self) {
+        self.color_filter_on = !self.color_filter_on;
+        self.queue_redraw_all();
+    }
+
     pub fn capture_screenshots<'a>(
         &'a self,
         renderer: &'a mut GlesRenderer,
@@ -5192,6 +6746,7 @@ impl Niri {
         output: &Output,
         write_to_disk: bool,
         include_pointer: bool,
+        color_filter: bool,
         path: Option<String>,
     ) -> anyhow::Result<()> {
         let _span = tracy_client::span!("Niri::screenshot");
@@ -5203,12 +6758,17 @@ impl Niri {
         let size = transform.transform_size(size);
 
         let scale = Scale::from(output.current_scale().fractional_scale());
-        let elements = self.render::<GlesRenderer>(
-            renderer,
-            output,
-            include_pointer,
-            RenderTarget::ScreenCapture,
-        );
+        let target = RenderTarget::ScreenCapture;
+        let elements = if color_filter {
+            self.render_with_postprocessing::<GlesRenderer>(
+                renderer,
+                output,
+                include_pointer,
+                target,
+            )
+        } else {
+            self.render::<GlesRenderer>(renderer, output, include_pointer, target)
+        };
         let elements = elements.iter().rev();
         let pixels = render_to_vec(
             renderer,
@@ -6133,9 +7693,26 @@ fn scale_relocate_crop<E: Element>(
     CropRenderElement::from_element(elem, output_scale, ws_geo)
 }
 
+/// Returns the render element states of the output contents.
+///
+/// When post-processing is on, the contents are rendered into offscreen buffers, so their states
+/// are in the offscreen data rather than in the output states.
+fn contents_render_element_states<'a>(
+    postprocess_data: &'a [OffscreenData],
+    mut states: &'a RenderElementStates,
+) -> &'a RenderElementStates {
+    for data in postprocess_data.iter().rev() {
+        if !states.element_was_presented(data.id.clone()) {
+            break;
+        }
+        states = &data.states;
+    }
+    states
+}
+
 niri_render_elements! {
     PointerRenderElements<R> => {
-        Wayland = WaylandSurfaceRenderElement<R>,
+        Wayland = SurfaceRenderElement<R>,
         NamedPointer = MemoryRenderBufferRenderElement<R>,
     }
 }
@@ -6147,6 +7724,18 @@ niri_render_elements! {
     }
 }
 
+niri_render_elements! {
+    ImageCopyCaptureRenderElement<R> => {
+        Output = OutputRenderElements<R>,
+        Window = WindowCastRenderElements<R>,
+        Pointer = RelocateRenderElement<PointerRenderElements<R>>,
+    }
+}
+
+/// Output contents scaled to fit on an output mirroring it.
+pub type MirrorRenderElement =
+    CropRenderElement<RelocateRenderElement<RescaleRenderElement<OffscreenRenderElement>>>;
+
 niri_render_elements! {
     OutputRenderElements<R> => {
         Monitor = MonitorRenderElement<R>,
@@ -6155,16 +7744,21 @@ niri_render_elements! {
         RelocatedLayerSurface = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
             LayerSurfaceRenderElement<R>
         >>>,
-        RelocatedColor = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
-            SolidColorRenderElement
+        RelocatedBackground = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
+            WorkspaceBackgroundRenderElement
         >>>,
+        Background = WorkspaceBackgroundRenderElement,
         Pointer = PointerRenderElements<R>,
-        Wayland = WaylandSurfaceRenderElement<R>,
+        Wayland = SurfaceRenderElement<R>,
         SolidColor = SolidColorRenderElement,
         ScreenshotUi = ScreenshotUiRenderElement,
         WindowMruUi = WindowMruUiRenderElement<R>,
         ExitConfirmDialog = ExitConfirmDialogRenderElement,
         Texture = PrimaryGpuTextureRenderElement,
+        ColorFilter = ColorFilterRenderElement,
+        Magnified = MagnifierRenderElement,
+        OutputColor = OutputColorRenderElement,
+        Offscreen = OffscreenRenderElement,
         // Used for the CPU-rendered panels.
         RelocatedMemoryBuffer = RelocateRenderElement<MemoryRenderBufferRenderElement<R>>,
     }
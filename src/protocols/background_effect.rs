@@ -0,0 +1,276 @@
+//! ext-background-effect-v1 protocol implementation.
+//!
+//! The blur region is double-buffered surface state, read with [`blur_region()`] when rendering.
+
+use std::sync::Mutex;
+
+use ext_background_effect_manager_v1::{Capability, ExtBackgroundEffectManagerV1};
+use ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1;
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{
+    get_region_attributes, with_states, Cacheable, RegionAttributes,
+};
+
+use super::raw::ext_background_effect::v1::server::{
+    ext_background_effect_manager_v1, ext_background_effect_surface_v1,
+};
+
+const VERSION: u32 = 1;
+
+pub struct BackgroundEffectManagerState {
+    /// Whether blur is currently available.
+    blur: bool,
+    /// Bound managers, to send capability updates to.
+    managers: Vec<ExtBackgroundEffectManagerV1>,
+}
+
+#[derive(Debug, Default)]
+struct BackgroundEffectSurfaceState {
+    /// Whether an ext_background_effect_surface_v1 object currently exists for this surface.
+    has_object: bool,
+}
+
+type BackgroundEffectSurfaceData = Mutex<BackgroundEffectSurfaceState>;
+
+#[derive(Debug, Default, Clone)]
+struct BackgroundEffectCachedState {
+    blur_region: Option<RegionAttributes>,
+}
+
+pub trait BackgroundEffectHandler {
+    fn background_effect_state(&mut self) -> &mut BackgroundEffectManagerState;
+}
+
+impl BackgroundEffectManagerState {
+    pub fn new<D>(display: &DisplayHandle, blur: bool) -> Self
+    where
+        D: GlobalDispatch<ExtBackgroundEffectManagerV1, ()>,
+        D: Dispatch<ExtBackgroundEffectManagerV1, ()>,
+        D: Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>>,
+        D: BackgroundEffectHandler,
+        D: 'static,
+    {
+        display.create_global::<D, ExtBackgroundEffectManagerV1, _>(VERSION, ());
+
+        Self {
+            blur,
+            managers: Vec::new(),
+        }
+    }
+
+    pub fn set_blur_available(&mut self, blur: bool) {
+        if self.blur == blur {
+            return;
+        }
+
+        self.blur = blur;
+        for manager in &self.managers {
+            manager.capabilities(self.capabilities());
+        }
+    }
+
+    fn capabilities(&self) -> Capability {
+        if self.blur {
+            Capability::Blur
+        } else {
+            Capability::empty()
+        }
+    }
+}
+
+/// Returns the blur region of the surface in surface-local coordinates, if any.
+pub fn blur_region(surface: &WlSurface) -> Option<RegionAttributes> {
+    with_states(surface, |states| {
+        states
+            .cached_state
+            .get::<BackgroundEffectCachedState>()
+            .current()
+            .blur_region
+            .clone()
+    })
+}
+
+impl Cacheable for BackgroundEffectCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        self.clone()
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+impl<D> GlobalDispatch<ExtBackgroundEffectManagerV1, (), D> for BackgroundEffectManagerState
+where
+    D: GlobalDispatch<ExtBackgroundEffectManagerV1, ()>,
+    D: Dispatch<ExtBackgroundEffectManagerV1, ()>,
+    D: Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>>,
+    D: BackgroundEffectHandler,
+    D: 'static,
+{
+    fn bind(
+        state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtBackgroundEffectManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let state = state.background_effect_state();
+        manager.capabilities(state.capabilities());
+        state.managers.push(manager);
+    }
+}
+
+impl<D> Dispatch<ExtBackgroundEffectManagerV1, (), D> for BackgroundEffectManagerState
+where
+    D: Dispatch<ExtBackgroundEffectManagerV1, ()>,
+    D: Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>>,
+    D: BackgroundEffectHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &ExtBackgroundEffectManagerV1,
+        request: <ExtBackgroundEffectManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_background_effect_manager_v1::Request::GetBackgroundEffect { id, surface } => {
+                let already_exists = with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(BackgroundEffectSurfaceData::default);
+                    let mut data = states
+                        .data_map
+                        .get::<BackgroundEffectSurfaceData>()
+                        .unwrap()
+                        .lock()
+                        .unwrap();
+
+                    if data.has_object {
+                        return true;
+                    }
+
+                    data.has_object = true;
+                    false
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        ext_background_effect_manager_v1::Error::BackgroundEffectExists,
+                        "the surface already has a background effect object",
+                    );
+                    return;
+                }
+
+                data_init.init(id, surface.downgrade());
+            }
+            ext_background_effect_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ExtBackgroundEffectManagerV1,
+        _data: &(),
+    ) {
+        state
+            .background_effect_state()
+            .managers
+            .retain(|x| x != resource);
+    }
+}
+
+impl<D> Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>, D> for BackgroundEffectManagerState
+where
+    D: Dispatch<ExtBackgroundEffectSurfaceV1, Weak<WlSurface>>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &ExtBackgroundEffectSurfaceV1,
+        request: <ExtBackgroundEffectSurfaceV1 as Resource>::Request,
+        data: &Weak<WlSurface>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_background_effect_surface_v1::Request::SetBlurRegion { region } => {
+                let Ok(surface) = data.upgrade() else {
+                    resource.post_error(
+                        ext_background_effect_surface_v1::Error::SurfaceDestroyed,
+                        "the surface of the background effect object was destroyed",
+                    );
+                    return;
+                };
+
+                let region = region.as_ref().map(get_region_attributes);
+                with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .get::<BackgroundEffectCachedState>()
+                        .pending()
+                        .blur_region = region;
+                });
+            }
+            ext_background_effect_surface_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _resource: &ExtBackgroundEffectSurfaceV1,
+        data: &Weak<WlSurface>,
+    ) {
+        let Ok(surface) = data.upgrade() else {
+            return;
+        };
+
+        with_states(&surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<BackgroundEffectSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            data.has_object = false;
+
+            // The effect is removed on the next commit.
+            states
+                .cached_state
+                .get::<BackgroundEffectCachedState>()
+                .pending()
+                .blur_region = None;
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_background_effect {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::protocols::raw::ext_background_effect::v1::server::ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1: ()
+        ] => $crate::protocols::background_effect::BackgroundEffectManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::protocols::raw::ext_background_effect::v1::server::ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1: ()
+        ] => $crate::protocols::background_effect::BackgroundEffectManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::protocols::raw::ext_background_effect::v1::server::ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::protocols::background_effect::BackgroundEffectManagerState);
+    };
+}
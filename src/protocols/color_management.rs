@@ -0,0 +1,990 @@
+//! wp-color-management-v1 protocol implementation.
+//!
+//! Only parametric image descriptions are supported. The image description set on a surface is
+//! double-buffered surface state, read with [`surface_image_description()`] when rendering.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use smithay::desktop::utils::surface_primary_scanout_output;
+use smithay::output::{Output, WeakOutput};
+use smithay::reexports::wayland_protocols::wp::color_management::v1::server::{
+    wp_color_management_output_v1, wp_color_management_surface_feedback_v1,
+    wp_color_management_surface_v1, wp_color_manager_v1, wp_image_description_creator_icc_v1,
+    wp_image_description_creator_params_v1, wp_image_description_info_v1, wp_image_description_v1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum, Weak,
+};
+use smithay::wayland::compositor::{with_states, Cacheable, SurfaceData};
+use wp_color_management_output_v1::WpColorManagementOutputV1;
+use wp_color_management_surface_feedback_v1::WpColorManagementSurfaceFeedbackV1;
+use wp_color_management_surface_v1::WpColorManagementSurfaceV1;
+use wp_color_manager_v1::WpColorManagerV1;
+use wp_image_description_creator_icc_v1::WpImageDescriptionCreatorIccV1;
+use wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1;
+use wp_image_description_info_v1::WpImageDescriptionInfoV1;
+use wp_image_description_v1::WpImageDescriptionV1;
+
+const VERSION: u32 = 1;
+
+/// Colorimetric description of image contents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDescription {
+    pub tf: TransferFunction,
+    pub primaries: Primaries,
+    pub luminances: Luminances,
+    /// Minimum and maximum luminance that the contents target, if narrower than the primary
+    /// luminance range.
+    ///
+    /// For outputs, this is the luminance range that the display can show.
+    pub target_luminance: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFunction {
+    /// Piece-wise sRGB transfer function.
+    Srgb,
+    /// Pure power curve with an exponent of 2.2.
+    Gamma22,
+    /// Linear transfer function, defined over all real numbers.
+    ExtLinear,
+    /// SMPTE ST 2084 perceptual quantizer.
+    St2084Pq,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primaries {
+    Named(NamedPrimaries),
+    Custom(Chromaticities),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedPrimaries {
+    Srgb,
+    Bt2020,
+    DisplayP3,
+    AdobeRgb,
+}
+
+/// CIE 1931 xy chromaticities of the primaries and the white point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chromaticities {
+    pub red: [f64; 2],
+    pub green: [f64; 2],
+    pub blue: [f64; 2],
+    pub white: [f64; 2],
+}
+
+/// Luminances in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Luminances {
+    pub min: f64,
+    pub max: f64,
+    /// Luminance of the reference white.
+    pub reference: f64,
+}
+
+pub struct ColorManagementState {
+    /// Image descriptions of the outputs that don't use the default sRGB one.
+    outputs: HashMap<Output, Record>,
+    /// Record of the default sRGB image description.
+    srgb: Record,
+    /// Bound output objects, to send image description changes to.
+    output_objects: Vec<WpColorManagementOutputV1>,
+    /// Feedback objects, to send preferred image description changes to.
+    feedbacks: Vec<Feedback>,
+    next_identity: u32,
+}
+
+/// Image description together with its identity, as handed out to clients.
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    description: ImageDescription,
+    identity: u32,
+}
+
+struct Feedback {
+    object: WpColorManagementSurfaceFeedbackV1,
+    /// Identity of the preferred image description, as last seen by the client.
+    identity: u32,
+}
+
+#[derive(Debug)]
+pub struct ImageDescriptionData {
+    /// The image description, or `None` if it failed to be created.
+    record: Option<Record>,
+    /// Whether get_information is allowed for this image description.
+    info_allowed: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ParametricCreatorData {
+    tf: Option<TransferFunction>,
+    primaries: Option<Primaries>,
+    luminances: Option<Luminances>,
+}
+
+#[derive(Debug, Default)]
+struct ColorManagementSurfaceState {
+    /// Whether a wp_color_management_surface_v1 object currently exists for this surface.
+    has_object: bool,
+}
+
+type ColorManagementSurfaceData = Mutex<ColorManagementSurfaceState>;
+
+#[derive(Debug, Default, Clone)]
+struct ColorManagementCachedState {
+    description: Option<ImageDescription>,
+}
+
+pub trait ColorManagementHandler {
+    fn color_management_state(&mut self) -> &mut ColorManagementState;
+}
+
+impl ImageDescription {
+    /// The image description assumed for surfaces that don't set one.
+    pub const SRGB: Self = Self {
+        tf: TransferFunction::Gamma22,
+        primaries: Primaries::Named(NamedPrimaries::Srgb),
+        luminances: TransferFunction::Gamma22.default_luminances(),
+        target_luminance: None,
+    };
+}
+
+impl TransferFunction {
+    /// Returns the luminances implied by the transfer function.
+    pub const fn default_luminances(self) -> Luminances {
+        match self {
+            TransferFunction::St2084Pq => Luminances {
+                min: 0.005,
+                max: 10000.,
+                reference: 203.,
+            },
+            TransferFunction::Srgb | TransferFunction::Gamma22 | TransferFunction::ExtLinear => {
+                Luminances {
+                    min: 0.2,
+                    max: 80.,
+                    reference: 80.,
+                }
+            }
+        }
+    }
+
+    fn from_protocol(tf: wp_color_manager_v1::TransferFunction) -> Option<Self> {
+        use wp_color_manager_v1::TransferFunction as Tf;
+
+        match tf {
+            Tf::Srgb => Some(Self::Srgb),
+            Tf::Gamma22 => Some(Self::Gamma22),
+            Tf::ExtLinear => Some(Self::ExtLinear),
+            Tf::St2084Pq => Some(Self::St2084Pq),
+            _ => None,
+        }
+    }
+
+    fn to_protocol(self) -> wp_color_manager_v1::TransferFunction {
+        use wp_color_manager_v1::TransferFunction as Tf;
+
+        match self {
+            Self::Srgb => Tf::Srgb,
+            Self::Gamma22 => Tf::Gamma22,
+            Self::ExtLinear => Tf::ExtLinear,
+            Self::St2084Pq => Tf::St2084Pq,
+        }
+    }
+}
+
+impl Primaries {
+    pub fn chromaticities(self) -> Chromaticities {
+        match self {
+            Primaries::Named(named) => named.chromaticities(),
+            Primaries::Custom(chromaticities) => chromaticities,
+        }
+    }
+}
+
+impl NamedPrimaries {
+    pub fn chromaticities(self) -> Chromaticities {
+        const D65: [f64; 2] = [0.3127, 0.3290];
+
+        let (red, green, blue) = match self {
+            NamedPrimaries::Srgb => ([0.64, 0.33], [0.30, 0.60], [0.15, 0.06]),
+            NamedPrimaries::Bt2020 => ([0.708, 0.292], [0.170, 0.797], [0.131, 0.046]),
+            NamedPrimaries::DisplayP3 => ([0.680, 0.320], [0.265, 0.690], [0.150, 0.060]),
+            NamedPrimaries::AdobeRgb => ([0.64, 0.33], [0.21, 0.71], [0.15, 0.06]),
+        };
+
+        Chromaticities {
+            red,
+            green,
+            blue,
+            white: D65,
+        }
+    }
+
+    fn from_protocol(primaries: wp_color_manager_v1::Primaries) -> Option<Self> {
+        use wp_color_manager_v1::Primaries as P;
+
+        match primaries {
+            P::Srgb => Some(Self::Srgb),
+            P::Bt2020 => Some(Self::Bt2020),
+            P::DisplayP3 => Some(Self::DisplayP3),
+            P::AdobeRgb => Some(Self::AdobeRgb),
+            _ => None,
+        }
+    }
+
+    fn to_protocol(self) -> wp_color_manager_v1::Primaries {
+        use wp_color_manager_v1::Primaries as P;
+
+        match self {
+            Self::Srgb => P::Srgb,
+            Self::Bt2020 => P::Bt2020,
+            Self::DisplayP3 => P::DisplayP3,
+            Self::AdobeRgb => P::AdobeRgb,
+        }
+    }
+}
+
+impl ColorManagementState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpColorManagerV1, ()>,
+        D: Dispatch<WpColorManagerV1, ()>,
+        D: ColorManagementHandler,
+        D: 'static,
+    {
+        display.create_global::<D, WpColorManagerV1, _>(VERSION, ());
+
+        Self {
+            outputs: HashMap::new(),
+            srgb: Record {
+                description: ImageDescription::SRGB,
+                identity: 1,
+            },
+            output_objects: Vec::new(),
+            feedbacks: Vec::new(),
+            next_identity: 2,
+        }
+    }
+
+    /// Sets the image description of the output, as sent to clients.
+    pub fn set_output_image_description(&mut self, output: &Output, description: ImageDescription) {
+        let current = self.output_record(Some(output));
+        if current.description == description {
+            return;
+        }
+
+        if description == ImageDescription::SRGB {
+            self.outputs.remove(output);
+        } else {
+            let record = self.new_record(description);
+            self.outputs.insert(output.clone(), record);
+        }
+
+        for object in &self.output_objects {
+            let data = object.data::<Option<WeakOutput>>().unwrap();
+            if data.as_ref().and_then(WeakOutput::upgrade).as_ref() == Some(output) {
+                object.image_description_changed();
+            }
+        }
+    }
+
+    pub fn remove_output(&mut self, output: &Output) {
+        self.outputs.remove(output);
+    }
+
+    /// Notifies the clients whose surfaces got a different preferred image description.
+    ///
+    /// The preferred image description is the one of the output where the surface is mostly
+    /// shown.
+    pub fn refresh(&mut self) {
+        let _span = tracy_client::span!("ColorManagementState::refresh");
+
+        let mut feedbacks = std::mem::take(&mut self.feedbacks);
+        for feedback in &mut feedbacks {
+            let Ok(surface) = feedback.object.data::<Weak<WlSurface>>().unwrap().upgrade() else {
+                continue;
+            };
+
+            let identity = self.preferred_record(&surface).identity;
+            if feedback.identity != identity {
+                feedback.identity = identity;
+                feedback.object.preferred_changed(identity);
+            }
+        }
+        self.feedbacks = feedbacks;
+    }
+
+    fn new_record(&mut self, description: ImageDescription) -> Record {
+        let identity = self.next_identity;
+        // Identities must not be recycled, so just stop counting at the end.
+        self.next_identity = self.next_identity.saturating_add(1);
+        Record {
+            description,
+            identity,
+        }
+    }
+
+    fn output_record(&self, output: Option<&Output>) -> Record {
+        output
+            .and_then(|output| self.outputs.get(output))
+            .copied()
+            .unwrap_or(self.srgb)
+    }
+
+    fn preferred_record(&self, surface: &WlSurface) -> Record {
+        let output = with_states(surface, |states| {
+            surface_primary_scanout_output(surface, states)
+        });
+        self.output_record(output.as_ref())
+    }
+}
+
+/// Returns the image description set on the surface, if any.
+pub fn surface_image_description(states: &SurfaceData) -> Option<ImageDescription> {
+    states
+        .cached_state
+        .get::<ColorManagementCachedState>()
+        .current()
+        .description
+}
+
+impl Cacheable for ColorManagementCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        self.clone()
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+fn init_image_description<D>(
+    data_init: &mut DataInit<'_, D>,
+    id: New<WpImageDescriptionV1>,
+    record: Option<Record>,
+    info_allowed: bool,
+) -> WpImageDescriptionV1
+where
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: 'static,
+{
+    let image_description = data_init.init(
+        id,
+        ImageDescriptionData {
+            record,
+            info_allowed,
+        },
+    );
+    if let Some(record) = record {
+        image_description.ready(record.identity);
+    }
+    image_description
+}
+
+fn send_information(info: &WpImageDescriptionInfoV1, description: &ImageDescription) {
+    // The protocol carries chromaticities multiplied by a million.
+    let to_int = |[x, y]: [f64; 2]| {
+        (
+            (x * 1_000_000.).round() as i32,
+            (y * 1_000_000.).round() as i32,
+        )
+    };
+
+    let c = description.primaries.chromaticities();
+    let (r_x, r_y) = to_int(c.red);
+    let (g_x, g_y) = to_int(c.green);
+    let (b_x, b_y) = to_int(c.blue);
+    let (w_x, w_y) = to_int(c.white);
+    info.primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    if let Primaries::Named(named) = description.primaries {
+        info.primaries_named(named.to_protocol());
+    }
+    info.tf_named(description.tf.to_protocol());
+
+    let lum = description.luminances;
+    let min_lum = (lum.min * 10000.).round() as u32;
+    info.luminances(
+        min_lum,
+        lum.max.round() as u32,
+        lum.reference.round() as u32,
+    );
+
+    // We don't support target primaries other than the primary ones.
+    info.target_primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    let (min, max) = description.target_luminance.unwrap_or((lum.min, lum.max));
+    info.target_luminance((min * 10000.).round() as u32, max.round() as u32);
+
+    info.done();
+}
+
+impl<D> GlobalDispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: GlobalDispatch<WpColorManagerV1, ()>,
+    D: Dispatch<WpColorManagerV1, ()>,
+    D: ColorManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpColorManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        use wp_color_manager_v1::Feature;
+
+        let manager = data_init.init(resource, ());
+
+        manager.supported_intent(wp_color_manager_v1::RenderIntent::Perceptual);
+        manager.supported_feature(Feature::Parametric);
+        manager.supported_feature(Feature::SetPrimaries);
+        manager.supported_feature(Feature::SetLuminances);
+        for tf in [
+            TransferFunction::Srgb,
+            TransferFunction::Gamma22,
+            TransferFunction::ExtLinear,
+            TransferFunction::St2084Pq,
+        ] {
+            manager.supported_tf_named(tf.to_protocol());
+        }
+        for primaries in [
+            NamedPrimaries::Srgb,
+            NamedPrimaries::Bt2020,
+            NamedPrimaries::DisplayP3,
+            NamedPrimaries::AdobeRgb,
+        ] {
+            manager.supported_primaries_named(primaries.to_protocol());
+        }
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagerV1, ()>,
+    D: Dispatch<WpColorManagementOutputV1, Option<WeakOutput>>,
+    D: Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>>,
+    D: Dispatch<WpColorManagementSurfaceFeedbackV1, Weak<WlSurface>>,
+    D: Dispatch<WpImageDescriptionCreatorIccV1, ()>,
+    D: Dispatch<WpImageDescriptionCreatorParamsV1, Mutex<ParametricCreatorData>>,
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: ColorManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &WpColorManagerV1,
+        request: <WpColorManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_manager_v1::Request::GetOutput { id, output } => {
+                let output = Output::from_resource(&output).map(|output| output.downgrade());
+                let object = data_init.init(id, output);
+                state.color_management_state().output_objects.push(object);
+            }
+            wp_color_manager_v1::Request::GetSurface { id, surface } => {
+                let already_exists = with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(ColorManagementSurfaceData::default);
+                    let mut data = states
+                        .data_map
+                        .get::<ColorManagementSurfaceData>()
+                        .unwrap()
+                        .lock()
+                        .unwrap();
+
+                    if data.has_object {
+                        return true;
+                    }
+
+                    data.has_object = true;
+                    false
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        wp_color_manager_v1::Error::SurfaceExists,
+                        "the surface already has a color management object",
+                    );
+                    return;
+                }
+
+                data_init.init(id, surface.downgrade());
+            }
+            wp_color_manager_v1::Request::GetSurfaceFeedback { id, surface } => {
+                let state = state.color_management_state();
+                let identity = state.preferred_record(&surface).identity;
+                let object = data_init.init(id, surface.downgrade());
+                state.feedbacks.push(Feedback { object, identity });
+            }
+            wp_color_manager_v1::Request::CreateIccCreator { obj } => {
+                data_init.init(obj, ());
+                resource.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature,
+                    "ICC profiles are not supported",
+                );
+            }
+            wp_color_manager_v1::Request::CreateParametricCreator { obj } => {
+                data_init.init(obj, Mutex::new(ParametricCreatorData::default()));
+            }
+            wp_color_manager_v1::Request::CreateWindowsScrgb { image_description } => {
+                init_image_description(data_init, image_description, None, false);
+                resource.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature,
+                    "Windows scRGB is not supported",
+                );
+            }
+            wp_color_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpColorManagementOutputV1, Option<WeakOutput>, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementOutputV1, Option<WeakOutput>>,
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: ColorManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &WpColorManagementOutputV1,
+        request: <WpColorManagementOutputV1 as Resource>::Request,
+        data: &Option<WeakOutput>,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_output_v1::Request::GetImageDescription { image_description } => {
+                let Some(output) = data.as_ref().and_then(WeakOutput::upgrade) else {
+                    let image_description =
+                        init_image_description(data_init, image_description, None, false);
+                    image_description.failed(
+                        wp_image_description_v1::Cause::NoOutput,
+                        String::from("the output no longer exists"),
+                    );
+                    return;
+                };
+
+                let record = state.color_management_state().output_record(Some(&output));
+                init_image_description(data_init, image_description, Some(record), true);
+            }
+            wp_color_management_output_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &WpColorManagementOutputV1,
+        _data: &Option<WeakOutput>,
+    ) {
+        state
+            .color_management_state()
+            .output_objects
+            .retain(|x| x != resource);
+    }
+}
+
+impl<D> Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceV1, Weak<WlSurface>>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpColorManagementSurfaceV1,
+        request: <WpColorManagementSurfaceV1 as Resource>::Request,
+        data: &Weak<WlSurface>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let description = match request {
+            wp_color_management_surface_v1::Request::SetImageDescription {
+                image_description,
+                render_intent,
+            } => {
+                let Some(record) = image_description
+                    .data::<ImageDescriptionData>()
+                    .and_then(|data| data.record)
+                else {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::ImageDescription,
+                        "the image description is not ready",
+                    );
+                    return;
+                };
+
+                let perceptual = wp_color_manager_v1::RenderIntent::Perceptual;
+                if !matches!(render_intent, WEnum::Value(intent) if intent == perceptual) {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::RenderIntent,
+                        "unsupported rendering intent",
+                    );
+                    return;
+                }
+
+                Some(record.description)
+            }
+            wp_color_management_surface_v1::Request::UnsetImageDescription => None,
+            wp_color_management_surface_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let Ok(surface) = data.upgrade() else {
+            resource.post_error(
+                wp_color_management_surface_v1::Error::Inert,
+                "the surface of the color management object was destroyed",
+            );
+            return;
+        };
+
+        with_states(&surface, |states| {
+            states
+                .cached_state
+                .get::<ColorManagementCachedState>()
+                .pending()
+                .description = description;
+        });
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _resource: &WpColorManagementSurfaceV1,
+        data: &Weak<WlSurface>,
+    ) {
+        let Ok(surface) = data.upgrade() else {
+            return;
+        };
+
+        with_states(&surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<ColorManagementSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            data.has_object = false;
+
+            // The image description is unset on the next commit.
+            states
+                .cached_state
+                .get::<ColorManagementCachedState>()
+                .pending()
+                .description = None;
+        });
+    }
+}
+
+impl<D> Dispatch<WpColorManagementSurfaceFeedbackV1, Weak<WlSurface>, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceFeedbackV1, Weak<WlSurface>>,
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: ColorManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &WpColorManagementSurfaceFeedbackV1,
+        request: <WpColorManagementSurfaceFeedbackV1 as Resource>::Request,
+        data: &Weak<WlSurface>,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_surface_feedback_v1::Request::GetPreferred {
+                image_description,
+            }
+            | wp_color_management_surface_feedback_v1::Request::GetPreferredParametric {
+                image_description,
+            } => {
+                let Ok(surface) = data.upgrade() else {
+                    init_image_description(data_init, image_description, None, false);
+                    resource.post_error(
+                        wp_color_management_surface_feedback_v1::Error::Inert,
+                        "the surface of the feedback object was destroyed",
+                    );
+                    return;
+                };
+
+                let record = state.color_management_state().preferred_record(&surface);
+                init_image_description(data_init, image_description, Some(record), true);
+            }
+            wp_color_management_surface_feedback_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &WpColorManagementSurfaceFeedbackV1,
+        _data: &Weak<WlSurface>,
+    ) {
+        state
+            .color_management_state()
+            .feedbacks
+            .retain(|x| x.object != *resource);
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionCreatorIccV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionCreatorIccV1, ()>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpImageDescriptionCreatorIccV1,
+        _request: <WpImageDescriptionCreatorIccV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        // The client was disconnected with an error upon creating this object.
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionCreatorParamsV1, Mutex<ParametricCreatorData>, D>
+    for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionCreatorParamsV1, Mutex<ParametricCreatorData>>,
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: ColorManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &WpImageDescriptionCreatorParamsV1,
+        request: <WpImageDescriptionCreatorParamsV1 as Resource>::Request,
+        data: &Mutex<ParametricCreatorData>,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        use wp_image_description_creator_params_v1::{Error, Request};
+
+        let mut data = data.lock().unwrap();
+        let already_set = |resource: &WpImageDescriptionCreatorParamsV1| {
+            resource.post_error(Error::AlreadySet, "the parameter was already set");
+        };
+
+        match request {
+            Request::Create { image_description } => {
+                let (Some(tf), Some(primaries)) = (data.tf, data.primaries) else {
+                    init_image_description(data_init, image_description, None, false);
+                    resource.post_error(
+                        Error::IncompleteSet,
+                        "the transfer function and the primaries must be set",
+                    );
+                    return;
+                };
+
+                let description = ImageDescription {
+                    tf,
+                    primaries,
+                    luminances: data.luminances.unwrap_or(tf.default_luminances()),
+                    target_luminance: None,
+                };
+                let record = state.color_management_state().new_record(description);
+                init_image_description(data_init, image_description, Some(record), false);
+            }
+            Request::SetTfNamed { tf } => {
+                if data.tf.is_some() {
+                    already_set(resource);
+                    return;
+                }
+
+                let tf = tf
+                    .into_result()
+                    .ok()
+                    .and_then(TransferFunction::from_protocol);
+                let Some(tf) = tf else {
+                    resource.post_error(Error::InvalidTf, "unsupported transfer function");
+                    return;
+                };
+                data.tf = Some(tf);
+            }
+            Request::SetPrimariesNamed { primaries } => {
+                if data.primaries.is_some() {
+                    already_set(resource);
+                    return;
+                }
+
+                let named = primaries
+                    .into_result()
+                    .ok()
+                    .and_then(NamedPrimaries::from_protocol);
+                let Some(named) = named else {
+                    resource.post_error(Error::InvalidPrimariesNamed, "unsupported primaries");
+                    return;
+                };
+                data.primaries = Some(Primaries::Named(named));
+            }
+            Request::SetPrimaries {
+                r_x,
+                r_y,
+                g_x,
+                g_y,
+                b_x,
+                b_y,
+                w_x,
+                w_y,
+            } => {
+                if data.primaries.is_some() {
+                    already_set(resource);
+                    return;
+                }
+
+                let xy = |x: i32, y: i32| [f64::from(x) / 1_000_000., f64::from(y) / 1_000_000.];
+                data.primaries = Some(Primaries::Custom(Chromaticities {
+                    red: xy(r_x, r_y),
+                    green: xy(g_x, g_y),
+                    blue: xy(b_x, b_y),
+                    white: xy(w_x, w_y),
+                }));
+            }
+            Request::SetLuminances {
+                min_lum,
+                max_lum,
+                reference_lum,
+            } => {
+                if data.luminances.is_some() {
+                    already_set(resource);
+                    return;
+                }
+
+                let luminances = Luminances {
+                    min: f64::from(min_lum) / 10000.,
+                    max: f64::from(max_lum),
+                    reference: f64::from(reference_lum),
+                };
+                if luminances.max <= luminances.min || luminances.reference <= luminances.min {
+                    resource.post_error(Error::InvalidLuminance, "invalid luminances");
+                    return;
+                }
+                data.luminances = Some(luminances);
+            }
+            Request::SetTfPower { .. }
+            | Request::SetMasteringDisplayPrimaries { .. }
+            | Request::SetMasteringLuminance { .. } => {
+                resource.post_error(Error::UnsupportedFeature, "unsupported feature");
+            }
+            // These are informational, and we have no use for them.
+            Request::SetMaxCll { .. } | Request::SetMaxFall { .. } => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionV1, ImageDescriptionData, D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionData>,
+    D: Dispatch<WpImageDescriptionInfoV1, ()>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpImageDescriptionV1,
+        request: <WpImageDescriptionV1 as Resource>::Request,
+        data: &ImageDescriptionData,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_image_description_v1::Request::GetInformation { information } => {
+                let info = data_init.init(information, ());
+
+                let Some(record) = data.record else {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NotReady,
+                        "the image description is not ready",
+                    );
+                    return;
+                };
+
+                if !data.info_allowed {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NoInformation,
+                        "get_information is not allowed for this image description",
+                    );
+                    return;
+                }
+
+                send_information(&info, &record.description);
+            }
+            wp_image_description_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpImageDescriptionInfoV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionInfoV1, ()>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpImageDescriptionInfoV1,
+        _request: <WpImageDescriptionInfoV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        unreachable!()
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_color_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_manager_v1::WpColorManagerV1: ()
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_manager_v1::WpColorManagerV1: ()
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_output_v1::WpColorManagementOutputV1: Option<smithay::output::WeakOutput>
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_v1::WpColorManagementSurfaceV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_feedback_v1::WpColorManagementSurfaceFeedbackV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_creator_icc_v1::WpImageDescriptionCreatorIccV1: ()
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1: std::sync::Mutex<$crate::protocols::color_management::ParametricCreatorData>
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_v1::WpImageDescriptionV1: $crate::protocols::color_management::ImageDescriptionData
+        ] => $crate::protocols::color_management::ColorManagementState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_info_v1::WpImageDescriptionInfoV1: ()
+        ] => $crate::protocols::color_management::ColorManagementState);
+    };
+}
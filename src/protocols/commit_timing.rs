@@ -0,0 +1,314 @@
+//! commit-timing-v1 protocol implementation.
+//!
+//! Commits with a timestamp in the future are held back with a compositor blocker until the
+//! output showing the surface is about to present a frame at or after that time, see
+//! [`signal_commit_timers()`].
+
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::{
+    wp_commit_timer_v1, wp_commit_timing_manager_v1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{
+    add_blocker, add_pre_commit_hook, with_states, Blocker, BlockerState, SurfaceData,
+};
+use wp_commit_timer_v1::WpCommitTimerV1;
+use wp_commit_timing_manager_v1::WpCommitTimingManagerV1;
+
+use crate::utils::get_monotonic_time;
+
+const VERSION: u32 = 1;
+
+pub struct CommitTimingManagerState;
+
+pub trait CommitTimingHandler {
+    /// A commit of the surface is held back until the given presentation time.
+    fn commit_timer_added(&mut self, surface: &WlSurface, target: Duration);
+}
+
+#[derive(Debug)]
+struct CommitTimer {
+    target: Duration,
+    cleared: AtomicBool,
+}
+
+#[derive(Debug)]
+struct CommitTimerBlocker(Arc<CommitTimer>);
+
+#[derive(Debug, Default)]
+struct CommitTimerSurfaceState {
+    /// Whether a wp_commit_timer_v1 object currently exists for this surface.
+    has_timer: bool,
+    /// Whether the commit hook was added to this surface.
+    has_hook: bool,
+    pending_timestamp: Option<Duration>,
+    /// Timers of the commits that are currently held back, in commit order.
+    timers: Vec<Arc<CommitTimer>>,
+}
+
+type CommitTimerSurfaceData = Mutex<CommitTimerSurfaceState>;
+
+impl CommitTimingManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpCommitTimingManagerV1, ()>,
+        D: Dispatch<WpCommitTimingManagerV1, ()>,
+        D: 'static,
+    {
+        display.create_global::<D, WpCommitTimingManagerV1, _>(VERSION, ());
+        Self
+    }
+}
+
+/// Releases the held back commits of the surface whose timestamp is at or before `until`.
+///
+/// Returns `true` if any commit was released, in which case the client's blockers must be
+/// re-checked.
+pub fn signal_commit_timers(states: &SurfaceData, until: Duration) -> bool {
+    let Some(data) = states.data_map.get::<CommitTimerSurfaceData>() else {
+        return false;
+    };
+
+    let mut data = data.lock().unwrap();
+    let len = data.timers.len();
+    data.timers.retain(|timer| {
+        if timer.target <= until {
+            timer.cleared.store(true, Ordering::Relaxed);
+            false
+        } else {
+            true
+        }
+    });
+    data.timers.len() != len
+}
+
+/// Returns the earliest timestamp of the held back commits of the surface.
+pub fn next_commit_timer(states: &SurfaceData) -> Option<Duration> {
+    let data = states.data_map.get::<CommitTimerSurfaceData>()?;
+    let data = data.lock().unwrap();
+    data.timers.iter().map(|timer| timer.target).min()
+}
+
+impl Blocker for CommitTimerBlocker {
+    fn state(&self) -> BlockerState {
+        if self.0.cleared.load(Ordering::Relaxed) {
+            BlockerState::Released
+        } else {
+            BlockerState::Pending
+        }
+    }
+}
+
+fn add_hook<D: CommitTimingHandler + 'static>(surface: &WlSurface) {
+    add_pre_commit_hook::<D, _>(surface, |state, _dh, surface| {
+        let timer = with_states(surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<CommitTimerSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            let target = data.pending_timestamp.take()?;
+
+            // Timestamps in the past don't need to hold anything back.
+            if target <= get_monotonic_time() {
+                return None;
+            }
+
+            let timer = Arc::new(CommitTimer {
+                target,
+                cleared: AtomicBool::new(false),
+            });
+            data.timers.push(timer.clone());
+            Some(timer)
+        });
+
+        if let Some(timer) = timer {
+            let target = timer.target;
+            add_blocker(surface, CommitTimerBlocker(timer));
+            trace!("added commit timer blocker for {target:?}");
+            state.commit_timer_added(surface, target);
+        }
+    });
+}
+
+impl<D> GlobalDispatch<WpCommitTimingManagerV1, (), D> for CommitTimingManagerState
+where
+    D: GlobalDispatch<WpCommitTimingManagerV1, ()>,
+    D: Dispatch<WpCommitTimingManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpCommitTimingManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpCommitTimingManagerV1, (), D> for CommitTimingManagerState
+where
+    D: Dispatch<WpCommitTimingManagerV1, ()>,
+    D: Dispatch<WpCommitTimerV1, Weak<WlSurface>>,
+    D: CommitTimingHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpCommitTimingManagerV1,
+        request: <WpCommitTimingManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_commit_timing_manager_v1::Request::GetTimer { id, surface } => {
+                let (already_exists, needs_hook) = with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(CommitTimerSurfaceData::default);
+                    let mut data = states
+                        .data_map
+                        .get::<CommitTimerSurfaceData>()
+                        .unwrap()
+                        .lock()
+                        .unwrap();
+
+                    if data.has_timer {
+                        return (true, false);
+                    }
+
+                    data.has_timer = true;
+                    (false, !mem::replace(&mut data.has_hook, true))
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        wp_commit_timing_manager_v1::Error::CommitTimerExists,
+                        "the surface already has a commit timer",
+                    );
+                    return;
+                }
+
+                if needs_hook {
+                    add_hook::<D>(&surface);
+                }
+
+                data_init.init(id, surface.downgrade());
+            }
+            wp_commit_timing_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpCommitTimerV1, Weak<WlSurface>, D> for CommitTimingManagerState
+where
+    D: Dispatch<WpCommitTimerV1, Weak<WlSurface>>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpCommitTimerV1,
+        request: <WpCommitTimerV1 as Resource>::Request,
+        data: &Weak<WlSurface>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_commit_timer_v1::Request::SetTimestamp {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+            } => {
+                let Ok(surface) = data.upgrade() else {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::SurfaceDestroyed,
+                        "the surface of the commit timer was destroyed",
+                    );
+                    return;
+                };
+
+                if tv_nsec >= 1_000_000_000 {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::InvalidTimestamp,
+                        "tv_nsec must be less than one second",
+                    );
+                    return;
+                }
+
+                let secs = (u64::from(tv_sec_hi) << 32) | u64::from(tv_sec_lo);
+                let timestamp = Duration::new(secs, tv_nsec);
+
+                let exists = with_states(&surface, |states| {
+                    let mut data = states
+                        .data_map
+                        .get::<CommitTimerSurfaceData>()
+                        .unwrap()
+                        .lock()
+                        .unwrap();
+                    data.pending_timestamp.replace(timestamp).is_some()
+                });
+
+                if exists {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::TimestampExists,
+                        "a timestamp was already set for this commit",
+                    );
+                }
+            }
+            wp_commit_timer_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _resource: &WpCommitTimerV1,
+        data: &Weak<WlSurface>,
+    ) {
+        let Ok(surface) = data.upgrade() else {
+            return;
+        };
+
+        with_states(&surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<CommitTimerSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            data.has_timer = false;
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_commit_timing {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timing_manager_v1::WpCommitTimingManagerV1: ()
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timing_manager_v1::WpCommitTimingManagerV1: ()
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timer_v1::WpCommitTimerV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+    };
+}
@@ -0,0 +1,315 @@
+//! ext-foreign-toplevel-list protocol implementation.
+//!
+//! Toplevel identifiers are the window ids from the niri IPC, so clients can correlate handles
+//! with `niri msg windows` output and IPC events.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1;
+use ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1;
+use smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::{
+    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::wayland::shell::xdg::XdgToplevelSurfaceRoleAttributes;
+
+use crate::niri::State;
+use crate::utils::with_toplevel_role;
+
+const VERSION: u32 = 1;
+
+pub struct ExtForeignToplevelListManagerState {
+    display: DisplayHandle,
+    instances: Vec<ExtForeignToplevelListV1>,
+    toplevels: HashMap<WlSurface, ToplevelData>,
+}
+
+pub trait ExtForeignToplevelListHandler {
+    fn ext_foreign_toplevel_list_state(&mut self) -> &mut ExtForeignToplevelListManagerState;
+}
+
+struct ToplevelData {
+    identifier: String,
+    title: Option<String>,
+    app_id: Option<String>,
+    instances: Vec<ExtForeignToplevelHandleV1>,
+}
+
+pub struct ExtForeignToplevelListGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+impl ExtForeignToplevelListManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtForeignToplevelListV1, ExtForeignToplevelListGlobalData>,
+        D: Dispatch<ExtForeignToplevelListV1, ()>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = ExtForeignToplevelListGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ExtForeignToplevelListV1, _>(VERSION, global_data);
+        Self {
+            display: display.clone(),
+            instances: Vec::new(),
+            toplevels: HashMap::new(),
+        }
+    }
+
+    /// Returns the toplevel surface that the handle refers to, if it is still open.
+    pub fn surface_for_handle(&self, handle: &ExtForeignToplevelHandleV1) -> Option<&WlSurface> {
+        self.toplevels
+            .iter()
+            .find(|(_, data)| data.instances.contains(handle))
+            .map(|(surface, _)| surface)
+    }
+}
+
+pub fn refresh(state: &mut State) {
+    let _span = tracy_client::span!("ext_foreign_toplevel_list::refresh");
+
+    let protocol_state = &mut state.niri.ext_foreign_toplevel_list_state;
+
+    // Handle closed windows.
+    protocol_state.toplevels.retain(|surface, data| {
+        if state.niri.layout.find_window_and_output(surface).is_some() {
+            return true;
+        }
+
+        for instance in &data.instances {
+            instance.closed();
+        }
+
+        false
+    });
+
+    // Handle new and existing windows.
+    state.niri.layout.with_windows(|mapped, _, _, _| {
+        let toplevel = mapped.toplevel();
+        let wl_surface = toplevel.wl_surface();
+        let id = mapped.id().get();
+
+        with_toplevel_role(toplevel, |role| {
+            refresh_toplevel(protocol_state, wl_surface, role, id);
+        });
+    });
+}
+
+fn refresh_toplevel(
+    protocol_state: &mut ExtForeignToplevelListManagerState,
+    wl_surface: &WlSurface,
+    role: &XdgToplevelSurfaceRoleAttributes,
+    id: u64,
+) {
+    match protocol_state.toplevels.entry(wl_surface.clone()) {
+        Entry::Occupied(entry) => {
+            // Existing window, check if anything changed.
+            let data = entry.into_mut();
+
+            let mut new_title = None;
+            if data.title != role.title {
+                data.title.clone_from(&role.title);
+                new_title = role.title.as_deref();
+
+                if new_title.is_none() {
+                    error!("toplevel title changed to None");
+                }
+            }
+
+            let mut new_app_id = None;
+            if data.app_id != role.app_id {
+                data.app_id.clone_from(&role.app_id);
+                new_app_id = role.app_id.as_deref();
+
+                if new_app_id.is_none() {
+                    error!("toplevel app_id changed to None");
+                }
+            }
+
+            if new_title.is_some() || new_app_id.is_some() {
+                for instance in &data.instances {
+                    if let Some(new_title) = new_title {
+                        instance.title(new_title.to_owned());
+                    }
+                    if let Some(new_app_id) = new_app_id {
+                        instance.app_id(new_app_id.to_owned());
+                    }
+                    instance.done();
+                }
+            }
+        }
+        Entry::Vacant(entry) => {
+            // New window, start tracking it.
+            // The identifier matches the window id in the niri IPC. Window ids are never reused
+            // while niri is running, as the protocol requires.
+            let mut data = ToplevelData {
+                identifier: id.to_string(),
+                title: role.title.clone(),
+                app_id: role.app_id.clone(),
+                instances: Vec::new(),
+            };
+
+            for manager in &protocol_state.instances {
+                if let Some(client) = manager.client() {
+                    data.add_instance::<State>(&protocol_state.display, &client, manager);
+                }
+            }
+
+            entry.insert(data);
+        }
+    }
+}
+
+impl ToplevelData {
+    fn add_instance<D>(
+        &mut self,
+        handle: &DisplayHandle,
+        client: &Client,
+        manager: &ExtForeignToplevelListV1,
+    ) where
+        D: Dispatch<ExtForeignToplevelHandleV1, ()>,
+        D: 'static,
+    {
+        let toplevel = client
+            .create_resource::<ExtForeignToplevelHandleV1, _, D>(handle, manager.version(), ())
+            .unwrap();
+        manager.toplevel(&toplevel);
+
+        toplevel.identifier(self.identifier.clone());
+
+        if let Some(title) = &self.title {
+            toplevel.title(title.clone());
+        }
+        if let Some(app_id) = &self.app_id {
+            toplevel.app_id(app_id.clone());
+        }
+
+        toplevel.done();
+
+        self.instances.push(toplevel);
+    }
+}
+
+impl<D> GlobalDispatch<ExtForeignToplevelListV1, ExtForeignToplevelListGlobalData, D>
+    for ExtForeignToplevelListManagerState
+where
+    D: GlobalDispatch<ExtForeignToplevelListV1, ExtForeignToplevelListGlobalData>,
+    D: Dispatch<ExtForeignToplevelListV1, ()>,
+    D: Dispatch<ExtForeignToplevelHandleV1, ()>,
+    D: ExtForeignToplevelListHandler,
+{
+    fn bind(
+        state: &mut D,
+        handle: &DisplayHandle,
+        client: &Client,
+        resource: New<ExtForeignToplevelListV1>,
+        _global_data: &ExtForeignToplevelListGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let state = state.ext_foreign_toplevel_list_state();
+
+        for data in state.toplevels.values_mut() {
+            data.add_instance::<D>(handle, client, &manager);
+        }
+
+        state.instances.push(manager);
+    }
+
+    fn can_view(client: Client, global_data: &ExtForeignToplevelListGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelListV1, (), D> for ExtForeignToplevelListManagerState
+where
+    D: Dispatch<ExtForeignToplevelListV1, ()>,
+    D: ExtForeignToplevelListHandler,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ExtForeignToplevelListV1,
+        request: <ExtForeignToplevelListV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_list_v1::Request::Stop => {
+                resource.finished();
+
+                let state = state.ext_foreign_toplevel_list_state();
+                state.instances.retain(|x| x != resource);
+            }
+            ext_foreign_toplevel_list_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ExtForeignToplevelListV1,
+        _data: &(),
+    ) {
+        let state = state.ext_foreign_toplevel_list_state();
+        state.instances.retain(|x| x != resource);
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelHandleV1, (), D> for ExtForeignToplevelListManagerState
+where
+    D: Dispatch<ExtForeignToplevelHandleV1, ()>,
+    D: ExtForeignToplevelListHandler,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtForeignToplevelHandleV1,
+        request: <ExtForeignToplevelHandleV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_handle_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ExtForeignToplevelHandleV1,
+        _data: &(),
+    ) {
+        let state = state.ext_foreign_toplevel_list_state();
+        for data in state.toplevels.values_mut() {
+            data.instances.retain(|instance| instance != resource);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_ext_foreign_toplevel_list {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1: $crate::protocols::ext_foreign_toplevel_list::ExtForeignToplevelListGlobalData
+        ] => $crate::protocols::ext_foreign_toplevel_list::ExtForeignToplevelListManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1: ()
+        ] => $crate::protocols::ext_foreign_toplevel_list::ExtForeignToplevelListManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1: ()
+        ] => $crate::protocols::ext_foreign_toplevel_list::ExtForeignToplevelListManagerState);
+    };
+}
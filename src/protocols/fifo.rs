@@ -0,0 +1,335 @@
+//! fifo-v1 protocol implementation.
+//!
+//! A barrier set by a commit clears at the next refresh cycle of the output showing the surface,
+//! see [`signal_fifo_barrier()`]. Commits waiting on a barrier are held back with a compositor
+//! blocker until then.
+
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use smithay::reexports::wayland_protocols::wp::fifo::v1::server::{wp_fifo_manager_v1, wp_fifo_v1};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{
+    add_blocker, add_post_commit_hook, add_pre_commit_hook, is_sync_subsurface, with_states,
+    Blocker, BlockerState, Cacheable, SurfaceData,
+};
+use wp_fifo_manager_v1::WpFifoManagerV1;
+use wp_fifo_v1::WpFifoV1;
+
+const VERSION: u32 = 1;
+
+pub struct FifoManagerState;
+
+pub trait FifoHandler {
+    /// The applied state of the surface set a fifo barrier.
+    fn fifo_barrier_set(&mut self, surface: &WlSurface);
+}
+
+#[derive(Debug, Default)]
+struct Barrier {
+    cleared: AtomicBool,
+}
+
+#[derive(Debug)]
+struct FifoBarrierBlocker(Arc<Barrier>);
+
+#[derive(Debug, Default)]
+struct FifoSurfaceState {
+    /// Whether a wp_fifo_v1 object currently exists for this surface.
+    has_fifo: bool,
+    /// Whether the commit hooks were added to this surface.
+    has_hooks: bool,
+    pending_set_barrier: bool,
+    pending_wait_barrier: bool,
+    /// Barrier set by the latest commit, which may not have been applied yet.
+    latest: Option<Arc<Barrier>>,
+    /// Barrier set by the applied surface state, cleared at the next refresh cycle.
+    active: Option<Arc<Barrier>>,
+}
+
+type FifoSurfaceData = Mutex<FifoSurfaceState>;
+
+/// Barrier carried along with the commit that set it, until that commit is applied.
+#[derive(Debug, Default)]
+struct FifoCachedState {
+    barrier: Option<Arc<Barrier>>,
+}
+
+impl FifoManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpFifoManagerV1, ()>,
+        D: Dispatch<WpFifoManagerV1, ()>,
+        D: 'static,
+    {
+        display.create_global::<D, WpFifoManagerV1, _>(VERSION, ());
+        Self
+    }
+}
+
+/// Clears the fifo barrier set by the applied state of the surface.
+///
+/// Should be called once per refresh cycle of the output showing the surface. Returns `true` if
+/// a barrier was cleared, in which case the client's blockers must be re-checked.
+pub fn signal_fifo_barrier(states: &SurfaceData) -> bool {
+    let Some(data) = states.data_map.get::<FifoSurfaceData>() else {
+        return false;
+    };
+
+    let mut data = data.lock().unwrap();
+    if let Some(barrier) = data.active.take() {
+        barrier.cleared.store(true, Ordering::Relaxed);
+        true
+    } else {
+        false
+    }
+}
+
+impl Blocker for FifoBarrierBlocker {
+    fn state(&self) -> BlockerState {
+        if self.0.cleared.load(Ordering::Relaxed) {
+            BlockerState::Released
+        } else {
+            BlockerState::Pending
+        }
+    }
+}
+
+impl Cacheable for FifoCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        mem::take(self)
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        if self.barrier.is_some() {
+            into.barrier = self.barrier;
+        }
+    }
+}
+
+fn add_hooks<D: FifoHandler + 'static>(surface: &WlSurface) {
+    add_pre_commit_hook::<D, _>(surface, |_state, _dh, surface| {
+        let is_sync = is_sync_subsurface(surface);
+
+        let wait_for = with_states(surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<FifoSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            let wait = mem::take(&mut data.pending_wait_barrier);
+            let set = mem::take(&mut data.pending_set_barrier);
+
+            // The wait constraint must be ignored for synchronized subsurfaces.
+            let wait_for = data
+                .latest
+                .clone()
+                .filter(|barrier| wait && !is_sync && !barrier.cleared.load(Ordering::Relaxed));
+
+            if set {
+                let barrier = Arc::new(Barrier::default());
+                data.latest = Some(barrier.clone());
+                states
+                    .cached_state
+                    .get::<FifoCachedState>()
+                    .pending()
+                    .barrier = Some(barrier);
+            }
+
+            wait_for
+        });
+
+        if let Some(barrier) = wait_for {
+            add_blocker(surface, FifoBarrierBlocker(barrier));
+            trace!("added fifo barrier blocker");
+        }
+    });
+
+    add_post_commit_hook::<D, _>(surface, |state, _dh, surface| {
+        let barrier_set = with_states(surface, |states| {
+            let barrier = states
+                .cached_state
+                .get::<FifoCachedState>()
+                .current()
+                .barrier
+                .take();
+            let Some(barrier) = barrier else {
+                return false;
+            };
+
+            let mut data = states
+                .data_map
+                .get::<FifoSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            // A newer barrier got applied before the previous one cleared; don't keep waiters
+            // of the previous one stuck.
+            if let Some(prev) = data.active.replace(barrier) {
+                prev.cleared.store(true, Ordering::Relaxed);
+            }
+            true
+        });
+
+        if barrier_set {
+            state.fifo_barrier_set(surface);
+        }
+    });
+}
+
+impl<D> GlobalDispatch<WpFifoManagerV1, (), D> for FifoManagerState
+where
+    D: GlobalDispatch<WpFifoManagerV1, ()>,
+    D: Dispatch<WpFifoManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpFifoManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpFifoManagerV1, (), D> for FifoManagerState
+where
+    D: Dispatch<WpFifoManagerV1, ()>,
+    D: Dispatch<WpFifoV1, Weak<WlSurface>>,
+    D: FifoHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpFifoManagerV1,
+        request: <WpFifoManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_fifo_manager_v1::Request::GetFifo { id, surface } => {
+                let (already_exists, needs_hooks) = with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(FifoSurfaceData::default);
+                    let mut data = states
+                        .data_map
+                        .get::<FifoSurfaceData>()
+                        .unwrap()
+                        .lock()
+                        .unwrap();
+
+                    if data.has_fifo {
+                        return (true, false);
+                    }
+
+                    data.has_fifo = true;
+                    (false, !mem::replace(&mut data.has_hooks, true))
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        wp_fifo_manager_v1::Error::AlreadyExists,
+                        "the surface already has a fifo object",
+                    );
+                    return;
+                }
+
+                if needs_hooks {
+                    add_hooks::<D>(&surface);
+                }
+
+                data_init.init(id, surface.downgrade());
+            }
+            wp_fifo_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpFifoV1, Weak<WlSurface>, D> for FifoManagerState
+where
+    D: Dispatch<WpFifoV1, Weak<WlSurface>>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpFifoV1,
+        request: <WpFifoV1 as Resource>::Request,
+        data: &Weak<WlSurface>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let set = match request {
+            wp_fifo_v1::Request::SetBarrier => true,
+            wp_fifo_v1::Request::WaitBarrier => false,
+            wp_fifo_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let Ok(surface) = data.upgrade() else {
+            resource.post_error(
+                wp_fifo_v1::Error::SurfaceDestroyed,
+                "the surface of the fifo object was destroyed",
+            );
+            return;
+        };
+
+        with_states(&surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<FifoSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            if set {
+                data.pending_set_barrier = true;
+            } else {
+                data.pending_wait_barrier = true;
+            }
+        });
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, _resource: &WpFifoV1, data: &Weak<WlSurface>) {
+        let Ok(surface) = data.upgrade() else {
+            return;
+        };
+
+        with_states(&surface, |states| {
+            let mut data = states
+                .data_map
+                .get::<FifoSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            data.has_fifo = false;
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_fifo {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_manager_v1::WpFifoManagerV1: ()
+        ] => $crate::protocols::fifo::FifoManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_manager_v1::WpFifoManagerV1: ()
+        ] => $crate::protocols::fifo::FifoManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_v1::WpFifoV1: smithay::reexports::wayland_server::Weak<smithay::reexports::wayland_server::protocol::wl_surface::WlSurface>
+        ] => $crate::protocols::fifo::FifoManagerState);
+    };
+}
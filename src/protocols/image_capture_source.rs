@@ -0,0 +1,249 @@
+//! ext-image-capture-source protocol implementation.
+//!
+//! Supports output sources and foreign toplevel sources, where toplevels come from
+//! ext-foreign-toplevel-list.
+
+use ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1;
+use ext_image_capture_source_v1::ExtImageCaptureSourceV1;
+use ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1;
+use smithay::output::{Output, WeakOutput};
+use smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::{
+    ext_foreign_toplevel_image_capture_source_manager_v1, ext_image_capture_source_v1,
+    ext_output_image_capture_source_manager_v1,
+};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::protocols::ext_foreign_toplevel_list::ExtForeignToplevelListHandler;
+
+const VERSION: u32 = 1;
+
+/// What an image capture source object refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageCaptureSource {
+    Output(WeakOutput),
+    Toplevel(WlSurface),
+    /// The source was created for an output or a toplevel that no longer exists.
+    Invalid,
+}
+
+pub struct ImageCaptureSourceState;
+
+pub struct ImageCaptureSourceGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+impl ImageCaptureSourceState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+        D: GlobalDispatch<
+            ExtForeignToplevelImageCaptureSourceManagerV1,
+            ImageCaptureSourceGlobalData,
+        >,
+        D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+        D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + Clone + 'static,
+    {
+        let global_data = ImageCaptureSourceGlobalData {
+            filter: Box::new(filter.clone()),
+        };
+        display.create_global::<D, ExtOutputImageCaptureSourceManagerV1, _>(VERSION, global_data);
+
+        let global_data = ImageCaptureSourceGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ExtForeignToplevelImageCaptureSourceManagerV1, _>(
+            VERSION,
+            global_data,
+        );
+
+        Self
+    }
+}
+
+impl ImageCaptureSource {
+    pub fn from_resource(resource: &ExtImageCaptureSourceV1) -> Self {
+        resource
+            .data::<ImageCaptureSource>()
+            .cloned()
+            .unwrap_or(ImageCaptureSource::Invalid)
+    }
+
+    pub fn output(&self) -> Option<Output> {
+        match self {
+            ImageCaptureSource::Output(output) => output.upgrade(),
+            _ => None,
+        }
+    }
+
+    pub fn toplevel(&self) -> Option<&WlSurface> {
+        match self {
+            ImageCaptureSource::Toplevel(surface) => Some(surface),
+            _ => None,
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData, D>
+    for ImageCaptureSourceState
+where
+    D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtOutputImageCaptureSourceManagerV1>,
+        _global_data: &ImageCaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D>
+    GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData, D>
+    for ImageCaptureSourceState
+where
+    D: GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtForeignToplevelImageCaptureSourceManagerV1>,
+        _global_data: &ImageCaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtOutputImageCaptureSourceManagerV1, (), D> for ImageCaptureSourceState
+where
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSource>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtOutputImageCaptureSourceManagerV1,
+        request: <ExtOutputImageCaptureSourceManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_output_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                output,
+            } => {
+                let source_data = match Output::from_resource(&output) {
+                    Some(output) => ImageCaptureSource::Output(output.downgrade()),
+                    None => {
+                        trace!("image capture source requested for non-existent output");
+                        ImageCaptureSource::Invalid
+                    }
+                };
+                data_init.init(source, source_data);
+            }
+            ext_output_image_capture_source_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, (), D> for ImageCaptureSourceState
+where
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSource>,
+    D: ExtForeignToplevelListHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        request: <ExtForeignToplevelImageCaptureSourceManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                toplevel_handle,
+            } => {
+                let list_state = state.ext_foreign_toplevel_list_state();
+                let source_data = match list_state.surface_for_handle(&toplevel_handle) {
+                    Some(surface) => ImageCaptureSource::Toplevel(surface.clone()),
+                    None => {
+                        trace!("image capture source requested for closed toplevel");
+                        ImageCaptureSource::Invalid
+                    }
+                };
+                data_init.init(source, source_data);
+            }
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCaptureSourceV1, ImageCaptureSource, D> for ImageCaptureSourceState
+where
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSource>,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtImageCaptureSourceV1,
+        request: <ExtImageCaptureSourceV1 as Resource>::Request,
+        _data: &ImageCaptureSource,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_capture_source_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_image_capture_source {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: $crate::protocols::image_capture_source::ImageCaptureSourceGlobalData
+        ] => $crate::protocols::image_capture_source::ImageCaptureSourceState);
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: $crate::protocols::image_capture_source::ImageCaptureSourceGlobalData
+        ] => $crate::protocols::image_capture_source::ImageCaptureSourceState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: ()
+        ] => $crate::protocols::image_capture_source::ImageCaptureSourceState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: ()
+        ] => $crate::protocols::image_capture_source::ImageCaptureSourceState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_image_capture_source_v1::ExtImageCaptureSourceV1: $crate::protocols::image_capture_source::ImageCaptureSource
+        ] => $crate::protocols::image_capture_source::ImageCaptureSourceState);
+    };
+}
@@ -0,0 +1,747 @@
+//! ext-image-copy-capture protocol implementation.
+//!
+//! Sessions capture an [`ImageCaptureSource`]. The compositor renders the source contents (or the
+//! pointer cursor, for cursor sessions) into the client buffer on a redraw of the output where the
+//! source is located, and only once there's new damage, except for the first frame.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+use ext_image_copy_capture_cursor_session_v1::ExtImageCopyCaptureCursorSessionV1;
+use ext_image_copy_capture_frame_v1::{ExtImageCopyCaptureFrameV1, FailureReason};
+use ext_image_copy_capture_manager_v1::{ExtImageCopyCaptureManagerV1, Options};
+use ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1;
+use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::{Buffer, Fourcc, Modifier};
+use smithay::backend::drm::DrmNode;
+use smithay::backend::renderer::damage::OutputDamageTracker;
+use smithay::backend::renderer::sync::SyncPoint;
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::{
+    ext_image_copy_capture_cursor_session_v1, ext_image_copy_capture_frame_v1,
+    ext_image_copy_capture_manager_v1, ext_image_copy_capture_session_v1,
+};
+use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+use smithay::reexports::wayland_server::protocol::wl_shm::Format;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+use smithay::utils::{Physical, Point, Rectangle, Size, Transform};
+use smithay::wayland::{dmabuf, shm};
+use wayland_backend::server::ClientId;
+
+use crate::protocols::image_capture_source::ImageCaptureSource;
+use crate::render_helpers::shm_buffer_fits;
+use crate::utils::get_monotonic_time;
+
+const VERSION: u32 = 1;
+
+const SHM_FORMATS: [Format; 2] = [Format::Xrgb8888, Format::Argb8888];
+const DMABUF_FORMATS: [Fourcc; 2] = [Fourcc::Xrgb8888, Fourcc::Argb8888];
+
+#[derive(Default)]
+pub struct ImageCopyCaptureManagerState {
+    sessions: HashMap<ExtImageCopyCaptureSessionV1, Session>,
+    /// DRM device for dma-buf buffers, or `None` if only SHM buffers are supported.
+    dmabuf_device: Option<DrmNode>,
+}
+
+pub struct ImageCopyCaptureGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// Image copy capture session.
+pub struct Session {
+    resource: ExtImageCopyCaptureSessionV1,
+    source: ImageCaptureSource,
+    kind: SessionKind,
+    dmabuf_device: Option<DrmNode>,
+    /// Buffer size last sent to the client in the buffer constraints.
+    buffer_size: Option<Size<i32, Physical>>,
+    damage_tracker: OutputDamageTracker,
+    /// The frame object currently existing for this session, if any.
+    frame: Option<ExtImageCopyCaptureFrameV1>,
+    /// Capture waiting for a redraw with damage.
+    capture: Option<Capture>,
+    stopped: bool,
+}
+
+pub enum SessionKind {
+    /// Session capturing the source contents.
+    Contents { paint_cursors: bool },
+    /// Session capturing the pointer cursor over the source.
+    Cursor(CursorState),
+}
+
+pub struct CursorState {
+    resource: ExtImageCopyCaptureCursorSessionV1,
+    entered: bool,
+    position: Option<Point<i32, Physical>>,
+    hotspot: Option<Point<i32, Physical>>,
+}
+
+pub struct CursorSessionData {
+    source: ImageCaptureSource,
+    has_session: Mutex<bool>,
+}
+
+pub struct FrameData {
+    session: ExtImageCopyCaptureSessionV1,
+    inner: Mutex<FrameInner>,
+}
+
+#[derive(Default)]
+struct FrameInner {
+    buffer: Option<WlBuffer>,
+    captured: bool,
+}
+
+/// Image copy capture buffer.
+#[derive(Clone)]
+pub enum CaptureBuffer {
+    Dmabuf(Dmabuf),
+    Shm(WlBuffer),
+}
+
+/// Frame waiting to be captured.
+pub struct Capture {
+    frame: ExtImageCopyCaptureFrameV1,
+    buffer: CaptureBuffer,
+    submitted: bool,
+}
+
+impl ImageCopyCaptureManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, dmabuf_device: Option<DrmNode>, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+        D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = ImageCopyCaptureGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ExtImageCopyCaptureManagerV1, _>(VERSION, global_data);
+
+        Self {
+            sessions: HashMap::new(),
+            dmabuf_device,
+        }
+    }
+
+    pub fn session_mut(&mut self, session: &ExtImageCopyCaptureSessionV1) -> Option<&mut Session> {
+        self.sessions.get_mut(session)
+    }
+
+    pub fn sessions_mut(&mut self) -> impl Iterator<Item = &mut Session> {
+        self.sessions.values_mut()
+    }
+
+    /// Returns whether any running session captures this toplevel.
+    pub fn is_capturing_toplevel(&self, surface: &WlSurface) -> bool {
+        self.sessions
+            .values()
+            .any(|session| !session.stopped && session.source.toplevel() == Some(surface))
+    }
+
+    fn add_session(
+        &mut self,
+        resource: ExtImageCopyCaptureSessionV1,
+        source: ImageCaptureSource,
+        kind: SessionKind,
+    ) {
+        let session = Session {
+            resource: resource.clone(),
+            source,
+            kind,
+            dmabuf_device: self.dmabuf_device,
+            buffer_size: None,
+            damage_tracker: OutputDamageTracker::new((0, 0), 1.0, Transform::Normal),
+            frame: None,
+            capture: None,
+            stopped: false,
+        };
+        self.sessions.insert(resource, session);
+    }
+}
+
+impl Session {
+    pub fn source(&self) -> &ImageCaptureSource {
+        &self.source
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn paint_cursors(&self) -> bool {
+        matches!(
+            self.kind,
+            SessionKind::Contents {
+                paint_cursors: true
+            }
+        )
+    }
+
+    pub fn is_cursor(&self) -> bool {
+        matches!(self.kind, SessionKind::Cursor(_))
+    }
+
+    pub fn has_capture(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Stops the session, for example when the source is gone.
+    pub fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.stopped = true;
+
+        if let Some(capture) = self.capture.take() {
+            capture.fail(FailureReason::Stopped);
+        }
+
+        self.resource.stopped();
+    }
+
+    /// Updates the buffer size, sending new buffer constraints to the client if it changed.
+    pub fn set_buffer_size(&mut self, size: Size<i32, Physical>) {
+        if self.stopped || self.buffer_size == Some(size) {
+            return;
+        }
+        self.buffer_size = Some(size);
+
+        // A capture with the previous buffer size can no longer succeed.
+        if let Some(capture) = self.capture.take() {
+            capture.fail(FailureReason::BufferConstraints);
+        }
+
+        self.resource.buffer_size(size.w as u32, size.h as u32);
+        for format in SHM_FORMATS {
+            self.resource.shm_format(format);
+        }
+        if let Some(device) = self.dmabuf_device {
+            self.resource
+                .dmabuf_device(device.dev_id().to_ne_bytes().to_vec());
+
+            let modifiers = u64::from(Modifier::Linear).to_ne_bytes().to_vec();
+            for format in DMABUF_FORMATS {
+                self.resource
+                    .dmabuf_format(format as u32, modifiers.clone());
+            }
+        }
+        self.resource.done();
+    }
+
+    /// Updates the cursor position over the source for cursor sessions.
+    ///
+    /// `position` is the hotspot position relative to the source buffer, and `hotspot` is the
+    /// hotspot offset in the cursor buffer. `None` means that the cursor is outside the captured
+    /// area.
+    pub fn set_cursor_position(
+        &mut self,
+        position: Option<(Point<i32, Physical>, Point<i32, Physical>)>,
+    ) {
+        let SessionKind::Cursor(cursor) = &mut self.kind else {
+            return;
+        };
+
+        match position {
+            Some((position, hotspot)) => {
+                if !cursor.entered {
+                    cursor.entered = true;
+                    cursor.position = None;
+                    cursor.hotspot = None;
+                    cursor.resource.enter();
+                }
+
+                if cursor.position != Some(position) {
+                    cursor.position = Some(position);
+                    cursor.resource.position(position.x, position.y);
+                }
+
+                if cursor.hotspot != Some(hotspot) {
+                    cursor.hotspot = Some(hotspot);
+                    cursor.resource.hotspot(hotspot.x, hotspot.y);
+                }
+            }
+            None => {
+                if cursor.entered {
+                    cursor.entered = false;
+                    cursor.resource.leave();
+                }
+            }
+        }
+    }
+
+    pub fn split(&mut self) -> (&mut OutputDamageTracker, Option<&Capture>) {
+        (&mut self.damage_tracker, self.capture.as_ref())
+    }
+
+    pub fn take_capture(&mut self) -> Option<Capture> {
+        self.capture.take()
+    }
+
+    /// Resets the damage tracker, so that the next capture reports full damage.
+    pub fn reset_damage(&mut self) {
+        self.damage_tracker = OutputDamageTracker::new((0, 0), 1.0, Transform::Normal);
+    }
+}
+
+impl Capture {
+    /// Get the target buffer to copy to.
+    pub fn buffer(&self) -> &CaptureBuffer {
+        &self.buffer
+    }
+
+    fn fail(mut self, reason: FailureReason) {
+        self.frame.failed(reason);
+        self.submitted = true;
+    }
+
+    /// Submit the copied content.
+    fn submit(
+        mut self,
+        transform: Transform,
+        damages: &[Rectangle<i32, smithay::utils::Buffer>],
+        timestamp: Duration,
+    ) {
+        self.frame.transform(transform.into());
+
+        for Rectangle { loc, size } in damages {
+            self.frame.damage(loc.x, loc.y, size.w, size.h);
+        }
+
+        let tv_sec_hi = (timestamp.as_secs() >> 32) as u32;
+        let tv_sec_lo = (timestamp.as_secs() & 0xFFFFFFFF) as u32;
+        let tv_nsec = timestamp.subsec_nanos();
+        self.frame.presentation_time(tv_sec_hi, tv_sec_lo, tv_nsec);
+
+        self.frame.ready();
+
+        // Mark frame as submitted to ensure destructor isn't run.
+        self.submitted = true;
+    }
+
+    pub fn submit_after_sync<T>(
+        self,
+        transform: Transform,
+        damages: Vec<Rectangle<i32, smithay::utils::Buffer>>,
+        sync_point: Option<SyncPoint>,
+        event_loop: &LoopHandle<'_, T>,
+    ) {
+        let timestamp = get_monotonic_time();
+        match sync_point.and_then(|s| s.export()) {
+            None => self.submit(transform, &damages, timestamp),
+            Some(sync_fd) => {
+                let source = Generic::new(sync_fd, Interest::READ, Mode::OneShot);
+                let mut capture = Some(self);
+                event_loop
+                    .insert_source(source, move |_, _, _| {
+                        capture
+                            .take()
+                            .unwrap()
+                            .submit(transform, &damages, timestamp);
+                        Ok(PostAction::Remove)
+                    })
+                    .unwrap();
+            }
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        if !self.submitted {
+            self.frame.failed(FailureReason::Unknown);
+        }
+    }
+}
+
+/// Handler trait for ext-image-copy-capture.
+pub trait ImageCopyCaptureHandler {
+    fn image_copy_capture_state(&mut self) -> &mut ImageCopyCaptureManagerState;
+
+    /// Handle a new session.
+    ///
+    /// The handler must synchronously either send the buffer constraints with
+    /// [`Session::set_buffer_size()`], or stop the session.
+    fn new_session(&mut self, session: &ExtImageCopyCaptureSessionV1);
+
+    /// Handle a new capture pushed to the session.
+    fn frame(&mut self, session: &ExtImageCopyCaptureSessionV1);
+}
+
+impl<D> GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData, D>
+    for ImageCopyCaptureManagerState
+where
+    D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtImageCopyCaptureManagerV1>,
+        _global_data: &ImageCopyCaptureGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCopyCaptureGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureManagerV1, (), D> for ImageCopyCaptureManagerState
+where
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &ExtImageCopyCaptureManagerV1,
+        request: <ExtImageCopyCaptureManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_manager_v1::Request::CreateSession {
+                session,
+                source,
+                options,
+            } => {
+                let session = data_init.init(session, ());
+
+                let paint_cursors = match options {
+                    WEnum::Value(options) => options.contains(Options::PaintCursors),
+                    WEnum::Unknown(_) => {
+                        manager.post_error(
+                            ext_image_copy_capture_manager_v1::Error::InvalidOption,
+                            "invalid options",
+                        );
+                        return;
+                    }
+                };
+
+                let source = ImageCaptureSource::from_resource(&source);
+                let kind = SessionKind::Contents { paint_cursors };
+                state
+                    .image_copy_capture_state()
+                    .add_session(session.clone(), source, kind);
+                state.new_session(&session);
+            }
+            ext_image_copy_capture_manager_v1::Request::CreatePointerCursorSession {
+                session,
+                source,
+                // We only have one seat, so there's only one pointer.
+                pointer: _,
+            } => {
+                let data = CursorSessionData {
+                    source: ImageCaptureSource::from_resource(&source),
+                    has_session: Mutex::new(false),
+                };
+                data_init.init(session, data);
+            }
+            ext_image_copy_capture_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData, D>
+    for ImageCopyCaptureManagerState
+where
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ()>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureCursorSessionV1,
+        request: <ExtImageCopyCaptureCursorSessionV1 as Resource>::Request,
+        data: &CursorSessionData,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_cursor_session_v1::Request::GetCaptureSession { session } => {
+                let session = data_init.init(session, ());
+
+                let mut has_session = data.has_session.lock().unwrap();
+                if *has_session {
+                    resource.post_error(
+                        ext_image_copy_capture_cursor_session_v1::Error::DuplicateSession,
+                        "get_capture_session was already sent",
+                    );
+                    return;
+                }
+                *has_session = true;
+                drop(has_session);
+
+                let kind = SessionKind::Cursor(CursorState {
+                    resource: resource.clone(),
+                    entered: false,
+                    position: None,
+                    hotspot: None,
+                });
+                state.image_copy_capture_state().add_session(
+                    session.clone(),
+                    data.source.clone(),
+                    kind,
+                );
+                state.new_session(&session);
+            }
+            ext_image_copy_capture_cursor_session_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureSessionV1, (), D> for ImageCopyCaptureManagerState
+where
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureSessionV1,
+        request: <ExtImageCopyCaptureSessionV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_session_v1::Request::CreateFrame { frame } => {
+                let data = FrameData {
+                    session: resource.clone(),
+                    inner: Mutex::new(FrameInner::default()),
+                };
+                let frame = data_init.init(frame, data);
+
+                let Some(session) = state.image_copy_capture_state().session_mut(resource) else {
+                    return;
+                };
+
+                if session
+                    .frame
+                    .as_ref()
+                    .is_some_and(|existing| existing.is_alive())
+                {
+                    resource.post_error(
+                        ext_image_copy_capture_session_v1::Error::DuplicateFrame,
+                        "create_frame sent before destroying the previous frame",
+                    );
+                    return;
+                }
+
+                session.frame = Some(frame);
+            }
+            ext_image_copy_capture_session_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ExtImageCopyCaptureSessionV1,
+        _data: &(),
+    ) {
+        let state = state.image_copy_capture_state();
+        if let Some(mut session) = state.sessions.remove(resource) {
+            if let Some(capture) = session.capture.take() {
+                capture.fail(FailureReason::Stopped);
+            }
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureFrameV1, FrameData, D> for ImageCopyCaptureManagerState
+where
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        frame: &ExtImageCopyCaptureFrameV1,
+        request: <ExtImageCopyCaptureFrameV1 as Resource>::Request,
+        data: &FrameData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+
+        match request {
+            ext_image_copy_capture_frame_v1::Request::AttachBuffer { buffer } => {
+                if inner.captured {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                        "attach_buffer sent after capture",
+                    );
+                    return;
+                }
+
+                inner.buffer = Some(buffer);
+            }
+            ext_image_copy_capture_frame_v1::Request::DamageBuffer {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if inner.captured {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                        "damage_buffer sent after capture",
+                    );
+                    return;
+                }
+
+                if x < 0 || y < 0 || width <= 0 || height <= 0 {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::InvalidBufferDamage,
+                        "invalid buffer damage",
+                    );
+                    return;
+                }
+
+                // We always redraw the full buffer, so we don't need the client damage.
+            }
+            ext_image_copy_capture_frame_v1::Request::Capture => {
+                if inner.captured {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                        "capture was already sent",
+                    );
+                    return;
+                }
+
+                let Some(buffer) = inner.buffer.clone() else {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::NoBuffer,
+                        "capture sent without attach_buffer",
+                    );
+                    return;
+                };
+
+                inner.captured = true;
+                drop(inner);
+
+                let Some(session) = state.image_copy_capture_state().session_mut(&data.session)
+                else {
+                    frame.failed(FailureReason::Stopped);
+                    return;
+                };
+
+                if session.stopped {
+                    frame.failed(FailureReason::Stopped);
+                    return;
+                }
+
+                let Some(size) = session.buffer_size else {
+                    frame.failed(FailureReason::BufferConstraints);
+                    return;
+                };
+
+                let Some(buffer) = validate_buffer(&buffer, size) else {
+                    frame.failed(FailureReason::BufferConstraints);
+                    return;
+                };
+
+                session.capture = Some(Capture {
+                    frame: frame.clone(),
+                    buffer,
+                    submitted: false,
+                });
+
+                let session = session.resource.clone();
+                state.frame(&session);
+            }
+            ext_image_copy_capture_frame_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        frame: &ExtImageCopyCaptureFrameV1,
+        data: &FrameData,
+    ) {
+        let state = state.image_copy_capture_state();
+        let Some(session) = state.sessions.get_mut(&data.session) else {
+            return;
+        };
+
+        if session.frame.as_ref() == Some(frame) {
+            session.frame = None;
+        }
+
+        if session
+            .capture
+            .as_ref()
+            .is_some_and(|capture| capture.frame == *frame)
+        {
+            session.capture = None;
+        }
+    }
+}
+
+fn validate_buffer(buffer: &WlBuffer, size: Size<i32, Physical>) -> Option<CaptureBuffer> {
+    if let Ok(dmabuf) = dmabuf::get_dmabuf(buffer) {
+        if DMABUF_FORMATS.contains(&dmabuf.format().code)
+            && dmabuf.width() == size.w as u32
+            && dmabuf.height() == size.h as u32
+        {
+            return Some(CaptureBuffer::Dmabuf(dmabuf.clone()));
+        }
+        return None;
+    }
+
+    let valid = shm::with_buffer_contents(buffer, |_, shm_len, buffer_data| {
+        SHM_FORMATS.contains(&buffer_data.format) && shm_buffer_fits(&buffer_data, shm_len, size)
+    })
+    .unwrap_or(false);
+
+    valid.then(|| CaptureBuffer::Shm(buffer.clone()))
+}
+
+#[macro_export]
+macro_rules! delegate_image_copy_capture {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: $crate::protocols::image_copy_capture::ImageCopyCaptureGlobalData
+        ] => $crate::protocols::image_copy_capture::ImageCopyCaptureManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: ()
+        ] => $crate::protocols::image_copy_capture::ImageCopyCaptureManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1: ()
+        ] => $crate::protocols::image_copy_capture::ImageCopyCaptureManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_cursor_session_v1::ExtImageCopyCaptureCursorSessionV1: $crate::protocols::image_copy_capture::CursorSessionData
+        ] => $crate::protocols::image_copy_capture::ImageCopyCaptureManagerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_frame_v1::ExtImageCopyCaptureFrameV1: $crate::protocols::image_copy_capture::FrameData
+        ] => $crate::protocols::image_copy_capture::ImageCopyCaptureManagerState);
+    };
+}
@@ -1,10 +1,19 @@
+pub mod background_effect;
+pub mod color_management;
+pub mod commit_timing;
+pub mod ext_foreign_toplevel_list;
 pub mod ext_workspace;
+pub mod fifo;
 pub mod foreign_toplevel;
 pub mod gamma_control;
+pub mod image_capture_source;
+pub mod image_copy_capture;
 pub mod mutter_x11_interop;
 pub mod output_management;
+pub mod output_power_management;
 pub mod screencopy;
 pub mod virtual_keyboard;
 pub mod virtual_pointer;
+pub mod xdg_toplevel_tag;
 
 pub mod raw;
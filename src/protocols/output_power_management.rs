@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr;
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+use wayland_protocols_wlr::output_power_management::v1::server::{
+    zwlr_output_power_manager_v1, zwlr_output_power_v1,
+};
+use zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1;
+use zwlr_output_power_v1::{Mode, ZwlrOutputPowerV1};
+
+const VERSION: u32 = 1;
+
+pub struct OutputPowerManagementManagerState {
+    // Active output power controls only. Failed ones are removed.
+    output_powers: HashMap<Output, Vec<ZwlrOutputPowerV1>>,
+}
+
+pub struct OutputPowerManagementManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+pub trait OutputPowerManagementHandler {
+    fn output_power_management_state(&mut self) -> &mut OutputPowerManagementManagerState;
+    fn output_power_mode(&mut self, output: &Output) -> Mode;
+    fn set_output_power_mode(&mut self, output: &Output, mode: Mode);
+}
+
+impl OutputPowerManagementManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementManagerGlobalData>,
+        D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+        D: Dispatch<ZwlrOutputPowerV1, ()>,
+        D: OutputPowerManagementHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = OutputPowerManagementManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ZwlrOutputPowerManagerV1, _>(VERSION, global_data);
+
+        Self {
+            output_powers: HashMap::new(),
+        }
+    }
+
+    pub fn output_power_mode_changed(&mut self, output: &Output, mode: Mode) {
+        if let Some(output_powers) = self.output_powers.get(output) {
+            for output_power in output_powers {
+                output_power.mode(mode);
+            }
+        }
+    }
+
+    pub fn output_removed(&mut self, output: &Output) {
+        if let Some(output_powers) = self.output_powers.remove(output) {
+            for output_power in output_powers {
+                output_power.failed();
+            }
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementManagerGlobalData, D>
+    for OutputPowerManagementManagerState
+where
+    D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementManagerGlobalData>,
+    D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+    D: Dispatch<ZwlrOutputPowerV1, ()>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrOutputPowerManagerV1>,
+        _manager_state: &OutputPowerManagementManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &OutputPowerManagementManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerManagerV1, (), D> for OutputPowerManagementManagerState
+where
+    D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+    D: Dispatch<ZwlrOutputPowerV1, ()>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputPowerManagerV1,
+        request: <ZwlrOutputPowerManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_manager_v1::Request::GetOutputPower { id, output } => {
+                let output_power = data_init.init(id, ());
+
+                let Some(output) = Output::from_resource(&output) else {
+                    output_power.failed();
+                    return;
+                };
+
+                output_power.mode(state.output_power_mode(&output));
+                state
+                    .output_power_management_state()
+                    .output_powers
+                    .entry(output)
+                    .or_default()
+                    .push(output_power);
+            }
+            zwlr_output_power_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerV1, (), D> for OutputPowerManagementManagerState
+where
+    D: Dispatch<ZwlrOutputPowerV1, ()>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrOutputPowerV1,
+        request: <ZwlrOutputPowerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_v1::Request::SetMode { mode } => {
+                let WEnum::Value(mode) = mode else {
+                    resource.post_error(zwlr_output_power_v1::Error::InvalidMode, "invalid mode");
+                    return;
+                };
+
+                let output_powers = &state.output_power_management_state().output_powers;
+                let Some((output, _)) = output_powers
+                    .iter()
+                    .find(|(_, x)| x.iter().any(|x| x == resource))
+                else {
+                    // The output is gone, and the failed event was already sent.
+                    return;
+                };
+                let output = output.clone();
+
+                trace!("setting power mode {mode:?} for output {}", output.name());
+                state.set_output_power_mode(&output, mode);
+            }
+            zwlr_output_power_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputPowerV1, _data: &()) {
+        let output_powers = &mut state.output_power_management_state().output_powers;
+        output_powers.retain(|_, x| {
+            x.retain(|x| x != resource);
+            !x.is_empty()
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_output_power_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: $crate::protocols::output_power_management::OutputPowerManagementManagerGlobalData
+        ] => $crate::protocols::output_power_management::OutputPowerManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: ()
+        ] => $crate::protocols::output_power_management::OutputPowerManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::ZwlrOutputPowerV1: ()
+        ] => $crate::protocols::output_power_management::OutputPowerManagementManagerState);
+    };
+}
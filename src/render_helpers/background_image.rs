@@ -0,0 +1,391 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::thread;
+
+use anyhow::{ensure, Context as _};
+use niri_config::{BackgroundImage, BackgroundImageMode};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::{ImportMem as _, Texture as _};
+use smithay::utils::{Buffer, Logical, Physical, Point, Rectangle, Size, Transform};
+
+use super::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use super::renderer::NiriRenderer;
+use super::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::expand_home;
+
+thread_local! {
+    /// Images by path, so that every file is decoded only once.
+    static IMAGES: RefCell<HashMap<PathBuf, Weak<Image>>> = RefCell::default();
+    /// Channel for sending images decoded on other threads back to the main thread.
+    static DECODED_TX: RefCell<Option<calloop::channel::Sender<DecodedImage>>> =
+        const { RefCell::new(None) };
+}
+
+/// Background image drawn over an area, such as a workspace or the overview backdrop.
+#[derive(Debug)]
+pub struct BackgroundImageBuffer {
+    config: Option<BackgroundImage>,
+    image: Option<Rc<Image>>,
+    size: Size<f64, Logical>,
+    /// Texture buffer for this area.
+    ///
+    /// Every area has its own buffer, so that render element ids are unique when the same image
+    /// is visible in several areas at once. The key is the tiled texture size, if any.
+    buffer: RefCell<Option<(Option<Size<i32, Buffer>>, TextureBuffer<GlesTexture>)>>,
+}
+
+/// Image shared between all areas showing it.
+#[derive(Debug)]
+struct Image {
+    /// Decoded pixels, `None` while decoding is in progress or if it failed.
+    pixels: RefCell<Option<Pixels>>,
+    texture: RefCell<Option<GlesTexture>>,
+    /// Textures with the image repeated to fill a given size.
+    tiled: RefCell<HashMap<(i32, i32), GlesTexture>>,
+}
+
+#[derive(Debug)]
+struct Pixels {
+    /// Pixels in Abgr8888 with premultiplied alpha.
+    data: Vec<u8>,
+    size: Size<i32, Buffer>,
+    is_opaque: bool,
+}
+
+/// Result of decoding an image on another thread.
+pub struct DecodedImage {
+    path: PathBuf,
+    pixels: anyhow::Result<Pixels>,
+}
+
+impl BackgroundImageBuffer {
+    pub fn new(config: Option<&BackgroundImage>, size: Size<f64, Logical>) -> Self {
+        let mut rv = Self {
+            config: None,
+            image: None,
+            size,
+            buffer: RefCell::new(None),
+        };
+        rv.update_config(config);
+        rv
+    }
+
+    /// Updates the config, returning whether it changed.
+    pub fn update_config(&mut self, config: Option<&BackgroundImage>) -> bool {
+        if self.config.as_ref() == config {
+            return false;
+        }
+
+        let path_changed = self.config.as_ref().map(|c| &c.path) != config.map(|c| &c.path);
+        self.config = config.cloned();
+        self.buffer = RefCell::new(None);
+
+        if path_changed {
+            self.image = config.and_then(|config| match Image::load_cached(&config.path) {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    warn!("error loading background image {:?}: {err:?}", config.path);
+                    None
+                }
+            });
+        }
+
+        true
+    }
+
+    pub fn resize(&mut self, size: Size<f64, Logical>) {
+        self.size = size;
+    }
+
+    pub fn size(&self) -> Size<f64, Logical> {
+        self.size
+    }
+
+    /// Returns whether the image is decoded and ready to render.
+    pub fn has_image(&self) -> bool {
+        self.image.as_ref().is_some_and(|image| image.is_decoded())
+    }
+
+    pub fn render(
+        &self,
+        renderer: &mut impl NiriRenderer,
+        location: Point<f64, Logical>,
+        scale: f64,
+        alpha: f32,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let config = self.config.as_ref()?;
+        let image = self.image.as_ref()?;
+        if !image.is_decoded() {
+            return None;
+        }
+        let renderer = renderer.as_gles_renderer();
+
+        let area = self.size;
+        if area.w <= 0. || area.h <= 0. {
+            return None;
+        }
+
+        // Tile mode uses a texture with the image already repeated to the area size, since
+        // textures are clamped to the edge.
+        let tiled_size = (config.mode == BackgroundImageMode::Tile).then(|| {
+            let size: Size<i32, Physical> = area.to_physical_precise_ceil(scale);
+            Size::<i32, Buffer>::from((size.w, size.h))
+        });
+
+        let mut cached = self.buffer.borrow_mut();
+        if cached.as_ref().is_none_or(|(key, _)| *key != tiled_size) {
+            let texture = match tiled_size {
+                Some(size) => image.tiled_texture(renderer, size),
+                None => image.texture(renderer),
+            };
+            let texture = match texture {
+                Ok(texture) => texture,
+                Err(err) => {
+                    warn!("error uploading background image: {err:?}");
+                    return None;
+                }
+            };
+
+            let opaque_regions = if image.is_opaque() {
+                vec![Rectangle::from_size(texture.size())]
+            } else {
+                Vec::new()
+            };
+            let buffer = TextureBuffer::from_texture(
+                renderer,
+                texture,
+                1.,
+                Transform::Normal,
+                opaque_regions,
+            );
+            *cached = Some((tiled_size, buffer));
+        }
+        let buffer = cached.as_ref().unwrap().1.clone();
+
+        // The buffer has scale 1, so its logical coordinates are image pixels.
+        let image_size = buffer.logical_size();
+        let (loc, src, size) = match config.mode {
+            BackgroundImageMode::Fill => {
+                let zoom = f64::max(area.w / image_size.w, area.h / image_size.h);
+                let src_size = area.downscale(zoom);
+                let src_loc = (image_size.to_point() - src_size.to_point()).downscale(2.);
+                (
+                    Point::default(),
+                    Some(Rectangle::new(src_loc, src_size)),
+                    area,
+                )
+            }
+            BackgroundImageMode::Fit => {
+                let zoom = f64::min(area.w / image_size.w, area.h / image_size.h);
+                let size = image_size.upscale(zoom);
+                let loc = (area.to_point() - size.to_point()).downscale(2.);
+                (loc, None, size)
+            }
+            BackgroundImageMode::Center => {
+                // Show image pixels as physical pixels, cropping whatever doesn't fit.
+                let logical_size = image_size.downscale(scale);
+                let size = Size::from((
+                    f64::min(logical_size.w, area.w),
+                    f64::min(logical_size.h, area.h),
+                ));
+                let src_size = size.upscale(scale);
+                let src_loc = (image_size.to_point() - src_size.to_point()).downscale(2.);
+                let loc = (area.to_point() - size.to_point()).downscale(2.);
+                let loc = loc.to_physical_precise_round(scale).to_logical(scale);
+                (loc, Some(Rectangle::new(src_loc, src_size)), size)
+            }
+            BackgroundImageMode::Tile => {
+                let src = Rectangle::from_size(area.upscale(scale));
+                (Point::default(), Some(src), area)
+            }
+        };
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location + loc,
+            alpha,
+            src,
+            Some(size),
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+/// Sets the channel for decoding images on separate threads.
+///
+/// Until this is called, images are decoded synchronously.
+pub fn set_decoded_sender(tx: calloop::channel::Sender<DecodedImage>) {
+    DECODED_TX.set(Some(tx));
+}
+
+/// Stores the pixels of an image decoded on another thread.
+pub fn finish_decoding(decoded: DecodedImage) {
+    let DecodedImage { path, pixels } = decoded;
+
+    let Some(image) = IMAGES.with_borrow(|images| images.get(&path).and_then(Weak::upgrade)) else {
+        // Nothing shows this image anymore.
+        return;
+    };
+
+    image.set_pixels(&path, pixels);
+}
+
+impl Image {
+    fn load_cached(path: &str) -> anyhow::Result<Rc<Self>> {
+        let path = Path::new(path);
+        let path = expand_home(path)
+            .context("error expanding ~")?
+            .unwrap_or_else(|| path.to_owned());
+
+        if let Some(image) = IMAGES.with_borrow(|images| images.get(&path).and_then(Weak::upgrade))
+        {
+            return Ok(image);
+        }
+
+        let image = Rc::new(Self {
+            pixels: RefCell::new(None),
+            texture: RefCell::new(None),
+            tiled: RefCell::new(HashMap::new()),
+        });
+        IMAGES.with_borrow_mut(|images| {
+            images.retain(|_, image| image.strong_count() > 0);
+            images.insert(path.clone(), Rc::downgrade(&image));
+        });
+
+        // Decoding large images takes a while, so do it on a separate thread when possible.
+        match DECODED_TX.with_borrow(|tx| tx.clone()) {
+            Some(tx) => {
+                let res = thread::Builder::new()
+                    .name("Background Image Decoder".to_owned())
+                    .spawn(move || {
+                        let _span = tracy_client::span!("Pixels::decode");
+                        let pixels = Pixels::decode(&path);
+                        let _ = tx.send(DecodedImage { path, pixels });
+                    });
+
+                if let Err(err) = res {
+                    warn!("error spawning a thread to decode the background image: {err:?}");
+                }
+            }
+            None => {
+                let pixels = Pixels::decode(&path);
+                image.set_pixels(&path, pixels);
+            }
+        }
+
+        Ok(image)
+    }
+
+    fn set_pixels(&self, path: &Path, pixels: anyhow::Result<Pixels>) {
+        match pixels {
+            Ok(pixels) => *self.pixels.borrow_mut() = Some(pixels),
+            Err(err) => warn!("error loading background image {path:?}: {err:?}"),
+        }
+    }
+
+    fn is_decoded(&self) -> bool {
+        self.pixels.borrow().is_some()
+    }
+
+    fn is_opaque(&self) -> bool {
+        self.pixels.borrow().as_ref().is_some_and(|p| p.is_opaque)
+    }
+
+    fn texture(&self, renderer: &mut GlesRenderer) -> anyhow::Result<GlesTexture> {
+        let mut texture = self.texture.borrow_mut();
+        if let Some(texture) = &*texture {
+            return Ok(texture.clone());
+        }
+
+        let pixels = self.pixels.borrow();
+        let pixels = pixels.as_ref().context("image is not decoded")?;
+
+        let new = renderer
+            .import_memory(&pixels.data, Fourcc::Abgr8888, pixels.size, false)
+            .context("error importing texture")?;
+        *texture = Some(new.clone());
+        Ok(new)
+    }
+
+    fn tiled_texture(
+        &self,
+        renderer: &mut GlesRenderer,
+        size: Size<i32, Buffer>,
+    ) -> anyhow::Result<GlesTexture> {
+        let mut tiled = self.tiled.borrow_mut();
+        if let Some(texture) = tiled.get(&(size.w, size.h)) {
+            return Ok(texture.clone());
+        }
+
+        let _span = tracy_client::span!("Image::tiled_texture");
+
+        let pixels = self.pixels.borrow();
+        let pixels = pixels.as_ref().context("image is not decoded")?;
+
+        let image_stride = pixels.size.w as usize * 4;
+        let stride = size.w as usize * 4;
+
+        // Build one row of tiles, then repeat it vertically.
+        let mut row_of_tiles = Vec::with_capacity(stride * pixels.size.h as usize);
+        for image_row in pixels.data.chunks_exact(image_stride) {
+            row_of_tiles.extend(image_row.iter().cycle().take(stride));
+        }
+
+        let mut data = Vec::with_capacity(stride * size.h as usize);
+        for row in row_of_tiles
+            .chunks_exact(stride)
+            .cycle()
+            .take(size.h as usize)
+        {
+            data.extend_from_slice(row);
+        }
+
+        let texture = renderer
+            .import_memory(&data, Fourcc::Abgr8888, size, false)
+            .context("error importing texture")?;
+        tiled.insert((size.w, size.h), texture.clone());
+        Ok(texture)
+    }
+}
+
+impl Pixels {
+    fn decode(path: &Path) -> anyhow::Result<Self> {
+        let image = image::ImageReader::open(path)
+            .context("error opening file")?
+            .with_guessed_format()
+            .context("error reading file")?
+            .decode()
+            .context("error decoding image")?
+            .into_rgba8();
+
+        let width = i32::try_from(image.width()).context("image is too large")?;
+        let height = i32::try_from(image.height()).context("image is too large")?;
+        ensure!(width > 0 && height > 0, "image is empty");
+
+        // Convert to premultiplied alpha.
+        let mut data = image.into_raw();
+        let mut is_opaque = true;
+        for px in data.chunks_exact_mut(4) {
+            let a = px[3];
+            if a == 255 {
+                continue;
+            }
+
+            is_opaque = false;
+            for x in &mut px[..3] {
+                *x = ((*x as u16 * a as u16 + 127) / 255) as u8;
+            }
+        }
+
+        Ok(Self {
+            data,
+            size: Size::from((width, height)),
+            is_opaque,
+        })
+    }
+}
@@ -0,0 +1,602 @@
+//! Background blur.
+//!
+//! Surfaces with blur push a [`BlurRenderElement`] right behind themselves and record it in the
+//! [`BlurRequests`] of the render. Once all output elements are known, the elements behind each
+//! blur element are rendered offscreen with damage tracking, cropped to the blurred area, and
+//! blurred with the dual Kawase algorithm only when their contents change, so static blur is
+//! cached across frames.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::iter::zip;
+use std::rc::Rc;
+
+use anyhow::{ensure, Context as _};
+use glam::{Mat3, Vec2};
+use niri_config::{BackgroundEffectRule, CornerRadius};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::utils::CropRenderElement;
+use smithay::backend::renderer::element::{Element, Id, Kind, RenderElement, UnderlyingStorage};
+use smithay::backend::renderer::gles::{
+    GlesError, GlesFrame, GlesRenderer, GlesTexProgram, GlesTexture, Uniform,
+};
+use smithay::backend::renderer::utils::{CommitCounter, DamageSet, OpaqueRegions};
+use smithay::backend::renderer::{
+    Bind as _, Color32F, ContextId, Frame as _, Offscreen as _, Renderer as _, Texture as _,
+};
+use smithay::utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform};
+use smithay::wayland::compositor::{RectangleKind, RegionAttributes};
+
+use super::offscreen::OffscreenBuffer;
+use super::renderer::{AsGlesFrame as _, NiriRenderer};
+use super::shaders::{mat3_uniform, Shaders};
+use crate::backend::tty::{TtyFrame, TtyRenderer, TtyRendererError};
+
+/// Upper limit on the number of blur passes.
+///
+/// Every pass halves the texture size, so more passes than this don't make a visible difference.
+const MAX_PASSES: u8 = 8;
+
+/// Cache for the blurred backdrop of one blur element.
+#[derive(Debug, Default)]
+pub struct BackdropBlur {
+    /// Offscreen buffer with the unblurred contents behind the element.
+    offscreen: OffscreenBuffer,
+
+    /// The blurred textures.
+    ///
+    /// Lazily created when `render` is called. Recreated when necessary.
+    inner: RefCell<Option<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Downsampled textures, from the largest to the smallest.
+    passes: Vec<GlesTexture>,
+    /// The final blurred texture, the size of the backdrop.
+    result: GlesTexture,
+    /// Id of the renderer context that the textures come from.
+    renderer_context_id: ContextId<GlesTexture>,
+    /// Size of the backdrop.
+    size: Size<i32, Buffer>,
+    /// Location of the backdrop in output coordinates.
+    loc: Point<i32, Physical>,
+    /// State of the offscreen backdrop that the result was computed from.
+    source: Option<(CommitCounter, u32)>,
+    /// Blur offset that the result was computed with.
+    offset: f64,
+    /// Commit counter of the result, incremented every time it is recomputed.
+    commit: CommitCounter,
+}
+
+/// Blurred backdrop of a blur element, ready to be drawn.
+#[derive(Debug, Clone)]
+pub struct BlurredBackdrop {
+    texture: GlesTexture,
+    renderer_context_id: ContextId<GlesTexture>,
+    loc: Point<i32, Physical>,
+    commit: CommitCounter,
+}
+
+/// Backdrop of a blur element, filled in once the elements behind it are known.
+type BackdropSlot = Rc<RefCell<Option<BlurredBackdrop>>>;
+
+/// Blur elements pushed during one render.
+#[derive(Debug, Default)]
+pub struct BlurRequests {
+    slots: RefCell<Vec<(Id, BackdropSlot)>>,
+}
+
+/// Area behind a surface to blur.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlurArea {
+    /// The whole surface geometry.
+    Geometry,
+    /// Rectangles relative to the surface geometry.
+    Region(Vec<Rectangle<i32, Logical>>),
+}
+
+/// Blur behind one surface.
+#[derive(Debug, Default)]
+pub struct SurfaceBlur {
+    /// Ids and backdrops of the blur elements, one per blurred rectangle.
+    slots: RefCell<Vec<(Id, BackdropSlot)>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlurRenderElement {
+    id: Id,
+    backdrop: BackdropSlot,
+    program: GlesTexProgram,
+    /// Area covered by this element.
+    area: Rectangle<f64, Logical>,
+    /// Surface geometry to clip to, relative to `area`.
+    clip: Rectangle<f64, Logical>,
+    corner_radius: CornerRadius,
+    alpha: f32,
+}
+
+impl BackdropBlur {
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: Scale<f64>,
+        config: niri_config::Blur,
+        elements: &[impl RenderElement<GlesRenderer>],
+    ) -> anyhow::Result<BlurredBackdrop> {
+        let _span = tracy_client::span!("BackdropBlur::render");
+
+        let shaders = Shaders::get(renderer);
+        let down = shaders
+            .blur_down
+            .clone()
+            .context("missing blur down shader")?;
+        let up = shaders.blur_up.clone().context("missing blur up shader")?;
+
+        let (source, _sync, _data) = self
+            .offscreen
+            .render(renderer, scale, elements)
+            .context("error rendering backdrop")?;
+
+        let geo = source.geometry(scale);
+        let size = geo.size.to_logical(1).to_buffer(1, Transform::Normal);
+        ensure!(size.w > 0 && size.h > 0, "backdrop is empty");
+
+        let passes = usize::from(config.passes.clamp(1, MAX_PASSES));
+
+        let mut inner = self.inner.borrow_mut();
+
+        // Check if we need to create or recreate the textures.
+        if let Some(x) = inner.as_ref() {
+            if x.renderer_context_id != renderer.context_id()
+                || x.size != size
+                || x.passes.len() != passes
+            {
+                *inner = None;
+            }
+        }
+
+        let inner = if let Some(inner) = inner.as_mut() {
+            inner
+        } else {
+            let _span = tracy_client::span!("creating blur buffers");
+
+            let mut pass_size = size;
+            let mut textures = Vec::with_capacity(passes);
+            for _ in 0..passes {
+                pass_size = Size::from((max_half(pass_size.w), max_half(pass_size.h)));
+                let texture: GlesTexture = renderer
+                    .create_buffer(Fourcc::Abgr8888, pass_size)
+                    .context("error creating texture")?;
+                textures.push(texture);
+            }
+
+            let result: GlesTexture = renderer
+                .create_buffer(Fourcc::Abgr8888, size)
+                .context("error creating texture")?;
+
+            inner.insert(Inner {
+                passes: textures,
+                result,
+                renderer_context_id: renderer.context_id(),
+                size,
+                loc: geo.loc,
+                source: None,
+                offset: config.offset,
+                commit: CommitCounter::default(),
+            })
+        };
+
+        // The offscreen texture can be recreated, so check its id along with the commit.
+        let source_state = (source.current_commit(), source.texture().tex_id());
+        if inner.source != Some(source_state)
+            || inner.offset != config.offset
+            || inner.loc != geo.loc
+        {
+            let _span = tracy_client::span!("blurring backdrop");
+
+            let offset = config.offset;
+
+            // Downsample through the chain.
+            let mut src = source.texture().clone();
+            let mut src_size = size;
+            for texture in &mut inner.passes {
+                render_pass(renderer, &down, &src, src_size, texture, offset)?;
+                src = texture.clone();
+                src_size = src.size();
+            }
+
+            // Then upsample back.
+            for i in (0..passes - 1).rev() {
+                let src = inner.passes[i + 1].clone();
+                render_pass(
+                    renderer,
+                    &up,
+                    &src,
+                    src.size(),
+                    &mut inner.passes[i],
+                    offset,
+                )?;
+            }
+            let src = inner.passes[0].clone();
+            render_pass(renderer, &up, &src, src.size(), &mut inner.result, offset)?;
+
+            inner.source = Some(source_state);
+            inner.offset = offset;
+            inner.loc = geo.loc;
+            inner.commit.increment();
+        }
+
+        Ok(BlurredBackdrop {
+            texture: inner.result.clone(),
+            renderer_context_id: inner.renderer_context_id.clone(),
+            loc: inner.loc,
+            commit: inner.commit,
+        })
+    }
+}
+
+impl BlurRequests {
+    pub fn is_empty(&self) -> bool {
+        self.slots.borrow().is_empty()
+    }
+
+    /// Renders the backdrops of the requested blur elements.
+    ///
+    /// `elements` are all output elements, front to back. `caches` holds the blurred backdrops
+    /// from the previous renders to the same target; caches of elements that are gone are
+    /// dropped.
+    pub fn resolve<E: RenderElement<GlesRenderer>>(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: Scale<f64>,
+        config: niri_config::Blur,
+        elements: &[E],
+        caches: &mut HashMap<Id, BackdropBlur>,
+    ) {
+        let _span = tracy_client::span!("BlurRequests::resolve");
+
+        let mut slots: HashMap<Id, BackdropSlot> = self.slots.take().into_iter().collect();
+
+        // Contents this far outside of the blurred area still affect it.
+        let passes = config.passes.clamp(1, MAX_PASSES);
+        let margin = (config.offset * f64::from(1u32 << passes)).ceil() as i32;
+
+        let mut resolved = HashSet::new();
+
+        // Go back to front, so that the blur behind other blur is resolved first.
+        for (i, elem) in elements.iter().enumerate().rev() {
+            let Some(slot) = slots.remove(elem.id()) else {
+                continue;
+            };
+
+            let area = elem.geometry(scale);
+            let area = Rectangle::new(
+                area.loc - Point::from((margin, margin)),
+                area.size + Size::from((margin * 2, margin * 2)),
+            );
+            let behind: Vec<_> = elements[i + 1..]
+                .iter()
+                .filter_map(|elem| CropRenderElement::from_element(elem, scale, area))
+                .collect();
+
+            let cache = caches.entry(elem.id().clone()).or_default();
+            let backdrop = match cache.render(renderer, scale, config, &behind) {
+                Ok(backdrop) => Some(backdrop),
+                Err(err) => {
+                    warn!("error rendering blurred backdrop: {err:?}");
+                    None
+                }
+            };
+            *slot.borrow_mut() = backdrop;
+            resolved.insert(elem.id().clone());
+        }
+
+        // These didn't end up among the output elements, so they won't be drawn anyway.
+        for slot in slots.into_values() {
+            *slot.borrow_mut() = None;
+        }
+
+        caches.retain(|id, _| resolved.contains(id));
+    }
+}
+
+fn max_half(x: i32) -> i32 {
+    (x / 2).max(1)
+}
+
+fn render_pass(
+    renderer: &mut GlesRenderer,
+    program: &GlesTexProgram,
+    src: &GlesTexture,
+    src_size: Size<i32, Buffer>,
+    dst: &mut GlesTexture,
+    offset: f64,
+) -> anyhow::Result<()> {
+    let dst_size = dst.size();
+    let output_size = dst_size.to_logical(1, Transform::Normal).to_physical(1);
+    let rect = Rectangle::from_size(output_size);
+
+    // The source texture can be larger than the part that we sample.
+    let tex_size = src.size();
+    let half_pixel = (
+        0.5 / dst_size.w as f32 * src_size.w as f32 / tex_size.w as f32,
+        0.5 / dst_size.h as f32 * src_size.h as f32 / tex_size.h as f32,
+    );
+
+    let mut target = renderer.bind(dst).context("error binding texture")?;
+    let mut frame = renderer
+        .render(&mut target, output_size, Transform::Normal)
+        .context("error starting frame")?;
+
+    frame
+        .clear(Color32F::TRANSPARENT, &[rect])
+        .context("error clearing")?;
+    frame
+        .render_texture_from_to(
+            src,
+            Rectangle::from_size(src_size).to_f64(),
+            rect,
+            &[rect],
+            &[],
+            Transform::Normal,
+            1.,
+            Some(program),
+            &[
+                Uniform::new("half_pixel", half_pixel),
+                Uniform::new("offset", offset as f32),
+            ],
+        )
+        .context("error drawing")?;
+
+    frame.finish().context("error finishing frame")?;
+    Ok(())
+}
+
+impl BlurArea {
+    /// Resolves the area to blur from the rule and the region requested by the client.
+    pub fn resolve(
+        rule: BackgroundEffectRule,
+        region: impl FnOnce() -> Option<Vec<Rectangle<i32, Logical>>>,
+    ) -> Option<Self> {
+        match rule.blur {
+            Some(true) => Some(BlurArea::Geometry),
+            Some(false) => None,
+            None => region().map(BlurArea::Region),
+        }
+    }
+}
+
+/// Converts a client-provided region into non-overlapping rectangles.
+pub fn region_rects(region: &RegionAttributes) -> Vec<Rectangle<i32, Logical>> {
+    let mut rects = Vec::new();
+
+    for (kind, rect) in &region.rects {
+        match kind {
+            RectangleKind::Add => {
+                let added = Rectangle::subtract_rects_many([*rect], rects.iter().copied());
+                rects.extend(added);
+            }
+            RectangleKind::Subtract => {
+                rects = Rectangle::subtract_rects_many(rects, [*rect]);
+            }
+        }
+    }
+
+    rects
+}
+
+impl SurfaceBlur {
+    /// Renders the blur behind a surface with the given visual geometry.
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        requests: &BlurRequests,
+        area: &BlurArea,
+        geometry: Rectangle<f64, Logical>,
+        corner_radius: CornerRadius,
+        push: &mut dyn FnMut(BlurRenderElement),
+    ) {
+        let Some(program) = Shaders::get(renderer).clipped_surface.clone() else {
+            return;
+        };
+
+        let areas = match area {
+            BlurArea::Geometry => vec![geometry],
+            BlurArea::Region(rects) => {
+                let bounds = Rectangle::from_size(geometry.size);
+                rects
+                    .iter()
+                    .filter_map(|rect| rect.to_f64().intersection(bounds))
+                    .map(|mut rect| {
+                        rect.loc += geometry.loc;
+                        rect
+                    })
+                    .collect()
+            }
+        };
+
+        let corner_radius = corner_radius.fit_to(geometry.size.w as f32, geometry.size.h as f32);
+
+        let mut slots = self.slots.borrow_mut();
+        if slots.len() < areas.len() {
+            slots.resize_with(areas.len(), || (Id::new(), BackdropSlot::default()));
+        }
+
+        for (area, (id, slot)) in zip(areas, slots.iter()) {
+            requests.slots.borrow_mut().push((id.clone(), slot.clone()));
+
+            push(BlurRenderElement {
+                id: id.clone(),
+                backdrop: slot.clone(),
+                program: program.clone(),
+                area,
+                clip: Rectangle::new(geometry.loc - area.loc, geometry.size),
+                corner_radius,
+                alpha: 1.,
+            });
+        }
+    }
+}
+
+impl BlurRenderElement {
+    fn compute_uniforms(
+        &self,
+        backdrop: &BlurredBackdrop,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+    ) -> Vec<Uniform<'static>> {
+        // The element can be rescaled and cropped on its way to the screen, so compute the
+        // geometry on screen from the part of the element we're drawing.
+        let dst = dst.to_f64();
+        let ratio = Vec2::new(
+            (dst.size.w / src.size.w) as f32,
+            (dst.size.h / src.size.h) as f32,
+        );
+
+        let dst_loc = Vec2::new(dst.loc.x as f32, dst.loc.y as f32);
+        let src_loc = Vec2::new(src.loc.x as f32, src.loc.y as f32);
+        let clip_loc = Vec2::new(self.clip.loc.x as f32, self.clip.loc.y as f32);
+        let clip_size = Vec2::new(self.clip.size.w as f32, self.clip.size.h as f32);
+
+        let geo_loc = dst_loc + (clip_loc - src_loc) * ratio;
+        let geo_size = clip_size * ratio;
+
+        let tex_size = backdrop.texture.size();
+        let tex_size = Vec2::new(tex_size.w as f32, tex_size.h as f32);
+        let tex_loc = Vec2::new(backdrop.loc.x as f32, backdrop.loc.y as f32);
+
+        // Texture coordinates go over the whole backdrop texture.
+        let input_to_geo = Mat3::from_translation((tex_loc - geo_loc) / geo_size)
+            * Mat3::from_scale(tex_size / geo_size);
+
+        vec![
+            Uniform::new("niri_scale", ratio.x),
+            Uniform::new("geo_size", (clip_size.x, clip_size.y)),
+            Uniform::new("corner_radius", <[f32; 4]>::from(self.corner_radius)),
+            mat3_uniform("input_to_geo", input_to_geo),
+        ]
+    }
+}
+
+impl Element for BlurRenderElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.backdrop
+            .borrow()
+            .as_ref()
+            .map_or_else(CommitCounter::default, |backdrop| backdrop.commit)
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.area.to_physical_precise_round(scale)
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::Normal
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        Rectangle::from_size(self.area.size.to_buffer(1., Transform::Normal))
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        if commit == Some(self.current_commit()) {
+            DamageSet::default()
+        } else {
+            DamageSet::from_slice(&[Rectangle::from_size(self.geometry(scale).size)])
+        }
+    }
+
+    fn opaque_regions(&self, _scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        OpaqueRegions::default()
+    }
+
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Unspecified
+    }
+}
+
+impl RenderElement<GlesRenderer> for BlurRenderElement {
+    fn draw(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        _opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        let backdrop = self.backdrop.borrow();
+        let Some(backdrop) = backdrop.as_ref() else {
+            return Ok(());
+        };
+
+        if frame.context_id() != backdrop.renderer_context_id {
+            warn!("trying to render texture from different renderer");
+            return Ok(());
+        }
+
+        if src.size.is_empty() || dst.size.is_empty() {
+            return Ok(());
+        }
+
+        let uniforms = self.compute_uniforms(backdrop, src, dst);
+
+        // Sample the blurred backdrop right under the element on screen.
+        let loc = dst.loc - backdrop.loc;
+        let tex_src = Rectangle::<i32, Buffer>::new(
+            Point::from((loc.x, loc.y)),
+            Size::from((dst.size.w, dst.size.h)),
+        )
+        .to_f64();
+
+        frame.render_texture_from_to(
+            &backdrop.texture,
+            tex_src,
+            dst,
+            damage,
+            &[],
+            Transform::Normal,
+            self.alpha,
+            Some(&self.program),
+            &uniforms,
+        )
+    }
+
+    fn underlying_storage(&self, _renderer: &mut GlesRenderer) -> Option<UnderlyingStorage<'_>> {
+        None
+    }
+}
+
+impl<'render> RenderElement<TtyRenderer<'render>> for BlurRenderElement {
+    fn draw(
+        &self,
+        frame: &mut TtyFrame<'_, '_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), TtyRendererError<'render>> {
+        let gles_frame = frame.as_gles_frame();
+        RenderElement::<GlesRenderer>::draw(&self, gles_frame, src, dst, damage, opaque_regions)?;
+        Ok(())
+    }
+
+    fn underlying_storage(
+        &self,
+        _renderer: &mut TtyRenderer<'render>,
+    ) -> Option<UnderlyingStorage<'_>> {
+        None
+    }
+}
@@ -8,6 +8,7 @@ use smithay::backend::renderer::gles::{
 use smithay::backend::renderer::utils::{CommitCounter, DamageSet, OpaqueRegions};
 use smithay::utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform};
 
+use super::color_management::ColorConversion;
 use super::damage::ExtraDamage;
 use super::renderer::{AsGlesFrame as _, NiriRenderer};
 use super::shaders::{mat3_uniform, Shaders};
@@ -20,6 +21,8 @@ pub struct ClippedSurfaceRenderElement<R: NiriRenderer> {
     corner_radius: CornerRadius,
     geometry: Rectangle<f64, Logical>,
     scale: f32,
+    /// Conversion into the composition space for color-managed surfaces.
+    conversion: Option<ColorConversion>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -42,9 +45,18 @@ impl<R: NiriRenderer> ClippedSurfaceRenderElement<R> {
             corner_radius,
             geometry,
             scale: scale.x as f32,
+            conversion: None,
         }
     }
 
+    /// Also converts the surface into the composition space while clipping.
+    ///
+    /// The program must come from [`Self::color_managed_shader()`].
+    pub fn with_conversion(mut self, conversion: ColorConversion) -> Self {
+        self.conversion = Some(conversion);
+        self
+    }
+
     fn compute_uniforms(&self) -> Vec<Uniform<'static>> {
         let scale = Scale::from(f64::from(self.scale));
         let elem_geo = self.inner.geometry(scale);
@@ -83,18 +95,26 @@ impl<R: NiriRenderer> ClippedSurfaceRenderElement<R> {
 
         let geo_size = (self.geometry.size.w as f32, self.geometry.size.h as f32);
 
-        vec![
+        let mut uniforms = vec![
             Uniform::new("niri_scale", self.scale),
             Uniform::new("geo_size", geo_size),
             Uniform::new("corner_radius", <[f32; 4]>::from(self.corner_radius)),
             mat3_uniform("input_to_geo", input_to_geo),
-        ]
+        ];
+        if let Some(conversion) = &self.conversion {
+            uniforms.extend(conversion.uniforms());
+        }
+        uniforms
     }
 
     pub fn shader(renderer: &mut R) -> Option<&GlesTexProgram> {
         Shaders::get(renderer).clipped_surface.as_ref()
     }
 
+    pub fn color_managed_shader(renderer: &mut R) -> Option<&GlesTexProgram> {
+        Shaders::get(renderer).clipped_color_conversion.as_ref()
+    }
+
     pub fn will_clip(
         elem: &WaylandSurfaceRenderElement<R>,
         scale: Scale<f64>,
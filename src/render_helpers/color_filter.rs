@@ -0,0 +1,292 @@
+use glam::{Mat3, Mat4, Vec3};
+use niri_config::ColorFilterKind;
+use smithay::backend::renderer::element::{Element, Id, Kind, RenderElement, UnderlyingStorage};
+use smithay::backend::renderer::gles::{GlesError, GlesFrame, GlesRenderer, GlesTexProgram};
+use smithay::backend::renderer::utils::{CommitCounter, DamageSet, OpaqueRegions};
+use smithay::utils::{Buffer, Physical, Rectangle, Scale, Transform};
+
+use super::offscreen::OffscreenRenderElement;
+use super::renderer::{AsGlesFrame as _, NiriRenderer};
+use super::shaders::{mat4_uniform, Shaders};
+use crate::backend::tty::{TtyFrame, TtyRenderer, TtyRendererError};
+
+/// Texture element drawn through a color filter.
+///
+/// Usually wraps the offscreened output contents.
+#[derive(Debug, Clone)]
+pub struct ColorFilterRenderElement<E = OffscreenRenderElement> {
+    inner: E,
+    program: GlesTexProgram,
+    matrix: Mat4,
+}
+
+impl<E> ColorFilterRenderElement<E> {
+    pub fn new(inner: E, program: GlesTexProgram, filter: ColorFilterKind) -> Self {
+        Self {
+            inner,
+            program,
+            matrix: color_matrix(filter),
+        }
+    }
+}
+
+impl ColorFilterRenderElement {
+    pub fn shader(renderer: &mut impl NiriRenderer) -> Option<&GlesTexProgram> {
+        Shaders::get(renderer).color_filter.as_ref()
+    }
+}
+
+impl<E: Element> Element for ColorFilterRenderElement<E> {
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        self.inner.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.inner.opaque_regions(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.inner.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.inner.kind()
+    }
+}
+
+impl<E: RenderElement<GlesRenderer>> RenderElement<GlesRenderer> for ColorFilterRenderElement<E> {
+    fn draw(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        let uniforms = vec![mat4_uniform("color_matrix", self.matrix)];
+        frame.override_default_tex_program(self.program.clone(), uniforms);
+        RenderElement::<GlesRenderer>::draw(&self.inner, frame, src, dst, damage, opaque_regions)?;
+        frame.clear_tex_program_override();
+        Ok(())
+    }
+
+    fn underlying_storage(&self, _renderer: &mut GlesRenderer) -> Option<UnderlyingStorage<'_>> {
+        // If scanout for things other than Wayland buffers is implemented, this will need to take
+        // the target GPU into account.
+        None
+    }
+}
+
+impl<'render, E: RenderElement<GlesRenderer>> RenderElement<TtyRenderer<'render>>
+    for ColorFilterRenderElement<E>
+{
+    fn draw(
+        &self,
+        frame: &mut TtyFrame<'render, '_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), TtyRendererError<'render>> {
+        let gles_frame = frame.as_gles_frame();
+        RenderElement::<GlesRenderer>::draw(&self, gles_frame, src, dst, damage, opaque_regions)?;
+        Ok(())
+    }
+
+    fn underlying_storage(
+        &self,
+        _renderer: &mut TtyRenderer<'render>,
+    ) -> Option<UnderlyingStorage<'_>> {
+        // If scanout for things other than Wayland buffers is implemented, this will need to take
+        // the target GPU into account.
+        None
+    }
+}
+
+/// Returns the matrix applied to premultiplied RGBA colors for the filter.
+///
+/// Constant terms go into the alpha column, so that they scale together with the alpha.
+fn color_matrix(filter: ColorFilterKind) -> Mat4 {
+    let (rgb, offset) = match filter {
+        ColorFilterKind::Grayscale => {
+            let luminance = Vec3::new(0.2126, 0.7152, 0.0722);
+            (from_rows([luminance, luminance, luminance]), Vec3::ZERO)
+        }
+        ColorFilterKind::Invert => (-Mat3::IDENTITY, Vec3::ONE),
+        ColorFilterKind::HighContrast => {
+            // Stretch the colors away from mid-gray.
+            let contrast = 2.;
+            (
+                Mat3::from_diagonal(Vec3::splat(contrast)),
+                Vec3::splat((1. - contrast) / 2.),
+            )
+        }
+        ColorFilterKind::Deuteranopia => (daltonize(DEUTERANOPIA, RED_GREEN_SHIFT), Vec3::ZERO),
+        ColorFilterKind::Protanopia => (daltonize(PROTANOPIA, RED_GREEN_SHIFT), Vec3::ZERO),
+        ColorFilterKind::Tritanopia => (daltonize(TRITANOPIA, BLUE_YELLOW_SHIFT), Vec3::ZERO),
+    };
+
+    Mat4::from_cols(
+        rgb.x_axis.extend(0.),
+        rgb.y_axis.extend(0.),
+        rgb.z_axis.extend(0.),
+        offset.extend(1.),
+    )
+}
+
+/// Returns a matrix that shifts the colors lost to a color vision deficiency into the channels
+/// that are still distinguishable.
+///
+/// `simulation` maps colors to how they are perceived with the deficiency, and `shift`
+/// redistributes the difference.
+fn daltonize(simulation: Mat3, shift: Mat3) -> Mat3 {
+    Mat3::IDENTITY + shift * (Mat3::IDENTITY - simulation)
+}
+
+const fn from_rows(rows: [Vec3; 3]) -> Mat3 {
+    Mat3::from_cols(
+        Vec3::new(rows[0].x, rows[1].x, rows[2].x),
+        Vec3::new(rows[0].y, rows[1].y, rows[2].y),
+        Vec3::new(rows[0].z, rows[1].z, rows[2].z),
+    )
+}
+
+// Simulation matrices for full severity from Machado, Oliveira and Fernandes, "A Physiologically
+// -based Model for Simulation of Color Vision Deficiency" (2009).
+const PROTANOPIA: Mat3 = from_rows([
+    Vec3::new(0.152286, 1.052583, -0.204868),
+    Vec3::new(0.114503, 0.786281, 0.099216),
+    Vec3::new(-0.003882, -0.048116, 1.051998),
+]);
+const DEUTERANOPIA: Mat3 = from_rows([
+    Vec3::new(0.367322, 0.860646, -0.227968),
+    Vec3::new(0.280085, 0.672501, 0.047413),
+    Vec3::new(-0.011820, 0.042940, 0.968881),
+]);
+const TRITANOPIA: Mat3 = from_rows([
+    Vec3::new(1.255528, -0.076749, -0.178779),
+    Vec3::new(-0.078411, 0.930809, 0.147602),
+    Vec3::new(0.004733, 0.691367, 0.303900),
+]);
+
+/// Moves the lost red-green difference into green and blue.
+const RED_GREEN_SHIFT: Mat3 = from_rows([
+    Vec3::new(0., 0., 0.),
+    Vec3::new(0.7, 1., 0.),
+    Vec3::new(0.7, 0., 1.),
+]);
+/// Moves the lost blue-yellow difference into red and green.
+const BLUE_YELLOW_SHIFT: Mat3 = from_rows([
+    Vec3::new(1., 0., 0.7),
+    Vec3::new(0., 1., 0.7),
+    Vec3::new(0., 0., 0.),
+]);
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec4;
+
+    use super::*;
+
+    const COLORS: [Vec3; 5] = [
+        Vec3::new(0., 0., 0.),
+        Vec3::new(1., 1., 1.),
+        Vec3::new(1., 0., 0.),
+        Vec3::new(0.2, 0.5, 0.9),
+        Vec3::new(0.9, 0.7, 0.1),
+    ];
+
+    /// Applies the filter to a color with premultiplied alpha.
+    fn apply(filter: ColorFilterKind, color: Vec3, alpha: f32) -> Vec4 {
+        color_matrix(filter) * (color * alpha).extend(alpha)
+    }
+
+    #[test]
+    fn grayscale_preserves_luminance() {
+        let luminance = Vec3::new(0.2126, 0.7152, 0.0722);
+
+        for color in COLORS {
+            for alpha in [1., 0.5] {
+                let gray = apply(ColorFilterKind::Grayscale, color, alpha);
+                let expected = Vec3::splat(luminance.dot(color) * alpha).extend(alpha);
+                assert!(
+                    gray.abs_diff_eq(expected, 1e-6),
+                    "{color} with alpha {alpha}: got {gray}, expected {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invert_respects_alpha() {
+        for color in COLORS {
+            let inverted = apply(ColorFilterKind::Invert, color, 0.5);
+            let expected = ((Vec3::ONE - color) * 0.5).extend(0.5);
+            assert!(inverted.abs_diff_eq(expected, 1e-6));
+        }
+    }
+
+    #[test]
+    fn daltonize_without_deficiency_is_identity() {
+        for shift in [RED_GREEN_SHIFT, BLUE_YELLOW_SHIFT] {
+            let matrix = daltonize(Mat3::IDENTITY, shift);
+            assert!(matrix.abs_diff_eq(Mat3::IDENTITY, 1e-6));
+        }
+    }
+
+    #[test]
+    fn filters_keep_alpha_and_grays() {
+        let filters = [
+            ColorFilterKind::Grayscale,
+            ColorFilterKind::Invert,
+            ColorFilterKind::HighContrast,
+            ColorFilterKind::Deuteranopia,
+            ColorFilterKind::Protanopia,
+            ColorFilterKind::Tritanopia,
+        ];
+
+        for filter in filters {
+            // No filter may change the alpha.
+            let row = color_matrix(filter).row(3);
+            assert_eq!(row, Vec4::W, "{filter:?} changes alpha");
+        }
+
+        // Grays don't carry color information, so color blindness correction leaves them as is.
+        for filter in [
+            ColorFilterKind::Deuteranopia,
+            ColorFilterKind::Protanopia,
+            ColorFilterKind::Tritanopia,
+        ] {
+            let gray = Vec3::splat(0.5);
+            let filtered = apply(filter, gray, 1.);
+            assert!(
+                filtered.abs_diff_eq(gray.extend(1.), 1e-3),
+                "{filter:?} changes gray to {filtered}"
+            );
+        }
+    }
+}
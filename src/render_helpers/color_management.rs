@@ -0,0 +1,351 @@
+//! Color conversion between image descriptions.
+//!
+//! Outputs are composited in extended sRGB: sRGB primaries encoded with gamma 2.2, where 1.0 is
+//! the reference white. Floating-point buffers keep values outside of [0, 1] for wider gamuts and
+//! brighter highlights. Surfaces with a different image description are converted into this space
+//! as they are drawn, and outputs with a different image description get the composited contents
+//! converted in a final pass.
+
+use glam::{Mat3, Vec3};
+use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
+use smithay::backend::renderer::element::{Element, Id, Kind, RenderElement, UnderlyingStorage};
+use smithay::backend::renderer::gles::{
+    GlesError, GlesFrame, GlesRenderer, GlesTexProgram, Uniform,
+};
+use smithay::backend::renderer::utils::{CommitCounter, DamageSet, OpaqueRegions};
+use smithay::utils::{Buffer, Physical, Rectangle, Scale, Transform};
+
+use super::offscreen::OffscreenRenderElement;
+use super::renderer::{AsGlesFrame as _, NiriRenderer};
+use super::shaders::{mat3_uniform, Shaders};
+use crate::backend::tty::{TtyFrame, TtyRenderer, TtyRendererError};
+use crate::protocols::color_management::{Chromaticities, ImageDescription, TransferFunction};
+
+/// Parameters of a conversion between two image descriptions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorConversion {
+    src_tf: TransferFunction,
+    dst_tf: TransferFunction,
+    /// Converts linear source colors to linear destination colors.
+    matrix: Mat3,
+    /// Maximum linear destination value, or 0 to leave the colors unclamped.
+    dst_max: f32,
+}
+
+impl ColorConversion {
+    /// Returns the conversion from the image description into the composition space.
+    pub fn to_composition(src: &ImageDescription) -> Self {
+        let composition = ImageDescription::SRGB.primaries.chromaticities();
+        let matrix = xyz_to_rgb(&composition) * rgb_to_xyz(&src.primaries.chromaticities());
+        // 1.0 in the composition space is the reference white.
+        let scale = encoding_max(src) / src.luminances.reference;
+
+        Self {
+            src_tf: src.tf,
+            dst_tf: TransferFunction::Gamma22,
+            matrix: matrix * scale as f32,
+            dst_max: 0.,
+        }
+    }
+
+    /// Returns the conversion from the composition space into the output image description.
+    pub fn from_composition(dst: &ImageDescription) -> Self {
+        let composition = ImageDescription::SRGB.primaries.chromaticities();
+        let matrix = xyz_to_rgb(&dst.primaries.chromaticities()) * rgb_to_xyz(&composition);
+        let scale = dst.luminances.reference / encoding_max(dst);
+
+        // Clip to what the display can show.
+        let max = dst
+            .target_luminance
+            .map_or(dst.luminances.max, |(_min, max)| max);
+        let dst_max = max / encoding_max(dst);
+
+        Self {
+            src_tf: TransferFunction::Gamma22,
+            dst_tf: dst.tf,
+            matrix: matrix * scale as f32,
+            dst_max: dst_max as f32,
+        }
+    }
+
+    /// Returns whether the conversion leaves the colors unchanged.
+    pub fn is_identity(&self) -> bool {
+        tf_uniform(self.src_tf) == tf_uniform(self.dst_tf)
+            && self.matrix.abs_diff_eq(Mat3::IDENTITY, 1e-4)
+    }
+
+    pub fn uniforms(&self) -> Vec<Uniform<'static>> {
+        vec![
+            Uniform::new("src_tf", tf_uniform(self.src_tf)),
+            Uniform::new("dst_tf", tf_uniform(self.dst_tf)),
+            mat3_uniform("conversion", self.matrix),
+            Uniform::new("dst_max", self.dst_max),
+        ]
+    }
+
+    pub fn shader(renderer: &mut impl NiriRenderer) -> Option<&GlesTexProgram> {
+        Shaders::get(renderer).color_conversion.as_ref()
+    }
+}
+
+/// Returns the luminance in cd/m² that corresponds to the linear value of 1.0.
+fn encoding_max(desc: &ImageDescription) -> f64 {
+    match desc.tf {
+        // PQ is absolute and always encodes up to 10000 cd/m².
+        TransferFunction::St2084Pq => 10000.,
+        _ => desc.luminances.max,
+    }
+}
+
+fn tf_uniform(tf: TransferFunction) -> f32 {
+    match tf {
+        // The sRGB transfer function is ambiguous, and displays generally decode sRGB contents
+        // with a pure gamma 2.2, so treat them the same.
+        TransferFunction::Srgb | TransferFunction::Gamma22 => 0.,
+        TransferFunction::ExtLinear => 1.,
+        TransferFunction::St2084Pq => 2.,
+    }
+}
+
+/// Returns the matrix converting linear RGB with the given primaries to CIE XYZ.
+fn rgb_to_xyz(c: &Chromaticities) -> Mat3 {
+    let xyz = |[x, y]: [f64; 2]| Vec3::new((x / y) as f32, 1., ((1. - x - y) / y) as f32);
+
+    let primaries = Mat3::from_cols(xyz(c.red), xyz(c.green), xyz(c.blue));
+    // Scale the primaries so that RGB 1, 1, 1 maps to the white point.
+    let scale = primaries.inverse() * xyz(c.white);
+    primaries * Mat3::from_diagonal(scale)
+}
+
+fn xyz_to_rgb(c: &Chromaticities) -> Mat3 {
+    rgb_to_xyz(c).inverse()
+}
+
+/// Wayland surface converted from its image description into the composition space.
+#[derive(Debug)]
+pub struct ColorManagedSurfaceRenderElement<R: NiriRenderer> {
+    inner: WaylandSurfaceRenderElement<R>,
+    program: GlesTexProgram,
+    conversion: ColorConversion,
+}
+
+impl<R: NiriRenderer> ColorManagedSurfaceRenderElement<R> {
+    pub fn new(
+        elem: WaylandSurfaceRenderElement<R>,
+        program: GlesTexProgram,
+        conversion: ColorConversion,
+    ) -> Self {
+        Self {
+            inner: elem,
+            program,
+            conversion,
+        }
+    }
+
+    pub fn inner(&self) -> &WaylandSurfaceRenderElement<R> {
+        &self.inner
+    }
+
+    pub fn into_parts(self) -> (WaylandSurfaceRenderElement<R>, ColorConversion) {
+        (self.inner, self.conversion)
+    }
+}
+
+impl<R: NiriRenderer> Element for ColorManagedSurfaceRenderElement<R> {
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        self.inner.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.inner.opaque_regions(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.inner.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.inner.kind()
+    }
+}
+
+impl RenderElement<GlesRenderer> for ColorManagedSurfaceRenderElement<GlesRenderer> {
+    fn draw(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        frame.override_default_tex_program(self.program.clone(), self.conversion.uniforms());
+        RenderElement::<GlesRenderer>::draw(&self.inner, frame, src, dst, damage, opaque_regions)?;
+        frame.clear_tex_program_override();
+        Ok(())
+    }
+
+    fn underlying_storage(&self, _renderer: &mut GlesRenderer) -> Option<UnderlyingStorage<'_>> {
+        // The buffer needs the conversion, so it can't be scanned out directly.
+        None
+    }
+}
+
+impl<'render> RenderElement<TtyRenderer<'render>>
+    for ColorManagedSurfaceRenderElement<TtyRenderer<'render>>
+{
+    fn draw(
+        &self,
+        frame: &mut TtyFrame<'render, '_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), TtyRendererError<'render>> {
+        frame
+            .as_gles_frame()
+            .override_default_tex_program(self.program.clone(), self.conversion.uniforms());
+        RenderElement::draw(&self.inner, frame, src, dst, damage, opaque_regions)?;
+        frame.as_gles_frame().clear_tex_program_override();
+        Ok(())
+    }
+
+    fn underlying_storage(
+        &self,
+        _renderer: &mut TtyRenderer<'render>,
+    ) -> Option<UnderlyingStorage<'_>> {
+        // The buffer needs the conversion, so it can't be scanned out directly.
+        None
+    }
+}
+
+/// Composited output contents converted into the output image description.
+#[derive(Debug, Clone)]
+pub struct OutputColorRenderElement<E = OffscreenRenderElement> {
+    inner: E,
+    program: GlesTexProgram,
+    conversion: ColorConversion,
+}
+
+impl<E> OutputColorRenderElement<E> {
+    pub fn new(inner: E, program: GlesTexProgram, conversion: ColorConversion) -> Self {
+        Self {
+            inner,
+            program,
+            conversion,
+        }
+    }
+}
+
+impl<E: Element> Element for OutputColorRenderElement<E> {
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        self.inner.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.inner.opaque_regions(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.inner.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.inner.kind()
+    }
+}
+
+impl<E: RenderElement<GlesRenderer>> RenderElement<GlesRenderer> for OutputColorRenderElement<E> {
+    fn draw(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        frame.override_default_tex_program(self.program.clone(), self.conversion.uniforms());
+        RenderElement::<GlesRenderer>::draw(&self.inner, frame, src, dst, damage, opaque_regions)?;
+        frame.clear_tex_program_override();
+        Ok(())
+    }
+
+    fn underlying_storage(&self, _renderer: &mut GlesRenderer) -> Option<UnderlyingStorage<'_>> {
+        // If scanout for things other than Wayland buffers is implemented, this will need to take
+        // the target GPU into account.
+        None
+    }
+}
+
+impl<'render, E: RenderElement<GlesRenderer>> RenderElement<TtyRenderer<'render>>
+    for OutputColorRenderElement<E>
+{
+    fn draw(
+        &self,
+        frame: &mut TtyFrame<'render, '_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), TtyRendererError<'render>> {
+        let gles_frame = frame.as_gles_frame();
+        RenderElement::<GlesRenderer>::draw(&self, gles_frame, src, dst, damage, opaque_regions)?;
+        Ok(())
+    }
+
+    fn underlying_storage(
+        &self,
+        _renderer: &mut TtyRenderer<'render>,
+    ) -> Option<UnderlyingStorage<'_>> {
+        // If scanout for things other than Wayland buffers is implemented, this will need to take
+        // the target GPU into account.
+        None
+    }
+}
@@ -1,6 +1,6 @@
 use std::ptr;
 
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
 use niri_config::BlockOutFrom;
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::allocator::{Buffer, Fourcc};
@@ -18,10 +18,15 @@ use solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use self::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use self::texture::{TextureBuffer, TextureRenderElement};
 
+pub mod background_image;
+pub mod blur;
 pub mod border;
 pub mod clipped_surface;
+pub mod color_filter;
+pub mod color_management;
 pub mod damage;
 pub mod debug;
+pub mod dim;
 pub mod gradient_fade_texture;
 pub mod memory;
 pub mod offscreen;
@@ -39,7 +44,7 @@ pub mod surface;
 pub mod texture;
 
 /// What we're rendering for.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RenderTarget {
     /// Rendering to display on screen.
     Output,
@@ -242,25 +247,32 @@ pub fn render_to_shm(
 ) -> anyhow::Result<()> {
     let _span = tracy_client::span!();
     shm::with_buffer_contents_mut(buffer, |shm_buffer, shm_len, buffer_data| {
+        // The buffer prefers pixels in little endian ...
+        let fourcc = match buffer_data.format {
+            wl_shm::Format::Xrgb8888 => Fourcc::Xrgb8888,
+            wl_shm::Format::Argb8888 => Fourcc::Argb8888,
+            _ => bail!("invalid buffer format"),
+        };
         ensure!(
-            // The buffer prefers pixels in little endian ...
-            buffer_data.format == wl_shm::Format::Xrgb8888
-                && buffer_data.width == size.w
-                && buffer_data.height == size.h
-                && buffer_data.stride == size.w * 4
-                && shm_len == buffer_data.stride as usize * buffer_data.height as usize,
-            "invalid buffer format or size"
+            shm_buffer_fits(&buffer_data, shm_len, size),
+            "invalid buffer size"
         );
-        let mapping =
-            render_and_download(renderer, size, scale, transform, Fourcc::Xrgb8888, elements)?;
+        let mapping = render_and_download(renderer, size, scale, transform, fourcc, elements)?;
 
         let bytes = renderer
             .map_texture(&mapping)
             .context("error mapping texture")?;
 
+        // The mapping is tightly packed, while the buffer rows can be padded.
+        let row_len = size.w as usize * 4;
+        let stride = buffer_data.stride as usize;
+        let offset = buffer_data.offset as usize;
         unsafe {
             let _span = tracy_client::span!("copy_nonoverlapping");
-            ptr::copy_nonoverlapping(bytes.as_ptr(), shm_buffer.cast(), shm_len);
+            let dst = shm_buffer.cast::<u8>().add(offset);
+            for (i, row) in bytes.chunks_exact(row_len).enumerate() {
+                ptr::copy_nonoverlapping(row.as_ptr(), dst.add(i * stride), row_len);
+            }
         }
 
         Ok(())
@@ -268,6 +280,26 @@ pub fn render_to_shm(
     .context("expected shm buffer, but didn't get one")?
 }
 
+/// Checks that an shm buffer can hold an image of this size with 4 bytes per pixel.
+///
+/// The rows may be padded, so any stride of at least the row length is accepted.
+pub fn shm_buffer_fits(
+    buffer_data: &shm::BufferData,
+    shm_len: usize,
+    size: Size<i32, Physical>,
+) -> bool {
+    if buffer_data.width != size.w
+        || buffer_data.height != size.h
+        || buffer_data.stride < size.w * 4
+        || buffer_data.offset < 0
+    {
+        return false;
+    }
+
+    let len = buffer_data.stride as usize * buffer_data.height as usize;
+    buffer_data.offset as usize + len <= shm_len
+}
+
 pub fn clear_dmabuf(renderer: &mut GlesRenderer, mut dmabuf: Dmabuf) -> anyhow::Result<SyncPoint> {
     let size = dmabuf.size();
     let size = size.to_logical(1, Transform::Normal).to_physical(1);
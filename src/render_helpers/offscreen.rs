@@ -26,6 +26,9 @@ use crate::backend::tty::{TtyFrame, TtyRenderer, TtyRendererError};
 pub struct OffscreenBuffer {
     id: Id,
 
+    /// Format of the texture.
+    format: Fourcc,
+
     /// The cached texture buffer.
     ///
     /// Lazily created when `render` is called. Recreated when necessary.
@@ -44,6 +47,10 @@ struct Inner {
     damage: OutputDamageTracker,
     /// Damage of this offscreen element itself facing outside.
     outer_damage: DamageBag<i32, Buffer>,
+    /// Offset of the contents of the last render.
+    offset: Point<f64, Logical>,
+    /// Size of the contents of the last render within the texture.
+    src_size: Size<i32, Buffer>,
 }
 
 #[derive(Debug, Clone)]
@@ -68,6 +75,24 @@ pub struct OffscreenData {
 }
 
 impl OffscreenBuffer {
+    /// Creates a buffer with the given texture format.
+    ///
+    /// Use a floating-point format to keep values outside of [0, 1].
+    pub fn with_format(format: Fourcc) -> Self {
+        Self {
+            format,
+            ..Self::default()
+        }
+    }
+
+    /// Changes the texture format, recreating the texture on the next render.
+    pub fn set_format(&mut self, format: Fourcc) {
+        if self.format != format {
+            self.format = format;
+            *self.inner.get_mut() = None;
+        }
+    }
+
     pub fn render(
         &self,
         renderer: &mut GlesRenderer,
@@ -126,7 +151,7 @@ impl OffscreenBuffer {
             span.emit_text(reason);
 
             let texture: GlesTexture = renderer
-                .create_buffer(Fourcc::Abgr8888, src_size)
+                .create_buffer(self.format, src_size)
                 .context("error creating texture")?;
 
             let buffer_size = src_size.to_logical(1, Transform::Normal).to_physical(1);
@@ -138,6 +163,8 @@ impl OffscreenBuffer {
                 scale,
                 damage,
                 outer_damage: DamageBag::default(),
+                offset,
+                src_size,
             })
         };
 
@@ -177,17 +204,9 @@ impl OffscreenBuffer {
             inner.outer_damage.add(damage);
         }
 
-        let elem = OffscreenRenderElement {
-            id: self.id.clone(),
-            texture: inner.texture.clone(),
-            renderer_context_id: inner.renderer_context_id.clone(),
-            scale,
-            damage: inner.outer_damage.snapshot(),
-            offset,
-            src_size,
-            alpha: 1.,
-            kind: Kind::Unspecified,
-        };
+        inner.offset = offset;
+        inner.src_size = src_size;
+        let elem = self.element(inner);
 
         let data = OffscreenData {
             id: self.id.clone(),
@@ -196,6 +215,28 @@ impl OffscreenBuffer {
 
         Ok((elem, res.sync, data))
     }
+
+    /// Returns an element with the contents of the last render, without rendering again.
+    pub fn last_element(&self) -> Option<OffscreenRenderElement> {
+        self.inner
+            .borrow()
+            .as_ref()
+            .map(|inner| self.element(inner))
+    }
+
+    fn element(&self, inner: &Inner) -> OffscreenRenderElement {
+        OffscreenRenderElement {
+            id: self.id.clone(),
+            texture: inner.texture.clone(),
+            renderer_context_id: inner.renderer_context_id.clone(),
+            scale: inner.scale,
+            damage: inner.outer_damage.snapshot(),
+            offset: inner.offset,
+            src_size: inner.src_size,
+            alpha: 1.,
+            kind: Kind::Unspecified,
+        }
+    }
 }
 
 impl Default for OffscreenBuffer {
@@ -203,6 +244,7 @@ impl Default for OffscreenBuffer {
         OffscreenBuffer {
             inner: RefCell::new(None),
             id: Id::new(),
+            format: Fourcc::Abgr8888,
         }
     }
 }
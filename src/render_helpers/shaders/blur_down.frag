@@ -0,0 +1,48 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision highp float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+// Half of a texel of the target texture, in source texture coordinates.
+uniform vec2 half_pixel;
+uniform float offset;
+
+void main() {
+    vec2 o = half_pixel * offset;
+
+    // Dual Kawase downsampling: the center sample weighted 4, plus four diagonal samples.
+    vec4 sum = texture2D(tex, v_coords) * 4.0;
+    sum += texture2D(tex, v_coords - o);
+    sum += texture2D(tex, v_coords + o);
+    sum += texture2D(tex, v_coords + vec2(o.x, -o.y));
+    sum += texture2D(tex, v_coords - vec2(o.x, -o.y));
+
+    vec4 color = sum / 8.0;
+
+    // Apply final alpha and tint.
+    color = color * alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
@@ -0,0 +1,134 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision highp float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+// Transfer functions: 0 is gamma 2.2, 1 is linear, 2 is PQ.
+uniform float src_tf;
+uniform float dst_tf;
+// Converts linear source colors to linear destination colors, including the luminance scaling.
+uniform mat3 conversion;
+// Maximum linear destination value, or 0 to leave the colors unclamped.
+uniform float dst_max;
+
+#if defined(CLIP)
+// Clipping to the geometry with rounded corners, same as in clipped_surface.frag.
+uniform float niri_scale;
+
+uniform vec2 geo_size;
+uniform vec4 corner_radius;
+uniform mat3 input_to_geo;
+
+float rounding_alpha(vec2 coords, vec2 size) {
+    vec2 center;
+    float radius;
+
+    if (coords.x < corner_radius.x && coords.y < corner_radius.x) {
+        radius = corner_radius.x;
+        center = vec2(radius, radius);
+    } else if (size.x - corner_radius.y < coords.x && coords.y < corner_radius.y) {
+        radius = corner_radius.y;
+        center = vec2(size.x - radius, radius);
+    } else if (size.x - corner_radius.z < coords.x && size.y - corner_radius.z < coords.y) {
+        radius = corner_radius.z;
+        center = vec2(size.x - radius, size.y - radius);
+    } else if (coords.x < corner_radius.w && size.y - corner_radius.w < coords.y) {
+        radius = corner_radius.w;
+        center = vec2(radius, size.y - radius);
+    } else {
+        return 1.0;
+    }
+
+    float dist = distance(coords, center);
+    float half_px = 0.5 / niri_scale;
+    return 1.0 - smoothstep(radius - half_px, radius + half_px, dist);
+}
+#endif
+
+const float PQ_M1 = 0.1593017578125;
+const float PQ_M2 = 78.84375;
+const float PQ_C1 = 0.8359375;
+const float PQ_C2 = 18.8515625;
+const float PQ_C3 = 18.6875;
+
+vec3 pq_eotf(vec3 color) {
+    vec3 p = pow(clamp(color, 0.0, 1.0), vec3(1.0 / PQ_M2));
+    return pow(max(p - PQ_C1, 0.0) / (PQ_C2 - PQ_C3 * p), vec3(1.0 / PQ_M1));
+}
+
+vec3 pq_inv_eotf(vec3 color) {
+    vec3 p = pow(clamp(color, 0.0, 1.0), vec3(PQ_M1));
+    return pow((PQ_C1 + PQ_C2 * p) / (1.0 + PQ_C3 * p), vec3(PQ_M2));
+}
+
+// Gamma 2.2 is extended to negative values by mirroring the curve.
+vec3 eotf(float tf, vec3 color) {
+    if (tf == 0.0)
+        return sign(color) * pow(abs(color), vec3(2.2));
+    else if (tf == 2.0)
+        return pq_eotf(color);
+    return color;
+}
+
+vec3 inv_eotf(float tf, vec3 color) {
+    if (tf == 0.0)
+        return sign(color) * pow(abs(color), vec3(1.0 / 2.2));
+    else if (tf == 2.0)
+        return pq_inv_eotf(color);
+    return color;
+}
+
+void main() {
+    // Sample the texture.
+    vec4 color = texture2D(tex, v_coords);
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0);
+#endif
+
+    // The transfer functions apply to straight colors.
+    if (color.a > 0.0)
+        color.rgb /= color.a;
+
+    vec3 linear = conversion * eotf(src_tf, color.rgb);
+    if (dst_max > 0.0)
+        linear = clamp(linear, 0.0, dst_max);
+    color.rgb = inv_eotf(dst_tf, linear) * color.a;
+
+#if defined(CLIP)
+    vec3 coords_geo = input_to_geo * vec3(v_coords, 1.0);
+    if (coords_geo.x < 0.0 || 1.0 < coords_geo.x || coords_geo.y < 0.0 || 1.0 < coords_geo.y) {
+        // Clip outside geometry.
+        color = vec4(0.0);
+    } else {
+        // Apply corner rounding inside geometry.
+        color = color * rounding_alpha(coords_geo.xy * geo_size, geo_size);
+    }
+#endif
+
+    // Apply final alpha and tint.
+    color = color * alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
@@ -1,6 +1,6 @@
 use std::cell::RefCell;
 
-use glam::Mat3;
+use glam::{Mat3, Mat4};
 use smithay::backend::renderer::gles::{
     GlesError, GlesFrame, GlesRenderer, GlesTexProgram, Uniform, UniformName, UniformType,
     UniformValue,
@@ -15,6 +15,12 @@ pub struct Shaders {
     pub clipped_surface: Option<GlesTexProgram>,
     pub resize: Option<ShaderProgram>,
     pub gradient_fade: Option<GlesTexProgram>,
+    pub blur_down: Option<GlesTexProgram>,
+    pub blur_up: Option<GlesTexProgram>,
+    pub dim: Option<GlesTexProgram>,
+    pub color_filter: Option<GlesTexProgram>,
+    pub color_conversion: Option<GlesTexProgram>,
+    pub clipped_color_conversion: Option<GlesTexProgram>,
     pub custom_resize: RefCell<Option<ShaderProgram>>,
     pub custom_close: RefCell<Option<ShaderProgram>>,
     pub custom_open: RefCell<Option<ShaderProgram>>,
@@ -107,12 +113,104 @@ impl Shaders {
             })
             .ok();
 
+        let blur_down = renderer
+            .compile_custom_texture_shader(
+                include_str!("blur_down.frag"),
+                &[
+                    UniformName::new("half_pixel", UniformType::_2f),
+                    UniformName::new("offset", UniformType::_1f),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling blur down shader: {err:?}");
+            })
+            .ok();
+
+        let blur_up = renderer
+            .compile_custom_texture_shader(
+                include_str!("blur_up.frag"),
+                &[
+                    UniformName::new("half_pixel", UniformType::_2f),
+                    UniformName::new("offset", UniformType::_1f),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling blur up shader: {err:?}");
+            })
+            .ok();
+
+        let dim = renderer
+            .compile_custom_texture_shader(
+                include_str!("dim.frag"),
+                &[
+                    UniformName::new("dim", UniformType::_1f),
+                    UniformName::new("desaturation", UniformType::_1f),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling dim shader: {err:?}");
+            })
+            .ok();
+
+        let color_filter = renderer
+            .compile_custom_texture_shader(
+                include_str!("color_filter.frag"),
+                &[UniformName::new("color_matrix", UniformType::Matrix4x4)],
+            )
+            .map_err(|err| {
+                warn!("error compiling color filter shader: {err:?}");
+            })
+            .ok();
+
+        let color_conversion = renderer
+            .compile_custom_texture_shader(
+                include_str!("color_conversion.frag"),
+                &[
+                    UniformName::new("src_tf", UniformType::_1f),
+                    UniformName::new("dst_tf", UniformType::_1f),
+                    UniformName::new("conversion", UniformType::Matrix3x3),
+                    UniformName::new("dst_max", UniformType::_1f),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling color conversion shader: {err:?}");
+            })
+            .ok();
+
+        // The same shader, also clipping to the geometry, for clipped color-managed surfaces.
+        let src = include_str!("color_conversion.frag");
+        let src = src.replacen("//_DEFINES_", "#define CLIP\n//_DEFINES_", 1);
+        let clipped_color_conversion = renderer
+            .compile_custom_texture_shader(
+                &src,
+                &[
+                    UniformName::new("src_tf", UniformType::_1f),
+                    UniformName::new("dst_tf", UniformType::_1f),
+                    UniformName::new("conversion", UniformType::Matrix3x3),
+                    UniformName::new("dst_max", UniformType::_1f),
+                    UniformName::new("niri_scale", UniformType::_1f),
+                    UniformName::new("geo_size", UniformType::_2f),
+                    UniformName::new("corner_radius", UniformType::_4f),
+                    UniformName::new("input_to_geo", UniformType::Matrix3x3),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling clipped color conversion shader: {err:?}");
+            })
+            .ok();
+
         Self {
             border,
             shadow,
             clipped_surface,
             resize,
             gradient_fade,
+            blur_down,
+            blur_up,
+            dim,
+            color_filter,
+            color_conversion,
+            clipped_color_conversion,
             custom_resize: RefCell::new(None),
             custom_close: RefCell::new(None),
             custom_open: RefCell::new(None),
@@ -318,3 +416,13 @@ pub fn mat3_uniform(name: &str, mat: Mat3) -> Uniform<'_> {
         },
     )
 }
+
+pub fn mat4_uniform(name: &str, mat: Mat4) -> Uniform<'_> {
+    Uniform::new(
+        name,
+        UniformValue::Matrix4x4 {
+            matrices: vec![mat.to_cols_array()],
+            transpose: false,
+        },
+    )
+}
@@ -2,13 +2,24 @@ use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::backend::renderer::utils::{import_surface, RendererSurfaceStateUserData};
-use smithay::backend::renderer::{ImportAll, Renderer};
+use smithay::backend::renderer::Renderer as _;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Physical, Point, Scale};
 use smithay::wayland::compositor::{with_surface_tree_downward, TraversalAction};
 
+use super::color_management::{ColorConversion, ColorManagedSurfaceRenderElement};
+use super::renderer::NiriRenderer;
 use super::texture::TextureBuffer;
 use super::BakedBuffer;
+use crate::niri_render_elements;
+use crate::protocols::color_management::surface_image_description;
+
+niri_render_elements! {
+    SurfaceRenderElement<R> => {
+        Wayland = WaylandSurfaceRenderElement<R>,
+        ColorManaged = ColorManagedSurfaceRenderElement<R>,
+    }
+}
 
 /// Renders elements from a surface tree as textures into `storage`.
 pub fn render_snapshot_from_surface_tree(
@@ -81,7 +92,10 @@ pub fn render_snapshot_from_surface_tree(
     );
 }
 
-pub fn push_elements_from_surface_tree<R>(
+/// Pushes render elements for a surface tree.
+///
+/// Surfaces with an image description other than sRGB are converted into the composition space.
+pub fn push_elements_from_surface_tree<R: NiriRenderer>(
     renderer: &mut R,
     surface: &WlSurface,
     // Fractional scale expects surface buffers to be aligned to physical pixels.
@@ -89,13 +103,12 @@ pub fn push_elements_from_surface_tree<R>(
     scale: Scale<f64>,
     alpha: f32,
     kind: Kind,
-    push: &mut dyn FnMut(WaylandSurfaceRenderElement<R>),
-) where
-    R: Renderer + ImportAll,
-    R::TextureId: Clone + 'static,
-{
+    push: &mut dyn FnMut(SurfaceRenderElement<R>),
+) {
     let _span = tracy_client::span!("push_elements_from_surface_tree");
 
+    let program = ColorConversion::shader(renderer).cloned();
+
     let location = location.to_f64();
 
     with_surface_tree_downward(
@@ -129,15 +142,28 @@ pub fn push_elements_from_surface_tree<R>(
                 };
 
                 if has_view {
-                    match WaylandSurfaceRenderElement::from_surface(
+                    let elem = match WaylandSurfaceRenderElement::from_surface(
                         renderer, surface, states, location, alpha, kind,
                     ) {
-                        Ok(Some(surface)) => push(surface),
-                        Ok(None) => {} // surface is not mapped
+                        Ok(Some(elem)) => elem,
+                        Ok(None) => return, // surface is not mapped
                         Err(err) => {
                             warn!("failed to import surface: {}", err);
+                            return;
                         }
                     };
+
+                    let conversion = surface_image_description(states)
+                        .map(|desc| ColorConversion::to_composition(&desc))
+                        .filter(|conversion| !conversion.is_identity());
+                    match (conversion, program.clone()) {
+                        (Some(conversion), Some(program)) => {
+                            let elem =
+                                ColorManagedSurfaceRenderElement::new(elem, program, conversion);
+                            push(elem.into());
+                        }
+                        _ => push(elem.into()),
+                    }
                 }
             }
         },
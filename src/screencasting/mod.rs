@@ -201,9 +201,12 @@ impl State {
 
             self.backend.with_primary_renderer(|renderer| {
                 let mut elements = Vec::new();
-                mapped.render_for_screen_cast(renderer, scale, &mut |elem| {
-                    elements.push(CastRenderElement::from(elem))
-                });
+                mapped.render_for_screen_cast(
+                    renderer,
+                    scale,
+                    RenderTarget::Screencast,
+                    &mut |elem| elements.push(CastRenderElement::from(elem)),
+                );
 
                 let mut pointer_elements = Vec::new();
                 let mut pointer_location = Point::default();
@@ -661,7 +664,7 @@ impl Niri {
             }
 
             let mut elements = Vec::new();
-            mapped.render_for_screen_cast(renderer, scale, &mut |elem| {
+            mapped.render_for_screen_cast(renderer, scale, RenderTarget::Screencast, &mut |elem| {
                 elements.push(CastRenderElement::from(elem))
             });
 
@@ -0,0 +1,140 @@
+use std::iter;
+
+use async_channel::Receiver;
+use niri_config::Config;
+use niri_ipc::{Event, EventCategory, EventStreamFilter};
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::ClientId;
+use super::*;
+use crate::layout::LayoutElement as _;
+
+fn set_up(config: &str) -> (Fixture, ClientId, WlSurface) {
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    (f, id, surface)
+}
+
+fn is_urgent(f: &mut Fixture) -> bool {
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    mapped.is_urgent()
+}
+
+fn is_flashing(f: &mut Fixture) -> bool {
+    let mut tiles = f
+        .niri()
+        .layout
+        .workspaces()
+        .flat_map(|(_, _, ws)| ws.tiles());
+    tiles.next().unwrap().is_bell_flashing()
+}
+
+#[test]
+fn ring_flashes_window() {
+    let (mut f, id, surface) = set_up("");
+
+    assert!(!is_flashing(&mut f));
+
+    f.client(id).ring_bell(Some(&surface));
+    f.roundtrip(id);
+
+    assert!(is_flashing(&mut f));
+    // Urgency is off by default.
+    assert!(!is_urgent(&mut f));
+}
+
+#[test]
+fn ring_sets_urgent() {
+    let (mut f, id, surface) = set_up("bell { urgent; flash false; }");
+
+    f.client(id).ring_bell(Some(&surface));
+    f.roundtrip(id);
+
+    assert!(is_urgent(&mut f));
+    assert!(!is_flashing(&mut f));
+}
+
+fn subscribe(f: &mut Fixture) -> Receiver<Event> {
+    let filter = EventStreamFilter {
+        categories: vec![EventCategory::Windows],
+        ..Default::default()
+    };
+    f.niri().ipc_server.as_ref().unwrap().subscribe(filter)
+}
+
+/// Returns the window ids of the bell events received so far.
+fn received_bells(events: &Receiver<Event>) -> Vec<Option<u64>> {
+    iter::from_fn(|| events.try_recv().ok())
+        .filter_map(|event| match event {
+            Event::WindowBell { id } => Some(id),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn ring_sends_ipc_event() {
+    let (mut f, id, surface) = set_up("");
+    let events = subscribe(&mut f);
+
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    let window_id = mapped.id().get();
+
+    f.client(id).ring_bell(Some(&surface));
+    f.roundtrip(id);
+    assert_eq!(received_bells(&events), [Some(window_id)]);
+
+    // Wait out the throttle and ring without a surface.
+    f.niri().last_bell_time = None;
+    f.client(id).ring_bell(None);
+    f.roundtrip(id);
+    assert_eq!(received_bells(&events), [None]);
+}
+
+#[test]
+fn rings_in_quick_succession_are_dropped() {
+    let (mut f, id, surface) = set_up("");
+    let events = subscribe(&mut f);
+
+    for _ in 0..10 {
+        f.client(id).ring_bell(Some(&surface));
+    }
+    f.roundtrip(id);
+    assert_eq!(received_bells(&events).len(), 1);
+
+    // Once the interval has passed, the bell rings again.
+    f.niri().last_bell_time = None;
+    for _ in 0..10 {
+        f.client(id).ring_bell(Some(&surface));
+    }
+    f.roundtrip(id);
+    assert_eq!(received_bells(&events).len(), 1);
+}
+
+#[test]
+fn ring_event_is_filtered_out() {
+    let (mut f, id, surface) = set_up("");
+    let filter = EventStreamFilter {
+        categories: vec![EventCategory::Workspaces],
+        ..Default::default()
+    };
+    let events = f.niri().ipc_server.as_ref().unwrap().subscribe(filter);
+
+    f.client(id).ring_bell(Some(&surface));
+    f.roundtrip(id);
+    assert!(received_bells(&events).is_empty());
+}
@@ -9,13 +9,48 @@ use std::time::Duration;
 
 use calloop::EventLoop;
 use calloop_wayland_source::WaylandSource;
+use color_management::v1::client::wp_color_management_output_v1::{
+    self, WpColorManagementOutputV1,
+};
+use color_management::v1::client::wp_color_management_surface_v1::WpColorManagementSurfaceV1;
+use color_management::v1::client::wp_color_manager_v1::{self, WpColorManagerV1};
+use color_management::v1::client::wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1;
+use color_management::v1::client::wp_image_description_info_v1::{self, WpImageDescriptionInfoV1};
+use color_management::v1::client::wp_image_description_v1::{self, WpImageDescriptionV1};
+use ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1;
+use ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1;
+use ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1;
+use ext_image_capture_source_v1::ExtImageCaptureSourceV1;
+use ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1;
+use ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1;
+use ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1;
+use fifo::v1::client::wp_fifo_manager_v1::WpFifoManagerV1;
+use fifo::v1::client::wp_fifo_v1::WpFifoV1;
+use linux_drm_syncobj::v1::client::wp_linux_drm_syncobj_manager_v1::WpLinuxDrmSyncobjManagerV1;
+use linux_drm_syncobj::v1::client::wp_linux_drm_syncobj_surface_v1::WpLinuxDrmSyncobjSurfaceV1;
+use linux_drm_syncobj::v1::client::wp_linux_drm_syncobj_timeline_v1::WpLinuxDrmSyncobjTimelineV1;
 use single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1;
+use smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
+};
+use smithay::reexports::wayland_protocols::ext::image_capture_source::v1::client::{
+    ext_foreign_toplevel_image_capture_source_manager_v1, ext_image_capture_source_v1,
+    ext_output_image_capture_source_manager_v1,
+};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::client::{
+    ext_image_copy_capture_manager_v1, ext_image_copy_capture_session_v1,
+};
+use smithay::reexports::wayland_protocols::wp::color_management;
+use smithay::reexports::wayland_protocols::wp::fifo;
+use smithay::reexports::wayland_protocols::wp::linux_drm_syncobj;
 use smithay::reexports::wayland_protocols::wp::single_pixel_buffer;
 use smithay::reexports::wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use smithay::reexports::wayland_protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
 use smithay::reexports::wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
 use smithay::reexports::wayland_protocols::xdg::shell::client::xdg_toplevel::{self, XdgToplevel};
 use smithay::reexports::wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+use smithay::reexports::wayland_protocols::xdg::system_bell::v1::client::xdg_system_bell_v1::XdgSystemBellV1;
+use smithay::reexports::wayland_protocols::xdg::toplevel_tag::v1::client::xdg_toplevel_tag_manager_v1::XdgToplevelTagManagerV1;
 use smithay::reexports::wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::{
     self, ZwlrLayerShellV1,
 };
@@ -31,7 +66,7 @@ use wayland_client::protocol::wl_display::WlDisplay;
 use wayland_client::protocol::wl_output::{self, WlOutput};
 use wayland_client::protocol::wl_registry::{self, WlRegistry};
 use wayland_client::protocol::wl_surface::{self, WlSurface};
-use wayland_client::{Connection, Dispatch, Proxy as _, QueueHandle};
+use wayland_client::{Connection, Dispatch, Proxy as _, QueueHandle, WEnum};
 
 use crate::utils::id::IdCounter;
 
@@ -42,6 +77,8 @@ pub struct Client {
     pub qh: QueueHandle<State>,
     pub display: WlDisplay,
     pub state: State,
+    /// Whether a protocol error is expected, so it shouldn't panic.
+    pub expect_protocol_error: bool,
 }
 
 pub struct State {
@@ -55,9 +92,24 @@ pub struct State {
     pub layer_shell: Option<ZwlrLayerShellV1>,
     pub spbm: Option<WpSinglePixelBufferManagerV1>,
     pub viewporter: Option<WpViewporter>,
+    pub syncobj_manager: Option<WpLinuxDrmSyncobjManagerV1>,
+    pub output_capture_source_manager: Option<ExtOutputImageCaptureSourceManagerV1>,
+    pub toplevel_capture_source_manager: Option<ExtForeignToplevelImageCaptureSourceManagerV1>,
+    pub foreign_toplevel_list: Option<ExtForeignToplevelListV1>,
+    pub image_copy_capture_manager: Option<ExtImageCopyCaptureManagerV1>,
+    pub fifo_manager: Option<WpFifoManagerV1>,
+    pub color_manager: Option<WpColorManagerV1>,
+    pub system_bell: Option<XdgSystemBellV1>,
+    pub toplevel_tag_manager: Option<XdgToplevelTagManagerV1>,
 
     pub windows: Vec<Window>,
     pub layers: Vec<LayerSurface>,
+    pub foreign_toplevels: Vec<ExtForeignToplevelHandleV1>,
+    pub capture_sessions: Vec<CaptureSession>,
+    pub image_descriptions: Vec<ImageDescription>,
+    pub image_description_infos: Vec<ImageDescriptionInfo>,
+    /// Number of image_description_changed events received on color management outputs.
+    pub output_image_description_changes: usize,
 }
 
 pub struct Window {
@@ -88,6 +140,30 @@ pub struct LayerSurface {
     pub configures_looked_at: usize,
 }
 
+pub struct CaptureSession {
+    pub session: ExtImageCopyCaptureSessionV1,
+    pub pending_buffer_size: Option<(u32, u32)>,
+    /// Buffer size from the last complete set of buffer constraints.
+    pub buffer_size: Option<(u32, u32)>,
+    pub stopped: bool,
+}
+
+pub struct ImageDescription {
+    pub object: WpImageDescriptionV1,
+    /// Identity from the ready event.
+    pub identity: Option<u32>,
+    pub failed: bool,
+}
+
+pub struct ImageDescriptionInfo {
+    pub object: WpImageDescriptionInfoV1,
+    pub tf_named: Option<WEnum<wp_color_manager_v1::TransferFunction>>,
+    pub primaries_named: Option<WEnum<wp_color_manager_v1::Primaries>>,
+    /// Minimum (in 0.0001 cd/m²), maximum and reference luminances.
+    pub luminances: Option<(u32, u32, u32)>,
+    pub done: bool,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Configure {
     pub size: (i32, i32),
@@ -181,8 +257,22 @@ impl Client {
             layer_shell: None,
             spbm: None,
             viewporter: None,
+            syncobj_manager: None,
+            output_capture_source_manager: None,
+            toplevel_capture_source_manager: None,
+            foreign_toplevel_list: None,
+            image_copy_capture_manager: None,
+            fifo_manager: None,
+            color_manager: None,
+            system_bell: None,
+            toplevel_tag_manager: None,
             windows: Vec::new(),
             layers: Vec::new(),
+            foreign_toplevels: Vec::new(),
+            capture_sessions: Vec::new(),
+            image_descriptions: Vec::new(),
+            image_description_infos: Vec::new(),
+            output_image_description_changes: 0,
         };
 
         Self {
@@ -192,17 +282,22 @@ impl Client {
             qh,
             display,
             state,
+            expect_protocol_error: false,
         }
     }
 
     pub fn dispatch(&mut self) {
-        self.event_loop
-            .dispatch(Duration::ZERO, &mut self.state)
-            .unwrap();
+        let res = self.event_loop.dispatch(Duration::ZERO, &mut self.state);
 
         if let Some(error) = self.connection.protocol_error() {
+            if self.expect_protocol_error {
+                return;
+            }
+
             panic!("{error}");
         }
+
+        res.unwrap();
     }
 
     pub fn send_sync(&self) -> Arc<SyncData> {
@@ -233,6 +328,57 @@ impl Client {
         self.state.layer(surface)
     }
 
+    pub fn create_output_capture_session(
+        &mut self,
+        output: &WlOutput,
+    ) -> ExtImageCopyCaptureSessionV1 {
+        self.state.create_output_capture_session(output)
+    }
+
+    pub fn create_toplevel_capture_session(
+        &mut self,
+        toplevel: &ExtForeignToplevelHandleV1,
+    ) -> ExtImageCopyCaptureSessionV1 {
+        self.state.create_toplevel_capture_session(toplevel)
+    }
+
+    pub fn ring_bell(&self, surface: Option<&WlSurface>) {
+        self.state.system_bell.as_ref().unwrap().ring(surface);
+    }
+
+    pub fn set_toplevel_tag(&mut self, surface: &WlSurface, tag: &str) {
+        let toplevel = self.state.window(surface).xdg_toplevel.clone();
+        let manager = self.state.toplevel_tag_manager.as_ref().unwrap();
+        manager.set_toplevel_tag(&toplevel, tag.to_owned());
+    }
+
+    pub fn capture_session(&mut self, session: &ExtImageCopyCaptureSessionV1) -> &CaptureSession {
+        self.state
+            .capture_sessions
+            .iter()
+            .find(|s| s.session == *session)
+            .unwrap()
+    }
+
+    pub fn image_description(&mut self, object: &WpImageDescriptionV1) -> &ImageDescription {
+        self.state
+            .image_descriptions
+            .iter()
+            .find(|d| d.object == *object)
+            .unwrap()
+    }
+
+    pub fn image_description_info(
+        &mut self,
+        object: &WpImageDescriptionInfoV1,
+    ) -> &ImageDescriptionInfo {
+        self.state
+            .image_description_infos
+            .iter()
+            .find(|i| i.object == *object)
+            .unwrap()
+    }
+
     pub fn output(&mut self, name: &str) -> WlOutput {
         self.state
             .outputs
@@ -319,6 +465,85 @@ impl State {
             .find(|w| w.surface == *surface)
             .unwrap()
     }
+
+    pub fn create_output_capture_session(
+        &mut self,
+        output: &WlOutput,
+    ) -> ExtImageCopyCaptureSessionV1 {
+        let source_manager = self.output_capture_source_manager.as_ref().unwrap();
+        let source = source_manager.create_source(output, &self.qh, ());
+        self.create_capture_session(source)
+    }
+
+    pub fn create_toplevel_capture_session(
+        &mut self,
+        toplevel: &ExtForeignToplevelHandleV1,
+    ) -> ExtImageCopyCaptureSessionV1 {
+        let source_manager = self.toplevel_capture_source_manager.as_ref().unwrap();
+        let source = source_manager.create_source(toplevel, &self.qh, ());
+        self.create_capture_session(source)
+    }
+
+    fn create_capture_session(
+        &mut self,
+        source: ExtImageCaptureSourceV1,
+    ) -> ExtImageCopyCaptureSessionV1 {
+        let manager = self.image_copy_capture_manager.as_ref().unwrap();
+        let session = manager.create_session(
+            &source,
+            ext_image_copy_capture_manager_v1::Options::empty(),
+            &self.qh,
+            (),
+        );
+        source.destroy();
+
+        self.capture_sessions.push(CaptureSession {
+            session: session.clone(),
+            pending_buffer_size: None,
+            buffer_size: None,
+            stopped: false,
+        });
+        session
+    }
+
+    /// Creates a parametric image description with the given parameters.
+    pub fn create_parametric_image_description(
+        &mut self,
+        set_params: impl FnOnce(&WpImageDescriptionCreatorParamsV1),
+    ) -> WpImageDescriptionV1 {
+        let manager = self.color_manager.as_ref().unwrap();
+        let creator = manager.create_parametric_creator(&self.qh, ());
+        set_params(&creator);
+        let object = creator.create(&self.qh, ());
+        self.track_image_description(object)
+    }
+
+    pub fn track_image_description(
+        &mut self,
+        object: WpImageDescriptionV1,
+    ) -> WpImageDescriptionV1 {
+        self.image_descriptions.push(ImageDescription {
+            object: object.clone(),
+            identity: None,
+            failed: false,
+        });
+        object
+    }
+
+    pub fn get_image_description_information(
+        &mut self,
+        description: &WpImageDescriptionV1,
+    ) -> WpImageDescriptionInfoV1 {
+        let object = description.get_information(&self.qh, ());
+        self.image_description_infos.push(ImageDescriptionInfo {
+            object: object.clone(),
+            tf_named: None,
+            primaries_named: None,
+            luminances: None,
+            done: false,
+        });
+        object
+    }
 }
 
 impl Window {
@@ -518,6 +743,43 @@ impl Dispatch<WlRegistry, ()> for State {
                 } else if interface == WpViewporter::interface().name {
                     let version = min(version, WpViewporter::interface().version);
                     state.viewporter = Some(registry.bind(name, version, qh, ()));
+                } else if interface == WpLinuxDrmSyncobjManagerV1::interface().name {
+                    let version = min(version, WpLinuxDrmSyncobjManagerV1::interface().version);
+                    state.syncobj_manager = Some(registry.bind(name, version, qh, ()));
+                } else if interface == ExtOutputImageCaptureSourceManagerV1::interface().name {
+                    let version = min(
+                        version,
+                        ExtOutputImageCaptureSourceManagerV1::interface().version,
+                    );
+                    state.output_capture_source_manager =
+                        Some(registry.bind(name, version, qh, ()));
+                } else if interface
+                    == ExtForeignToplevelImageCaptureSourceManagerV1::interface().name
+                {
+                    let version = min(
+                        version,
+                        ExtForeignToplevelImageCaptureSourceManagerV1::interface().version,
+                    );
+                    state.toplevel_capture_source_manager =
+                        Some(registry.bind(name, version, qh, ()));
+                } else if interface == ExtForeignToplevelListV1::interface().name {
+                    let version = min(version, ExtForeignToplevelListV1::interface().version);
+                    state.foreign_toplevel_list = Some(registry.bind(name, version, qh, ()));
+                } else if interface == ExtImageCopyCaptureManagerV1::interface().name {
+                    let version = min(version, ExtImageCopyCaptureManagerV1::interface().version);
+                    state.image_copy_capture_manager = Some(registry.bind(name, version, qh, ()));
+                } else if interface == WpFifoManagerV1::interface().name {
+                    let version = min(version, WpFifoManagerV1::interface().version);
+                    state.fifo_manager = Some(registry.bind(name, version, qh, ()));
+                } else if interface == WpColorManagerV1::interface().name {
+                    let version = min(version, WpColorManagerV1::interface().version);
+                    state.color_manager = Some(registry.bind(name, version, qh, ()));
+                } else if interface == XdgSystemBellV1::interface().name {
+                    let version = min(version, XdgSystemBellV1::interface().version);
+                    state.system_bell = Some(registry.bind(name, version, qh, ()));
+                } else if interface == XdgToplevelTagManagerV1::interface().name {
+                    let version = min(version, XdgToplevelTagManagerV1::interface().version);
+                    state.toplevel_tag_manager = Some(registry.bind(name, version, qh, ()));
                 } else if interface == WlOutput::interface().name {
                     let version = min(version, WlOutput::interface().version);
                     let output = registry.bind(name, version, qh, ());
@@ -775,3 +1037,349 @@ impl Dispatch<WpViewport, ()> for State {
         unreachable!()
     }
 }
+
+impl Dispatch<WpLinuxDrmSyncobjManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpLinuxDrmSyncobjManagerV1,
+        _event: <WpLinuxDrmSyncobjManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpLinuxDrmSyncobjSurfaceV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpLinuxDrmSyncobjSurfaceV1,
+        _event: <WpLinuxDrmSyncobjSurfaceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpLinuxDrmSyncobjTimelineV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpLinuxDrmSyncobjTimelineV1,
+        _event: <WpLinuxDrmSyncobjTimelineV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<ExtOutputImageCaptureSourceManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtOutputImageCaptureSourceManagerV1,
+        _event: <ExtOutputImageCaptureSourceManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        _event: <ExtForeignToplevelImageCaptureSourceManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &ExtForeignToplevelListV1,
+        event: <ExtForeignToplevelListV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } => {
+                state.foreign_toplevels.push(toplevel);
+            }
+            ext_foreign_toplevel_list_v1::Event::Finished => (),
+            _ => unreachable!(),
+        }
+    }
+
+    wayland_client::event_created_child!(State, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        toplevel: &ExtForeignToplevelHandleV1,
+        event: <ExtForeignToplevelHandleV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                state.foreign_toplevels.retain(|x| x != toplevel);
+            }
+            ext_foreign_toplevel_handle_v1::Event::Done => (),
+            ext_foreign_toplevel_handle_v1::Event::Title { .. } => (),
+            ext_foreign_toplevel_handle_v1::Event::AppId { .. } => (),
+            ext_foreign_toplevel_handle_v1::Event::Identifier { .. } => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<ExtImageCaptureSourceV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtImageCaptureSourceV1,
+        _event: <ExtImageCaptureSourceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtImageCopyCaptureManagerV1,
+        _event: <ExtImageCopyCaptureManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureSessionV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        session: &ExtImageCopyCaptureSessionV1,
+        event: <ExtImageCopyCaptureSessionV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let session = state
+            .capture_sessions
+            .iter_mut()
+            .find(|s| s.session == *session)
+            .unwrap();
+
+        match event {
+            ext_image_copy_capture_session_v1::Event::BufferSize { width, height } => {
+                session.pending_buffer_size = Some((width, height));
+            }
+            ext_image_copy_capture_session_v1::Event::ShmFormat { .. } => (),
+            ext_image_copy_capture_session_v1::Event::DmabufDevice { .. } => (),
+            ext_image_copy_capture_session_v1::Event::DmabufFormat { .. } => (),
+            ext_image_copy_capture_session_v1::Event::Done => {
+                session.buffer_size = session.pending_buffer_size;
+            }
+            ext_image_copy_capture_session_v1::Event::Stopped => session.stopped = true,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<WpFifoManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpFifoManagerV1,
+        _event: <WpFifoManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpFifoV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpFifoV1,
+        _event: <WpFifoV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<XdgSystemBellV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &XdgSystemBellV1,
+        _event: <XdgSystemBellV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<XdgToplevelTagManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &XdgToplevelTagManagerV1,
+        _event: <XdgToplevelTagManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpColorManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpColorManagerV1,
+        event: <WpColorManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_color_manager_v1::Event::SupportedIntent { .. } => (),
+            wp_color_manager_v1::Event::SupportedFeature { .. } => (),
+            wp_color_manager_v1::Event::SupportedTfNamed { .. } => (),
+            wp_color_manager_v1::Event::SupportedPrimariesNamed { .. } => (),
+            wp_color_manager_v1::Event::Done => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<WpColorManagementOutputV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &WpColorManagementOutputV1,
+        event: <WpColorManagementOutputV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_color_management_output_v1::Event::ImageDescriptionChanged => {
+                state.output_image_description_changes += 1;
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<WpColorManagementSurfaceV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpColorManagementSurfaceV1,
+        _event: <WpColorManagementSurfaceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpImageDescriptionCreatorParamsV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WpImageDescriptionCreatorParamsV1,
+        _event: <WpImageDescriptionCreatorParamsV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl Dispatch<WpImageDescriptionV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        description: &WpImageDescriptionV1,
+        event: <WpImageDescriptionV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let description = state
+            .image_descriptions
+            .iter_mut()
+            .find(|d| d.object == *description)
+            .unwrap();
+
+        match event {
+            wp_image_description_v1::Event::Ready { identity } => {
+                description.identity = Some(identity);
+            }
+            wp_image_description_v1::Event::Failed { .. } => description.failed = true,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<WpImageDescriptionInfoV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        info: &WpImageDescriptionInfoV1,
+        event: <WpImageDescriptionInfoV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let info = state
+            .image_description_infos
+            .iter_mut()
+            .find(|i| i.object == *info)
+            .unwrap();
+
+        match event {
+            wp_image_description_info_v1::Event::TfNamed { tf } => {
+                info.tf_named = Some(tf);
+            }
+            wp_image_description_info_v1::Event::PrimariesNamed { primaries } => {
+                info.primaries_named = Some(primaries);
+            }
+            wp_image_description_info_v1::Event::Luminances {
+                min_lum,
+                max_lum,
+                reference_lum,
+            } => {
+                info.luminances = Some((min_lum, max_lum, reference_lum));
+            }
+            wp_image_description_info_v1::Event::Primaries { .. } => (),
+            wp_image_description_info_v1::Event::TargetPrimaries { .. } => (),
+            wp_image_description_info_v1::Event::TargetLuminance { .. } => (),
+            wp_image_description_info_v1::Event::Done => info.done = true,
+            _ => unreachable!(),
+        }
+    }
+}
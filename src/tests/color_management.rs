@@ -0,0 +1,242 @@
+use smithay::reexports::wayland_protocols::wp::color_management::v1::client::{
+    wp_color_manager_v1, wp_image_description_creator_params_v1, wp_image_description_v1,
+};
+use smithay::wayland::compositor::with_states;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::WEnum;
+use wp_color_manager_v1::{Primaries as ProtoPrimaries, RenderIntent, TransferFunction as ProtoTf};
+use wp_image_description_creator_params_v1::{
+    Error as ParamsError, WpImageDescriptionCreatorParamsV1,
+};
+
+use super::client::ClientId;
+use super::*;
+use crate::protocols::color_management::{
+    surface_image_description, ImageDescription, NamedPrimaries, Primaries, TransferFunction,
+};
+
+fn set_up() -> (Fixture, ClientId) {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+    (f, id)
+}
+
+fn create_window(f: &mut Fixture, id: ClientId) -> WlSurface {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    surface
+}
+
+/// Returns the image description of the mapped window on the compositor side.
+fn window_description(f: &mut Fixture) -> Option<ImageDescription> {
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    let surface = mapped.toplevel().wl_surface();
+    with_states(surface, surface_image_description)
+}
+
+fn srgb_params(creator: &WpImageDescriptionCreatorParamsV1) {
+    creator.set_tf_named(ProtoTf::Gamma22);
+    creator.set_primaries_named(ProtoPrimaries::Srgb);
+}
+
+fn hdr_description() -> ImageDescription {
+    ImageDescription {
+        tf: TransferFunction::St2084Pq,
+        primaries: Primaries::Named(NamedPrimaries::Bt2020),
+        luminances: TransferFunction::St2084Pq.default_luminances(),
+        target_luminance: Some((0.005, 1000.)),
+    }
+}
+
+#[test]
+fn parametric_description_applies_on_commit() {
+    let (mut f, id) = set_up();
+    let surface = create_window(&mut f, id);
+
+    let client = f.client(id);
+    let desc = client.state.create_parametric_image_description(|creator| {
+        creator.set_tf_named(ProtoTf::St2084Pq);
+        creator.set_primaries_named(ProtoPrimaries::Bt2020);
+    });
+    f.roundtrip(id);
+    assert!(f.client(id).image_description(&desc).identity.is_some());
+
+    let client = f.client(id);
+    let manager = client.state.color_manager.clone().unwrap();
+    let cm_surface = manager.get_surface(&surface, &client.qh, ());
+    cm_surface.set_image_description(&desc, RenderIntent::Perceptual);
+    f.roundtrip(id);
+
+    // The image description is double-buffered.
+    assert_eq!(window_description(&mut f), None);
+
+    f.client(id).window(&surface).commit();
+    f.roundtrip(id);
+
+    let expected = ImageDescription {
+        tf: TransferFunction::St2084Pq,
+        primaries: Primaries::Named(NamedPrimaries::Bt2020),
+        luminances: TransferFunction::St2084Pq.default_luminances(),
+        target_luminance: None,
+    };
+    assert_eq!(window_description(&mut f), Some(expected));
+
+    // Destroying the surface object unsets the image description on the next commit.
+    cm_surface.destroy();
+    f.client(id).window(&surface).commit();
+    f.roundtrip(id);
+    assert_eq!(window_description(&mut f), None);
+}
+
+#[test]
+fn created_descriptions_get_unique_identities() {
+    let (mut f, id) = set_up();
+
+    let client = f.client(id);
+    let first = client
+        .state
+        .create_parametric_image_description(srgb_params);
+    let second = client
+        .state
+        .create_parametric_image_description(srgb_params);
+    f.roundtrip(id);
+
+    let first = f.client(id).image_description(&first).identity.unwrap();
+    let second = f.client(id).image_description(&second).identity.unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn incomplete_parametric_description_is_an_error() {
+    let (mut f, id) = set_up();
+
+    f.client(id)
+        .state
+        .create_parametric_image_description(|creator| {
+            creator.set_tf_named(ProtoTf::St2084Pq);
+        });
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(
+        error.object_interface,
+        "wp_image_description_creator_params_v1"
+    );
+    assert_eq!(error.code, ParamsError::IncompleteSet as u32);
+}
+
+#[test]
+fn setting_a_parameter_twice_is_an_error() {
+    let (mut f, id) = set_up();
+
+    let client = f.client(id);
+    let manager = client.state.color_manager.clone().unwrap();
+    let creator = manager.create_parametric_creator(&client.qh, ());
+    creator.set_tf_named(ProtoTf::Srgb);
+    creator.set_tf_named(ProtoTf::Gamma22);
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.code, ParamsError::AlreadySet as u32);
+}
+
+#[test]
+fn invalid_luminances_are_an_error() {
+    let (mut f, id) = set_up();
+
+    let client = f.client(id);
+    let manager = client.state.color_manager.clone().unwrap();
+    let creator = manager.create_parametric_creator(&client.qh, ());
+    // The minimum of 100 cd/m² is above the maximum of 80 cd/m².
+    creator.set_luminances(1_000_000, 80, 80);
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.code, ParamsError::InvalidLuminance as u32);
+}
+
+#[test]
+fn second_surface_object_is_an_error() {
+    let (mut f, id) = set_up();
+    let surface = create_window(&mut f, id);
+
+    let client = f.client(id);
+    let manager = client.state.color_manager.clone().unwrap();
+    manager.get_surface(&surface, &client.qh, ());
+    manager.get_surface(&surface, &client.qh, ());
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.object_interface, "wp_color_manager_v1");
+    assert_eq!(error.code, wp_color_manager_v1::Error::SurfaceExists as u32);
+}
+
+#[test]
+fn created_descriptions_have_no_information() {
+    let (mut f, id) = set_up();
+
+    let client = f.client(id);
+    let desc = client
+        .state
+        .create_parametric_image_description(srgb_params);
+    client.state.get_image_description_information(&desc);
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.object_interface, "wp_image_description_v1");
+    assert_eq!(
+        error.code,
+        wp_image_description_v1::Error::NoInformation as u32
+    );
+}
+
+#[test]
+fn output_description_changes_are_announced() {
+    let (mut f, id) = set_up();
+
+    let client = f.client(id);
+    let output = client.output("headless-1");
+    let manager = client.state.color_manager.clone().unwrap();
+    let cm_output = manager.get_output(&output, &client.qh, ());
+    let object = cm_output.get_image_description(&client.qh, ());
+    let srgb = client.state.track_image_description(object);
+    f.roundtrip(id);
+
+    let srgb = f.client(id).image_description(&srgb).identity.unwrap();
+    assert_eq!(f.client(id).state.output_image_description_changes, 0);
+
+    let niri_output = f.niri_output(1);
+    f.niri()
+        .set_output_image_description(&niri_output, hdr_description());
+    f.roundtrip(id);
+    assert_eq!(f.client(id).state.output_image_description_changes, 1);
+
+    // Setting the same description again doesn't notify.
+    f.niri()
+        .set_output_image_description(&niri_output, hdr_description());
+    f.roundtrip(id);
+    assert_eq!(f.client(id).state.output_image_description_changes, 1);
+
+    let client = f.client(id);
+    let object = cm_output.get_image_description(&client.qh, ());
+    let hdr = client.state.track_image_description(object);
+    let info = client.state.get_image_description_information(&hdr);
+    f.roundtrip(id);
+
+    let hdr = f.client(id).image_description(&hdr).identity.unwrap();
+    assert_ne!(hdr, srgb);
+
+    let info = f.client(id).image_description_info(&info);
+    assert!(info.done);
+    assert_eq!(info.tf_named, Some(WEnum::Value(ProtoTf::St2084Pq)));
+    assert_eq!(
+        info.primaries_named,
+        Some(WEnum::Value(ProtoPrimaries::Bt2020))
+    );
+    assert_eq!(info.luminances, Some((50, 10000, 203)));
+}
@@ -0,0 +1,74 @@
+use niri_config::Config;
+
+use super::*;
+
+fn config_with_gaps(gaps: u16) -> Config {
+    Config::parse_mem(&format!("layout {{ gaps {gaps}; }}")).unwrap()
+}
+
+fn gaps(f: &mut Fixture) -> f64 {
+    f.niri().config.borrow().layout.gaps
+}
+
+#[test]
+fn overlay_survives_config_reload() {
+    let mut f = Fixture::with_config(config_with_gaps(8));
+    f.add_output(1, (1920, 1080));
+
+    let state = f.niri_state();
+    let id = state
+        .apply_config_overlay(String::from("layout { gaps 32; }"))
+        .unwrap();
+    assert_eq!(gaps(&mut f), 32.);
+
+    // The overlay stays on top of the reloaded config file.
+    f.niri_state().reload_config(Ok(config_with_gaps(16)));
+    assert_eq!(gaps(&mut f), 32.);
+
+    let state = f.niri_state();
+    assert!(state.niri.config_overlays[0].error.is_none());
+    state.remove_config_overlay(id).unwrap();
+    assert_eq!(gaps(&mut f), 16.);
+
+    assert!(f.niri_state().remove_config_overlay(id).is_err());
+}
+
+#[test]
+fn overlays_apply_in_order() {
+    let mut f = Fixture::with_config(config_with_gaps(8));
+    f.add_output(1, (1920, 1080));
+
+    let state = f.niri_state();
+    let first = state
+        .apply_config_overlay(String::from("layout { gaps 16; }"))
+        .unwrap();
+    let second = state
+        .apply_config_overlay(String::from("layout { gaps 24; }"))
+        .unwrap();
+    assert_ne!(first, second);
+    assert_eq!(gaps(&mut f), 24.);
+
+    f.niri_state().remove_config_overlay(second).unwrap();
+    assert_eq!(gaps(&mut f), 16.);
+
+    f.niri_state().reset_config_overlays();
+    assert_eq!(gaps(&mut f), 8.);
+    assert!(f.niri().config_overlays.is_empty());
+}
+
+#[test]
+fn invalid_overlay_is_rejected() {
+    let mut f = Fixture::with_config(config_with_gaps(8));
+    f.add_output(1, (1920, 1080));
+
+    let state = f.niri_state();
+    assert!(state
+        .apply_config_overlay(String::from("layout { gaps -1; }"))
+        .is_err());
+    assert!(state
+        .apply_config_overlay(String::from(r#"include "other.kdl""#))
+        .is_err());
+
+    assert!(f.niri().config_overlays.is_empty());
+    assert_eq!(gaps(&mut f), 8.);
+}
@@ -0,0 +1,134 @@
+use std::fs::File;
+use std::os::fd::AsFd as _;
+
+use smithay::reexports::wayland_protocols::wp::linux_drm_syncobj::v1::client::{
+    wp_linux_drm_syncobj_manager_v1, wp_linux_drm_syncobj_surface_v1,
+};
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::ClientId;
+use super::*;
+
+/// Sets up a fixture with the syncobj global.
+///
+/// Tests using this need a real DRM device, which CI machines usually don't have, so they are
+/// ignored by default. Run them with `cargo test -- --ignored`.
+///
+/// Choosing between the acquire point and the implicit fence is covered with a fake timeline in
+/// the compositor handler tests.
+fn set_up() -> (Fixture, ClientId) {
+    let mut f = Fixture::new();
+    assert!(
+        f.add_drm_syncobj(),
+        "no render node with syncobj eventfd support"
+    );
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+    (f, id)
+}
+
+fn create_unmapped_window(f: &mut Fixture, id: ClientId) -> WlSurface {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+    surface
+}
+
+#[test]
+fn no_global_without_drm_device() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    assert!(f.client(id).state.syncobj_manager.is_none());
+}
+
+#[test]
+#[ignore = "needs a render node with syncobj eventfd support"]
+fn get_surface_twice() {
+    let (mut f, id) = set_up();
+
+    let surface = create_unmapped_window(&mut f, id);
+
+    let client = f.client(id);
+    let manager = client.state.syncobj_manager.clone().unwrap();
+    manager.get_surface(&surface, &client.qh, ());
+    manager.get_surface(&surface, &client.qh, ());
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.object_interface, "wp_linux_drm_syncobj_manager_v1");
+    assert_eq!(
+        error.code,
+        wp_linux_drm_syncobj_manager_v1::Error::SurfaceExists as u32
+    );
+}
+
+#[test]
+#[ignore = "needs a render node with syncobj eventfd support"]
+fn import_invalid_timeline() {
+    let (mut f, id) = set_up();
+
+    // Not a syncobj fd.
+    let file = File::open("/dev/null").unwrap();
+
+    let client = f.client(id);
+    let manager = client.state.syncobj_manager.clone().unwrap();
+    manager.import_timeline(file.as_fd(), &client.qh, ());
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.object_interface, "wp_linux_drm_syncobj_manager_v1");
+    assert_eq!(
+        error.code,
+        wp_linux_drm_syncobj_manager_v1::Error::InvalidTimeline as u32
+    );
+}
+
+#[test]
+#[ignore = "needs a render node with syncobj eventfd support"]
+fn commit_buffer_without_acquire_point() {
+    let (mut f, id) = set_up();
+
+    let surface = create_unmapped_window(&mut f, id);
+
+    let client = f.client(id);
+    let manager = client.state.syncobj_manager.clone().unwrap();
+    manager.get_surface(&surface, &client.qh, ());
+
+    let window = client.window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+
+    let error = f.roundtrip_expecting_error(id);
+    assert_eq!(error.object_interface, "wp_linux_drm_syncobj_surface_v1");
+
+    // The single-pixel buffer is not a dmabuf and has no acquire point, so either error is valid.
+    let unsupported_buffer = wp_linux_drm_syncobj_surface_v1::Error::UnsupportedBuffer as u32;
+    let no_acquire_point = wp_linux_drm_syncobj_surface_v1::Error::NoAcquirePoint as u32;
+    assert!(
+        error.code == unsupported_buffer || error.code == no_acquire_point,
+        "unexpected error: {error}"
+    );
+}
+
+#[test]
+#[ignore = "needs a render node with syncobj eventfd support"]
+fn destroyed_syncobj_surface_allows_implicit_sync() {
+    let (mut f, id) = set_up();
+
+    let surface = create_unmapped_window(&mut f, id);
+
+    let client = f.client(id);
+    let manager = client.state.syncobj_manager.clone().unwrap();
+    let syncobj_surface = manager.get_surface(&surface, &client.qh, ());
+    syncobj_surface.destroy();
+
+    let window = client.window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(f.niri().layout.windows().count(), 1);
+}
@@ -0,0 +1,150 @@
+use smithay::reexports::wayland_protocols::wp::fifo::v1::client::wp_fifo_v1::WpFifoV1;
+use smithay::utils::{Logical, Size};
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::ClientId;
+use super::*;
+use crate::layout::LayoutElement as _;
+
+fn set_up() -> (Fixture, ClientId) {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+    (f, id)
+}
+
+fn create_window_with_fifo(f: &mut Fixture, id: ClientId) -> (WlSurface, WpFifoV1) {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let client = f.client(id);
+    let manager = client.state.fifo_manager.clone().unwrap();
+    let fifo = manager.get_fifo(&surface, &client.qh, ());
+
+    (surface, fifo)
+}
+
+fn window_size(f: &mut Fixture) -> Size<i32, Logical> {
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    mapped.size()
+}
+
+/// Runs the refresh cycle for surfaces not shown on any output, which is all surfaces in tests.
+fn signal_barriers(f: &mut Fixture, id: ClientId) {
+    let state = f.niri_state();
+    state
+        .niri
+        .send_frame_callbacks_on_fallback_timer(&state.backend);
+    f.double_roundtrip(id);
+}
+
+#[test]
+fn wait_without_barrier_applies_immediately() {
+    let (mut f, id) = set_up();
+    let (surface, fifo) = create_window_with_fifo(&mut f, id);
+
+    fifo.wait_barrier();
+    f.client(id).window(&surface).set_size(200, 200);
+    f.client(id).window(&surface).commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(window_size(&mut f), Size::from((200, 200)));
+    assert!(f.niri().presentation_barrier_surfaces.is_empty());
+}
+
+#[test]
+fn set_barrier_doesnt_block_commits_that_dont_wait() {
+    let (mut f, id) = set_up();
+    let (surface, fifo) = create_window_with_fifo(&mut f, id);
+
+    fifo.set_barrier();
+    f.client(id).window(&surface).commit();
+    f.client(id).window(&surface).set_size(200, 200);
+    f.client(id).window(&surface).commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(window_size(&mut f), Size::from((200, 200)));
+    assert_eq!(f.niri().presentation_barrier_surfaces.len(), 1);
+
+    signal_barriers(&mut f, id);
+    assert!(f.niri().presentation_barrier_surfaces.is_empty());
+}
+
+#[test]
+fn wait_is_held_until_barrier_clears() {
+    let (mut f, id) = set_up();
+    let (surface, fifo) = create_window_with_fifo(&mut f, id);
+
+    fifo.set_barrier();
+    f.client(id).window(&surface).commit();
+    fifo.wait_barrier();
+    f.client(id).window(&surface).set_size(200, 200);
+    f.client(id).window(&surface).commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(window_size(&mut f), Size::from((100, 100)));
+
+    signal_barriers(&mut f, id);
+    assert_eq!(window_size(&mut f), Size::from((200, 200)));
+    assert!(f.niri().presentation_barrier_surfaces.is_empty());
+}
+
+#[test]
+fn barriers_clear_one_per_refresh_cycle() {
+    let (mut f, id) = set_up();
+    let (surface, fifo) = create_window_with_fifo(&mut f, id);
+
+    fifo.set_barrier();
+    f.client(id).window(&surface).commit();
+
+    fifo.wait_barrier();
+    fifo.set_barrier();
+    f.client(id).window(&surface).set_size(200, 200);
+    f.client(id).window(&surface).commit();
+
+    fifo.wait_barrier();
+    f.client(id).window(&surface).set_size(300, 300);
+    f.client(id).window(&surface).commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(window_size(&mut f), Size::from((100, 100)));
+
+    // The second commit applies and sets a barrier of its own, which the third commit waits on.
+    signal_barriers(&mut f, id);
+    assert_eq!(window_size(&mut f), Size::from((200, 200)));
+    assert_eq!(f.niri().presentation_barrier_surfaces.len(), 1);
+
+    signal_barriers(&mut f, id);
+    assert_eq!(window_size(&mut f), Size::from((300, 300)));
+    assert!(f.niri().presentation_barrier_surfaces.is_empty());
+}
+
+#[test]
+fn destroyed_surface_is_dropped_from_tracking() {
+    let (mut f, id) = set_up();
+    let (surface, fifo) = create_window_with_fifo(&mut f, id);
+
+    fifo.set_barrier();
+    f.client(id).window(&surface).commit();
+    f.double_roundtrip(id);
+    assert_eq!(f.niri().presentation_barrier_surfaces.len(), 1);
+
+    fifo.destroy();
+    let window = f.client(id).window(&surface);
+    window.xdg_toplevel.destroy();
+    window.xdg_surface.destroy();
+    window.viewport.destroy();
+    surface.destroy();
+    f.double_roundtrip(id);
+
+    signal_barriers(&mut f, id);
+    assert!(f.niri().presentation_barrier_surfaces.is_empty());
+}
@@ -1,4 +1,5 @@
-use std::os::fd::AsFd as _;
+use std::fs::{self, OpenOptions};
+use std::os::fd::{AsFd as _, OwnedFd};
 use std::os::unix::net::UnixStream;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -6,7 +7,11 @@ use std::time::Duration;
 use calloop::generic::Generic;
 use calloop::{EventLoop, Interest, LoopHandle, Mode, PostAction};
 use niri_config::Config;
+use smithay::backend::drm::DrmDeviceFd;
 use smithay::output::Output;
+use smithay::utils::DeviceFd;
+use smithay::wayland::drm_syncobj::{supports_syncobj_eventfd, DrmSyncobjState};
+use wayland_backend::protocol::ProtocolError;
 
 use super::client::{Client, ClientId};
 use super::server::Server;
@@ -95,6 +100,26 @@ impl Fixture {
         state.backend.headless().add_output(niri, n, size);
     }
 
+    pub fn remove_output(&mut self, n: u8) {
+        let state = self.niri_state();
+        let niri = &mut state.niri;
+        state.backend.headless().remove_output(niri, n);
+    }
+
+    /// Exposes the linux-drm-syncobj global, backed by the first render node that supports it.
+    ///
+    /// Returns `false` if there's no such render node.
+    pub fn add_drm_syncobj(&mut self) -> bool {
+        let Some(device_fd) = find_syncobj_render_node() else {
+            return false;
+        };
+
+        let niri = self.niri();
+        let state = DrmSyncobjState::new::<crate::niri::State>(&niri.display_handle, device_fd);
+        niri.drm_syncobj_state = Some(state);
+        true
+    }
+
     pub fn add_client(&mut self) -> ClientId {
         let (sock1, sock2) = UnixStream::pair().unwrap();
         self.niri().insert_client(NewClient {
@@ -132,6 +157,25 @@ impl Fixture {
         }
     }
 
+    /// Roundtrip until the client receives a protocol error, and return it.
+    pub fn roundtrip_expecting_error(&mut self, id: ClientId) -> ProtocolError {
+        let client = self.state.client(id);
+        client.expect_protocol_error = true;
+        let data = client.send_sync();
+        loop {
+            self.dispatch();
+
+            let client = self.state.client(id);
+            if let Some(error) = client.connection.protocol_error() {
+                return error;
+            }
+            assert!(
+                !data.done.load(Ordering::Relaxed),
+                "expected a protocol error"
+            );
+        }
+    }
+
     /// Roundtrip twice in a row.
     ///
     /// For some reason, when running tests on many threads at once, a single roundtrip is
@@ -152,3 +196,23 @@ impl State {
         self.clients.iter_mut().find(|c| c.id == id).unwrap()
     }
 }
+
+fn find_syncobj_render_node() -> Option<DrmDeviceFd> {
+    let mut paths: Vec<_> = fs::read_dir("/dev/dri")
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("renderD"))
+        })
+        .collect();
+    paths.sort();
+
+    paths.into_iter().find_map(|path| {
+        let file = OpenOptions::new().read(true).write(true).open(path).ok()?;
+        let device_fd = DrmDeviceFd::new(DeviceFd::from(OwnedFd::from(file)));
+        supports_syncobj_eventfd(&device_fd).then_some(device_fd)
+    })
+}
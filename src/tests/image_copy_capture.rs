@@ -0,0 +1,82 @@
+use niri_config::Config;
+
+use super::*;
+
+#[test]
+fn output_session_stops_when_output_is_removed() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    f.add_output(2, (1280, 720));
+    let id = f.add_client();
+
+    let client = f.client(id);
+    let output = client.output("headless-2");
+    let session = client.create_output_capture_session(&output);
+    f.double_roundtrip(id);
+
+    let state = f.client(id).capture_session(&session);
+    assert_eq!(state.buffer_size, Some((1280, 720)));
+    assert!(!state.stopped);
+
+    f.remove_output(2);
+    f.double_roundtrip(id);
+
+    assert!(f.client(id).capture_session(&session).stopped);
+}
+
+#[test]
+fn output_session_keeps_running_when_other_output_is_removed() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    f.add_output(2, (1280, 720));
+    let id = f.add_client();
+
+    let client = f.client(id);
+    let output = client.output("headless-1");
+    let session = client.create_output_capture_session(&output);
+    f.double_roundtrip(id);
+
+    f.remove_output(2);
+    f.double_roundtrip(id);
+
+    let state = f.client(id).capture_session(&session);
+    assert_eq!(state.buffer_size, Some((1920, 1080)));
+    assert!(!state.stopped);
+}
+
+#[test]
+fn window_session_off_output_uses_last_scale() {
+    let config = r##"
+output "headless-1" {
+    scale 2
+}
+"##;
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let toplevel = f.client(id).state.foreign_toplevels[0].clone();
+
+    // The window stays around without an output.
+    f.remove_output(1);
+    f.double_roundtrip(id);
+
+    let session = f.client(id).create_toplevel_capture_session(&toplevel);
+    f.double_roundtrip(id);
+
+    let state = f.client(id).capture_session(&session);
+    assert_eq!(state.buffer_size, Some((200, 200)));
+    assert!(!state.stopped);
+}
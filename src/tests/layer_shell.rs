@@ -256,3 +256,46 @@ fn multiple_commits_before_mapping() {
     // Configure with new size.
     assert_snapshot!(layer.format_recent_configures(), @"size: 1920 × 50");
 }
+
+#[test]
+fn layers_request_lists_unmapped_surfaces() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let layer = f.client(id).create_layer(None, Layer::Top, "bar");
+    let surface = layer.surface.clone();
+    layer.set_configure_props(LayerConfigureProps {
+        anchor: Some(Anchor::Left | Anchor::Right | Anchor::Top),
+        size: Some((0, 50)),
+        ..Default::default()
+    });
+    layer.commit();
+    f.double_roundtrip(id);
+
+    // The surface is listed before it maps.
+    let layers = f.niri_state().ipc_layers();
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0].namespace, "bar");
+    let layer_id = layers[0].id;
+
+    let layer = f.client(id).layer(&surface);
+    layer.attach_new_buffer();
+    layer.set_size(100, 100);
+    layer.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let layers = f.niri_state().ipc_layers();
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0].id, layer_id);
+
+    // Unmapping keeps the surface listed with the same id.
+    let layer = f.client(id).layer(&surface);
+    layer.attach_null();
+    layer.commit();
+    f.double_roundtrip(id);
+
+    let layers = f.niri_state().ipc_layers();
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0].id, layer_id);
+}
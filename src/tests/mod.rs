@@ -5,8 +5,14 @@ mod fixture;
 mod server;
 
 mod animations;
+mod bell;
+mod color_management;
+mod config_overlays;
+mod explicit_sync;
+mod fifo;
 mod floating;
 mod fullscreen;
+mod image_copy_capture;
 mod layer_shell;
 mod transactions;
 mod window_opening;
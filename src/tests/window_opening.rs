@@ -8,6 +8,7 @@ use super::*;
 use crate::layout::LayoutElement as _;
 use crate::utils::spawning::store_and_increase_nofile_rlimit;
 use crate::utils::with_toplevel_role;
+use crate::window::{ResolvedWindowRules, RuleMatch, WindowRef};
 
 #[test]
 fn simple_no_workspaces() {
@@ -891,3 +892,144 @@ post-map configures:
     let _guard = settings.bind_to_scope();
     assert_snapshot!(snapshot);
 }
+
+#[test]
+fn window_rules_explained() {
+    let config = r##"
+window-rule {
+    match title=r#"^fo\w$"#
+    open-floating true
+}
+
+window-rule {
+    match title="bar"
+    match title="foo"
+    exclude at-startup=true
+    exclude title="^f"
+    opacity 0.5
+}
+
+window-rule {
+    exclude title="bar"
+    max-width 500
+}
+"##;
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.set_title("foo");
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let niri = f.niri();
+    let (_, mapped) = niri.layout.windows().next().unwrap();
+    let window_id = mapped.id().get();
+    let config = niri.config.borrow();
+
+    // After startup, the second rule is excluded by its second exclude.
+    let (resolved, explanation) = ResolvedWindowRules::compute_explained(
+        &config.window_rules,
+        WindowRef::Mapped(mapped),
+        false,
+    );
+    assert_eq!(
+        explanation,
+        [
+            RuleMatch {
+                applied: true,
+                matched_by: Some(0),
+                excluded_by: None,
+            },
+            RuleMatch {
+                applied: false,
+                matched_by: Some(1),
+                excluded_by: Some(1),
+            },
+            RuleMatch {
+                applied: true,
+                matched_by: None,
+                excluded_by: None,
+            },
+        ]
+    );
+    assert_eq!(resolved.open_floating, Some(true));
+    assert_eq!(resolved.opacity, None);
+    assert_eq!(resolved.max_width, Some(500));
+
+    // The window opened at startup, so the at-startup exclude matched first back then.
+    let at_open = mapped.rules_at_open().unwrap();
+    assert_eq!(
+        at_open.matches[1],
+        RuleMatch {
+            applied: false,
+            matched_by: Some(1),
+            excluded_by: Some(0),
+        }
+    );
+    assert_eq!(at_open.resolved.open_floating, Some(true));
+    assert_eq!(at_open.resolved.max_width, Some(500));
+    drop(config);
+
+    // Over IPC, matchers are formatted like in the config.
+    let explanation = f.niri_state().ipc_window_rules(Some(window_id)).unwrap();
+    let at_open = explanation.at_open.unwrap();
+    assert_eq!(at_open.rules[0].matches, [r##"title=r#"^fo\w$"#"##]);
+    assert_eq!(
+        at_open.rules[1].excludes,
+        ["at-startup=true", r#"title="^f""#]
+    );
+    assert_eq!(at_open.rules[1].excluded_by, Some(0));
+    assert_eq!(at_open.resolved.open_floating, Some(true));
+}
+
+#[test]
+fn window_rules_match_toplevel_tag() {
+    let config = r#"
+window-rule {
+    match tag="^main$"
+    open-floating true
+}
+
+window-rule {
+    match tag="^main$"
+    opacity 0.5
+}
+"#;
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    f.client(id).set_toplevel_tag(&surface, "main");
+    f.client(id).window(&surface).commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    // The tag set before the initial commit applies the window opening rules.
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    assert_eq!(mapped.toplevel_tag().tag.as_deref(), Some("main"));
+    assert!(mapped.is_floating());
+    assert_eq!(mapped.rules().opacity, Some(0.5));
+
+    // Changing the tag afterwards recomputes the rules.
+    f.client(id).set_toplevel_tag(&surface, "other");
+    f.double_roundtrip(id);
+
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    assert_eq!(mapped.rules().opacity, None);
+}
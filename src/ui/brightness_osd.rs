@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::FontDescription;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Point, Transform};
+
+use crate::animation::{Animation, Clock, Curve};
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round};
+
+const WIDTH: i32 = 240;
+const PADDING: i32 = 12;
+const BAR_HEIGHT: i32 = 6;
+const BOTTOM_MARGIN: i32 = 64;
+const FONT: &str = "sans 14px";
+
+const FADE_DURATION_MS: u64 = 150;
+const SHOW_DURATION: Duration = Duration::from_millis(1500);
+
+/// On-screen display showing the backlight brightness after a change.
+pub struct BrightnessOsd {
+    state: State,
+    /// Output to show the OSD on.
+    output: Option<Output>,
+    /// Brightness in percent.
+    percent: u8,
+    /// Rendered buffer for the current scale and percentage.
+    buffer: RefCell<Option<(NotNan<f64>, u8, Option<TextureBuffer<GlesTexture>>)>>,
+    clock: Clock,
+}
+
+enum State {
+    Hidden,
+    Showing(Animation),
+    Shown(Duration),
+    Hiding(Animation),
+}
+
+impl BrightnessOsd {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            state: State::Hidden,
+            output: None,
+            percent: 0,
+            buffer: RefCell::new(None),
+            clock,
+        }
+    }
+
+    fn animation(&self, from: f64, to: f64) -> Animation {
+        Animation::ease(
+            self.clock.clone(),
+            from,
+            to,
+            0.,
+            FADE_DURATION_MS,
+            Curve::EaseOutCubic,
+        )
+    }
+
+    /// Shows the OSD on the output with the brightness from 0 to 1.
+    pub fn show(&mut self, output: Output, brightness: f64) {
+        self.percent = (brightness * 100.).round().clamp(0., 100.) as u8;
+
+        if self.output.as_ref() != Some(&output) {
+            self.output = Some(output);
+            self.state = State::Showing(self.animation(0., 1.));
+            return;
+        }
+
+        match &self.state {
+            State::Hidden => self.state = State::Showing(self.animation(0., 1.)),
+            // Fade back in from the current opacity.
+            State::Hiding(anim) => self.state = State::Showing(self.animation(anim.value(), 1.)),
+            // The timer to hide restarts once showing is done.
+            State::Showing(_) => (),
+            State::Shown(_) => {
+                self.state = State::Shown(self.clock.now_unadjusted() + SHOW_DURATION);
+            }
+        }
+    }
+
+    pub fn hide(&mut self) {
+        if matches!(self.state, State::Hidden | State::Hiding(_)) {
+            return;
+        }
+
+        let from = match &self.state {
+            State::Showing(anim) => anim.value(),
+            _ => 1.,
+        };
+        self.state = State::Hiding(self.animation(from, 0.));
+    }
+
+    pub fn advance_animations(&mut self) {
+        match &mut self.state {
+            State::Hidden => (),
+            State::Showing(anim) => {
+                if anim.is_done() {
+                    self.state = State::Shown(self.clock.now_unadjusted() + SHOW_DURATION);
+                }
+            }
+            State::Shown(deadline) => {
+                if self.clock.now_unadjusted() >= *deadline {
+                    self.hide();
+                }
+            }
+            State::Hiding(anim) => {
+                if anim.is_clamped_done() {
+                    self.state = State::Hidden;
+                    self.output = None;
+                }
+            }
+        }
+    }
+
+    pub fn are_animations_ongoing(&self) -> bool {
+        !matches!(self.state, State::Hidden)
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if self.output.as_ref() != Some(output) {
+            return None;
+        }
+
+        let alpha = match &self.state {
+            State::Hidden => return None,
+            State::Showing(anim) | State::Hiding(anim) => anim.clamped_value().clamp(0., 1.),
+            State::Shown(_) => 1.,
+        };
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut cached = self.buffer.borrow_mut();
+        let key = (NotNan::new(scale).unwrap(), self.percent);
+        if cached.as_ref().is_none_or(|(s, p, _)| (*s, *p) != key) {
+            let buffer = render(renderer.as_gles_renderer(), scale, self.percent).ok();
+            *cached = Some((key.0, key.1, buffer));
+        }
+        let buffer = cached.as_ref().unwrap().2.clone()?;
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w).max(0.) / 2.;
+        let y = (output_size.h - size.h - f64::from(BOTTOM_MARGIN)).max(0.);
+
+        let location = Point::from((x, y));
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            alpha as f32,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    percent: u8,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("brightness_osd::render");
+
+    let width: i32 = to_physical_precise_round(scale, WIDTH);
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let bar_height: i32 = to_physical_precise_round(scale, BAR_HEIGHT);
+
+    let text = format!("Brightness {percent}%");
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(&text);
+
+    let (_, text_height) = layout.pixel_size();
+    let height = padding * 3 + text_height + bar_height;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(&text);
+
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    let bar_x = f64::from(padding);
+    let bar_y = f64::from(padding * 2 + text_height);
+    let bar_width = f64::from(width - padding * 2);
+
+    cr.rectangle(bar_x, bar_y, bar_width, bar_height.into());
+    cr.set_source_rgb(0.3, 0.3, 0.3);
+    cr.fill()?;
+
+    cr.rectangle(
+        bar_x,
+        bar_y,
+        bar_width * f64::from(percent) / 100.,
+        bar_height.into(),
+    );
+    cr.set_source_rgb(1., 1., 1.);
+    cr.fill()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
@@ -31,6 +31,8 @@ pub struct HotkeyOverlay {
     is_open: bool,
     config: Rc<RefCell<Config>>,
     mod_key: ModKey,
+    /// Descriptions of custom actions registered over IPC, by name.
+    custom_actions: HashMap<String, String>,
     buffers: RefCell<HashMap<WeakOutput, RenderedOverlay>>,
 }
 
@@ -44,6 +46,7 @@ impl HotkeyOverlay {
             is_open: false,
             config,
             mod_key,
+            custom_actions: HashMap::new(),
             buffers: RefCell::new(HashMap::new()),
         }
     }
@@ -75,6 +78,41 @@ impl HotkeyOverlay {
         self.buffers.borrow_mut().clear();
     }
 
+    /// Registers a custom action description and returns whether the overlay needs a redraw.
+    pub fn register_custom_action(&mut self, name: String, description: String) -> bool {
+        if self.custom_actions.get(&name) == Some(&description) {
+            return false;
+        }
+
+        self.custom_actions.insert(name, description);
+        self.buffers.borrow_mut().clear();
+        self.is_open
+    }
+
+    /// Unregisters a custom action and returns whether the overlay needs a redraw.
+    pub fn unregister_custom_action(&mut self, name: &str) -> bool {
+        if self.custom_actions.remove(name).is_none() {
+            return false;
+        }
+
+        self.buffers.borrow_mut().clear();
+        self.is_open
+    }
+
+    /// Returns the registered custom actions, sorted by name.
+    pub fn custom_actions(&self) -> Vec<niri_ipc::CustomAction> {
+        let mut actions: Vec<_> = self
+            .custom_actions
+            .iter()
+            .map(|(name, description)| niri_ipc::CustomAction {
+                name: name.clone(),
+                description: description.clone(),
+            })
+            .collect();
+        actions.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        actions
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -102,8 +140,14 @@ impl HotkeyOverlay {
 
         let rendered = buffers.entry(weak).or_insert_with(|| {
             let renderer = renderer.as_gles_renderer();
-            render(renderer, &self.config.borrow(), self.mod_key, scale)
-                .unwrap_or_else(|_| RenderedOverlay { buffer: None })
+            render(
+                renderer,
+                &self.config.borrow(),
+                &self.custom_actions,
+                self.mod_key,
+                scale,
+            )
+            .unwrap_or_else(|_| RenderedOverlay { buffer: None })
         });
         let buffer = rendered.buffer.as_ref()?;
 
@@ -127,13 +171,14 @@ impl HotkeyOverlay {
 
     pub fn a11y_text(&self) -> String {
         let config = self.config.borrow();
-        let actions = collect_actions(&config);
+        let actions = collect_actions(&config, &self.custom_actions);
 
         let mut buf = String::new();
         writeln!(&mut buf, "{TITLE}").unwrap();
 
         for action in actions {
-            let Some((key, action)) = format_bind(&config.binds.0, action) else {
+            let Some((key, action)) = format_bind(&config.binds.0, action, &self.custom_actions)
+            else {
                 continue;
             };
 
@@ -152,7 +197,11 @@ impl HotkeyOverlay {
     }
 }
 
-fn format_bind(binds: &[Bind], action: &Action) -> Option<(Option<Key>, String)> {
+fn format_bind(
+    binds: &[Bind],
+    action: &Action,
+    custom_actions: &HashMap<String, String>,
+) -> Option<(Option<Key>, String)> {
     let mut bind_with_non_null = None;
     let mut bind_with_custom_title = None;
     let mut found_null_title = false;
@@ -189,12 +238,15 @@ fn format_bind(binds: &[Bind], action: &Action) -> Option<(Option<Key>, String)>
     } else {
         None
     };
-    let title = title.unwrap_or_else(|| action_name(action));
+    let title = title.unwrap_or_else(|| action_name(action, custom_actions));
 
     Some((key, title))
 }
 
-fn collect_actions(config: &Config) -> Vec<&Action> {
+fn collect_actions<'a>(
+    config: &'a Config,
+    custom_actions: &HashMap<String, String>,
+) -> Vec<&'a Action> {
     let binds = &config.binds.0;
 
     // Collect actions that we want to show.
@@ -263,7 +315,7 @@ fn collect_actions(config: &Config) -> Vec<&Action> {
     // Screenshot is not as important, can omit if not bound.
     if let Some(bind) = binds
         .iter()
-        .find(|bind| matches!(bind.action, Action::Screenshot(_, _)))
+        .find(|bind| matches!(bind.action, Action::Screenshot(_, _, _)))
     {
         actions.push(&bind.action);
     }
@@ -295,6 +347,15 @@ fn collect_actions(config: &Config) -> Vec<&Action> {
         }
     }
 
+    // Add the custom actions that were registered over IPC.
+    for bind in binds {
+        if let Action::SendIpcEvent(name, _) = &bind.action {
+            if custom_actions.contains_key(name) && !actions.contains(&&bind.action) {
+                actions.push(&bind.action);
+            }
+        }
+    }
+
     if config.hotkey_overlay.hide_not_bound {
         // Only keep actions that have been bound
         actions.retain(|&action| binds.iter().any(|bind| bind.action == *action))
@@ -306,6 +367,7 @@ fn collect_actions(config: &Config) -> Vec<&Action> {
 fn render(
     renderer: &mut GlesRenderer,
     config: &Config,
+    custom_actions: &HashMap<String, String>,
     mod_key: ModKey,
     scale: f64,
 ) -> anyhow::Result<RenderedOverlay> {
@@ -321,9 +383,9 @@ fn render(
     // target_size.h -= margin * 2;
     // anyhow::ensure!(target_size.w > 0 && target_size.h > 0);
 
-    let strings = collect_actions(config)
+    let strings = collect_actions(config, custom_actions)
         .into_iter()
-        .filter_map(|action| format_bind(&config.binds.0, action))
+        .filter_map(|action| format_bind(&config.binds.0, action, custom_actions))
         .map(|(key, action)| {
             let key = key.map(|key| key_name(false, mod_key, &key));
             let key = key.as_deref().unwrap_or("(not bound)");
@@ -455,7 +517,7 @@ fn render(
     })
 }
 
-fn action_name(action: &Action) -> String {
+fn action_name(action: &Action, custom_actions: &HashMap<String, String>) -> String {
     match action {
         Action::Quit(_) => String::from("Exit niri"),
         Action::ShowHotkeyOverlay => String::from("Show Important Hotkeys"),
@@ -479,7 +541,7 @@ fn action_name(action: &Action) -> String {
             String::from("Switch Focus Between Floating and Tiling")
         }
         Action::ToggleOverview => String::from("Open the Overview"),
-        Action::Screenshot(_, _) => String::from("Take a Screenshot"),
+        Action::Screenshot(_, _, _) => String::from("Take a Screenshot"),
         Action::Spawn(args) => format!(
             "Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
             args.first().unwrap_or(&String::new())
@@ -489,6 +551,11 @@ fn action_name(action: &Action) -> String {
             // Fairly crude but should get the job done in most cases.
             command.split_ascii_whitespace().next().unwrap_or("")
         ),
+        // Descriptions come from IPC clients, so they are not treated as markup.
+        Action::SendIpcEvent(name, _) => {
+            let description = custom_actions.get(name).unwrap_or(name);
+            pango::glib::markup_escape_text(description).into()
+        }
         _ => String::from("FIXME: Unknown"),
     }
 }
@@ -608,7 +675,7 @@ mod tests {
     #[track_caller]
     fn check(config: &str, action: Action) -> String {
         let config = Config::parse_mem(config).unwrap();
-        if let Some((key, title)) = format_bind(&config.binds.0, &action) {
+        if let Some((key, title)) = format_bind(&config.binds.0, &action, &HashMap::new()) {
             let key = key.map(|key| key_name(false, ModKey::Super, &key));
             let key = key.as_deref().unwrap_or("(not bound)");
             format!(" {key} : {title}")
@@ -620,7 +687,7 @@ mod tests {
     #[test]
     fn test_format_bind() {
         // Not bound.
-        assert_snapshot!(check("", Action::Screenshot(true, None)), @" (not bound) : Take a Screenshot");
+        assert_snapshot!(check("", Action::Screenshot(true, false, None)), @" (not bound) : Take a Screenshot");
 
         // Bound with a default title.
         assert_snapshot!(
@@ -628,7 +695,7 @@ mod tests {
                 r#"binds {
                     Mod+P { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" Super + P : Take a Screenshot"
         );
@@ -639,7 +706,7 @@ mod tests {
                 r#"binds {
                     Mod+P hotkey-overlay-title="Hello" { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" Super + P : Hello"
         );
@@ -651,7 +718,7 @@ mod tests {
                     Mod+P { screenshot; }
                     Print { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" Super + P : Take a Screenshot"
         );
@@ -663,7 +730,7 @@ mod tests {
                     Mod+P { screenshot; }
                     Print hotkey-overlay-title="My Cool Bind" { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" PrtSc : My Cool Bind"
         );
@@ -675,7 +742,7 @@ mod tests {
                     Mod+P hotkey-overlay-title="First" { screenshot; }
                     Print hotkey-overlay-title="My Cool Bind" { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" Super + P : First"
         );
@@ -687,7 +754,7 @@ mod tests {
                     Mod+P { screenshot; }
                     Print hotkey-overlay-title=null { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @"None"
         );
@@ -699,9 +766,67 @@ mod tests {
                     Mod+P hotkey-overlay-title="Hello" { screenshot; }
                     Print hotkey-overlay-title=null { screenshot; }
                 }"#,
-                Action::Screenshot(true, None),
+                Action::Screenshot(true, false, None),
             ),
             @" Super + P : Hello"
         );
     }
+
+    #[test]
+    fn custom_actions() {
+        let config = Config::parse_mem(
+            r#"binds {
+                Mod+D { send-ipc-event "dashboard"; }
+                Mod+N { send-ipc-event "notes" { page "today"; }; }
+            }"#,
+        )
+        .unwrap();
+
+        let mut custom_actions = HashMap::new();
+        custom_actions.insert(String::from("notes"), String::from("Open <Notes>"));
+
+        let actions = collect_actions(&config, &custom_actions);
+        let action = Action::SendIpcEvent(
+            String::from("notes"),
+            vec![niri_config::IpcEventArg {
+                name: String::from("page"),
+                value: String::from("today"),
+            }],
+        );
+        assert!(actions.contains(&&action));
+        // Unregistered custom actions are not shown.
+        assert!(!actions
+            .iter()
+            .any(|action| matches!(action, Action::SendIpcEvent(name, _) if name == "dashboard")));
+
+        let (_, title) = format_bind(&config.binds.0, &action, &custom_actions).unwrap();
+        assert_eq!(title, "Open &lt;Notes&gt;");
+    }
+
+    #[test]
+    fn registered_custom_actions_are_listed() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let mut overlay = HotkeyOverlay::new(config, ModKey::Super);
+
+        overlay.register_custom_action(String::from("notes"), String::from("Notes"));
+        overlay.register_custom_action(String::from("dashboard"), String::from("Dashboard"));
+        overlay.register_custom_action(String::from("notes"), String::from("Open Notes"));
+
+        let names: Vec<_> = overlay
+            .custom_actions()
+            .into_iter()
+            .map(|action| (action.name, action.description))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (String::from("dashboard"), String::from("Dashboard")),
+                (String::from("notes"), String::from("Open Notes")),
+            ]
+        );
+
+        assert!(!overlay.unregister_custom_action("missing"));
+        overlay.unregister_custom_action("notes");
+        assert_eq!(overlay.custom_actions().len(), 1);
+    }
 }
@@ -0,0 +1,180 @@
+use anyhow::Context as _;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::utils::{CropRenderElement, RescaleRenderElement};
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
+
+use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
+
+/// Zoom level change for one zoom-in or zoom-out step.
+const STEP: f64 = 1.25;
+/// Maximum zoom level.
+const MAX_LEVEL: f64 = 16.;
+
+pub type MagnifierRenderElement = CropRenderElement<RescaleRenderElement<OffscreenRenderElement>>;
+
+/// Screen magnifier of an output.
+#[derive(Debug)]
+pub struct Magnifier {
+    /// Current zoom level, 1 when the magnifier is off.
+    level: f64,
+    /// Buffer that the output contents are rendered into before magnifying.
+    buffer: OffscreenBuffer,
+}
+
+/// Text caret that the magnifier follows.
+#[derive(Debug, Clone, Copy)]
+pub struct MagnifierCaret {
+    /// Caret location in global coordinates.
+    pub location: Point<f64, Logical>,
+    /// Pointer location when the caret moved.
+    ///
+    /// The magnifier goes back to following the pointer once it moves away from here.
+    pub pointer_location: Point<f64, Logical>,
+}
+
+impl Magnifier {
+    pub fn new() -> Self {
+        Self {
+            level: 1.,
+            buffer: OffscreenBuffer::default(),
+        }
+    }
+
+    /// Sets the format of the buffer that the output contents are rendered into.
+    pub fn set_format(&mut self, format: Fourcc) {
+        self.buffer.set_format(format);
+    }
+
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.level > 1.
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.level = f64::min(self.level * STEP, MAX_LEVEL);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.level = self.level / STEP;
+        // Snap back to 1 to avoid floating point leftovers after zooming in and out.
+        if self.level < 1. + 1e-6 {
+            self.level = 1.;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.level = 1.;
+    }
+
+    /// Converts a point on the magnified output to the point of the contents shown there.
+    ///
+    /// Both points and the focus are in output-local coordinates.
+    pub fn unmagnify(
+        &self,
+        focus: Point<f64, Logical>,
+        pos: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        focus + (pos - focus).downscale(self.level)
+    }
+
+    /// Renders the output contents magnified around the focus.
+    ///
+    /// The focus is in output-local coordinates and stays in place on screen, so the contents
+    /// under the focus are drawn where they actually are. When following the pointer, this keeps
+    /// clicks landing on the contents under the magnified pointer.
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: Scale<f64>,
+        output_size: Size<f64, Logical>,
+        focus: Point<f64, Logical>,
+        elements: &[impl RenderElement<GlesRenderer>],
+    ) -> anyhow::Result<(MagnifierRenderElement, OffscreenData)> {
+        let _span = tracy_client::span!("Magnifier::render");
+
+        let (elem, _sync, data) = self.buffer.render(renderer, scale, elements)?;
+
+        let origin = focus.to_physical_precise_round(scale);
+        let elem = RescaleRenderElement::from_element(elem, origin, self.level);
+
+        let output_rect = Rectangle::from_size(output_size).to_physical_precise_round(scale);
+        let elem = CropRenderElement::from_element(elem, scale, output_rect)
+            .context("magnified contents are outside the output")?;
+
+        Ok((elem, data))
+    }
+}
+
+impl Default for Magnifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_in_and_out_steps() {
+        let mut magnifier = Magnifier::new();
+        assert!(!magnifier.is_active());
+
+        magnifier.zoom_in();
+        assert_eq!(magnifier.level(), STEP);
+        assert!(magnifier.is_active());
+
+        magnifier.zoom_in();
+        assert_eq!(magnifier.level(), STEP * STEP);
+
+        magnifier.zoom_out();
+        magnifier.zoom_out();
+        assert_eq!(magnifier.level(), 1.);
+        assert!(!magnifier.is_active());
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut magnifier = Magnifier::new();
+        for _ in 0..100 {
+            magnifier.zoom_in();
+        }
+        assert_eq!(magnifier.level(), MAX_LEVEL);
+
+        magnifier.reset();
+        assert_eq!(magnifier.level(), 1.);
+
+        // Zooming out can't go below the unmagnified screen.
+        magnifier.zoom_out();
+        assert_eq!(magnifier.level(), 1.);
+        assert!(!magnifier.is_active());
+    }
+
+    #[test]
+    fn unmagnify() {
+        let focus = Point::from((100., 50.));
+        let pos = Point::from((300., 250.));
+
+        let magnifier = Magnifier::new();
+        assert_eq!(magnifier.unmagnify(focus, pos), pos);
+
+        let mut magnifier = Magnifier::new();
+        magnifier.zoom_in();
+        magnifier.zoom_in();
+        let level = magnifier.level();
+
+        // The focus stays in place.
+        assert_eq!(magnifier.unmagnify(focus, focus), focus);
+
+        // Distances from the focus shrink by the zoom level.
+        let unmagnified = magnifier.unmagnify(focus, pos);
+        let expected = Point::from((100. + 200. / level, 50. + 200. / level));
+        assert!((unmagnified.x - expected.x).abs() < 1e-9);
+        assert!((unmagnified.y - expected.y).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,8 @@
+pub mod brightness_osd;
 pub mod config_error_notification;
 pub mod exit_confirm_dialog;
 pub mod hotkey_overlay;
+pub mod magnifier;
 pub mod mru;
 pub mod screen_transition;
 pub mod screenshot_ui;
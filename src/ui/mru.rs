@@ -35,6 +35,7 @@ use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenRenderElement};
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::surface::SurfaceRenderElement;
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
 use crate::render_helpers::RenderTarget;
 use crate::utils::{
@@ -379,10 +380,12 @@ impl Thumbnail {
 
         let has_border_shader = BorderRenderElement::has_shader(renderer);
         let clip_shader = ClippedSurfaceRenderElement::shader(renderer).cloned();
+        let color_clip_shader =
+            ClippedSurfaceRenderElement::color_managed_shader(renderer).cloned();
         let geo = Rectangle::from_size(self.size.to_f64());
         // FIXME: deduplicate code with Tile::render_inner()
         let clip = move |elem| match elem {
-            LayoutElementRenderElement::Wayland(elem) => {
+            LayoutElementRenderElement::Wayland(SurfaceRenderElement::Wayland(elem)) => {
                 if let Some(shader) = clip_shader.clone() {
                     if ClippedSurfaceRenderElement::will_clip(&elem, s, geo, radius) {
                         let elem =
@@ -392,7 +395,21 @@ impl Thumbnail {
                 }
 
                 // If we don't have the shader, render it normally.
-                let elem = LayoutElementRenderElement::Wayland(elem);
+                let elem = LayoutElementRenderElement::Wayland(elem.into());
+                ThumbnailRenderElement::LayoutElement(elem)
+            }
+            LayoutElementRenderElement::Wayland(SurfaceRenderElement::ColorManaged(elem)) => {
+                if let Some(shader) = color_clip_shader.clone() {
+                    if ClippedSurfaceRenderElement::will_clip(elem.inner(), s, geo, radius) {
+                        let (elem, conversion) = elem.into_parts();
+                        let elem =
+                            ClippedSurfaceRenderElement::new(elem, s, geo, shader.clone(), radius)
+                                .with_conversion(conversion);
+                        return ThumbnailRenderElement::ClippedSurface(elem);
+                    }
+                }
+
+                let elem = LayoutElementRenderElement::Wayland(elem.into());
                 ThumbnailRenderElement::LayoutElement(elem)
             }
             LayoutElementRenderElement::SolidColor(elem) => {
@@ -1906,7 +1923,7 @@ fn make_dynamic_opened_binds(config: &Config) -> Vec<Bind> {
             Action::FocusColumnFirst => Action::MruFirst,
             Action::FocusColumnLast => Action::MruLast,
             Action::CloseWindow => Action::MruCloseCurrentWindow,
-            x @ Action::Screenshot(_, _) => x.clone(),
+            x @ Action::Screenshot(_, _, _) => x.clone(),
             _ => continue,
         };
 
@@ -7,7 +7,7 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use arrayvec::ArrayVec;
-use niri_config::{Action, Config};
+use niri_config::{Action, ColorFilterKind, Config};
 use niri_ipc::SizeChange;
 use pango::{Alignment, FontDescription};
 use pangocairo::cairo::{self, ImageSurface};
@@ -24,6 +24,7 @@ use smithay::utils::{Buffer, Physical, Point, Rectangle, Scale, Size, Transform}
 use crate::animation::{Animation, Clock};
 use crate::layout::floating::DIRECTIONAL_MOVE_PX;
 use crate::niri_render_elements;
+use crate::render_helpers::color_filter::ColorFilterRenderElement;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
@@ -60,6 +61,8 @@ pub enum ScreenshotUi {
         output_data: HashMap<Output, OutputData>,
         button: Button,
         show_pointer: bool,
+        /// Whether to apply the output color filter to the saved screenshot.
+        color_filter: bool,
         open_anim: Animation,
         clock: Clock,
         config: Rc<RefCell<Config>>,
@@ -142,6 +145,7 @@ impl ScreenshotUi {
         screenshots: HashMap<Output, [OutputScreenshot; 3]>,
         default_output: Output,
         show_pointer: bool,
+        color_filter: bool,
         path: Option<String>,
     ) -> bool {
         if screenshots.is_empty() {
@@ -234,6 +238,7 @@ impl ScreenshotUi {
             output_data,
             button: Button::Up,
             show_pointer,
+            color_filter,
             open_anim,
             clock: clock.clone(),
             config: config.clone(),
@@ -697,6 +702,7 @@ impl ScreenshotUi {
     pub fn capture(
         &self,
         renderer: &mut GlesRenderer,
+        color_filter: Option<ColorFilterKind>,
     ) -> anyhow::Result<(Size<i32, Physical>, Vec<u8>)> {
         let _span = tracy_client::span!("ScreenshotUi::capture");
 
@@ -715,35 +721,57 @@ impl ScreenshotUi {
 
         let screenshot = &data.screenshot[0];
 
-        // Composite the pointer on top if needed.
+        let pointer = screenshot.pointer.clone().filter(|_| *show_pointer);
+        let color_filter = color_filter.and_then(|filter| {
+            let program = ColorFilterRenderElement::shader(renderer)?.clone();
+            Some((program, filter))
+        });
+
+        // Composite the pointer on top and apply the color filter if needed.
         let mut tex_rect = None;
-        if *show_pointer {
-            if let Some(pointer) = screenshot.pointer.clone() {
-                let scale = pointer.0.buffer().texture_scale();
-                let offset = rect.loc.upscale(-1);
+        if pointer.is_some() || color_filter.is_some() {
+            let scale = screenshot.buffer.0.buffer().texture_scale();
+            let offset = rect.loc.upscale(-1);
 
-                let mut elements = ArrayVec::<_, 2>::new();
+            let mut elements = ArrayVec::<_, 2>::new();
+            if let Some(pointer) = pointer {
                 elements.push(pointer);
-                elements.push(screenshot.buffer.clone());
-                let elements = elements.iter().rev().map(|elem| {
-                    RelocateRenderElement::from_element(elem, offset, Relocate::Relative)
-                });
-
-                let res = render_to_texture(
+            }
+            elements.push(screenshot.buffer.clone());
+            let elements = elements
+                .iter()
+                .rev()
+                .map(|elem| RelocateRenderElement::from_element(elem, offset, Relocate::Relative));
+
+            let res = if let Some((program, filter)) = color_filter {
+                // The filter is linear in premultiplied colors, so it can be applied to every
+                // element separately.
+                let elements = elements
+                    .map(|elem| ColorFilterRenderElement::new(elem, program.clone(), filter));
+                render_to_texture(
                     renderer,
                     rect.size,
                     scale,
                     Transform::Normal,
                     Fourcc::Abgr8888,
                     elements,
-                );
-                match res {
-                    Ok((texture, _)) => {
-                        tex_rect = Some((texture, Rectangle::from_size(rect.size)));
-                    }
-                    Err(err) => {
-                        warn!("error compositing pointer onto screenshot: {err:?}");
-                    }
+                )
+            } else {
+                render_to_texture(
+                    renderer,
+                    rect.size,
+                    scale,
+                    Transform::Normal,
+                    Fourcc::Abgr8888,
+                    elements,
+                )
+            };
+            match res {
+                Ok((texture, _)) => {
+                    tex_rect = Some((texture, Rectangle::from_size(rect.size)));
+                }
+                Err(err) => {
+                    warn!("error compositing screenshot: {err:?}");
                 }
             }
         }
@@ -0,0 +1,212 @@
+//! Backlight devices of built-in panels.
+//!
+//! The brightness is read from sysfs, but written through logind, which doesn't require any
+//! special permissions.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io, mem};
+
+use smithay::reexports::udev::{MonitorBuilder, MonitorSocket};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// Connector types of built-in panels.
+const INTERNAL_CONNECTORS: [&str; 3] = ["eDP", "LVDS", "DSI"];
+
+/// Backlight device of an output.
+#[derive(Debug)]
+pub struct Backlight {
+    /// Name of the device in `/sys/class/backlight`.
+    name: String,
+    path: PathBuf,
+    max_brightness: u32,
+    brightness: u32,
+}
+
+/// Change of the brightness, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrightnessChange {
+    Up(u8),
+    Down(u8),
+    Set(u8),
+}
+
+impl Backlight {
+    /// Finds the backlight device of a DRM connector.
+    ///
+    /// `card` is the name of the DRM device, such as `card1`, and `connector` is the connector
+    /// name, such as `eDP-1`.
+    pub fn for_connector(card: &str, connector: &str) -> Option<Self> {
+        let entries = match fs::read_dir(BACKLIGHT_DIR) {
+            Ok(x) => x,
+            Err(err) => {
+                debug!("error reading {BACKLIGHT_DIR}: {err:?}");
+                return None;
+            }
+        };
+
+        let is_internal = INTERNAL_CONNECTORS.iter().any(|c| connector.starts_with(c));
+        let connector_dir = format!("{card}-{connector}");
+        let card_device = fs::canonicalize(Path::new("/sys/class/drm").join(card).join("device"));
+
+        // Same preference as in other desktops: firmware interfaces tend to work best, and raw
+        // ones may not be tied to any particular panel.
+        let mut best: Option<(u8, PathBuf)> = None;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(kind) = fs::read_to_string(path.join("type")) else {
+                continue;
+            };
+
+            let priority = match kind.trim() {
+                "firmware" if is_internal => 0,
+                "platform" if is_internal => 1,
+                "raw" => {
+                    let Ok(parent) = fs::canonicalize(path.join("device")) else {
+                        continue;
+                    };
+
+                    if parent
+                        .file_name()
+                        .is_some_and(|name| *name == *connector_dir)
+                    {
+                        // Raw backlight of this exact connector.
+                        2
+                    } else if is_internal && card_device.as_ref().is_ok_and(|dev| *dev == parent) {
+                        // Raw backlight of the GPU driving the panel.
+                        3
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+
+            if best.as_ref().is_none_or(|(best, _)| priority < *best) {
+                best = Some((priority, path));
+            }
+        }
+
+        let (_, path) = best?;
+        let name = path.file_name()?.to_str()?.to_owned();
+        let max_brightness = match read_value(&path.join("max_brightness")) {
+            Ok(0) => return None,
+            Ok(x) => x,
+            Err(err) => {
+                warn!("error reading max brightness of {name}: {err:?}");
+                return None;
+            }
+        };
+
+        let mut backlight = Self {
+            name,
+            path,
+            max_brightness,
+            brightness: max_brightness,
+        };
+        backlight.refresh();
+
+        debug!(
+            "using backlight {} for connector {connector}",
+            backlight.name
+        );
+        Some(backlight)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the brightness from 0 to 1.
+    pub fn brightness(&self) -> f64 {
+        f64::from(self.brightness) / f64::from(self.max_brightness)
+    }
+
+    /// Reads the brightness again in case something else changed it.
+    ///
+    /// Returns whether the brightness changed.
+    pub fn refresh(&mut self) -> bool {
+        match read_value(&self.path.join("brightness")) {
+            Ok(x) => {
+                let brightness = x.min(self.max_brightness);
+                mem::replace(&mut self.brightness, brightness) != brightness
+            }
+            Err(err) => {
+                warn!("error reading brightness of {}: {err:?}", self.name);
+                false
+            }
+        }
+    }
+
+    /// Applies the change and returns the new raw brightness value.
+    pub fn apply(&mut self, change: BrightnessChange) -> u32 {
+        let max = f64::from(self.max_brightness);
+        let current = self.brightness() * 100.;
+
+        let brightness = match change {
+            BrightnessChange::Up(step) => current + f64::from(step),
+            BrightnessChange::Down(step) => current - f64::from(step),
+            BrightnessChange::Set(percent) => f64::from(percent),
+        };
+        let mut brightness = (brightness.clamp(0., 100.) / 100. * max).round() as u32;
+
+        // Don't turn the backlight off with relative changes, it's hard to find the way back
+        // with a black screen.
+        if matches!(change, BrightnessChange::Down(_)) {
+            brightness = brightness.max(1).min(self.brightness);
+        }
+
+        self.brightness = brightness;
+        brightness
+    }
+}
+
+/// Creates a udev monitor for the backlight devices.
+///
+/// The kernel sends a change event whenever the brightness changes, whether through sysfs or
+/// through the firmware handling a brightness key.
+pub fn monitor() -> io::Result<MonitorSocket> {
+    MonitorBuilder::new()?
+        .match_subsystem("backlight")?
+        .listen()
+}
+
+fn read_value(path: &Path) -> anyhow::Result<u32> {
+    let value = fs::read_to_string(path)?;
+    Ok(value.trim().parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backlight(max_brightness: u32, brightness: u32) -> Backlight {
+        Backlight {
+            name: String::from("test"),
+            path: PathBuf::new(),
+            max_brightness,
+            brightness,
+        }
+    }
+
+    #[test]
+    fn brightness_changes() {
+        let mut b = backlight(1000, 500);
+        assert_eq!(b.apply(BrightnessChange::Up(5)), 550);
+        assert_eq!(b.apply(BrightnessChange::Up(100)), 1000);
+        assert_eq!(b.apply(BrightnessChange::Set(20)), 200);
+        assert_eq!(b.apply(BrightnessChange::Set(0)), 0);
+        assert_eq!(b.apply(BrightnessChange::Up(1)), 10);
+    }
+
+    #[test]
+    fn brightness_down_keeps_backlight_on() {
+        let mut b = backlight(15, 2);
+        assert_eq!(b.apply(BrightnessChange::Down(5)), 1);
+        assert_eq!(b.apply(BrightnessChange::Down(5)), 1);
+
+        // Going down from zero doesn't turn the backlight on.
+        let mut b = backlight(15, 0);
+        assert_eq!(b.apply(BrightnessChange::Down(5)), 0);
+    }
+}
@@ -34,6 +34,7 @@ use wayland_backend::server::Credentials;
 use crate::handlers::KdeDecorationsModeState;
 use crate::niri::ClientState;
 
+pub mod backlight;
 pub mod id;
 pub mod scale;
 pub mod signals;
@@ -234,6 +235,30 @@ pub fn panel_orientation(output: &Output) -> Transform {
         .unwrap_or(Transform::Normal)
 }
 
+/// Scale requested when creating a virtual output, overriding the configured scale.
+pub struct ScaleOverride(pub f64);
+
+/// Scale for an output without a configured scale, overriding the guess from its size.
+pub struct DefaultScale(pub f64);
+
+/// Returns the scale to use for an output, given its scale from the config.
+pub fn output_scale(output: &Output, configured: Option<f64>) -> f64 {
+    let user_data = output.user_data();
+    if let Some(scale) = user_data.get::<ScaleOverride>() {
+        return scale.0;
+    }
+    if let Some(scale) = configured {
+        return scale;
+    }
+    if let Some(scale) = user_data.get::<DefaultScale>() {
+        return scale.0;
+    }
+
+    let size_mm = output.physical_properties().size;
+    let resolution = output.current_mode().unwrap().size;
+    scale::guess_monitor_scale(size_mm, resolution)
+}
+
 pub fn ipc_transform_to_smithay(transform: niri_ipc::Transform) -> Transform {
     match transform {
         niri_ipc::Transform::Normal => Transform::Normal,
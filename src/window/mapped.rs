@@ -2,7 +2,6 @@ use std::cell::{Cell, Ref, RefCell};
 use std::time::Duration;
 
 use niri_config::{Color, CornerRadius, GradientInterpolation, WindowRule};
-use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::space::SpaceElement as _;
@@ -21,20 +20,22 @@ use smithay::wayland::shell::xdg::{
 };
 use wayland_backend::server::Credentials;
 
-use super::{ResolvedWindowRules, WindowRef};
+use super::{ResolvedWindowRules, RulesAtOpen, ToplevelTag, WindowRef};
 use crate::handlers::KdeDecorationsModeState;
 use crate::layout::{
     ConfigureIntent, InteractiveResizeData, LayoutElement, LayoutElementRenderElement,
     LayoutElementRenderSnapshot, SizingMode,
 };
 use crate::niri_render_elements;
+use crate::protocols::background_effect::blur_region;
+use crate::render_helpers::blur::region_rects;
 use crate::render_helpers::border::BorderRenderElement;
 use crate::render_helpers::offscreen::OffscreenData;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::surface::{
-    push_elements_from_surface_tree, render_snapshot_from_surface_tree,
+    push_elements_from_surface_tree, render_snapshot_from_surface_tree, SurfaceRenderElement,
 };
 use crate::render_helpers::{BakedBuffer, RenderTarget};
 use crate::utils::id::IdCounter;
@@ -61,6 +62,9 @@ pub struct Mapped {
     /// Up-to-date rules.
     rules: ResolvedWindowRules,
 
+    /// Rules as evaluated for the initial configure, for explaining them over IPC.
+    rules_at_open: Option<RulesAtOpen>,
+
     /// Whether the window rules need to be recomputed.
     ///
     /// This is not used in all cases; for example, app ID and title changes recompute the rules
@@ -104,6 +108,9 @@ pub struct Mapped {
     /// Buffer to draw instead of the window when it should be blocked out.
     block_out_buffer: RefCell<SolidColorBuffer>,
 
+    /// Fractional scale last sent to the window.
+    preferred_scale: Cell<Option<f64>>,
+
     /// Whether the next configure should be animated, if the configured state changed.
     animate_next_configure: bool,
 
@@ -188,6 +195,9 @@ pub struct Mapped {
 
     /// Most recent monotonic time when the window had the focus.
     focus_timestamp: Option<Duration>,
+
+    /// Tag and description set through xdg-toplevel-tag.
+    toplevel_tag: ToplevelTag,
 }
 
 niri_render_elements! {
@@ -249,7 +259,12 @@ enum RequestSizeOnce {
 }
 
 impl Mapped {
-    pub fn new(window: Window, rules: ResolvedWindowRules, hook: HookId) -> Self {
+    pub fn new(
+        window: Window,
+        rules: ResolvedWindowRules,
+        rules_at_open: Option<RulesAtOpen>,
+        hook: HookId,
+    ) -> Self {
         let surface = window.wl_surface().expect("no X11 support");
         let credentials = get_credentials_for_surface(&surface);
 
@@ -259,6 +274,7 @@ impl Mapped {
             credentials,
             pre_commit_hook: hook,
             rules,
+            rules_at_open,
             need_to_recompute_rules: false,
             needs_configure: false,
             needs_frame_callback: false,
@@ -270,6 +286,7 @@ impl Mapped {
             is_window_cast_target: false,
             ignore_opacity_window_rule: false,
             block_out_buffer: RefCell::new(SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.])),
+            preferred_scale: Cell::new(None),
             animate_next_configure: false,
             animate_serials: Vec::new(),
             animation_snapshot: None,
@@ -285,6 +302,7 @@ impl Mapped {
             is_pending_maximized: false,
             uncommitted_maximized: Vec::new(),
             focus_timestamp: None,
+            toplevel_tag: ToplevelTag::default(),
         };
 
         rv.is_maximized = rv.sizing_mode().is_maximized();
@@ -474,6 +492,7 @@ impl Mapped {
         &self,
         renderer: &mut R,
         scale: Scale<f64>,
+        target: RenderTarget,
         push: &mut dyn FnMut(WindowCastRenderElements<R>),
     ) {
         let bbox = self.window.bbox_with_popups().to_physical_precise_up(scale);
@@ -519,14 +538,9 @@ impl Mapped {
             WindowCastRenderElements::from(elem)
         };
 
-        self.render(
-            renderer,
-            location,
-            scale,
-            1.,
-            RenderTarget::Screencast,
-            &mut |elem| push(use_border(elem)),
-        );
+        self.render(renderer, location, scale, 1., target, &mut |elem| {
+            push(use_border(elem))
+        });
     }
 
     pub fn get_focus_timestamp(&self) -> Option<Duration> {
@@ -583,6 +597,25 @@ impl Mapped {
     pub fn is_urgent(&self) -> bool {
         self.is_urgent
     }
+
+    pub fn rules_at_open(&self) -> Option<&RulesAtOpen> {
+        self.rules_at_open.as_ref()
+    }
+
+    /// Returns the fractional scale last sent to the window, if any.
+    pub fn preferred_scale(&self) -> Option<f64> {
+        self.preferred_scale.get()
+    }
+
+    pub fn toplevel_tag(&self) -> &ToplevelTag {
+        &self.toplevel_tag
+    }
+
+    pub fn set_toplevel_tag(&mut self, toplevel_tag: ToplevelTag) {
+        let changed = self.toplevel_tag != toplevel_tag;
+        self.toplevel_tag = toplevel_tag;
+        self.need_to_recompute_rules |= changed;
+    }
 }
 
 impl Drop for Mapped {
@@ -629,7 +662,7 @@ impl LayoutElement for Mapped {
         } else {
             let buf_pos = location - self.window.geometry().loc.to_f64();
             let surface = self.toplevel().wl_surface();
-            let mut push = |elem: WaylandSurfaceRenderElement<R>| push(elem.into());
+            let mut push = |elem: SurfaceRenderElement<R>| push(elem.into());
             push_elements_from_surface_tree(
                 renderer,
                 surface,
@@ -657,7 +690,7 @@ impl LayoutElement for Mapped {
 
         let buf_pos = location - self.window.geometry().loc.to_f64();
         let surface = self.toplevel().wl_surface();
-        let mut push = |elem: WaylandSurfaceRenderElement<R>| push(elem.into());
+        let mut push = |elem: SurfaceRenderElement<R>| push(elem.into());
         for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
             let offset = self.window.geometry().loc + popup_offset - popup.geometry().loc;
 
@@ -826,6 +859,7 @@ impl LayoutElement for Mapped {
     }
 
     fn set_preferred_scale_transform(&self, scale: output::Scale, transform: Transform) {
+        self.preferred_scale.set(Some(scale.fractional_scale()));
         self.window.with_surfaces(|surface, data| {
             send_scale_transform(surface, data, scale, transform);
         });
@@ -1260,6 +1294,21 @@ impl LayoutElement for Mapped {
         &self.rules
     }
 
+    fn blur_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        let region = blur_region(self.toplevel().wl_surface())?;
+
+        // The region is in surface-local coordinates.
+        let geo_loc = self.window.geometry().loc;
+        let rects = region_rects(&region)
+            .into_iter()
+            .map(|mut rect| {
+                rect.loc -= geo_loc;
+                rect
+            })
+            .collect();
+        Some(rects)
+    }
+
     fn take_animation_snapshot(&mut self) -> Option<LayoutElementRenderSnapshot> {
         self.animation_snapshot.take()
     }
@@ -3,8 +3,8 @@ use std::cmp::{max, min};
 use niri_config::utils::MergeWith as _;
 use niri_config::window_rule::{Match, WindowRule};
 use niri_config::{
-    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, PresetSize, ShadowRule,
-    TabIndicatorRule,
+    BackgroundEffectRule, BlockOutFrom, BorderRule, CornerRadius, DimInactiveRule,
+    FloatingPosition, PresetSize, ShadowRule, TabIndicatorRule,
 };
 use niri_ipc::ColumnDisplay;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
@@ -29,6 +29,15 @@ pub enum WindowRef<'a> {
     Mapped(&'a Mapped),
 }
 
+/// Tag and description of a toplevel set through xdg-toplevel-tag.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ToplevelTag {
+    /// Untranslated tag identifying the toplevel among the windows of its app.
+    pub tag: Option<String>,
+    /// Translated, human-readable description of the tag.
+    pub description: Option<String>,
+}
+
 /// Rules fully resolved for a window.
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct ResolvedWindowRules {
@@ -90,6 +99,8 @@ pub struct ResolvedWindowRules {
     pub shadow: ShadowRule,
     /// Tab indicator overrides.
     pub tab_indicator: TabIndicatorRule,
+    /// Inactive window dimming overrides.
+    pub dim_inactive: DimInactiveRule,
 
     /// Whether or not to draw the border with a solid background.
     ///
@@ -119,6 +130,9 @@ pub struct ResolvedWindowRules {
 
     /// Override whether to set the Tiled xdg-toplevel state on the window.
     pub tiled_state: Option<bool>,
+
+    /// Background effect overrides.
+    pub background_effect: BackgroundEffectRule,
 }
 
 impl<'a> WindowRef<'a> {
@@ -129,6 +143,13 @@ impl<'a> WindowRef<'a> {
         }
     }
 
+    pub fn toplevel_tag(self) -> &'a ToplevelTag {
+        match self {
+            WindowRef::Unmapped(unmapped) => &unmapped.toplevel_tag,
+            WindowRef::Mapped(mapped) => mapped.toplevel_tag(),
+        }
+    }
+
     pub fn is_focused(self) -> bool {
         match self {
             WindowRef::Unmapped(_) => false,
@@ -173,10 +194,53 @@ impl<'a> WindowRef<'a> {
     }
 }
 
+/// Window rules as they were evaluated for the initial configure of a window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulesAtOpen {
+    /// Rules resolved for the initial configure.
+    pub resolved: ResolvedWindowRules,
+    /// How each window rule from the config at that time matched the window.
+    pub matches: Vec<RuleMatch>,
+}
+
+/// How a window rule matched a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleMatch {
+    /// Whether the rule applied to the window.
+    pub applied: bool,
+    /// Index of the first `match` that matched the window.
+    ///
+    /// `None` if no `match` matched, or if the rule has no `match` and so matches all windows.
+    pub matched_by: Option<usize>,
+    /// Index of the first `exclude` that matched the window.
+    ///
+    /// Excludes are only checked for rules that matched.
+    pub excluded_by: Option<usize>,
+}
+
 impl ResolvedWindowRules {
     pub fn compute(rules: &[WindowRule], window: WindowRef, is_at_startup: bool) -> Self {
         let _span = tracy_client::span!("ResolvedWindowRules::compute");
+        Self::compute_inner(rules, window, is_at_startup, None)
+    }
+
+    /// Computes the rules and how each of them matched the window, for debugging.
+    pub fn compute_explained(
+        rules: &[WindowRule],
+        window: WindowRef,
+        is_at_startup: bool,
+    ) -> (Self, Vec<RuleMatch>) {
+        let mut explanation = Vec::with_capacity(rules.len());
+        let resolved = Self::compute_inner(rules, window, is_at_startup, Some(&mut explanation));
+        (resolved, explanation)
+    }
 
+    fn compute_inner(
+        rules: &[WindowRule],
+        window: WindowRef,
+        is_at_startup: bool,
+        mut explanation: Option<&mut Vec<RuleMatch>>,
+    ) -> Self {
         let mut resolved = ResolvedWindowRules::default();
 
         with_toplevel_role(window.toplevel(), |role| {
@@ -199,11 +263,24 @@ impl ResolvedWindowRules {
                     window_matches(window, role, m)
                 };
 
-                if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
-                    continue;
+                let matched_by = rule.matches.iter().position(matches);
+                let is_match = rule.matches.is_empty() || matched_by.is_some();
+                let excluded_by = if is_match {
+                    rule.excludes.iter().position(matches)
+                } else {
+                    None
+                };
+                let applied = is_match && excluded_by.is_none();
+
+                if let Some(explanation) = explanation.as_deref_mut() {
+                    explanation.push(RuleMatch {
+                        applied,
+                        matched_by,
+                        excluded_by,
+                    });
                 }
 
-                if rule.excludes.iter().any(matches) {
+                if !applied {
                     continue;
                 }
 
@@ -268,6 +345,7 @@ impl ResolvedWindowRules {
                 resolved.border.merge_with(&rule.border);
                 resolved.shadow.merge_with(&rule.shadow);
                 resolved.tab_indicator.merge_with(&rule.tab_indicator);
+                resolved.dim_inactive.merge_with(&rule.dim_inactive);
 
                 if let Some(x) = rule.draw_border_with_background {
                     resolved.draw_border_with_background = Some(x);
@@ -296,6 +374,10 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.tiled_state {
                     resolved.tiled_state = Some(x);
                 }
+
+                resolved
+                    .background_effect
+                    .merge_with(&rule.background_effect);
             }
 
             resolved.open_on_output = open_on_output.map(|x| x.to_owned());
@@ -415,6 +497,15 @@ fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m:
         }
     }
 
+    if let Some(tag_re) = &m.tag {
+        let Some(tag) = &window.toplevel_tag().tag else {
+            return false;
+        };
+        if !tag_re.0.is_match(tag) {
+            return false;
+        }
+    }
+
     if let Some(is_active_in_column) = m.is_active_in_column {
         if window.is_active_in_column() != is_active_in_column {
             return false;
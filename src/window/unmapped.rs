@@ -4,7 +4,7 @@ use smithay::output::Output;
 use smithay::wayland::shell::xdg::ToplevelSurface;
 use smithay::wayland::xdg_activation::XdgActivationTokenData;
 
-use super::ResolvedWindowRules;
+use super::{ResolvedWindowRules, RulesAtOpen, ToplevelTag};
 
 #[derive(Debug)]
 pub struct Unmapped {
@@ -12,6 +12,8 @@ pub struct Unmapped {
     pub state: InitialConfigureState,
     /// Activation token, if one was used on this unmapped window.
     pub activation_token_data: Option<XdgActivationTokenData>,
+    /// Tag and description set through xdg-toplevel-tag.
+    pub toplevel_tag: ToplevelTag,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -33,6 +35,9 @@ pub enum InitialConfigureState {
         /// affect anything before that.
         rules: ResolvedWindowRules,
 
+        /// Rules as evaluated for the initial configure, for explaining them over IPC.
+        rules_at_open: RulesAtOpen,
+
         /// Resolved scrolling default width for this window.
         ///
         /// `None` means that the window will pick its own width.
@@ -87,6 +92,7 @@ impl Unmapped {
                 wants_maximized: false,
             },
             activation_token_data: None,
+            toplevel_tag: ToplevelTag::default(),
         }
     }
 